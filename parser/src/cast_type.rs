@@ -0,0 +1,38 @@
+use super::tokenizer::Token;
+
+/// The types that a value can be converted to with `CAST(expression AS type)` or
+/// `expression::type`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CastType {
+    Integer,
+    Boolean,
+    Text,
+    /// A `numeric` that keeps the digits after the point of the value.
+    Numeric,
+    Bytea,
+    Uuid,
+    Json,
+}
+
+impl CastType {
+    /// Returns the type that the token names, if it names one.
+    pub fn from_token(token: &Token) -> Option<Self> {
+        match token {
+            Token::IntegerKeyword => Some(CastType::Integer),
+            Token::Identifier(name) if name.eq_ignore_ascii_case("boolean") => {
+                Some(CastType::Boolean)
+            }
+            Token::Identifier(name) if name.eq_ignore_ascii_case("text") => Some(CastType::Text),
+            Token::Identifier(name)
+                if name.eq_ignore_ascii_case("numeric") || name.eq_ignore_ascii_case("decimal") =>
+            {
+                Some(CastType::Numeric)
+            }
+            Token::Identifier(name) if name.eq_ignore_ascii_case("bytea") => Some(CastType::Bytea),
+            Token::Identifier(name) if name.eq_ignore_ascii_case("uuid") => Some(CastType::Uuid),
+            Token::Identifier(name) if name.eq_ignore_ascii_case("json") => Some(CastType::Json),
+
+            _ => None,
+        }
+    }
+}
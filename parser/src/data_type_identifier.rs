@@ -0,0 +1,52 @@
+use super::decimal::MAX_PRECISION;
+use super::tokenizer::Token;
+
+#[derive(Debug, PartialEq)]
+pub enum DataTypeIdentifier {
+    Integer,
+    /// An integer which is assigned an increasing value when it's left out of an `INSERT`.
+    Serial,
+    Text,
+    /// `numeric(precision, scale)`, or `decimal`. Like in the SQL standard, the scale is 0 when
+    /// it's left out, and the precision is the largest one that is supported.
+    Numeric {
+        precision: u8,
+        scale: u8,
+    },
+    /// Binary data, also called `blob`.
+    Bytea,
+    Uuid,
+    Json,
+}
+
+impl From<Token> for Option<DataTypeIdentifier> {
+    fn from(value: Token) -> Self {
+        match value {
+            Token::IntegerKeyword => Some(DataTypeIdentifier::Integer),
+            Token::SerialKeyword => Some(DataTypeIdentifier::Serial),
+            Token::Identifier(name) if name.eq_ignore_ascii_case("text") => {
+                Some(DataTypeIdentifier::Text)
+            }
+            Token::Identifier(name)
+                if name.eq_ignore_ascii_case("numeric") || name.eq_ignore_ascii_case("decimal") =>
+            {
+                Some(DataTypeIdentifier::Numeric {
+                    precision: MAX_PRECISION,
+                    scale: 0,
+                })
+            }
+            Token::Identifier(name)
+                if name.eq_ignore_ascii_case("bytea") || name.eq_ignore_ascii_case("blob") =>
+            {
+                Some(DataTypeIdentifier::Bytea)
+            }
+            Token::Identifier(name) if name.eq_ignore_ascii_case("uuid") => {
+                Some(DataTypeIdentifier::Uuid)
+            }
+            Token::Identifier(name) if name.eq_ignore_ascii_case("json") => {
+                Some(DataTypeIdentifier::Json)
+            }
+            _ => None,
+        }
+    }
+}
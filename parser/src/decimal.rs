@@ -0,0 +1,276 @@
+use alloc::format;
+use core::cmp::Ordering;
+use core::fmt::{self, Display, Formatter};
+
+/// The largest number of digits of a `numeric` value, since the digits are kept in an `i128`.
+pub const MAX_PRECISION: u8 = 38;
+
+/// The smallest number of digits after the point that the quotient of a division keeps.
+const MIN_DIVISION_SCALE: u8 = 6;
+
+/// An exact fixed-point number, which is `mantissa / 10^scale`. Values with a different scale can
+/// still be equal, e.g. `1.5` and `1.50`.
+#[derive(Debug, Clone, Copy)]
+pub struct Decimal {
+    mantissa: i128,
+    scale: u8,
+}
+
+impl Decimal {
+    pub fn new(mantissa: i128, scale: u8) -> Self {
+        Self { mantissa, scale }
+    }
+
+    /// Parses a number like `-12.50`, which keeps the digits after the point as its scale.
+    /// Returns `None` when the text isn't a number or has too many digits.
+    pub fn parse(text: &str) -> Option<Self> {
+        let (negative, digits) = match text.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (integer_digits, fraction_digits) = digits.split_once('.').unwrap_or((digits, ""));
+        if integer_digits.is_empty() && fraction_digits.is_empty()
+            || fraction_digits.len() > MAX_PRECISION as usize
+        {
+            return None;
+        }
+
+        let mut mantissa: i128 = 0;
+        for character in integer_digits.chars().chain(fraction_digits.chars()) {
+            let digit = character.to_digit(10)?;
+            mantissa = mantissa.checked_mul(10)?.checked_add(digit as i128)?;
+        }
+
+        Some(Self {
+            mantissa: if negative { -mantissa } else { mantissa },
+            scale: fraction_digits.len() as u8,
+        })
+    }
+
+    pub fn mantissa(&self) -> i128 {
+        self.mantissa
+    }
+
+    pub fn scale(&self) -> u8 {
+        self.scale
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa == 0
+    }
+
+    /// Returns whether the value has at most `precision` digits.
+    pub fn fits_precision(&self, precision: u8) -> bool {
+        self.mantissa.unsigned_abs() < 10u128.pow(precision.min(MAX_PRECISION) as u32)
+    }
+
+    /// Returns the value with `scale` digits after the point, rounding half away from zero when
+    /// digits are dropped. Returns `None` when the value doesn't fit.
+    pub fn rescale(&self, scale: u8) -> Option<Self> {
+        let mantissa = match scale.cmp(&self.scale) {
+            Ordering::Equal => self.mantissa,
+            Ordering::Greater => self
+                .mantissa
+                .checked_mul(power_of_ten(scale - self.scale)?)?,
+            Ordering::Less => divide_rounded(self.mantissa, power_of_ten(self.scale - scale)?)?,
+        };
+
+        Some(Self { mantissa, scale })
+    }
+
+    /// Rounds the value to an integer, or returns `None` when it doesn't fit in an `i64`.
+    pub fn to_integer(&self) -> Option<i64> {
+        i64::try_from(self.rescale(0)?.mantissa).ok()
+    }
+
+    pub fn checked_neg(&self) -> Option<Self> {
+        Some(Self {
+            mantissa: self.mantissa.checked_neg()?,
+            scale: self.scale,
+        })
+    }
+
+    pub fn checked_add(&self, other: &Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale);
+        let mantissa =
+            (self.rescale(scale)?.mantissa).checked_add(other.rescale(scale)?.mantissa)?;
+
+        Some(Self { mantissa, scale })
+    }
+
+    pub fn checked_sub(&self, other: &Self) -> Option<Self> {
+        self.checked_add(&other.checked_neg()?)
+    }
+
+    /// Multiplies the values, keeping the digits after the point of both of them (up to
+    /// `MAX_PRECISION` digits).
+    pub fn checked_mul(&self, other: &Self) -> Option<Self> {
+        let product = Self {
+            mantissa: self.mantissa.checked_mul(other.mantissa)?,
+            scale: self.scale + other.scale,
+        };
+
+        product.rescale(product.scale.min(MAX_PRECISION))
+    }
+
+    /// Divides the value by `other`. The quotient has as many digits after the point as the
+    /// operand with the most of them, but at least `MIN_DIVISION_SCALE`, and is rounded half away
+    /// from zero. Returns `None` when `other` is zero or the quotient doesn't fit.
+    pub fn checked_div(&self, other: &Self) -> Option<Self> {
+        let scale = self.scale.max(other.scale).max(MIN_DIVISION_SCALE);
+        let dividend = self
+            .mantissa
+            .checked_mul(power_of_ten(scale + other.scale - self.scale)?)?;
+
+        Some(Self {
+            mantissa: divide_rounded(dividend, other.mantissa)?,
+            scale,
+        })
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(value: i64) -> Self {
+        Self::new(value as i128, 0)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+
+        match (self.rescale(scale), other.rescale(scale)) {
+            (Some(lhs), Some(rhs)) => lhs.mantissa.cmp(&rhs.mantissa),
+            // Only the value with fewer digits after the point is scaled, so when it doesn't fit
+            // it's further away from zero than the other value.
+            (None, _) if self.mantissa < 0 => Ordering::Less,
+            (None, _) => Ordering::Greater,
+            (_, None) if other.mantissa < 0 => Ordering::Greater,
+            (_, None) => Ordering::Less,
+        }
+    }
+}
+
+impl Display for Decimal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let sign = if self.mantissa < 0 { "-" } else { "" };
+        let scale = self.scale as usize;
+        let digits = format!(
+            "{:0>width$}",
+            self.mantissa.unsigned_abs(),
+            width = scale + 1
+        );
+
+        if scale == 0 {
+            return write!(f, "{}{}", sign, digits);
+        }
+
+        let (integer_digits, fraction_digits) = digits.split_at(digits.len() - scale);
+        write!(f, "{}{}.{}", sign, integer_digits, fraction_digits)
+    }
+}
+
+fn power_of_ten(exponent: u8) -> Option<i128> {
+    10i128.checked_pow(exponent as u32)
+}
+
+/// Divides `dividend` by `divisor`, rounding half away from zero.
+fn divide_rounded(dividend: i128, divisor: i128) -> Option<i128> {
+    let quotient = dividend.checked_div(divisor)?;
+    let remainder = dividend.checked_rem(divisor)?;
+
+    if remainder.unsigned_abs() * 2 < divisor.unsigned_abs() {
+        Some(quotient)
+    } else if (dividend < 0) != (divisor < 0) {
+        quotient.checked_sub(1)
+    } else {
+        quotient.checked_add(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(text: &str) -> Decimal {
+        Decimal::parse(text).unwrap()
+    }
+
+    #[test]
+    fn test_parsing_and_formatting() {
+        assert_eq!(Some(Decimal::new(-1250, 2)), Decimal::parse("-12.50"));
+        assert_eq!(2, decimal("-12.50").scale());
+        assert_eq!("-12.50", decimal("-12.50").to_string());
+        assert_eq!("0.05", decimal(".05").to_string());
+        assert_eq!("7", decimal("+7").to_string());
+        assert_eq!("3", decimal("3.").to_string());
+
+        assert_eq!(None, Decimal::parse(""));
+        assert_eq!(None, Decimal::parse("-."));
+        assert_eq!(None, Decimal::parse("1.2.3"));
+        assert_eq!(None, Decimal::parse("12a"));
+        assert_eq!(None, Decimal::parse(&"9".repeat(40)));
+    }
+
+    #[test]
+    fn test_comparing_values_with_different_scales() {
+        assert_eq!(decimal("1.5"), decimal("1.50"));
+        assert!(decimal("1.05") < decimal("1.5"));
+        assert!(decimal("-2") < decimal("-1.99"));
+
+        // Scaling the value with fewer digits after the point overflows.
+        let large = Decimal::new(i128::MAX / 10, 0);
+        let small = Decimal::new(1, MAX_PRECISION);
+        assert!(large > small);
+        assert!(large.checked_neg().unwrap() < small);
+    }
+
+    #[test]
+    fn test_rounding() {
+        assert_eq!("1.24", decimal("1.235").rescale(2).unwrap().to_string());
+        assert_eq!("-1.24", decimal("-1.235").rescale(2).unwrap().to_string());
+        assert_eq!("1.23", decimal("1.2349").rescale(2).unwrap().to_string());
+        assert_eq!("5.000", decimal("5").rescale(3).unwrap().to_string());
+        assert_eq!(Some(-3), decimal("-2.5").to_integer());
+        assert_eq!(None, decimal("9223372036854775808").to_integer());
+
+        assert!(decimal("999.99").fits_precision(5));
+        assert!(!decimal("1000.00").fits_precision(5));
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let sum = decimal("0.1").checked_add(&decimal("0.2")).unwrap();
+        assert_eq!("0.3", sum.to_string());
+
+        let difference = decimal("10").checked_sub(&decimal("0.25")).unwrap();
+        assert_eq!("9.75", difference.to_string());
+
+        let product = decimal("19.99").checked_mul(&decimal("3")).unwrap();
+        assert_eq!("59.97", product.to_string());
+
+        let quotient = decimal("10").checked_div(&decimal("3")).unwrap();
+        assert_eq!("3.333333", quotient.to_string());
+        let quotient = decimal("-2").checked_div(&decimal("3.0000000")).unwrap();
+        assert_eq!("-0.6666667", quotient.to_string());
+
+        assert_eq!(None, decimal("1").checked_div(&decimal("0.00")));
+        let large = Decimal::new(i128::MAX, 0);
+        assert_eq!(None, large.checked_add(&decimal("1")));
+        assert_eq!(None, large.checked_mul(&decimal("2")));
+    }
+}
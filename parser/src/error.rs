@@ -0,0 +1,59 @@
+use alloc::string::String;
+use core::fmt::{self, Display, Formatter};
+
+use super::Token;
+
+/// The errors of parsing a statement. bsql reports them with the same variants of its own `Error`,
+/// see its `From<Error>` implementation.
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    MissingToken,
+    UnexpectedToken {
+        actual: Token,
+    },
+    /// A `$n` parameter that no value was supplied for.
+    MissingParameter(usize),
+    /// A type name in a cast that isn't a type.
+    TypeDoesNotExist(String),
+    IntegerOutOfRange,
+    /// The value of a storage option like `fillfactor` is outside of the range it allows.
+    OptionValueOutOfRange {
+        option: String,
+        value: u64,
+        min: u64,
+        max: u64,
+    },
+    /// The percentage of `TABLESAMPLE` is greater than 100.
+    SamplePercentageOutOfRange(u64),
+}
+
+impl Display for Error {
+    /// Writes a message for people, in the style of the messages of PostgreSQL.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingToken => write!(f, "syntax error at end of input"),
+            Error::UnexpectedToken { actual } => {
+                write!(f, "syntax error at or near \"{}\"", actual.to_sql())
+            }
+            Error::MissingParameter(index) => write!(f, "there is no parameter ${}", index),
+            Error::TypeDoesNotExist(name) => write!(f, "type \"{}\" does not exist", name),
+            Error::IntegerOutOfRange => write!(f, "integer out of range"),
+            Error::OptionValueOutOfRange {
+                option,
+                value,
+                min,
+                max,
+            } => write!(
+                f,
+                "value {} out of bounds for option \"{}\", valid values are between \"{}\" and \
+                 \"{}\"",
+                value, option, min, max
+            ),
+            Error::SamplePercentageOutOfRange(percentage) => write!(
+                f,
+                "sample percentage {} must be between 0 and 100",
+                percentage
+            ),
+        }
+    }
+}
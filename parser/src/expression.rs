@@ -0,0 +1,219 @@
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use super::{
+    ArithmeticOperation, CastType, Command, CompareOperation, LiteralValue, LogicalOperation,
+};
+
+#[derive(Debug, PartialEq)]
+pub enum Expression {
+    /// `*`, either as the whole select list or as the argument of `COUNT(*)`.
+    Wildcard,
+    Null,
+    Identifier(String),
+    Literal(LiteralValue),
+
+    Function {
+        name: String,
+        arguments: Vec<Expression>,
+    },
+    /// `DISTINCT expression` as the first argument of an aggregate function, e.g.
+    /// `COUNT(DISTINCT user_id)`, which aggregates every distinct value only once.
+    Distinct(Box<Expression>),
+
+    Comparison {
+        lhs: Box<Expression>,
+        operation: CompareOperation,
+        rhs: Box<Expression>,
+    },
+
+    Arithmetic {
+        lhs: Box<Expression>,
+        operation: ArithmeticOperation,
+        rhs: Box<Expression>,
+    },
+
+    Logical {
+        lhs: Box<Expression>,
+        operation: LogicalOperation,
+        rhs: Box<Expression>,
+    },
+    Not(Box<Expression>),
+    /// Unary minus, e.g. `-5` or `-age`.
+    Negation(Box<Expression>),
+
+    /// `expression [NOT] LIKE pattern`, where `%` in the pattern matches any number of characters
+    /// and `_` matches a single character.
+    Like {
+        expression: Box<Expression>,
+        pattern: Box<Expression>,
+        negated: bool,
+    },
+
+    /// `expression [NOT] BETWEEN low AND high`, which includes both bounds.
+    Between {
+        expression: Box<Expression>,
+        low: Box<Expression>,
+        high: Box<Expression>,
+        negated: bool,
+    },
+
+    /// `expression [NOT] IN (value, ..)`.
+    InList {
+        expression: Box<Expression>,
+        list: Vec<Expression>,
+        negated: bool,
+    },
+
+    /// `CASE [operand] WHEN condition THEN result .. [ELSE else_result] END`. When an `operand` is
+    /// given each condition is compared to it instead of being evaluated as a boolean.
+    Case {
+        operand: Option<Box<Expression>>,
+        branches: Vec<(Expression, Expression)>,
+        else_result: Option<Box<Expression>>,
+    },
+
+    /// `CAST(expression AS data_type)` or `expression::data_type`.
+    Cast {
+        expression: Box<Expression>,
+        data_type: CastType,
+    },
+
+    /// A parenthesized `SELECT` which evaluates to a single value.
+    Subquery(Box<Command>),
+}
+
+impl Expression {
+    /// The name used for the column when the expression is part of a select list.
+    pub fn column_name(&self) -> String {
+        match self {
+            Expression::Identifier(name) => name.clone(),
+            Expression::Function { name, .. } => name.to_lowercase(),
+            Expression::Case { .. } => "case".to_string(),
+            Expression::Cast { expression, .. } => expression.column_name(),
+
+            _ => "?column?".to_string(),
+        }
+    }
+
+    /// Rebuilds the expression bottom-up by passing every sub-expression (and finally the
+    /// expression itself) through `f`.
+    pub fn transform<E>(
+        self,
+        f: &mut impl FnMut(Expression) -> Result<Expression, E>,
+    ) -> Result<Expression, E> {
+        let expression = match self {
+            Expression::Function { name, arguments } => Expression::Function {
+                name,
+                arguments: arguments
+                    .into_iter()
+                    .map(|argument| argument.transform(f))
+                    .collect::<Result<Vec<Expression>, E>>()?,
+            },
+
+            Expression::Comparison {
+                lhs,
+                operation,
+                rhs,
+            } => Expression::Comparison {
+                lhs: Box::new(lhs.transform(f)?),
+                operation,
+                rhs: Box::new(rhs.transform(f)?),
+            },
+
+            Expression::Arithmetic {
+                lhs,
+                operation,
+                rhs,
+            } => Expression::Arithmetic {
+                lhs: Box::new(lhs.transform(f)?),
+                operation,
+                rhs: Box::new(rhs.transform(f)?),
+            },
+
+            Expression::Logical {
+                lhs,
+                operation,
+                rhs,
+            } => Expression::Logical {
+                lhs: Box::new(lhs.transform(f)?),
+                operation,
+                rhs: Box::new(rhs.transform(f)?),
+            },
+            Expression::Not(expression) => Expression::Not(Box::new(expression.transform(f)?)),
+            Expression::Negation(expression) => {
+                Expression::Negation(Box::new(expression.transform(f)?))
+            }
+            Expression::Distinct(expression) => {
+                Expression::Distinct(Box::new(expression.transform(f)?))
+            }
+
+            Expression::Like {
+                expression,
+                pattern,
+                negated,
+            } => Expression::Like {
+                expression: Box::new(expression.transform(f)?),
+                pattern: Box::new(pattern.transform(f)?),
+                negated,
+            },
+
+            Expression::Between {
+                expression,
+                low,
+                high,
+                negated,
+            } => Expression::Between {
+                expression: Box::new(expression.transform(f)?),
+                low: Box::new(low.transform(f)?),
+                high: Box::new(high.transform(f)?),
+                negated,
+            },
+
+            Expression::InList {
+                expression,
+                list,
+                negated,
+            } => Expression::InList {
+                expression: Box::new(expression.transform(f)?),
+                list: list
+                    .into_iter()
+                    .map(|item| item.transform(f))
+                    .collect::<Result<Vec<Expression>, E>>()?,
+                negated,
+            },
+
+            Expression::Case {
+                operand,
+                branches,
+                else_result,
+            } => Expression::Case {
+                operand: match operand {
+                    Some(operand) => Some(Box::new(operand.transform(f)?)),
+                    None => None,
+                },
+                branches: branches
+                    .into_iter()
+                    .map(|(condition, result)| Ok((condition.transform(f)?, result.transform(f)?)))
+                    .collect::<Result<Vec<(Expression, Expression)>, E>>()?,
+                else_result: match else_result {
+                    Some(else_result) => Some(Box::new(else_result.transform(f)?)),
+                    None => None,
+                },
+            },
+
+            Expression::Cast {
+                expression,
+                data_type,
+            } => Expression::Cast {
+                expression: Box::new(expression.transform(f)?),
+                data_type,
+            },
+
+            expression => expression,
+        };
+
+        f(expression)
+    }
+}
@@ -0,0 +1,45 @@
+//! Hex digits for bytes, as in `X'beef'` literals and the text of `bytea` values.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Writes `bytes` as lowercase hex digits, two for every byte.
+pub fn encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Reads pairs of hex digits (in either case) into bytes. Returns `None` when there's an odd
+/// number of digits or a character that isn't a hex digit.
+pub fn decode(digits: &str) -> Option<Vec<u8>> {
+    if digits.len() % 2 != 0 {
+        return None;
+    }
+
+    digits
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let high = (pair[0] as char).to_digit(16)?;
+            let low = (pair[1] as char).to_digit(16)?;
+            Some((high * 16 + low) as u8)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encoding_and_decoding() {
+        assert_eq!("deadbeef00", encode(&[0xDE, 0xAD, 0xBE, 0xEF, 0x00]));
+        assert_eq!("", encode(&[]));
+
+        assert_eq!(Some(vec![0xDE, 0xAD, 0xBE, 0xEF]), decode("DEADbeef"));
+        assert_eq!(Some(vec![]), decode(""));
+        assert_eq!(None, decode("ABC"));
+        assert_eq!(None, decode("0G"));
+        assert_eq!(None, decode("é1"));
+    }
+}
@@ -0,0 +1,2891 @@
+//! The SQL front-end of bsql: tokenizing, parsing and rendering commands back into SQL, together
+//! with the values that literals are parsed into. It only uses `core` and `alloc`, so that it can
+//! be embedded without the storage engine, e.g. for linting queries.
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+mod cast_type;
+mod data_type_identifier;
+mod decimal;
+mod error;
+mod expression;
+pub mod hex;
+mod literal_value;
+mod syntax_error;
+mod to_sql;
+mod tokenizer;
+mod uuid;
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::{IntoIter, Vec};
+use alloc::{format, vec};
+use core::iter::Peekable;
+
+use decimal::MAX_PRECISION;
+
+pub use cast_type::CastType;
+pub use data_type_identifier::DataTypeIdentifier;
+pub use decimal::Decimal;
+pub use error::Error;
+pub use expression::Expression;
+pub use literal_value::LiteralValue;
+pub use syntax_error::{locate_syntax_error, SyntaxErrorLocation};
+pub use tokenizer::Token;
+pub use uuid::Uuid;
+
+#[macro_export]
+macro_rules! expect_token {
+    ( $token:expr, $expected_token:pat ) => {{
+        let token = $token;
+
+        match token {
+            Some($expected_token) => Ok(token.unwrap()),
+            Some(_) => Err(Error::UnexpectedToken {
+                actual: token.unwrap(),
+            }),
+
+            None => Err(Error::MissingToken),
+        }
+    }};
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    CreateDatabase {
+        database_name: String,
+    },
+
+    CreateTable {
+        database_name: Option<String>,
+        table_name: String,
+        column_definitions: Vec<ColumnDeclaration>,
+
+        /// `WITH (fillfactor = n)`, the percentage of a page that inserts fill, see
+        /// `TableManager::fill_factor`.
+        fill_factor: Option<u8>,
+    },
+
+    InsertInto {
+        database_name: Option<String>,
+        table_name: String,
+        /// The columns the `values` are for, or `None` for all columns in order.
+        column_names: Option<Vec<String>>,
+        /// The values of every row to insert, which are `None` for `NULL`.
+        values: Vec<Vec<Option<LiteralValue>>>,
+        on_conflict: Option<OnConflict>,
+        /// The select list of `RETURNING`, which is evaluated for every inserted row.
+        returning: Option<Vec<Expression>>,
+    },
+
+    Select {
+        expressions: Vec<Expression>,
+        database_name: Option<String>,
+        /// The table of `FROM`, or `None` for a `SELECT` without `FROM`, which evaluates the select
+        /// list once, e.g. `SELECT 1 + 2`.
+        table_name: Option<String>,
+        table_sample: Option<TableSample>,
+        where_clause: Option<Expression>,
+    },
+
+    /// `DECLARE cursor_name CURSOR FOR SELECT ..`
+    DeclareCursor {
+        cursor_name: String,
+        query: Box<Command>,
+    },
+
+    /// `FETCH count [FROM] cursor_name`
+    Fetch {
+        cursor_name: String,
+        count: usize,
+    },
+
+    CloseCursor {
+        cursor_name: String,
+    },
+
+    /// `BEGIN [TRANSACTION]`
+    Begin,
+    /// `COMMIT [TRANSACTION]`
+    Commit,
+    /// `ROLLBACK [TRANSACTION]`
+    Rollback,
+    /// `SAVEPOINT name`
+    Savepoint {
+        name: String,
+    },
+    /// `ROLLBACK [TRANSACTION] TO [SAVEPOINT] name`
+    RollbackToSavepoint {
+        name: String,
+    },
+    /// `RELEASE [SAVEPOINT] name`
+    ReleaseSavepoint {
+        name: String,
+    },
+
+    /// `USE database_name`, which makes the database the active database like `\c` does.
+    Use {
+        database_name: String,
+    },
+
+    /// `COPY table_name [(column_names)] FROM 'filename' [(FORMAT csv [, HEADER [boolean]])]`,
+    /// which inserts the records of a CSV file into the table.
+    Copy {
+        database_name: Option<String>,
+        table_name: String,
+        /// The columns the fields of every record are for, or `None` for all columns in order.
+        column_names: Option<Vec<String>>,
+        filename: String,
+        /// Whether the first line of the file is a header that's skipped.
+        header: bool,
+    },
+
+    /// `SHOW DATABASES`, which lists the databases like `\l` does.
+    ShowDatabases,
+
+    /// `SHOW TABLES [FROM database_name]`, which lists the tables of a database like `\dt` does.
+    ShowTables {
+        database_name: Option<String>,
+    },
+
+    /// `EXPLAIN SELECT ..`, which describes how the query would be executed instead of running it.
+    Explain {
+        query: Box<Command>,
+    },
+
+    /// `CREATE SEQUENCE sequence_name [START [WITH] n]`, a counter whose values are handed out by
+    /// `nextval('sequence_name')`. The first value is `start`, or 1 when it's `None`.
+    CreateSequence {
+        database_name: Option<String>,
+        sequence_name: String,
+        start: Option<i64>,
+    },
+
+    /// `VACUUM [table_name]`, which compacts the pages of the table (or of every table of the
+    /// active database) and frees the pages that are left over.
+    Vacuum {
+        database_name: Option<String>,
+        table_name: Option<String>,
+    },
+}
+
+/// A column in `CREATE TABLE`, e.g. `age integer NOT NULL` or `id integer PRIMARY KEY`.
+#[derive(Debug, PartialEq)]
+pub struct ColumnDeclaration {
+    pub name: String,
+    pub data_type: DataTypeIdentifier,
+    pub not_null: bool,
+    pub primary_key: bool,
+}
+
+/// `ON CONFLICT (columns) DO ...` of an `INSERT`. A row conflicts with an existing row when they
+/// have the same values for all of the `columns`.
+#[derive(Debug, PartialEq)]
+pub struct OnConflict {
+    pub columns: Vec<String>,
+    pub action: ConflictAction,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConflictAction {
+    /// Skips the conflicting row.
+    DoNothing,
+
+    /// Updates the existing row with `SET column = expression, ..`. The expressions can refer to
+    /// the row that was going to be inserted as `excluded.column`.
+    DoUpdate(Vec<(String, Expression)>),
+}
+
+/// `TABLESAMPLE SYSTEM (percentage) [REPEATABLE (seed)]`, which only reads a random selection of
+/// about `percentage` percent of the table's pages.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TableSample {
+    pub percentage: u8,
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOperation {
+    Equality,
+    Inequality,
+    LessThan,
+    LessThanOrEqual,
+    GreaterThan,
+    GreaterThanOrEqual,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogicalOperation {
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArithmeticOperation {
+    Addition,
+    Subtraction,
+    Multiplication,
+    Division,
+}
+
+/// Parses the first statement of `input`, see `parse_statements` for inputs with more than one.
+pub fn parse(input: &str) -> Result<Command, Error> {
+    parse_tokens(tokenizer::tokenize(input))
+}
+
+/// Parses the first statement of `input` like `parse`, replacing its `$n` and `?` placeholders with
+/// the n-th of the `parameters`. Values are bound as single tokens, so they can't change the
+/// structure of the statement the way formatting them into the SQL could.
+pub fn parse_with_parameters(input: &str, parameters: &[LiteralValue]) -> Result<Command, Error> {
+    let tokens = tokenizer::tokenize(input)
+        .into_iter()
+        .map(|token| match token {
+            Token::Parameter(index) => index
+                .checked_sub(1)
+                .and_then(|index| parameters.get(index))
+                .map(LiteralValue::to_token)
+                .ok_or(Error::MissingParameter(index)),
+            token => Ok(token),
+        })
+        .collect::<Result<Vec<Token>, Error>>()?;
+
+    parse_tokens(tokens)
+}
+
+fn parse_tokens(tokens: Vec<Token>) -> Result<Command, Error> {
+    let command_tokens: Vec<Token> = tokens
+        .into_iter()
+        .take_while(|t| *t != Token::Semicolon)
+        .collect();
+
+    match command_tokens.first() {
+        Some(Token::CreateKeyword) => parse_create_command(command_tokens),
+        Some(Token::InsertKeyword) => parse_insert_command(command_tokens),
+        Some(Token::SelectKeyword) => parse_select_command(command_tokens),
+        Some(Token::DeclareKeyword) => parse_declare_cursor_command(command_tokens),
+        Some(Token::FetchKeyword) => parse_fetch_command(command_tokens),
+        Some(Token::CloseKeyword) => parse_close_cursor_command(command_tokens),
+        Some(
+            Token::BeginKeyword
+            | Token::CommitKeyword
+            | Token::RollbackKeyword
+            | Token::SavepointKeyword
+            | Token::ReleaseKeyword,
+        ) => parse_transaction_command(command_tokens),
+        Some(Token::UseKeyword) => parse_use_command(command_tokens),
+        Some(Token::CopyKeyword) => parse_copy_command(command_tokens),
+        Some(Token::ShowKeyword) => parse_show_command(command_tokens),
+        Some(Token::VacuumKeyword) => parse_vacuum_command(command_tokens),
+        Some(Token::ExplainKeyword) => Ok(Command::Explain {
+            query: Box::new(parse_select_command(
+                command_tokens.into_iter().skip(1).collect(),
+            )?),
+        }),
+
+        Some(token) => Err(Error::UnexpectedToken {
+            actual: token.clone(),
+        }),
+        None => Err(Error::MissingToken),
+    }
+}
+
+/// Returns the first statement of `input` with every literal replaced by `?`, so that statements
+/// which only differ in their values or formatting normalize to the same text.
+pub fn normalize(input: &str) -> String {
+    let tokens: Vec<Token> = tokenizer::tokenize(input)
+        .into_iter()
+        .take_while(|t| *t != Token::Semicolon)
+        .collect();
+
+    // A minus is unary, like in `-5` or `= -age`, unless it follows an operand.
+    let is_unary_minus = |tokens: &[Token], index: usize| {
+        tokens[index] == Token::MinusSign
+            && (index == 0
+                || !matches!(
+                    tokens[index - 1],
+                    Token::Identifier(_)
+                        | Token::NumericLiteral(_)
+                        | Token::StringLiteral(_)
+                        | Token::HexStringLiteral(_)
+                        | Token::Parameter(_)
+                        | Token::ClosingParenthesis
+                        | Token::NullKeyword
+                        | Token::TrueKeyword
+                        | Token::FalseKeyword
+                        | Token::EndKeyword
+                        | Token::IntegerKeyword
+                ))
+    };
+
+    // The unary minus of a number is part of its literal, so `-5` normalizes to `?` like `5`.
+    let tokens: Vec<Token> = tokens
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| {
+            !is_unary_minus(&tokens, *index)
+                || !matches!(tokens.get(index + 1), Some(Token::NumericLiteral(_)))
+        })
+        .map(|(_, token)| token.clone())
+        .collect();
+
+    let mut normalized = String::new();
+    for (index, token) in tokens.iter().enumerate() {
+        // Parentheses directly follow function and table names, and operands directly follow
+        // unary minuses. Casts are written without spaces, like `age::text`.
+        let separated = index > 0
+            && !matches!(
+                token,
+                Token::Comma | Token::Dot | Token::ClosingParenthesis | Token::DoubleColon
+            )
+            && !matches!(
+                tokens[index - 1],
+                Token::Dot | Token::OpeningParenthesis | Token::DoubleColon
+            )
+            && (*token != Token::OpeningParenthesis
+                || !matches!(tokens[index - 1], Token::Identifier(_)))
+            && !is_unary_minus(&tokens, index - 1);
+        if separated {
+            normalized.push(' ');
+        }
+
+        match token {
+            Token::NumericLiteral(_)
+            | Token::StringLiteral(_)
+            | Token::HexStringLiteral(_)
+            | Token::Parameter(_) => normalized.push('?'),
+            token => normalized.push_str(&token.to_sql()),
+        }
+    }
+
+    normalized
+}
+
+/// Returns the number of parameters that the statements of `input` use, i.e. the highest `$n`
+/// of any statement. The `?` placeholders are numbered within their statement.
+pub fn parameter_count(input: &str) -> usize {
+    split_statements(input)
+        .into_iter()
+        .flat_map(tokenizer::tokenize)
+        .filter_map(|token| match token {
+            Token::Parameter(index) => Some(index),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+/// Parses every statement of `input` in order, see `split_statements`.
+pub fn parse_statements(input: &str) -> Result<Vec<Command>, Error> {
+    split_statements(input).into_iter().map(parse).collect()
+}
+
+/// Splits `input` into its statements, each including its `;`. A `;` inside a string literal
+/// doesn't end a statement, and statements without any tokens are skipped.
+pub fn split_statements(input: &str) -> Vec<&str> {
+    let mut statements = vec![];
+    let mut start = 0;
+    let mut in_string_literal = false;
+
+    for (index, character) in input.char_indices() {
+        match character {
+            // An escaped quote toggles twice, so it stays inside the string literal.
+            '\'' => in_string_literal = !in_string_literal,
+            ';' if !in_string_literal => {
+                statements.push(&input[start..=index]);
+                start = index + 1;
+            }
+            _ => (),
+        }
+    }
+    statements.push(&input[start..]);
+
+    statements
+        .into_iter()
+        .map(str::trim)
+        .filter(|statement| {
+            tokenizer::tokenize(statement)
+                .iter()
+                .any(|t| *t != Token::Semicolon)
+        })
+        .collect()
+}
+
+fn parse_create_command(mut tokens: Vec<Token>) -> Result<Command, Error> {
+    tokens.reverse();
+    expect_token!(tokens.pop(), Token::CreateKeyword)?;
+
+    let create_type_keyword = tokens.pop().ok_or(Error::MissingToken)?;
+
+    match create_type_keyword {
+        Token::DatabaseKeyword => Ok(Command::CreateDatabase {
+            database_name: expect_identifier(tokens.pop())?,
+        }),
+        Token::TableKeyword => {
+            let (database_name, table_name) = pop_qualified_identifier(&mut tokens)?;
+
+            tokens.reverse(); // Reverse them back to the input order
+            return parse_create_table_command(database_name, table_name, tokens);
+        }
+        Token::Identifier(object) if object.eq_ignore_ascii_case("sequence") => {
+            let (database_name, sequence_name) = pop_qualified_identifier(&mut tokens)?;
+
+            let start = match tokens.pop() {
+                Some(Token::Identifier(option)) if option.eq_ignore_ascii_case("start") => {
+                    if tokens.last() == Some(&Token::WithKeyword) {
+                        tokens.pop();
+                    }
+                    Some(pop_integer(&mut tokens)?)
+                }
+                Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+                None => None,
+            };
+            if let Some(token) = tokens.pop() {
+                return Err(Error::UnexpectedToken { actual: token });
+            }
+
+            Ok(Command::CreateSequence {
+                database_name,
+                sequence_name,
+                start,
+            })
+        }
+
+        _ => Err(Error::UnexpectedToken {
+            actual: create_type_keyword,
+        }),
+    }
+}
+
+fn parse_use_command(tokens: Vec<Token>) -> Result<Command, Error> {
+    let mut tokens = tokens.into_iter();
+    expect_token!(tokens.next(), Token::UseKeyword)?;
+
+    let database_name = expect_identifier(tokens.next())?;
+    if let Some(token) = tokens.next() {
+        return Err(Error::UnexpectedToken { actual: token });
+    }
+
+    Ok(Command::Use { database_name })
+}
+
+fn parse_show_command(tokens: Vec<Token>) -> Result<Command, Error> {
+    let mut tokens = tokens.into_iter();
+    expect_token!(tokens.next(), Token::ShowKeyword)?;
+
+    let command = match tokens.next() {
+        Some(Token::Identifier(object)) if object.eq_ignore_ascii_case("databases") => {
+            Command::ShowDatabases
+        }
+        Some(Token::Identifier(object)) if object.eq_ignore_ascii_case("tables") => {
+            let database_name = match tokens.next() {
+                Some(Token::FromKeyword) => Some(expect_identifier(tokens.next())?),
+                Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+                None => None,
+            };
+
+            Command::ShowTables { database_name }
+        }
+        Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+        None => return Err(Error::MissingToken),
+    };
+
+    if let Some(token) = tokens.next() {
+        return Err(Error::UnexpectedToken { actual: token });
+    }
+
+    Ok(command)
+}
+
+fn parse_vacuum_command(tokens: Vec<Token>) -> Result<Command, Error> {
+    let mut tokens = tokens.into_iter().peekable();
+    expect_token!(tokens.next(), Token::VacuumKeyword)?;
+
+    let (database_name, table_name) = match tokens.peek() {
+        Some(_) => {
+            let (database_name, table_name) = parse_qualified_identifier(&mut tokens)?;
+            (database_name, Some(table_name))
+        }
+        None => (None, None),
+    };
+
+    if let Some(token) = tokens.next() {
+        return Err(Error::UnexpectedToken { actual: token });
+    }
+
+    Ok(Command::Vacuum {
+        database_name,
+        table_name,
+    })
+}
+
+fn parse_copy_command(tokens: Vec<Token>) -> Result<Command, Error> {
+    let mut tokens = tokens.into_iter().peekable();
+    expect_token!(tokens.next(), Token::CopyKeyword)?;
+
+    let (database_name, table_name) = parse_qualified_identifier(&mut tokens)?;
+
+    let column_names = match tokens.next_if_eq(&Token::OpeningParenthesis) {
+        Some(_) => {
+            let mut column_names = vec![expect_identifier(tokens.next())?];
+            while tokens.next_if_eq(&Token::Comma).is_some() {
+                column_names.push(expect_identifier(tokens.next())?);
+            }
+            expect_token!(tokens.next(), Token::ClosingParenthesis)?;
+
+            Some(column_names)
+        }
+        None => None,
+    };
+
+    expect_token!(tokens.next(), Token::FromKeyword)?;
+    let filename = match tokens.next() {
+        Some(Token::StringLiteral(filename)) => filename,
+        Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+        None => return Err(Error::MissingToken),
+    };
+
+    let header = match tokens.next_if_eq(&Token::OpeningParenthesis) {
+        Some(_) => parse_copy_options(&mut tokens)?,
+        None => false,
+    };
+
+    if let Some(token) = tokens.next() {
+        return Err(Error::UnexpectedToken { actual: token });
+    }
+
+    Ok(Command::Copy {
+        database_name,
+        table_name,
+        column_names,
+        filename,
+        header,
+    })
+}
+
+/// Parses the options of `COPY` after the opening parenthesis, and returns whether the file has a
+/// header. CSV is the only format, so `FORMAT csv` can be left out.
+fn parse_copy_options(tokens: &mut Peekable<IntoIter<Token>>) -> Result<bool, Error> {
+    let mut header = false;
+
+    loop {
+        match tokens.next() {
+            Some(Token::Identifier(option)) if option.eq_ignore_ascii_case("format") => {
+                match tokens.next() {
+                    Some(Token::Identifier(format)) if format.eq_ignore_ascii_case("csv") => (),
+                    Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+                    None => return Err(Error::MissingToken),
+                }
+            }
+            Some(Token::Identifier(option)) if option.eq_ignore_ascii_case("header") => {
+                header = match tokens
+                    .next_if(|token| matches!(token, Token::TrueKeyword | Token::FalseKeyword))
+                {
+                    Some(token) => token == Token::TrueKeyword,
+                    None => true,
+                };
+            }
+            Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+            None => return Err(Error::MissingToken),
+        }
+
+        match tokens.next() {
+            Some(Token::Comma) => (),
+            Some(Token::ClosingParenthesis) => return Ok(header),
+            Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+            None => return Err(Error::MissingToken),
+        }
+    }
+}
+
+fn parse_select_command(tokens: Vec<Token>) -> Result<Command, Error> {
+    let mut tokens = tokens.into_iter().peekable();
+    expect_token!(tokens.next(), Token::SelectKeyword)?;
+
+    let mut expressions: Vec<Expression> = vec![];
+
+    loop {
+        expressions.push(parse_expression(&mut tokens)?);
+
+        if tokens.next_if_eq(&Token::Comma).is_none() {
+            break;
+        }
+    }
+
+    let (database_name, table_name, table_sample) = match tokens.next_if_eq(&Token::FromKeyword) {
+        Some(_) => {
+            let (database_name, table_name) = parse_qualified_identifier(&mut tokens)?;
+            let table_sample = match tokens.next_if_eq(&Token::TablesampleKeyword) {
+                Some(_) => Some(parse_table_sample(&mut tokens)?),
+                None => None,
+            };
+
+            (database_name, Some(table_name), table_sample)
+        }
+        None => (None, None, None),
+    };
+
+    let where_clause = match tokens.next() {
+        Some(Token::WhereKeyword) => Some(parse_expression(&mut tokens)?),
+        Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+        None => None,
+    };
+
+    if let Some(token) = tokens.next() {
+        return Err(Error::UnexpectedToken { actual: token });
+    }
+
+    return Ok(Command::Select {
+        expressions,
+        database_name,
+        table_name,
+        table_sample,
+        where_clause,
+    });
+}
+
+/// Parses what follows `TABLESAMPLE`.
+fn parse_table_sample(tokens: &mut Peekable<IntoIter<Token>>) -> Result<TableSample, Error> {
+    expect_token!(tokens.next(), Token::SystemKeyword)?;
+
+    expect_token!(tokens.next(), Token::OpeningParenthesis)?;
+    let percentage = parse_number(tokens.next()).and_then(|percentage| match percentage {
+        0..=100 => Ok(percentage as u8),
+        _ => Err(Error::SamplePercentageOutOfRange(percentage)),
+    })?;
+    expect_token!(tokens.next(), Token::ClosingParenthesis)?;
+
+    let seed = match tokens.next_if_eq(&Token::RepeatableKeyword) {
+        Some(_) => {
+            expect_token!(tokens.next(), Token::OpeningParenthesis)?;
+            let seed = parse_number(tokens.next())?;
+            expect_token!(tokens.next(), Token::ClosingParenthesis)?;
+
+            Some(seed)
+        }
+        None => None,
+    };
+
+    Ok(TableSample { percentage, seed })
+}
+
+fn parse_number(token: Option<Token>) -> Result<u64, Error> {
+    match token {
+        Some(Token::NumericLiteral(number)) => {
+            number.parse::<u64>().map_err(|_| Error::UnexpectedToken {
+                actual: Token::NumericLiteral(number),
+            })
+        }
+
+        Some(token) => Err(Error::UnexpectedToken { actual: token }),
+        None => Err(Error::MissingToken),
+    }
+}
+
+/// Pops an integer, which may be negative, from tokens that have been reversed into a stack.
+fn pop_integer(tokens: &mut Vec<Token>) -> Result<i64, Error> {
+    let sign = match tokens.last() {
+        Some(Token::MinusSign) => {
+            tokens.pop();
+            "-"
+        }
+        _ => "",
+    };
+
+    match tokens.pop() {
+        Some(Token::NumericLiteral(number)) => format!("{}{}", sign, number)
+            .parse::<i64>()
+            .map_err(|_| Error::UnexpectedToken {
+                actual: Token::NumericLiteral(number),
+            }),
+
+        Some(token) => Err(Error::UnexpectedToken { actual: token }),
+        None => Err(Error::MissingToken),
+    }
+}
+
+fn parse_declare_cursor_command(tokens: Vec<Token>) -> Result<Command, Error> {
+    let mut tokens = tokens.into_iter();
+
+    expect_token!(tokens.next(), Token::DeclareKeyword)?;
+    let cursor_name = expect_identifier(tokens.next())?;
+    expect_token!(tokens.next(), Token::CursorKeyword)?;
+    expect_token!(tokens.next(), Token::ForKeyword)?;
+
+    Ok(Command::DeclareCursor {
+        cursor_name,
+        query: Box::new(parse_select_command(tokens.collect())?),
+    })
+}
+
+fn parse_fetch_command(tokens: Vec<Token>) -> Result<Command, Error> {
+    let mut tokens = tokens.into_iter().peekable();
+    expect_token!(tokens.next(), Token::FetchKeyword)?;
+
+    let count = parse_number(tokens.next())? as usize;
+
+    tokens.next_if_eq(&Token::FromKeyword);
+    let cursor_name = expect_identifier(tokens.next())?;
+
+    if let Some(token) = tokens.next() {
+        return Err(Error::UnexpectedToken { actual: token });
+    }
+
+    Ok(Command::Fetch { cursor_name, count })
+}
+
+fn parse_close_cursor_command(tokens: Vec<Token>) -> Result<Command, Error> {
+    let mut tokens = tokens.into_iter();
+
+    expect_token!(tokens.next(), Token::CloseKeyword)?;
+    let cursor_name = expect_identifier(tokens.next())?;
+
+    if let Some(token) = tokens.next() {
+        return Err(Error::UnexpectedToken { actual: token });
+    }
+
+    Ok(Command::CloseCursor { cursor_name })
+}
+
+fn parse_transaction_command(tokens: Vec<Token>) -> Result<Command, Error> {
+    let mut tokens = tokens.into_iter().peekable();
+
+    let command = match tokens.next() {
+        Some(Token::BeginKeyword) => {
+            tokens.next_if_eq(&Token::TransactionKeyword);
+            Command::Begin
+        }
+        Some(Token::CommitKeyword) => {
+            tokens.next_if_eq(&Token::TransactionKeyword);
+            Command::Commit
+        }
+        Some(Token::RollbackKeyword) => {
+            tokens.next_if_eq(&Token::TransactionKeyword);
+
+            if tokens.next_if_eq(&Token::ToKeyword).is_some() {
+                tokens.next_if_eq(&Token::SavepointKeyword);
+                Command::RollbackToSavepoint {
+                    name: expect_identifier(tokens.next())?,
+                }
+            } else {
+                Command::Rollback
+            }
+        }
+        Some(Token::SavepointKeyword) => Command::Savepoint {
+            name: expect_identifier(tokens.next())?,
+        },
+        Some(Token::ReleaseKeyword) => {
+            tokens.next_if_eq(&Token::SavepointKeyword);
+            Command::ReleaseSavepoint {
+                name: expect_identifier(tokens.next())?,
+            }
+        }
+
+        Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+        None => return Err(Error::MissingToken),
+    };
+
+    if let Some(token) = tokens.next() {
+        return Err(Error::UnexpectedToken { actual: token });
+    }
+
+    Ok(command)
+}
+
+fn parse_insert_command(mut tokens: Vec<Token>) -> Result<Command, Error> {
+    tokens.reverse();
+
+    expect_token!(tokens.pop(), Token::InsertKeyword)?;
+    expect_token!(tokens.pop(), Token::IntoKeyword)?;
+
+    let (database_name, table_name) = pop_qualified_identifier(&mut tokens)?;
+
+    let column_names = match tokens.last() {
+        Some(Token::OpeningParenthesis) => {
+            tokens.pop();
+            let mut column_names = vec![expect_identifier(tokens.pop())?];
+
+            while tokens.last() == Some(&Token::Comma) {
+                tokens.pop();
+                column_names.push(expect_identifier(tokens.pop())?);
+            }
+
+            expect_token!(tokens.pop(), Token::ClosingParenthesis)?;
+            Some(column_names)
+        }
+
+        _ => None,
+    };
+
+    expect_token!(tokens.pop(), Token::ValuesKeyword)?;
+
+    tokens.reverse(); // Return the list back to the input order
+    let mut tokens = tokens.into_iter().peekable();
+
+    let mut rows = vec![parse_insert_values(&mut tokens)?];
+    while tokens.next_if_eq(&Token::Comma).is_some() {
+        rows.push(parse_insert_values(&mut tokens)?);
+    }
+
+    let on_conflict = match tokens.next_if_eq(&Token::OnKeyword) {
+        Some(_) => Some(parse_on_conflict(&mut tokens)?),
+        None => None,
+    };
+
+    let returning = match tokens.next() {
+        Some(Token::ReturningKeyword) => {
+            let mut expressions = vec![parse_expression(&mut tokens)?];
+            while tokens.next_if_eq(&Token::Comma).is_some() {
+                expressions.push(parse_expression(&mut tokens)?);
+            }
+
+            Some(expressions)
+        }
+        Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+        None => None,
+    };
+
+    if let Some(token) = tokens.next() {
+        return Err(Error::UnexpectedToken { actual: token });
+    }
+
+    return Ok(Command::InsertInto {
+        database_name,
+        table_name,
+        column_names,
+        values: rows,
+        on_conflict,
+        returning,
+    });
+}
+
+/// Parses what follows `ON` in `INSERT INTO ... ON CONFLICT (columns) DO ...`.
+fn parse_on_conflict(tokens: &mut Peekable<IntoIter<Token>>) -> Result<OnConflict, Error> {
+    expect_token!(tokens.next(), Token::ConflictKeyword)?;
+
+    expect_token!(tokens.next(), Token::OpeningParenthesis)?;
+    let mut columns = vec![expect_identifier(tokens.next())?];
+    while tokens.next_if_eq(&Token::Comma).is_some() {
+        columns.push(expect_identifier(tokens.next())?);
+    }
+    expect_token!(tokens.next(), Token::ClosingParenthesis)?;
+
+    expect_token!(tokens.next(), Token::DoKeyword)?;
+
+    let action = match tokens.next() {
+        Some(Token::NothingKeyword) => ConflictAction::DoNothing,
+
+        Some(Token::UpdateKeyword) => {
+            expect_token!(tokens.next(), Token::SetKeyword)?;
+
+            let mut assignments = vec![];
+            loop {
+                let column_name = expect_identifier(tokens.next())?;
+                expect_token!(tokens.next(), Token::EqualSign)?;
+                assignments.push((column_name, parse_expression(tokens)?));
+
+                if tokens.next_if_eq(&Token::Comma).is_none() {
+                    break;
+                }
+            }
+
+            ConflictAction::DoUpdate(assignments)
+        }
+
+        Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+        None => return Err(Error::MissingToken),
+    };
+
+    Ok(OnConflict { columns, action })
+}
+
+/// Parses the parenthesized values of a single row in `INSERT INTO ... VALUES`.
+fn parse_insert_values(
+    tokens: &mut Peekable<IntoIter<Token>>,
+) -> Result<Vec<Option<LiteralValue>>, Error> {
+    expect_token!(tokens.next(), Token::OpeningParenthesis)?;
+
+    let mut literal_values: Vec<Option<LiteralValue>> = vec![];
+
+    loop {
+        let next_token = tokens.next();
+
+        match next_token {
+            Some(Token::ClosingParenthesis) => break,
+
+            Some(Token::NullKeyword) => {
+                literal_values.push(None);
+
+                if let Some(Token::Comma) = tokens.peek() {
+                    tokens.next();
+                }
+            }
+
+            Some(token) => {
+                // A `-` in front of a number is part of the literal.
+                let token = match token {
+                    Token::MinusSign => match tokens.next() {
+                        Some(Token::NumericLiteral(digits)) => {
+                            Token::NumericLiteral(format!("-{}", digits))
+                        }
+                        Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+                        None => return Err(Error::MissingToken),
+                    },
+                    token => token,
+                };
+
+                literal_values.push(Some(parse_literal(token)?));
+
+                if let Some(Token::Comma) = tokens.peek() {
+                    tokens.next();
+                }
+            }
+
+            None => return Err(Error::MissingToken),
+        }
+    }
+
+    Ok(literal_values)
+}
+
+/// Converts a literal token into its value. Numbers that don't fit into an integer are out of range.
+fn parse_literal(token: Token) -> Result<LiteralValue, Error> {
+    let literal: Option<LiteralValue> = token.clone().into();
+
+    literal.ok_or(match token {
+        Token::NumericLiteral(_) => Error::IntegerOutOfRange,
+        token => Error::UnexpectedToken { actual: token },
+    })
+}
+
+fn parse_create_table_command(
+    database_name: Option<String>,
+    table_name: String,
+    tokens: Vec<Token>,
+) -> Result<Command, Error> {
+    let mut tokens = tokens.into_iter().peekable();
+    let column_definitions = parse_column_definitions(&mut tokens)?;
+
+    let fill_factor = match tokens.next() {
+        Some(Token::WithKeyword) => Some(parse_fill_factor(&mut tokens)?),
+        Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+        None => None,
+    };
+
+    if let Some(token) = tokens.next() {
+        return Err(Error::UnexpectedToken { actual: token });
+    }
+
+    Ok(Command::CreateTable {
+        database_name,
+        table_name,
+        column_definitions,
+        fill_factor,
+    })
+}
+
+/// Parses the `(fillfactor = n)` after `WITH`, where `n` is a percentage between 10 and 100.
+fn parse_fill_factor(tokens: &mut Peekable<IntoIter<Token>>) -> Result<u8, Error> {
+    expect_token!(tokens.next(), Token::OpeningParenthesis)?;
+    match tokens.next() {
+        Some(Token::Identifier(option)) if option.eq_ignore_ascii_case("fillfactor") => (),
+        Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+        None => return Err(Error::MissingToken),
+    }
+    expect_token!(tokens.next(), Token::EqualSign)?;
+
+    let fill_factor = parse_number(tokens.next()).and_then(|fill_factor| match fill_factor {
+        10..=100 => Ok(fill_factor as u8),
+        value => Err(Error::OptionValueOutOfRange {
+            option: "fillfactor".to_string(),
+            value,
+            min: 10,
+            max: 100,
+        }),
+    })?;
+    expect_token!(tokens.next(), Token::ClosingParenthesis)?;
+
+    Ok(fill_factor)
+}
+
+fn parse_column_definitions(
+    tokens: &mut Peekable<IntoIter<Token>>,
+) -> Result<Vec<ColumnDeclaration>, Error> {
+    let mut column_info_list = vec![];
+
+    expect_token!(tokens.next(), Token::OpeningParenthesis)?;
+
+    loop {
+        if tokens.next_if_eq(&Token::ClosingParenthesis).is_some() {
+            break;
+        } else if let (Some(identifier_token), Some(data_type_token)) =
+            (tokens.next(), tokens.next())
+        {
+            let (name, data_type) = parse_column_definition(identifier_token, data_type_token)?;
+            let data_type = parse_type_modifiers(tokens, data_type)?;
+            let mut column = ColumnDeclaration {
+                name,
+                data_type,
+                not_null: false,
+                primary_key: false,
+            };
+            parse_column_constraints(tokens, &mut column)?;
+
+            column_info_list.push(column);
+
+            if let Some(Token::Comma) = tokens.peek() {
+                tokens.next(); // Step over the trailing comma
+            }
+        } else {
+            match tokens.next() {
+                Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+                _ => return Err(Error::MissingToken),
+            }
+        }
+    }
+
+    return Ok(column_info_list);
+}
+
+fn parse_column_definition(
+    identifier_token: Token,
+    data_type_token: Token,
+) -> Result<(String, DataTypeIdentifier), Error> {
+    match identifier_token {
+        Token::Identifier(identifier) => {
+            let data_type: Option<DataTypeIdentifier> = data_type_token.clone().into();
+            Ok((
+                identifier,
+                data_type.ok_or(Error::UnexpectedToken {
+                    actual: data_type_token,
+                })?,
+            ))
+        }
+
+        actual => Err(Error::UnexpectedToken { actual }),
+    }
+}
+
+/// Parses the `(precision, scale)` or `(precision)` that may follow `numeric`.
+fn parse_type_modifiers(
+    tokens: &mut Peekable<IntoIter<Token>>,
+    data_type: DataTypeIdentifier,
+) -> Result<DataTypeIdentifier, Error> {
+    let DataTypeIdentifier::Numeric { .. } = data_type else {
+        return Ok(data_type);
+    };
+    if tokens.next_if_eq(&Token::OpeningParenthesis).is_none() {
+        return Ok(data_type);
+    }
+
+    let precision = parse_number(tokens.next())?;
+    let scale = match tokens.next_if_eq(&Token::Comma) {
+        Some(_) => parse_number(tokens.next())?,
+        None => 0,
+    };
+    expect_token!(tokens.next(), Token::ClosingParenthesis)?;
+
+    if !(1..=MAX_PRECISION as u64).contains(&precision) {
+        return Err(Error::UnexpectedToken {
+            actual: Token::NumericLiteral(precision.to_string()),
+        });
+    }
+    if scale > precision {
+        return Err(Error::UnexpectedToken {
+            actual: Token::NumericLiteral(scale.to_string()),
+        });
+    }
+
+    Ok(DataTypeIdentifier::Numeric {
+        precision: precision as u8,
+        scale: scale as u8,
+    })
+}
+
+/// Parses the constraints that follow the data type of a column into `column`.
+fn parse_column_constraints(
+    tokens: &mut Peekable<IntoIter<Token>>,
+    column: &mut ColumnDeclaration,
+) -> Result<(), Error> {
+    loop {
+        match tokens.peek() {
+            Some(Token::NotKeyword) => {
+                tokens.next();
+                expect_token!(tokens.next(), Token::NullKeyword)?;
+                column.not_null = true;
+            }
+
+            Some(Token::NullKeyword) => {
+                tokens.next();
+                column.not_null = false;
+            }
+
+            Some(Token::PrimaryKeyword) => {
+                tokens.next();
+                expect_token!(tokens.next(), Token::KeyKeyword)?;
+                column.primary_key = true;
+            }
+
+            _ => return Ok(()),
+        }
+    }
+}
+
+fn parse_expression(tokens: &mut Peekable<IntoIter<Token>>) -> Result<Expression, Error> {
+    parse_or(tokens)
+}
+
+fn parse_or(tokens: &mut Peekable<IntoIter<Token>>) -> Result<Expression, Error> {
+    let mut lhs = parse_and(tokens)?;
+
+    while let Some(Token::OrKeyword) = tokens.peek() {
+        tokens.next();
+
+        lhs = Expression::Logical {
+            lhs: Box::new(lhs),
+            operation: LogicalOperation::Or,
+            rhs: Box::new(parse_and(tokens)?),
+        };
+    }
+
+    Ok(lhs)
+}
+
+fn parse_and(tokens: &mut Peekable<IntoIter<Token>>) -> Result<Expression, Error> {
+    let mut lhs = parse_not(tokens)?;
+
+    while let Some(Token::AndKeyword) = tokens.peek() {
+        tokens.next();
+
+        lhs = Expression::Logical {
+            lhs: Box::new(lhs),
+            operation: LogicalOperation::And,
+            rhs: Box::new(parse_not(tokens)?),
+        };
+    }
+
+    Ok(lhs)
+}
+
+fn parse_not(tokens: &mut Peekable<IntoIter<Token>>) -> Result<Expression, Error> {
+    if let Some(Token::NotKeyword) = tokens.peek() {
+        tokens.next();
+        return Ok(Expression::Not(Box::new(parse_not(tokens)?)));
+    }
+
+    parse_comparison(tokens)
+}
+
+fn parse_comparison(tokens: &mut Peekable<IntoIter<Token>>) -> Result<Expression, Error> {
+    let lhs = parse_json_access(tokens)?;
+
+    // `NOT` can't follow an operand anywhere else, so it has to be the start of `NOT LIKE`,
+    // `NOT BETWEEN` or `NOT IN`.
+    let negated = tokens.next_if_eq(&Token::NotKeyword).is_some();
+
+    match tokens.peek() {
+        Some(Token::LikeKeyword) => {
+            tokens.next();
+
+            return Ok(Expression::Like {
+                expression: Box::new(lhs),
+                pattern: Box::new(parse_json_access(tokens)?),
+                negated,
+            });
+        }
+
+        Some(Token::BetweenKeyword) => {
+            tokens.next();
+
+            let low = parse_json_access(tokens)?;
+            expect_token!(tokens.next(), Token::AndKeyword)?;
+            let high = parse_json_access(tokens)?;
+
+            return Ok(Expression::Between {
+                expression: Box::new(lhs),
+                low: Box::new(low),
+                high: Box::new(high),
+                negated,
+            });
+        }
+
+        Some(Token::InKeyword) => {
+            tokens.next();
+            expect_token!(tokens.next(), Token::OpeningParenthesis)?;
+
+            let list = parse_function_arguments(tokens)?;
+            if list.is_empty() {
+                return Err(Error::UnexpectedToken {
+                    actual: Token::ClosingParenthesis,
+                });
+            }
+
+            return Ok(Expression::InList {
+                expression: Box::new(lhs),
+                list,
+                negated,
+            });
+        }
+
+        _ if negated => {
+            return Err(match tokens.next() {
+                Some(token) => Error::UnexpectedToken { actual: token },
+                None => Error::MissingToken,
+            })
+        }
+
+        _ => (),
+    }
+
+    let operation = match tokens.peek() {
+        Some(Token::EqualSign) => CompareOperation::Equality,
+        Some(Token::NotEqualSign) => CompareOperation::Inequality,
+        Some(Token::LessThanSign) => CompareOperation::LessThan,
+        Some(Token::LessThanOrEqualSign) => CompareOperation::LessThanOrEqual,
+        Some(Token::GreaterThanSign) => CompareOperation::GreaterThan,
+        Some(Token::GreaterThanOrEqualSign) => CompareOperation::GreaterThanOrEqual,
+        _ => return Ok(lhs),
+    };
+    tokens.next();
+
+    Ok(Expression::Comparison {
+        lhs: Box::new(lhs),
+        operation,
+        rhs: Box::new(parse_json_access(tokens)?),
+    })
+}
+
+/// Parses an operand followed by any number of `-> key` accessors, which bind looser than
+/// arithmetic like other operators in PostgreSQL. `doc -> 'tags'` is read as
+/// `JSON_EXTRACT(doc, 'tags')`.
+fn parse_json_access(tokens: &mut Peekable<IntoIter<Token>>) -> Result<Expression, Error> {
+    let mut expression = parse_additive(tokens)?;
+
+    while tokens.next_if_eq(&Token::Arrow).is_some() {
+        expression = Expression::Function {
+            name: "JSON_EXTRACT".to_string(),
+            arguments: vec![expression, parse_additive(tokens)?],
+        };
+    }
+
+    Ok(expression)
+}
+
+fn parse_additive(tokens: &mut Peekable<IntoIter<Token>>) -> Result<Expression, Error> {
+    let mut lhs = parse_multiplicative(tokens)?;
+
+    loop {
+        let operation = match tokens.peek() {
+            Some(Token::PlusSign) => ArithmeticOperation::Addition,
+            Some(Token::MinusSign) => ArithmeticOperation::Subtraction,
+            _ => return Ok(lhs),
+        };
+        tokens.next();
+
+        lhs = Expression::Arithmetic {
+            lhs: Box::new(lhs),
+            operation,
+            rhs: Box::new(parse_multiplicative(tokens)?),
+        };
+    }
+}
+
+fn parse_multiplicative(tokens: &mut Peekable<IntoIter<Token>>) -> Result<Expression, Error> {
+    let mut lhs = parse_negation(tokens)?;
+
+    loop {
+        let operation = match tokens.peek() {
+            Some(Token::Asterisk) => ArithmeticOperation::Multiplication,
+            Some(Token::Slash) => ArithmeticOperation::Division,
+            _ => return Ok(lhs),
+        };
+        tokens.next();
+
+        lhs = Expression::Arithmetic {
+            lhs: Box::new(lhs),
+            operation,
+            rhs: Box::new(parse_negation(tokens)?),
+        };
+    }
+}
+
+/// Parses any number of unary minuses, which bind looser than `::type` casts.
+fn parse_negation(tokens: &mut Peekable<IntoIter<Token>>) -> Result<Expression, Error> {
+    if tokens.next_if_eq(&Token::MinusSign).is_none() {
+        return parse_typecast(tokens);
+    }
+
+    // A minus in front of a number is part of the literal, so that the smallest integer can be
+    // written. Unless the number is cast, like in `-5::text`, which negates `5::text`.
+    if let Some(Token::NumericLiteral(digits)) = tokens.peek() {
+        let digits = digits.clone();
+        tokens.next();
+
+        if tokens.peek() != Some(&Token::DoubleColon) {
+            let literal = parse_literal(Token::NumericLiteral(format!("-{}", digits)))?;
+            return Ok(Expression::Literal(literal));
+        }
+
+        let literal = Expression::Literal(parse_literal(Token::NumericLiteral(digits))?);
+        return Ok(Expression::Negation(Box::new(parse_casts(
+            literal, tokens,
+        )?)));
+    }
+
+    Ok(Expression::Negation(Box::new(parse_negation(tokens)?)))
+}
+
+/// Parses an operand followed by any number of `::type` casts.
+fn parse_typecast(tokens: &mut Peekable<IntoIter<Token>>) -> Result<Expression, Error> {
+    let expression = parse_primary(tokens)?;
+
+    parse_casts(expression, tokens)
+}
+
+/// Parses any number of `::type` casts of `expression`.
+fn parse_casts(
+    mut expression: Expression,
+    tokens: &mut Peekable<IntoIter<Token>>,
+) -> Result<Expression, Error> {
+    while tokens.next_if_eq(&Token::DoubleColon).is_some() {
+        expression = Expression::Cast {
+            expression: Box::new(expression),
+            data_type: parse_cast_type(tokens.next())?,
+        };
+    }
+
+    Ok(expression)
+}
+
+fn parse_cast_type(token: Option<Token>) -> Result<CastType, Error> {
+    match token {
+        Some(token) => CastType::from_token(&token).ok_or(match token {
+            Token::Identifier(name) => Error::TypeDoesNotExist(name),
+            token => Error::UnexpectedToken { actual: token },
+        }),
+        None => Err(Error::MissingToken),
+    }
+}
+
+fn parse_primary(tokens: &mut Peekable<IntoIter<Token>>) -> Result<Expression, Error> {
+    match tokens.next() {
+        Some(Token::Asterisk) => Ok(Expression::Wildcard),
+        Some(Token::NullKeyword) => Ok(Expression::Null),
+        Some(Token::CaseKeyword) => parse_case_expression(tokens),
+
+        Some(Token::CastKeyword) => {
+            expect_token!(tokens.next(), Token::OpeningParenthesis)?;
+            let expression = parse_expression(tokens)?;
+            expect_token!(tokens.next(), Token::AsKeyword)?;
+            let data_type = parse_cast_type(tokens.next())?;
+            expect_token!(tokens.next(), Token::ClosingParenthesis)?;
+
+            Ok(Expression::Cast {
+                expression: Box::new(expression),
+                data_type,
+            })
+        }
+
+        Some(Token::Identifier(name)) => {
+            if let Some(Token::OpeningParenthesis) = tokens.peek() {
+                tokens.next();
+
+                Ok(Expression::Function {
+                    name,
+                    arguments: parse_function_arguments(tokens)?,
+                })
+            } else if tokens.next_if_eq(&Token::Dot).is_some() {
+                let column_name = expect_identifier(tokens.next())?;
+
+                Ok(Expression::Identifier(format!("{}.{}", name, column_name)))
+            } else {
+                Ok(Expression::Identifier(name))
+            }
+        }
+
+        Some(Token::OpeningParenthesis) => {
+            if let Some(Token::SelectKeyword) = tokens.peek() {
+                let subquery_tokens = take_until_closing_parenthesis(tokens)?;
+                let subquery = parse_select_command(subquery_tokens)?;
+
+                Ok(Expression::Subquery(Box::new(subquery)))
+            } else {
+                let expression = parse_expression(tokens)?;
+                expect_token!(tokens.next(), Token::ClosingParenthesis)?;
+
+                Ok(expression)
+            }
+        }
+
+        Some(token) => parse_literal(token).map(Expression::Literal),
+
+        None => Err(Error::MissingToken),
+    }
+}
+
+/// Parses the rest of a `CASE [operand] WHEN .. THEN .. [ELSE ..] END` expression after the
+/// `CASE` keyword.
+fn parse_case_expression(tokens: &mut Peekable<IntoIter<Token>>) -> Result<Expression, Error> {
+    let operand = match tokens.peek() {
+        Some(Token::WhenKeyword) => None,
+        _ => Some(Box::new(parse_expression(tokens)?)),
+    };
+
+    let mut branches = vec![];
+    let mut else_result = None;
+
+    loop {
+        match tokens.next() {
+            Some(Token::WhenKeyword) => {
+                let condition = parse_expression(tokens)?;
+                expect_token!(tokens.next(), Token::ThenKeyword)?;
+                branches.push((condition, parse_expression(tokens)?));
+            }
+
+            Some(Token::ElseKeyword) if !branches.is_empty() && else_result.is_none() => {
+                else_result = Some(Box::new(parse_expression(tokens)?));
+            }
+
+            Some(Token::EndKeyword) if !branches.is_empty() => break,
+
+            Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+            None => return Err(Error::MissingToken),
+        }
+    }
+
+    Ok(Expression::Case {
+        operand,
+        branches,
+        else_result,
+    })
+}
+
+fn parse_function_arguments(
+    tokens: &mut Peekable<IntoIter<Token>>,
+) -> Result<Vec<Expression>, Error> {
+    let mut arguments = vec![];
+
+    if let Some(Token::ClosingParenthesis) = tokens.peek() {
+        tokens.next();
+        return Ok(arguments);
+    }
+
+    if tokens.next_if_eq(&Token::DistinctKeyword).is_some() {
+        arguments.push(Expression::Distinct(Box::new(parse_expression(tokens)?)));
+    } else {
+        arguments.push(parse_expression(tokens)?);
+    }
+
+    loop {
+        match tokens.next() {
+            Some(Token::Comma) => arguments.push(parse_expression(tokens)?),
+            Some(Token::ClosingParenthesis) => return Ok(arguments),
+
+            Some(token) => return Err(Error::UnexpectedToken { actual: token }),
+            None => return Err(Error::MissingToken),
+        }
+    }
+}
+
+/// Consumes the tokens up to (and including) the parenthesis that closes an already consumed
+/// opening parenthesis and returns the tokens in between.
+fn take_until_closing_parenthesis(
+    tokens: &mut Peekable<IntoIter<Token>>,
+) -> Result<Vec<Token>, Error> {
+    let mut depth = 0;
+    let mut inner_tokens = vec![];
+
+    loop {
+        match tokens.next() {
+            Some(Token::ClosingParenthesis) if depth == 0 => return Ok(inner_tokens),
+            Some(token) => {
+                match token {
+                    Token::OpeningParenthesis => depth += 1,
+                    Token::ClosingParenthesis => depth -= 1,
+                    _ => (),
+                }
+
+                inner_tokens.push(token);
+            }
+
+            None => return Err(Error::MissingToken),
+        }
+    }
+}
+
+fn expect_identifier(token: Option<Token>) -> Result<String, Error> {
+    match token {
+        Some(Token::Identifier(identifier)) => Ok(identifier),
+        Some(token) => Err(Error::UnexpectedToken { actual: token }),
+        None => Err(Error::MissingToken),
+    }
+}
+
+/// Parses a `name` or `database_name.name` identifier.
+fn parse_qualified_identifier(
+    tokens: &mut Peekable<IntoIter<Token>>,
+) -> Result<(Option<String>, String), Error> {
+    let identifier = expect_identifier(tokens.next())?;
+
+    if tokens.next_if_eq(&Token::Dot).is_some() {
+        return Ok((Some(identifier), expect_identifier(tokens.next())?));
+    }
+
+    Ok((None, identifier))
+}
+
+/// Same as `parse_qualified_identifier`, but for tokens that have been reversed into a stack.
+fn pop_qualified_identifier(tokens: &mut Vec<Token>) -> Result<(Option<String>, String), Error> {
+    let identifier = expect_identifier(tokens.pop())?;
+
+    if tokens.last() == Some(&Token::Dot) {
+        tokens.pop();
+        return Ok((Some(identifier), expect_identifier(tokens.pop())?));
+    }
+
+    Ok((None, identifier))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsing_create_database_expression() {
+        assert_eq!(
+            Ok(Command::CreateDatabase {
+                database_name: "my_database".to_string()
+            }),
+            parse("CREATE DATABASE my_database;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_create_sequence_expression() {
+        assert_eq!(
+            Ok(Command::CreateSequence {
+                database_name: None,
+                sequence_name: "order_numbers".to_string(),
+                start: None,
+            }),
+            parse("CREATE SEQUENCE order_numbers;"),
+        );
+        assert_eq!(
+            Ok(Command::CreateSequence {
+                database_name: Some("shop".to_string()),
+                sequence_name: "order_numbers".to_string(),
+                start: Some(-5),
+            }),
+            parse("CREATE sequence shop.order_numbers START WITH -5;"),
+        );
+        assert_eq!(
+            Ok(Command::CreateSequence {
+                database_name: None,
+                sequence_name: "order_numbers".to_string(),
+                start: Some(1000),
+            }),
+            parse("CREATE SEQUENCE order_numbers start 1000;"),
+        );
+        assert_eq!(
+            Err(Error::MissingToken),
+            parse("CREATE SEQUENCE order_numbers START WITH;"),
+        );
+    }
+
+    #[test]
+    fn test_normalizing_statements() {
+        assert_eq!(
+            "SELECT name, COUNT(*) FROM db.users WHERE age > ? AND name LIKE ?",
+            normalize("SELECT name,COUNT( * )  FROM db . users WHERE age > 30 AND name LIKE 'a%';")
+        );
+        assert_eq!(
+            normalize("INSERT INTO users VALUES (1, 'a');"),
+            normalize("INSERT INTO users VALUES (2,'b'); SELECT 1;")
+        );
+
+        // Unary minuses and casts are written like they usually are.
+        assert_eq!(
+            "SELECT -age, age - ?, -(age) FROM users WHERE age::text = ?::integer",
+            normalize("SELECT - age, age-1, - ( age) FROM users WHERE age :: text = '3'::integer;")
+        );
+
+        // Negative numbers are literals too.
+        assert_eq!(
+            normalize("INSERT INTO users VALUES (5);"),
+            normalize("INSERT INTO users VALUES (-5);")
+        );
+        assert_eq!(
+            "SELECT ? - ?, (?) FROM users WHERE id = ?",
+            normalize("SELECT 5 - -5, ( - 2) FROM users WHERE id = -5;")
+        );
+    }
+
+    #[test]
+    fn test_parsing_create_table_expression() {
+        assert_eq!(
+            Ok(Command::CreateTable {
+                database_name: None,
+                table_name: "users".to_string(),
+                fill_factor: None,
+                column_definitions: vec![
+                    ColumnDeclaration {
+                        name: "age".to_string(),
+                        data_type: DataTypeIdentifier::Integer,
+                        not_null: false,
+                        primary_key: false
+                    },
+                    ColumnDeclaration {
+                        name: "birthyear".to_string(),
+                        data_type: DataTypeIdentifier::Integer,
+                        not_null: false,
+                        primary_key: false
+                    }
+                ]
+            }),
+            parse("CREATE TABLE users (age integer, birthyear integer);"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_create_table_expression_with_not_null() {
+        assert_eq!(
+            Ok(Command::CreateTable {
+                database_name: None,
+                table_name: "users".to_string(),
+                fill_factor: None,
+                column_definitions: vec![
+                    ColumnDeclaration {
+                        name: "id".to_string(),
+                        data_type: DataTypeIdentifier::Integer,
+                        not_null: true,
+                        primary_key: false
+                    },
+                    ColumnDeclaration {
+                        name: "age".to_string(),
+                        data_type: DataTypeIdentifier::Integer,
+                        not_null: false,
+                        primary_key: false
+                    }
+                ]
+            }),
+            parse("CREATE TABLE users (id integer NOT NULL, age integer NULL);"),
+        );
+
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::Comma
+            }),
+            parse("CREATE TABLE users (id integer NOT, age integer);"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_create_table_expression_with_fill_factor() {
+        assert_eq!(
+            Ok(Command::CreateTable {
+                database_name: None,
+                table_name: "users".to_string(),
+                fill_factor: Some(70),
+                column_definitions: vec![ColumnDeclaration {
+                    name: "id".to_string(),
+                    data_type: DataTypeIdentifier::Integer,
+                    not_null: false,
+                    primary_key: false
+                }]
+            }),
+            parse("CREATE TABLE users (id integer) WITH (fillfactor = 70);"),
+        );
+
+        for fill_factor in [5, 500] {
+            assert_eq!(
+                Err(Error::OptionValueOutOfRange {
+                    option: "fillfactor".to_string(),
+                    value: fill_factor,
+                    min: 10,
+                    max: 100,
+                }),
+                parse(&format!(
+                    "CREATE TABLE users (id integer) WITH (fillfactor = {});",
+                    fill_factor
+                )),
+            );
+        }
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::Identifier("other".to_string())
+            }),
+            parse("CREATE TABLE users (id integer) other;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_create_table_expression_with_numeric_columns() {
+        let numeric_column = |name: &str, precision, scale| ColumnDeclaration {
+            name: name.to_string(),
+            data_type: DataTypeIdentifier::Numeric { precision, scale },
+            not_null: false,
+            primary_key: false,
+        };
+
+        assert_eq!(
+            Ok(Command::CreateTable {
+                database_name: None,
+                table_name: "prices".to_string(),
+                fill_factor: None,
+                column_definitions: vec![
+                    numeric_column("price", 10, 2),
+                    numeric_column("count", 5, 0),
+                    numeric_column("total", 38, 0),
+                ]
+            }),
+            parse("CREATE TABLE prices (price numeric(10, 2), count NUMERIC(5), total decimal);"),
+        );
+
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::NumericLiteral("39".to_string())
+            }),
+            parse("CREATE TABLE prices (price numeric(39, 2));"),
+        );
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::NumericLiteral("3".to_string())
+            }),
+            parse("CREATE TABLE prices (price numeric(2, 3));"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_create_table_expression_with_primary_key() {
+        assert_eq!(
+            Ok(Command::CreateTable {
+                database_name: None,
+                table_name: "users".to_string(),
+                fill_factor: None,
+                column_definitions: vec![
+                    ColumnDeclaration {
+                        name: "id".to_string(),
+                        data_type: DataTypeIdentifier::Integer,
+                        not_null: false,
+                        primary_key: true
+                    },
+                    ColumnDeclaration {
+                        name: "age".to_string(),
+                        data_type: DataTypeIdentifier::Integer,
+                        not_null: true,
+                        primary_key: false
+                    }
+                ]
+            }),
+            parse("CREATE TABLE users (id integer PRIMARY KEY, age integer NOT NULL);"),
+        );
+
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::Comma
+            }),
+            parse("CREATE TABLE users (id integer PRIMARY, age integer);"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_insert_into_expression() {
+        assert_eq!(
+            Ok(Command::InsertInto {
+                database_name: None,
+                table_name: "users2".to_string(),
+                column_names: None,
+                values: vec![vec![Some(LiteralValue::Integer(12))]],
+                on_conflict: None,
+                returning: None,
+            }),
+            parse("INSERT INTO users2 VALUES (12);"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_insert_into_expression_with_column_names() {
+        assert_eq!(
+            Ok(Command::InsertInto {
+                database_name: None,
+                table_name: "users".to_string(),
+                column_names: Some(vec!["age".to_string(), "id".to_string()]),
+                values: vec![vec![
+                    Some(LiteralValue::Integer(30)),
+                    Some(LiteralValue::Integer(1))
+                ]],
+                on_conflict: None,
+                returning: None,
+            }),
+            parse("INSERT INTO users (age, id) VALUES (30, 1);"),
+        );
+
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::ClosingParenthesis
+            }),
+            parse("INSERT INTO users () VALUES (30);"),
+        );
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::Identifier("id".to_string())
+            }),
+            parse("INSERT INTO users (age id) VALUES (30, 1);"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_insert_into_expression_with_multiple_values() {
+        assert_eq!(
+            Ok(Command::InsertInto {
+                database_name: None,
+                table_name: "users2".to_string(),
+                column_names: None,
+                values: vec![vec![
+                    Some(LiteralValue::Integer(12)),
+                    Some(LiteralValue::Integer(14))
+                ]],
+                on_conflict: None,
+                returning: None,
+            }),
+            parse("INSERT INTO users2 VALUES (12, 14);"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_insert_into_expression_with_null_values() {
+        assert_eq!(
+            Ok(Command::InsertInto {
+                database_name: None,
+                table_name: "users".to_string(),
+                column_names: None,
+                values: vec![vec![Some(LiteralValue::Integer(12)), None]],
+                on_conflict: None,
+                returning: None,
+            }),
+            parse("INSERT INTO users VALUES (12, NULL);"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_insert_into_expression_with_multiple_rows() {
+        assert_eq!(
+            Ok(Command::InsertInto {
+                database_name: None,
+                table_name: "users".to_string(),
+                column_names: None,
+                values: vec![
+                    vec![Some(LiteralValue::Integer(1))],
+                    vec![Some(LiteralValue::Integer(2))],
+                    vec![Some(LiteralValue::Integer(3))]
+                ],
+                on_conflict: None,
+                returning: None,
+            }),
+            parse("INSERT INTO users VALUES (1), (2), (3);"),
+        );
+
+        assert_eq!(
+            Err(Error::MissingToken),
+            parse("INSERT INTO users VALUES (1),;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_insert_into_expression_with_returning() {
+        assert_eq!(
+            Ok(Command::InsertInto {
+                database_name: None,
+                table_name: "users".to_string(),
+                column_names: None,
+                values: vec![vec![Some(LiteralValue::Integer(1))]],
+                on_conflict: None,
+                returning: Some(vec![
+                    Expression::Wildcard,
+                    Expression::Identifier("id".to_string())
+                ]),
+            }),
+            parse("INSERT INTO users VALUES (1) RETURNING *, id;"),
+        );
+
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::Identifier("id".to_string())
+            }),
+            parse("INSERT INTO users VALUES (1) id;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_insert_into_expression_with_on_conflict() {
+        let Ok(Command::InsertInto { on_conflict, .. }) =
+            parse("INSERT INTO users VALUES (1, 2) ON CONFLICT (id) DO NOTHING;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            Some(OnConflict {
+                columns: vec!["id".to_string()],
+                action: ConflictAction::DoNothing
+            }),
+            on_conflict
+        );
+
+        let Ok(Command::InsertInto {
+            on_conflict,
+            returning,
+            ..
+        }) = parse(
+            "INSERT INTO users VALUES (1, 2) ON CONFLICT (id, age) DO UPDATE SET age = excluded.age, id = 3 RETURNING *;",
+        )
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            Some(OnConflict {
+                columns: vec!["id".to_string(), "age".to_string()],
+                action: ConflictAction::DoUpdate(vec![
+                    (
+                        "age".to_string(),
+                        Expression::Identifier("excluded.age".to_string())
+                    ),
+                    (
+                        "id".to_string(),
+                        Expression::Literal(LiteralValue::Integer(3))
+                    ),
+                ])
+            }),
+            on_conflict
+        );
+        assert_eq!(Some(vec![Expression::Wildcard]), returning);
+
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::SelectKeyword
+            }),
+            parse("INSERT INTO users VALUES (1) ON CONFLICT (id) DO SELECT;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_select_all() {
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![Expression::Wildcard],
+                database_name: None,
+                table_name: Some("my_table".to_string()),
+                table_sample: None,
+                where_clause: None,
+            }),
+            parse("SELECT * FROM my_table;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_select_without_from() {
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![Expression::Arithmetic {
+                    lhs: Box::new(Expression::Literal(LiteralValue::Integer(1))),
+                    operation: ArithmeticOperation::Addition,
+                    rhs: Box::new(Expression::Literal(LiteralValue::Integer(2))),
+                }],
+                database_name: None,
+                table_name: None,
+                table_sample: None,
+                where_clause: None,
+            }),
+            parse("SELECT 1 + 2;")
+        );
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![Expression::Literal(LiteralValue::Integer(1))],
+                database_name: None,
+                table_name: None,
+                table_sample: None,
+                where_clause: Some(Expression::Literal(LiteralValue::Boolean(false))),
+            }),
+            parse("SELECT 1 WHERE FALSE;")
+        );
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::TablesampleKeyword
+            }),
+            parse("SELECT 1 TABLESAMPLE SYSTEM (10);")
+        );
+    }
+
+    #[test]
+    fn test_parsing_distinct_function_arguments() {
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![Expression::Function {
+                    name: "COUNT".to_string(),
+                    arguments: vec![Expression::Distinct(Box::new(Expression::Identifier(
+                        "user_id".to_string()
+                    )))],
+                }],
+                database_name: None,
+                table_name: Some("events".to_string()),
+                table_sample: None,
+                where_clause: None,
+            }),
+            parse("SELECT COUNT(DISTINCT user_id) FROM events;")
+        );
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::DistinctKeyword
+            }),
+            parse("SELECT COUNT(user_id, DISTINCT age) FROM events;")
+        );
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::DistinctKeyword
+            }),
+            parse("SELECT DISTINCT user_id FROM events;")
+        );
+    }
+
+    #[test]
+    fn test_parsing_select_with_scalar_subquery() {
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![
+                    Expression::Identifier("name".to_string()),
+                    Expression::Subquery(Box::new(Command::Select {
+                        expressions: vec![Expression::Function {
+                            name: "MAX".to_string(),
+                            arguments: vec![Expression::Identifier("id".to_string())],
+                        }],
+                        database_name: None,
+                        table_name: Some("users".to_string()),
+                        table_sample: None,
+                        where_clause: None,
+                    })),
+                ],
+                database_name: None,
+                table_name: Some("my_table".to_string()),
+                table_sample: None,
+                where_clause: None,
+            }),
+            parse("SELECT name, (SELECT MAX(id) FROM users) FROM my_table;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_unterminated_subquery() {
+        assert_eq!(
+            Err(Error::MissingToken),
+            parse("SELECT (SELECT MAX(id) FROM users FROM my_table;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_select_with_where_condition() {
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![Expression::Wildcard],
+                database_name: None,
+                table_name: Some("my_table".to_string()),
+                table_sample: None,
+                where_clause: Some(Expression::Comparison {
+                    lhs: Box::new(Expression::Identifier("favorite_number".to_string())),
+                    operation: CompareOperation::Equality,
+                    rhs: Box::new(Expression::Literal(LiteralValue::Integer(42))),
+                }),
+            }),
+            parse("SELECT * FROM my_table WHERE favorite_number = 42;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_select_with_trailing_tokens() {
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::Identifier("favorite_number".to_string())
+            }),
+            parse("SELECT * FROM my_table favorite_number = 42;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_condition_expression() {
+        let mut tokens = vec![
+            Token::Identifier("user_id".to_string()),
+            Token::EqualSign,
+            Token::NumericLiteral(3.to_string()),
+        ]
+        .into_iter()
+        .peekable();
+
+        assert_eq!(
+            Ok(Expression::Comparison {
+                lhs: Box::new(Expression::Identifier("user_id".to_string())),
+                operation: CompareOperation::Equality,
+                rhs: Box::new(Expression::Literal(LiteralValue::Integer(3))),
+            }),
+            parse_expression(&mut tokens)
+        );
+    }
+
+    #[test]
+    fn test_parsing_comparison_operators() {
+        let Ok(Command::Select {
+            where_clause: Some(Expression::Logical { lhs, rhs, .. }),
+            ..
+        }) = parse("SELECT * FROM t WHERE a >= 1 AND a <> 5;")
+        else {
+            panic!("Did not get the expected result");
+        };
+
+        assert_eq!(
+            Expression::Comparison {
+                lhs: Box::new(Expression::Identifier("a".to_string())),
+                operation: CompareOperation::GreaterThanOrEqual,
+                rhs: Box::new(Expression::Literal(LiteralValue::Integer(1))),
+            },
+            *lhs
+        );
+        assert_eq!(
+            Expression::Comparison {
+                lhs: Box::new(Expression::Identifier("a".to_string())),
+                operation: CompareOperation::Inequality,
+                rhs: Box::new(Expression::Literal(LiteralValue::Integer(5))),
+            },
+            *rhs
+        );
+    }
+
+    #[test]
+    fn test_parsing_like_expressions() {
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![Expression::Wildcard],
+                database_name: None,
+                table_name: Some("users".to_string()),
+                table_sample: None,
+                where_clause: Some(Expression::Not(Box::new(Expression::Like {
+                    expression: Box::new(Expression::Identifier("name".to_string())),
+                    pattern: Box::new(Expression::Literal(LiteralValue::Text("Jo%".to_string()))),
+                    negated: true,
+                }))),
+            }),
+            parse("SELECT * FROM users WHERE NOT name NOT LIKE 'Jo%';"),
+        );
+
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::NullKeyword
+            }),
+            parse("SELECT * FROM users WHERE name NOT NULL;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_cursor_commands() {
+        assert_eq!(
+            Ok(Command::DeclareCursor {
+                cursor_name: "c".to_string(),
+                query: Box::new(Command::Select {
+                    expressions: vec![Expression::Wildcard],
+                    database_name: None,
+                    table_name: Some("users".to_string()),
+                    table_sample: None,
+                    where_clause: None,
+                }),
+            }),
+            parse("DECLARE c CURSOR FOR SELECT * FROM users;"),
+        );
+
+        assert_eq!(
+            Ok(Command::Fetch {
+                cursor_name: "c".to_string(),
+                count: 1000,
+            }),
+            parse("FETCH 1000 FROM c;"),
+        );
+        assert_eq!(
+            Ok(Command::Fetch {
+                cursor_name: "c".to_string(),
+                count: 10,
+            }),
+            parse("FETCH 10 c;"),
+        );
+        assert_eq!(
+            Ok(Command::CloseCursor {
+                cursor_name: "c".to_string(),
+            }),
+            parse("CLOSE c;"),
+        );
+
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::InsertKeyword
+            }),
+            parse("DECLARE c CURSOR FOR INSERT INTO users VALUES (1);"),
+        );
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::Identifier("c".to_string())
+            }),
+            parse("FETCH c;"),
+        );
+    }
+
+    #[test]
+    fn test_splitting_statements() {
+        assert_eq!(
+            vec!["BEGIN;", "SELECT 'a;b' FROM t;", "COMMIT"],
+            split_statements("BEGIN; ;SELECT 'a;b' FROM t;\nCOMMIT")
+        );
+        assert_eq!(
+            vec!["SELECT 'it''s;' FROM t;"],
+            split_statements("SELECT 'it''s;' FROM t;")
+        );
+        assert!(split_statements(" ;; ").is_empty());
+    }
+
+    #[test]
+    fn test_parsing_multiple_statements() {
+        assert_eq!(
+            Ok(vec![Command::Begin, Command::Commit]),
+            parse_statements("BEGIN; COMMIT;")
+        );
+        assert_eq!(Ok(vec![]), parse_statements(""));
+        assert_eq!(
+            Err(Error::MissingToken),
+            parse_statements("BEGIN; SAVEPOINT;")
+        );
+    }
+
+    #[test]
+    fn test_parsing_with_parameters() {
+        assert_eq!(
+            Ok(Command::InsertInto {
+                database_name: None,
+                table_name: "t".to_string(),
+                column_names: None,
+                values: vec![vec![
+                    Some(LiteralValue::Integer(1)),
+                    Some(LiteralValue::Integer(1)),
+                    Some(LiteralValue::Text("'); CREATE DATABASE x; --".to_string())),
+                ]],
+                returning: None,
+                on_conflict: None,
+            }),
+            parse_with_parameters(
+                "INSERT INTO t VALUES ($1, ?, ?);",
+                &[
+                    LiteralValue::Integer(1),
+                    LiteralValue::Text("'); CREATE DATABASE x; --".to_string()),
+                ]
+            )
+        );
+
+        assert_eq!(
+            Err(Error::MissingParameter(2)),
+            parse_with_parameters("SELECT * FROM t WHERE a = $2;", &[LiteralValue::Integer(1)])
+        );
+        assert_eq!(
+            Err(Error::MissingParameter(0)),
+            parse_with_parameters("SELECT * FROM t WHERE a = $0;", &[LiteralValue::Integer(1)])
+        );
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::Parameter(1)
+            }),
+            parse("SELECT * FROM t WHERE a = $1;")
+        );
+
+        assert_eq!(0, parameter_count("SELECT '$1?' FROM t;"));
+        assert_eq!(
+            2,
+            parameter_count("INSERT INTO t VALUES (?, ?); SELECT * FROM t WHERE a = $1;")
+        );
+    }
+
+    #[test]
+    fn test_parsing_use_command() {
+        assert_eq!(
+            Ok(Command::Use {
+                database_name: "mydb".to_string()
+            }),
+            parse("USE mydb;")
+        );
+        assert_eq!(Err(Error::MissingToken), parse("USE;"));
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::Identifier("other".to_string())
+            }),
+            parse("USE mydb other;")
+        );
+    }
+
+    #[test]
+    fn test_parsing_show_commands() {
+        assert_eq!(Ok(Command::ShowDatabases), parse("SHOW DATABASES;"));
+        assert_eq!(
+            Ok(Command::ShowTables {
+                database_name: None
+            }),
+            parse("SHOW tables;")
+        );
+        assert_eq!(
+            Ok(Command::ShowTables {
+                database_name: Some("db".to_string())
+            }),
+            parse("SHOW TABLES FROM db;")
+        );
+        assert_eq!(Err(Error::MissingToken), parse("SHOW;"));
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::Identifier("users".to_string())
+            }),
+            parse("SHOW users;")
+        );
+    }
+
+    #[test]
+    fn test_parsing_copy_command() {
+        assert_eq!(
+            Ok(Command::Copy {
+                database_name: None,
+                table_name: "users".to_string(),
+                column_names: None,
+                filename: "users.csv".to_string(),
+                header: false,
+            }),
+            parse("COPY users FROM 'users.csv' (FORMAT csv);")
+        );
+        assert_eq!(
+            Ok(Command::Copy {
+                database_name: Some("db".to_string()),
+                table_name: "users".to_string(),
+                column_names: Some(vec!["id".to_string(), "age".to_string()]),
+                filename: "users.csv".to_string(),
+                header: true,
+            }),
+            parse("COPY db.users (id, age) FROM 'users.csv' (FORMAT CSV, HEADER);")
+        );
+        assert_eq!(
+            Ok(Command::Copy {
+                database_name: None,
+                table_name: "users".to_string(),
+                column_names: None,
+                filename: "users.csv".to_string(),
+                header: false,
+            }),
+            parse("COPY users FROM 'users.csv' (HEADER FALSE);")
+        );
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::Identifier("users".to_string())
+            }),
+            parse("COPY users FROM users;")
+        );
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::Identifier("binary".to_string())
+            }),
+            parse("COPY users FROM 'users.csv' (FORMAT binary);")
+        );
+    }
+
+    #[test]
+    fn test_parsing_transaction_commands() {
+        assert_eq!(Ok(Command::Begin), parse("BEGIN;"));
+        assert_eq!(Ok(Command::Begin), parse("BEGIN TRANSACTION;"));
+        assert_eq!(Ok(Command::Commit), parse("COMMIT;"));
+        assert_eq!(Ok(Command::Rollback), parse("ROLLBACK TRANSACTION;"));
+
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::Identifier("work".to_string())
+            }),
+            parse("COMMIT work;"),
+        );
+
+        let name = "loaded".to_string();
+        assert_eq!(
+            Ok(Command::Savepoint { name: name.clone() }),
+            parse("SAVEPOINT loaded;")
+        );
+        assert_eq!(
+            Ok(Command::RollbackToSavepoint { name: name.clone() }),
+            parse("ROLLBACK TO loaded;")
+        );
+        assert_eq!(
+            Ok(Command::RollbackToSavepoint { name: name.clone() }),
+            parse("ROLLBACK TRANSACTION TO SAVEPOINT loaded;")
+        );
+        assert_eq!(
+            Ok(Command::ReleaseSavepoint { name: name.clone() }),
+            parse("RELEASE SAVEPOINT loaded;")
+        );
+        assert_eq!(
+            Ok(Command::ReleaseSavepoint { name }),
+            parse("RELEASE loaded;")
+        );
+        assert_eq!(Err(Error::MissingToken), parse("SAVEPOINT;"));
+    }
+
+    #[test]
+    fn test_parsing_explain() {
+        assert_eq!(
+            Ok(Command::Explain {
+                query: Box::new(Command::Select {
+                    expressions: vec![Expression::Wildcard],
+                    database_name: None,
+                    table_name: Some("users".to_string()),
+                    table_sample: None,
+                    where_clause: None,
+                }),
+            }),
+            parse("EXPLAIN SELECT * FROM users;"),
+        );
+
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::ExplainKeyword
+            }),
+            parse("EXPLAIN EXPLAIN SELECT * FROM users;"),
+        );
+        assert_eq!(Err(Error::MissingToken), parse("EXPLAIN;"));
+    }
+
+    #[test]
+    fn test_parsing_table_samples() {
+        let Ok(Command::Select { table_sample, .. }) =
+            parse("SELECT * FROM t TABLESAMPLE SYSTEM (10) WHERE x = 1;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            Some(TableSample {
+                percentage: 10,
+                seed: None
+            }),
+            table_sample
+        );
+
+        let Ok(Command::Select { table_sample, .. }) =
+            parse("SELECT * FROM t TABLESAMPLE SYSTEM (50) REPEATABLE (1234);")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            Some(TableSample {
+                percentage: 50,
+                seed: Some(1234)
+            }),
+            table_sample
+        );
+
+        assert_eq!(
+            Err(Error::SamplePercentageOutOfRange(101)),
+            parse("SELECT * FROM t TABLESAMPLE SYSTEM (101);"),
+        );
+        assert_eq!(
+            Err(Error::SamplePercentageOutOfRange(150)),
+            parse("SELECT * FROM t TABLESAMPLE SYSTEM (150);"),
+        );
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::OpeningParenthesis
+            }),
+            parse("SELECT * FROM t TABLESAMPLE (10);"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_qualified_table_names() {
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![Expression::Wildcard],
+                database_name: Some("db".to_string()),
+                table_name: Some("users".to_string()),
+                table_sample: None,
+                where_clause: None,
+            }),
+            parse("SELECT * FROM db.users;"),
+        );
+
+        assert_eq!(
+            Ok(Command::InsertInto {
+                database_name: Some("db".to_string()),
+                table_name: "users".to_string(),
+                column_names: None,
+                values: vec![vec![Some(LiteralValue::Integer(1))]],
+                on_conflict: None,
+                returning: None,
+            }),
+            parse("INSERT INTO db.users VALUES (1);"),
+        );
+
+        assert_eq!(
+            Ok(Command::CreateTable {
+                database_name: Some("db".to_string()),
+                table_name: "users".to_string(),
+                fill_factor: None,
+                column_definitions: vec![ColumnDeclaration {
+                    name: "id".to_string(),
+                    data_type: DataTypeIdentifier::Integer,
+                    not_null: false,
+                    primary_key: false
+                }],
+            }),
+            parse("CREATE TABLE db.users (id integer);"),
+        );
+
+        assert_eq!(Err(Error::MissingToken), parse("SELECT * FROM db.;"));
+    }
+
+    #[test]
+    fn test_parsing_between() {
+        let integer = |value: i64| Box::new(Expression::Literal(LiteralValue::Integer(value)));
+
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![Expression::Wildcard],
+                database_name: None,
+                table_name: Some("t".to_string()),
+                table_sample: None,
+                where_clause: Some(Expression::Logical {
+                    lhs: Box::new(Expression::Between {
+                        expression: Box::new(Expression::Identifier("age".to_string())),
+                        low: integer(18),
+                        high: Box::new(Expression::Arithmetic {
+                            lhs: integer(20),
+                            operation: ArithmeticOperation::Addition,
+                            rhs: integer(10),
+                        }),
+                        negated: true,
+                    }),
+                    operation: LogicalOperation::And,
+                    rhs: Box::new(Expression::Literal(LiteralValue::Boolean(true))),
+                }),
+            }),
+            parse("SELECT * FROM t WHERE age NOT BETWEEN 18 AND 20 + 10 AND TRUE;"),
+        );
+
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::OrKeyword
+            }),
+            parse("SELECT * FROM t WHERE age BETWEEN 18 OR 30;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_in_lists() {
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![Expression::Wildcard],
+                database_name: None,
+                table_name: Some("t".to_string()),
+                table_sample: None,
+                where_clause: Some(Expression::InList {
+                    expression: Box::new(Expression::Identifier("status".to_string())),
+                    list: vec![
+                        Expression::Literal(LiteralValue::Integer(1)),
+                        Expression::Literal(LiteralValue::Integer(2)),
+                        Expression::Null,
+                    ],
+                    negated: true,
+                }),
+            }),
+            parse("SELECT * FROM t WHERE status NOT IN (1, 2, NULL);"),
+        );
+
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::ClosingParenthesis
+            }),
+            parse("SELECT * FROM t WHERE status IN ();"),
+        );
+        assert_eq!(
+            Err(Error::MissingToken),
+            parse("SELECT * FROM t WHERE status IN (1, 2")
+        );
+    }
+
+    #[test]
+    fn test_parsing_boolean_expressions() {
+        let equals = |name: &str, value: i64| Expression::Comparison {
+            lhs: Box::new(Expression::Identifier(name.to_string())),
+            operation: CompareOperation::Equality,
+            rhs: Box::new(Expression::Literal(LiteralValue::Integer(value))),
+        };
+
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![Expression::Wildcard],
+                database_name: None,
+                table_name: Some("t".to_string()),
+                table_sample: None,
+                where_clause: Some(Expression::Logical {
+                    lhs: Box::new(Expression::Logical {
+                        lhs: Box::new(equals("a", 1)),
+                        operation: LogicalOperation::And,
+                        rhs: Box::new(Expression::Logical {
+                            lhs: Box::new(equals("b", 2)),
+                            operation: LogicalOperation::Or,
+                            rhs: Box::new(Expression::Not(Box::new(equals("c", 3)))),
+                        }),
+                    }),
+                    operation: LogicalOperation::Or,
+                    rhs: Box::new(equals("d", 4)),
+                }),
+            }),
+            parse("SELECT * FROM t WHERE a = 1 AND (b = 2 OR NOT c = 3) OR d = 4;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_arithmetic_expressions() {
+        let identifier = |name: &str| Box::new(Expression::Identifier(name.to_string()));
+
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![
+                    Expression::Arithmetic {
+                        lhs: identifier("age"),
+                        operation: ArithmeticOperation::Addition,
+                        rhs: Box::new(Expression::Literal(LiteralValue::Integer(1))),
+                    },
+                    Expression::Arithmetic {
+                        lhs: identifier("a"),
+                        operation: ArithmeticOperation::Subtraction,
+                        rhs: Box::new(Expression::Arithmetic {
+                            lhs: identifier("price"),
+                            operation: ArithmeticOperation::Multiplication,
+                            rhs: identifier("quantity"),
+                        }),
+                    },
+                    Expression::Arithmetic {
+                        lhs: Box::new(Expression::Arithmetic {
+                            lhs: identifier("a"),
+                            operation: ArithmeticOperation::Subtraction,
+                            rhs: identifier("b"),
+                        }),
+                        operation: ArithmeticOperation::Division,
+                        rhs: identifier("c"),
+                    },
+                ],
+                database_name: None,
+                table_name: Some("t".to_string()),
+                table_sample: None,
+                where_clause: None,
+            }),
+            parse("SELECT age + 1, a - price * quantity, (a - b) / c FROM t;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_negations() {
+        let negation = |expression: Expression| Expression::Negation(Box::new(expression));
+        let integer = |value: i64| Expression::Literal(LiteralValue::Integer(value));
+
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![
+                    integer(-5),
+                    Expression::Arithmetic {
+                        lhs: Box::new(integer(1)),
+                        operation: ArithmeticOperation::Subtraction,
+                        rhs: Box::new(negation(integer(-2))),
+                    },
+                    negation(Expression::Cast {
+                        expression: Box::new(Expression::Identifier("a".to_string())),
+                        data_type: CastType::Integer,
+                    }),
+                    negation(Expression::Cast {
+                        expression: Box::new(integer(5)),
+                        data_type: CastType::Text,
+                    }),
+                    integer(i64::MIN),
+                    Expression::Literal(LiteralValue::Decimal(
+                        crate::Decimal::parse("-1.5").unwrap()
+                    )),
+                ],
+                database_name: None,
+                table_name: Some("t".to_string()),
+                table_sample: None,
+                where_clause: None,
+            }),
+            parse("SELECT -5, 1 - - -2, -a::integer, -5::text, -9223372036854775808, -1.5 FROM t;"),
+        );
+        assert_eq!(
+            Err(Error::IntegerOutOfRange),
+            parse("SELECT -9223372036854775809;")
+        );
+
+        assert_eq!(
+            Ok(Command::InsertInto {
+                database_name: None,
+                table_name: "t".to_string(),
+                column_names: None,
+                values: vec![vec![
+                    Some(LiteralValue::Integer(-5)),
+                    Some(LiteralValue::Integer(i64::MIN))
+                ]],
+                on_conflict: None,
+                returning: None,
+            }),
+            parse("INSERT INTO t VALUES (-5, -9223372036854775808);")
+        );
+        assert_eq!(
+            Err(Error::IntegerOutOfRange),
+            parse("INSERT INTO t VALUES (9223372036854775808);")
+        );
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::TrueKeyword
+            }),
+            parse("INSERT INTO t VALUES (-TRUE);")
+        );
+    }
+
+    #[test]
+    fn test_parsing_cast_expressions() {
+        let cast = |expression: Expression, data_type| Expression::Cast {
+            expression: Box::new(expression),
+            data_type,
+        };
+
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![
+                    cast(Expression::Identifier("age".to_string()), CastType::Text),
+                    Expression::Arithmetic {
+                        lhs: Box::new(Expression::Literal(LiteralValue::Integer(1))),
+                        operation: ArithmeticOperation::Addition,
+                        rhs: Box::new(cast(
+                            cast(
+                                Expression::Literal(LiteralValue::Text("2".to_string())),
+                                CastType::Integer
+                            ),
+                            CastType::Boolean
+                        )),
+                    },
+                ],
+                database_name: None,
+                table_name: Some("t".to_string()),
+                table_sample: None,
+                where_clause: None,
+            }),
+            parse("SELECT CAST(age AS text), 1 + '2'::integer::boolean FROM t;"),
+        );
+
+        assert_eq!(
+            Err(Error::TypeDoesNotExist("float".to_string())),
+            parse("SELECT age::float FROM t;"),
+        );
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::Comma
+            }),
+            parse("SELECT CAST(age, text) FROM t;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_json_accessors() {
+        let json_extract = |json: Expression, key: Expression| Expression::Function {
+            name: "JSON_EXTRACT".to_string(),
+            arguments: vec![json, key],
+        };
+
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![json_extract(
+                    json_extract(
+                        Expression::Identifier("doc".to_string()),
+                        Expression::Literal(LiteralValue::Text("tags".to_string())),
+                    ),
+                    Expression::Arithmetic {
+                        lhs: Box::new(Expression::Literal(LiteralValue::Integer(1))),
+                        operation: ArithmeticOperation::Subtraction,
+                        rhs: Box::new(Expression::Literal(LiteralValue::Integer(1))),
+                    },
+                )],
+                database_name: None,
+                table_name: Some("t".to_string()),
+                table_sample: None,
+                where_clause: Some(Expression::Comparison {
+                    lhs: Box::new(json_extract(
+                        Expression::Identifier("doc".to_string()),
+                        Expression::Literal(LiteralValue::Text("kind".to_string())),
+                    )),
+                    operation: CompareOperation::Equality,
+                    rhs: Box::new(Expression::Literal(LiteralValue::Text(
+                        "\"click\"".to_string()
+                    ))),
+                }),
+            }),
+            parse("SELECT doc->'tags' -> 1 - 1 FROM t WHERE doc -> 'kind' = '\"click\"';"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_case_expression() {
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![Expression::Case {
+                    operand: None,
+                    branches: vec![(
+                        Expression::Comparison {
+                            lhs: Box::new(Expression::Identifier("age".to_string())),
+                            operation: CompareOperation::Equality,
+                            rhs: Box::new(Expression::Literal(LiteralValue::Integer(18))),
+                        },
+                        Expression::Literal(LiteralValue::Integer(1)),
+                    )],
+                    else_result: Some(Box::new(Expression::Literal(LiteralValue::Integer(0)))),
+                }],
+                database_name: None,
+                table_name: Some("users".to_string()),
+                table_sample: None,
+                where_clause: None,
+            }),
+            parse("SELECT CASE WHEN age = 18 THEN 1 ELSE 0 END FROM users;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_case_expression_with_operand() {
+        assert_eq!(
+            Ok(Command::Select {
+                expressions: vec![Expression::Case {
+                    operand: Some(Box::new(Expression::Identifier("age".to_string()))),
+                    branches: vec![
+                        (
+                            Expression::Literal(LiteralValue::Integer(18)),
+                            Expression::Literal(LiteralValue::Integer(1)),
+                        ),
+                        (
+                            Expression::Literal(LiteralValue::Integer(19)),
+                            Expression::Literal(LiteralValue::Integer(2)),
+                        ),
+                    ],
+                    else_result: None,
+                }],
+                database_name: None,
+                table_name: Some("users".to_string()),
+                table_sample: None,
+                where_clause: None,
+            }),
+            parse("SELECT CASE age WHEN 18 THEN 1 WHEN 19 THEN 2 END FROM users;"),
+        );
+    }
+
+    #[test]
+    fn test_parsing_case_expression_without_branches() {
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::EndKeyword
+            }),
+            parse("SELECT CASE END FROM users;"),
+        );
+    }
+}
@@ -0,0 +1,57 @@
+use alloc::string::{String, ToString};
+
+use alloc::vec::Vec;
+
+use super::tokenizer::Token;
+use super::{hex, Decimal, Uuid};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LiteralValue {
+    Integer(i64),
+    Boolean(bool),
+    Text(String),
+    Decimal(Decimal),
+    Bytes(Vec<u8>),
+    /// A UUID from a subquery, since there's no token for `uuid` values.
+    Uuid(Uuid),
+    /// JSON text from a subquery.
+    Json(String),
+}
+
+impl LiteralValue {
+    /// Returns the token that the literal is read from.
+    pub fn to_token(&self) -> Token {
+        match self {
+            LiteralValue::Integer(value) => Token::NumericLiteral(value.to_string()),
+            LiteralValue::Boolean(true) => Token::TrueKeyword,
+            LiteralValue::Boolean(false) => Token::FalseKeyword,
+            LiteralValue::Text(value) => Token::StringLiteral(value.clone()),
+            LiteralValue::Decimal(value) => Token::NumericLiteral(value.to_string()),
+            LiteralValue::Bytes(value) => Token::HexStringLiteral(hex::encode(value)),
+            LiteralValue::Uuid(value) => Token::StringLiteral(value.to_string()),
+            LiteralValue::Json(value) => Token::StringLiteral(value.clone()),
+        }
+    }
+}
+
+impl From<Token> for Option<LiteralValue> {
+    fn from(value: Token) -> Self {
+        match value {
+            Token::NumericLiteral(literal) => {
+                if let Ok(value) = literal.parse::<i64>() {
+                    Some(LiteralValue::Integer(value))
+                } else if literal.contains('.') {
+                    Decimal::parse(&literal).map(LiteralValue::Decimal)
+                } else {
+                    None
+                }
+            }
+            Token::TrueKeyword => Some(LiteralValue::Boolean(true)),
+            Token::FalseKeyword => Some(LiteralValue::Boolean(false)),
+            Token::StringLiteral(string) => Some(LiteralValue::Text(string)),
+            Token::HexStringLiteral(digits) => hex::decode(&digits).map(LiteralValue::Bytes),
+
+            _ => None,
+        }
+    }
+}
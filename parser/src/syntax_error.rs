@@ -0,0 +1,199 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+use super::tokenizer::{tokenize_with_offsets, Token, KEYWORDS};
+use super::{parse_tokens, Error};
+
+/// Listing more alternatives than this doesn't help anyone fixing a typo.
+const MAX_EXPECTED_TOKENS: usize = 8;
+
+/// Where a statement stops being valid SQL, and what would have been valid there instead.
+#[derive(Debug, PartialEq)]
+pub struct SyntaxErrorLocation {
+    /// The byte offset of the unexpected token, or the end of the statement if a token is missing.
+    pub offset: usize,
+    /// The line and column of `offset`, both starting at 1. Columns count characters.
+    pub line: usize,
+    pub column: usize,
+
+    /// The tokens that would have been accepted at `offset`, as they'd be written in SQL.
+    pub expected: Vec<String>,
+    /// The unexpected token as it's written in SQL, or `None` at the end of the statement.
+    pub found: Option<String>,
+}
+
+impl SyntaxErrorLocation {
+    /// Describes the error as e.g. `expected RETURNING, ON or ",", found "x"`, or only as
+    /// `unexpected "x"` when too many tokens would have been accepted.
+    pub fn message(&self) -> String {
+        let found = match &self.found {
+            Some(found) => format!("\"{}\"", found),
+            None => "end of input".to_string(),
+        };
+
+        match &self.expected[..] {
+            [expected] => format!("expected {}, found {}", expected, found),
+            [expected @ .., last] if expected.len() < MAX_EXPECTED_TOKENS => {
+                format!(
+                    "expected {} or {}, found {}",
+                    expected.join(", "),
+                    last,
+                    found
+                )
+            }
+            _ => format!("unexpected {}", found),
+        }
+    }
+}
+
+/// Finds the token of `statement` that `parse` fails on with `UnexpectedToken` or `MissingToken`.
+/// Returns `None` if the statement parses or fails with any other error.
+pub fn locate_syntax_error(statement: &str) -> Option<SyntaxErrorLocation> {
+    let (tokens, offsets): (Vec<Token>, Vec<usize>) = tokenize_with_offsets(statement)
+        .into_iter()
+        .take_while(|(token, _offset)| *token != Token::Semicolon)
+        .unzip();
+
+    let (index, found) = match parse_tokens(tokens.clone()) {
+        Err(Error::MissingToken) => (tokens.len(), None),
+
+        // The parser reads from left to right, so the shortest prefix that fails the same way ends
+        // with the unexpected token.
+        Err(Error::UnexpectedToken { actual }) => {
+            let index = (0..tokens.len()).find(|index| {
+                tokens[*index] == actual
+                    && parse_tokens(tokens[..=*index].to_vec())
+                        == Err(Error::UnexpectedToken {
+                            actual: actual.clone(),
+                        })
+            })?;
+            (index, Some(actual.to_sql()))
+        }
+
+        _ => return None,
+    };
+
+    let offset = match offsets.get(index) {
+        Some(offset) => *offset,
+        None => statement.trim_end().trim_end_matches(';').trim_end().len(),
+    };
+    let before = &statement[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+
+    Some(SyntaxErrorLocation {
+        offset,
+        line,
+        column,
+        expected: expected_tokens(&tokens[..index]),
+        found,
+    })
+}
+
+/// Returns the kinds of tokens that the parser accepts after `prefix`.
+fn expected_tokens(prefix: &[Token]) -> Vec<String> {
+    let candidates = KEYWORDS
+        .iter()
+        .map(|(_keyword, token)| (token.clone(), token.to_sql()))
+        .chain(
+            [
+                Token::OpeningParenthesis,
+                Token::ClosingParenthesis,
+                Token::Comma,
+                Token::Dot,
+                Token::Asterisk,
+                Token::EqualSign,
+                Token::NotEqualSign,
+                Token::LessThanSign,
+                Token::LessThanOrEqualSign,
+                Token::GreaterThanSign,
+                Token::GreaterThanOrEqualSign,
+                Token::PlusSign,
+                Token::MinusSign,
+                Token::Slash,
+                Token::DoubleColon,
+                Token::Arrow,
+            ]
+            .into_iter()
+            .map(|token| {
+                let sql = format!("\"{}\"", token.to_sql());
+                (token, sql)
+            }),
+        )
+        .chain([
+            (Token::Identifier("x".to_string()), "identifier".to_string()),
+            (Token::NumericLiteral("1".to_string()), "number".to_string()),
+            (Token::StringLiteral(String::new()), "string".to_string()),
+        ]);
+
+    let mut expected: Vec<String> = vec![];
+    for (candidate, sql) in candidates {
+        let mut tokens = prefix.to_vec();
+        tokens.push(candidate);
+
+        let accepted = matches!(parse_tokens(tokens), Ok(_) | Err(Error::MissingToken));
+        if accepted && !expected.contains(&sql) {
+            expected.push(sql);
+        }
+    }
+
+    expected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locating_unexpected_tokens() {
+        let location = locate_syntax_error("SELECT a, b c FROM t;").unwrap();
+        assert_eq!(12, location.offset);
+        assert_eq!((1, 13), (location.line, location.column));
+        assert_eq!(Some("c".to_string()), location.found);
+        assert!(location.expected.contains(&"\",\"".to_string()));
+        assert!(location.expected.contains(&"FROM".to_string()));
+        assert!(!location.expected.contains(&"identifier".to_string()));
+
+        // Only the second comma is unexpected.
+        let location = locate_syntax_error("SELECT a,\n  b,, c FROM t;").unwrap();
+        assert_eq!(14, location.offset);
+        assert_eq!((2, 5), (location.line, location.column));
+    }
+
+    #[test]
+    fn test_locating_missing_tokens() {
+        let location = locate_syntax_error("SELECT a FROM ;").unwrap();
+        assert_eq!(13, location.offset);
+        assert_eq!(None, location.found);
+        assert_eq!(
+            "expected identifier, found end of input",
+            location.message()
+        );
+    }
+
+    #[test]
+    fn test_describing_syntax_errors() {
+        assert_eq!(
+            "unexpected \"c\"",
+            locate_syntax_error("SELECT a, b c FROM t;")
+                .unwrap()
+                .message()
+        );
+        assert_eq!(
+            "expected RETURNING, ON or \",\", found \"x\"",
+            locate_syntax_error("INSERT INTO t VALUES (1) x;")
+                .unwrap()
+                .message()
+        );
+        assert_eq!(
+            "expected \"(\", found \"users\"",
+            locate_syntax_error("INSERT INTO t VALUES users;")
+                .unwrap()
+                .message()
+        );
+        assert_eq!(None, locate_syntax_error("SELECT a FROM t;"));
+        assert_eq!(None, locate_syntax_error("SELECT a::float FROM t;"));
+    }
+}
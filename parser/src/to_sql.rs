@@ -0,0 +1,668 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use super::hex;
+use super::tokenizer::KEYWORDS;
+use super::{
+    ArithmeticOperation, CastType, Command, CompareOperation, ConflictAction, DataTypeIdentifier,
+    Expression, LiteralValue, LogicalOperation, OnConflict, TableSample, Token,
+};
+
+impl Command {
+    /// Renders the command as canonical SQL which parses back into the same command. Keywords are
+    /// uppercase, items are separated by a single space and parentheses are only added where the
+    /// precedence of the operators requires them.
+    pub fn to_sql(&self) -> String {
+        match self {
+            Command::CreateDatabase { database_name } => {
+                format!("CREATE DATABASE {}", database_name)
+            }
+
+            Command::CreateTable {
+                database_name,
+                table_name,
+                column_definitions,
+                fill_factor,
+            } => format!(
+                "CREATE TABLE {} ({}){}",
+                qualified_name(database_name, table_name),
+                column_definitions
+                    .iter()
+                    .map(|column| format!(
+                        "{} {}{}{}",
+                        column.name,
+                        column.data_type.to_sql(),
+                        if column.not_null { " NOT NULL" } else { "" },
+                        if column.primary_key {
+                            " PRIMARY KEY"
+                        } else {
+                            ""
+                        }
+                    ))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                match fill_factor {
+                    Some(fill_factor) => format!(" WITH (fillfactor = {})", fill_factor),
+                    None => String::new(),
+                }
+            ),
+
+            Command::InsertInto {
+                database_name,
+                table_name,
+                column_names,
+                values,
+                on_conflict,
+                returning,
+            } => format!(
+                "INSERT INTO {}{} VALUES {}{}{}",
+                qualified_name(database_name, table_name),
+                match column_names {
+                    Some(column_names) => format!(" ({})", column_names.join(", ")),
+                    None => String::new(),
+                },
+                values
+                    .iter()
+                    .map(|row| format!(
+                        "({})",
+                        row.iter()
+                            .map(|value| match value {
+                                Some(value) => value.to_sql(),
+                                None => "NULL".to_string(),
+                            })
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    ))
+                    .collect::<Vec<String>>()
+                    .join(", "),
+                match on_conflict {
+                    Some(on_conflict) => on_conflict.to_sql(),
+                    None => String::new(),
+                },
+                match returning {
+                    Some(expressions) => format!(
+                        " RETURNING {}",
+                        expressions
+                            .iter()
+                            .map(Expression::to_sql)
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    ),
+                    None => String::new(),
+                }
+            ),
+
+            Command::Select {
+                expressions,
+                database_name,
+                table_name,
+                table_sample,
+                where_clause,
+            } => {
+                let mut sql = format!(
+                    "SELECT {}",
+                    expressions
+                        .iter()
+                        .map(Expression::to_sql)
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                );
+
+                if let Some(table_name) = table_name {
+                    sql.push_str(&format!(
+                        " FROM {}",
+                        qualified_name(database_name, table_name)
+                    ));
+                }
+
+                if let Some(TableSample { percentage, seed }) = table_sample {
+                    sql.push_str(&format!(" TABLESAMPLE SYSTEM ({})", percentage));
+
+                    if let Some(seed) = seed {
+                        sql.push_str(&format!(" REPEATABLE ({})", seed));
+                    }
+                }
+
+                if let Some(condition) = where_clause {
+                    sql.push_str(&format!(" WHERE {}", condition.to_sql()));
+                }
+
+                sql
+            }
+
+            Command::DeclareCursor { cursor_name, query } => {
+                format!("DECLARE {} CURSOR FOR {}", cursor_name, query.to_sql())
+            }
+            Command::Fetch { cursor_name, count } => {
+                format!("FETCH {} FROM {}", count, cursor_name)
+            }
+            Command::CloseCursor { cursor_name } => format!("CLOSE {}", cursor_name),
+
+            Command::Begin => "BEGIN".to_string(),
+            Command::Commit => "COMMIT".to_string(),
+            Command::Rollback => "ROLLBACK".to_string(),
+            Command::Savepoint { name } => format!("SAVEPOINT {}", name),
+            Command::RollbackToSavepoint { name } => format!("ROLLBACK TO SAVEPOINT {}", name),
+            Command::ReleaseSavepoint { name } => format!("RELEASE SAVEPOINT {}", name),
+
+            Command::Use { database_name } => format!("USE {}", database_name),
+
+            Command::Copy {
+                database_name,
+                table_name,
+                column_names,
+                filename,
+                header,
+            } => format!(
+                "COPY {}{} FROM {} (FORMAT csv{})",
+                qualified_name(database_name, table_name),
+                match column_names {
+                    Some(column_names) => format!(" ({})", column_names.join(", ")),
+                    None => String::new(),
+                },
+                LiteralValue::Text(filename.clone()).to_sql(),
+                if *header { ", HEADER" } else { "" }
+            ),
+
+            Command::ShowDatabases => "SHOW DATABASES".to_string(),
+            Command::ShowTables { database_name } => match database_name {
+                Some(database_name) => format!("SHOW TABLES FROM {}", database_name),
+                None => "SHOW TABLES".to_string(),
+            },
+
+            Command::Explain { query } => format!("EXPLAIN {}", query.to_sql()),
+
+            Command::CreateSequence {
+                database_name,
+                sequence_name,
+                start,
+            } => match start {
+                Some(start) => format!(
+                    "CREATE SEQUENCE {} START WITH {}",
+                    qualified_name(database_name, sequence_name),
+                    start
+                ),
+                None => format!(
+                    "CREATE SEQUENCE {}",
+                    qualified_name(database_name, sequence_name)
+                ),
+            },
+
+            Command::Vacuum {
+                database_name,
+                table_name,
+            } => match table_name {
+                Some(table_name) => format!("VACUUM {}", qualified_name(database_name, table_name)),
+                None => "VACUUM".to_string(),
+            },
+        }
+    }
+}
+
+fn qualified_name(database_name: &Option<String>, name: &str) -> String {
+    match database_name {
+        Some(database_name) => format!("{}.{}", database_name, name),
+        None => name.to_string(),
+    }
+}
+
+impl OnConflict {
+    fn to_sql(&self) -> String {
+        format!(
+            " ON CONFLICT ({}) DO {}",
+            self.columns.join(", "),
+            match &self.action {
+                ConflictAction::DoNothing => "NOTHING".to_string(),
+                ConflictAction::DoUpdate(assignments) => format!(
+                    "UPDATE SET {}",
+                    assignments
+                        .iter()
+                        .map(|(column_name, expression)| format!(
+                            "{} = {}",
+                            column_name,
+                            expression.to_sql()
+                        ))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                ),
+            }
+        )
+    }
+}
+
+impl Expression {
+    pub fn to_sql(&self) -> String {
+        match self {
+            Expression::Wildcard => "*".to_string(),
+            Expression::Null => "NULL".to_string(),
+            Expression::Identifier(name) => name.clone(),
+            Expression::Literal(value) => value.to_sql(),
+
+            Expression::Function { name, arguments } => format!(
+                "{}({})",
+                name,
+                arguments
+                    .iter()
+                    .map(Expression::to_sql)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+
+            // Comparisons can't be chained, so both sides have to bind tighter.
+            Expression::Comparison {
+                lhs,
+                operation,
+                rhs,
+            } => format!(
+                "{} {} {}",
+                lhs.to_sql_within(self.precedence() + 1),
+                operation.to_sql(),
+                rhs.to_sql_within(self.precedence() + 1)
+            ),
+
+            // The operators are left-associative, so only the right side has to bind tighter.
+            Expression::Arithmetic {
+                lhs,
+                operation,
+                rhs,
+            } => format!(
+                "{} {} {}",
+                lhs.to_sql_within(self.precedence()),
+                operation.to_sql(),
+                rhs.to_sql_within(self.precedence() + 1)
+            ),
+            Expression::Logical {
+                lhs,
+                operation,
+                rhs,
+            } => format!(
+                "{} {} {}",
+                lhs.to_sql_within(self.precedence()),
+                operation.to_sql(),
+                rhs.to_sql_within(self.precedence() + 1)
+            ),
+
+            Expression::Not(expression) => {
+                format!("NOT {}", expression.to_sql_within(self.precedence()))
+            }
+            Expression::Negation(expression) => {
+                format!("-{}", expression.to_sql_within(self.precedence()))
+            }
+            Expression::Distinct(expression) => format!("DISTINCT {}", expression.to_sql()),
+
+            Expression::Like {
+                expression,
+                pattern,
+                negated,
+            } => format!(
+                "{} {}LIKE {}",
+                expression.to_sql_within(self.precedence() + 1),
+                if *negated { "NOT " } else { "" },
+                pattern.to_sql_within(self.precedence() + 1)
+            ),
+
+            Expression::Between {
+                expression,
+                low,
+                high,
+                negated,
+            } => format!(
+                "{} {}BETWEEN {} AND {}",
+                expression.to_sql_within(self.precedence() + 1),
+                if *negated { "NOT " } else { "" },
+                low.to_sql_within(self.precedence() + 1),
+                high.to_sql_within(self.precedence() + 1)
+            ),
+
+            Expression::InList {
+                expression,
+                list,
+                negated,
+            } => format!(
+                "{} {}IN ({})",
+                expression.to_sql_within(self.precedence() + 1),
+                if *negated { "NOT " } else { "" },
+                list.iter()
+                    .map(Expression::to_sql)
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+
+            Expression::Case {
+                operand,
+                branches,
+                else_result,
+            } => {
+                let mut sql = "CASE".to_string();
+
+                if let Some(operand) = operand {
+                    sql.push_str(&format!(" {}", operand.to_sql()));
+                }
+
+                for (condition, result) in branches {
+                    sql.push_str(&format!(
+                        " WHEN {} THEN {}",
+                        condition.to_sql(),
+                        result.to_sql()
+                    ));
+                }
+
+                if let Some(else_result) = else_result {
+                    sql.push_str(&format!(" ELSE {}", else_result.to_sql()));
+                }
+
+                sql.push_str(" END");
+                sql
+            }
+
+            Expression::Cast {
+                expression,
+                data_type,
+            } => format!("CAST({} AS {})", expression.to_sql(), data_type.to_sql()),
+
+            Expression::Subquery(command) => format!("({})", command.to_sql()),
+        }
+    }
+
+    /// Renders the expression as an operand of an operator with the given `precedence`, adding
+    /// parentheses when the expression binds looser than the operator.
+    fn to_sql_within(&self, precedence: u8) -> String {
+        if self.precedence() < precedence {
+            format!("({})", self.to_sql())
+        } else {
+            self.to_sql()
+        }
+    }
+
+    /// How tightly the expression binds, following the layers of the expression parser.
+    fn precedence(&self) -> u8 {
+        match self {
+            Expression::Logical {
+                operation: LogicalOperation::Or,
+                ..
+            } => 1,
+            Expression::Logical {
+                operation: LogicalOperation::And,
+                ..
+            } => 2,
+            Expression::Not(_) => 3,
+            Expression::Comparison { .. }
+            | Expression::Like { .. }
+            | Expression::Between { .. }
+            | Expression::InList { .. } => 4,
+            Expression::Arithmetic {
+                operation: ArithmeticOperation::Addition | ArithmeticOperation::Subtraction,
+                ..
+            } => 5,
+            Expression::Arithmetic { .. } => 6,
+            Expression::Negation(_) => 7,
+
+            _ => 8,
+        }
+    }
+}
+
+impl LiteralValue {
+    pub fn to_sql(&self) -> String {
+        match self {
+            LiteralValue::Integer(value) => value.to_string(),
+            LiteralValue::Decimal(value) => value.to_string(),
+            LiteralValue::Boolean(true) => "TRUE".to_string(),
+            LiteralValue::Boolean(false) => "FALSE".to_string(),
+            LiteralValue::Text(value) => format!("'{}'", value.replace('\'', "''")),
+            LiteralValue::Bytes(value) => format!("X'{}'", hex::encode(value)),
+            LiteralValue::Uuid(value) => format!("'{}'::uuid", value),
+            LiteralValue::Json(value) => format!("'{}'::json", value.replace('\'', "''")),
+        }
+    }
+}
+
+impl Token {
+    /// Renders the token the way it's written in SQL.
+    pub fn to_sql(&self) -> String {
+        match self {
+            Token::OpeningParenthesis => "(".to_string(),
+            Token::ClosingParenthesis => ")".to_string(),
+            Token::Comma => ",".to_string(),
+            Token::Dot => ".".to_string(),
+            Token::Semicolon => ";".to_string(),
+            Token::Asterisk => "*".to_string(),
+            Token::EqualSign => "=".to_string(),
+            Token::NotEqualSign => "<>".to_string(),
+            Token::LessThanSign => "<".to_string(),
+            Token::LessThanOrEqualSign => "<=".to_string(),
+            Token::GreaterThanSign => ">".to_string(),
+            Token::GreaterThanOrEqualSign => ">=".to_string(),
+            Token::PlusSign => "+".to_string(),
+            Token::MinusSign => "-".to_string(),
+            Token::Slash => "/".to_string(),
+            Token::DoubleColon => "::".to_string(),
+            Token::Arrow => "->".to_string(),
+
+            Token::Identifier(identifier) => identifier.clone(),
+            Token::NumericLiteral(literal) => literal.clone(),
+            Token::StringLiteral(literal) => LiteralValue::Text(literal.clone()).to_sql(),
+            Token::HexStringLiteral(digits) => format!("X'{}'", digits),
+            Token::Parameter(index) => format!("${}", index),
+
+            keyword => KEYWORDS
+                .iter()
+                .find(|(_keyword, token)| token == keyword)
+                .map(|(keyword, _token)| keyword.to_string())
+                .expect("Every other token is a keyword"),
+        }
+    }
+}
+
+impl DataTypeIdentifier {
+    pub fn to_sql(&self) -> String {
+        match self {
+            DataTypeIdentifier::Integer => "integer".to_string(),
+            DataTypeIdentifier::Serial => "serial".to_string(),
+            DataTypeIdentifier::Text => "text".to_string(),
+            DataTypeIdentifier::Bytea => "bytea".to_string(),
+            DataTypeIdentifier::Uuid => "uuid".to_string(),
+            DataTypeIdentifier::Json => "json".to_string(),
+            DataTypeIdentifier::Numeric { precision, scale } => {
+                format!("numeric({}, {})", precision, scale)
+            }
+        }
+    }
+}
+
+impl CastType {
+    pub fn to_sql(self) -> &'static str {
+        match self {
+            CastType::Integer => "integer",
+            CastType::Boolean => "boolean",
+            CastType::Text => "text",
+            CastType::Numeric => "numeric",
+            CastType::Bytea => "bytea",
+            CastType::Uuid => "uuid",
+            CastType::Json => "json",
+        }
+    }
+}
+
+impl CompareOperation {
+    pub fn to_sql(self) -> &'static str {
+        match self {
+            CompareOperation::Equality => "=",
+            CompareOperation::Inequality => "<>",
+            CompareOperation::LessThan => "<",
+            CompareOperation::LessThanOrEqual => "<=",
+            CompareOperation::GreaterThan => ">",
+            CompareOperation::GreaterThanOrEqual => ">=",
+        }
+    }
+}
+
+impl LogicalOperation {
+    pub fn to_sql(self) -> &'static str {
+        match self {
+            LogicalOperation::And => "AND",
+            LogicalOperation::Or => "OR",
+        }
+    }
+}
+
+impl ArithmeticOperation {
+    pub fn to_sql(self) -> &'static str {
+        match self {
+            ArithmeticOperation::Addition => "+",
+            ArithmeticOperation::Subtraction => "-",
+            ArithmeticOperation::Multiplication => "*",
+            ArithmeticOperation::Division => "/",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::parse;
+
+    fn assert_round_trip(input: &str, canonical: &str) {
+        let command = parse(input).expect("Failed to parse the input");
+        assert_eq!(canonical, command.to_sql());
+        assert_eq!(Ok(command), parse(canonical));
+    }
+
+    #[test]
+    fn test_rendering_commands() {
+        assert_round_trip("CREATE DATABASE   db;", "CREATE DATABASE db");
+        assert_round_trip(
+            "CREATE SEQUENCE db.s START -3;",
+            "CREATE SEQUENCE db.s START WITH -3",
+        );
+        assert_round_trip(
+            "CREATE TABLE t (a integer,b integer);",
+            "CREATE TABLE t (a integer, b integer)",
+        );
+        assert_round_trip(
+            "CREATE TABLE t (a integer NOT NULL,b integer NULL);",
+            "CREATE TABLE t (a integer NOT NULL, b integer)",
+        );
+        assert_round_trip(
+            "CREATE TABLE t (a integer PRIMARY KEY,b integer);",
+            "CREATE TABLE t (a integer PRIMARY KEY, b integer)",
+        );
+        assert_round_trip(
+            "CREATE TABLE t (a serial,b integer);",
+            "CREATE TABLE t (a serial, b integer)",
+        );
+        assert_round_trip(
+            "CREATE TABLE t (a numeric(10,2),b decimal);",
+            "CREATE TABLE t (a numeric(10, 2), b numeric(38, 0))",
+        );
+        assert_round_trip(
+            "CREATE TABLE t (a integer) WITH (FILLFACTOR=70);",
+            "CREATE TABLE t (a integer) WITH (fillfactor = 70)",
+        );
+        assert_round_trip(
+            "INSERT INTO t VALUES (1,TRUE);",
+            "INSERT INTO t VALUES (1, TRUE)",
+        );
+        assert_round_trip(
+            "INSERT INTO t (a,b) VALUES (1,2);",
+            "INSERT INTO t (a, b) VALUES (1, 2)",
+        );
+        assert_round_trip(
+            "INSERT INTO t VALUES (1),(2);",
+            "INSERT INTO t VALUES (1), (2)",
+        );
+        assert_round_trip(
+            "INSERT INTO t VALUES (1) RETURNING *,a+1;",
+            "INSERT INTO t VALUES (1) RETURNING *, a + 1",
+        );
+        assert_round_trip(
+            "INSERT INTO t VALUES (1,2) ON CONFLICT (a) DO UPDATE SET b=excluded.b+b RETURNING a;",
+            "INSERT INTO t VALUES (1, 2) ON CONFLICT (a) DO UPDATE SET b = excluded.b + b RETURNING a",
+        );
+        assert_round_trip(
+            "INSERT INTO t VALUES (1) ON CONFLICT (a,b) DO NOTHING;",
+            "INSERT INTO t VALUES (1) ON CONFLICT (a, b) DO NOTHING",
+        );
+        assert_round_trip("SELECT 1+2;", "SELECT 1 + 2");
+        assert_round_trip(
+            "SELECT 1.50*a::numeric;",
+            "SELECT 1.50 * CAST(a AS numeric)",
+        );
+        assert_round_trip(
+            "SELECT COUNT( DISTINCT a+1 ) FROM t;",
+            "SELECT COUNT(DISTINCT a + 1) FROM t",
+        );
+        assert_round_trip(
+            "SELECT a, COUNT(*) FROM db.t WHERE b = (SELECT MAX(b) FROM t);",
+            "SELECT a, COUNT(*) FROM db.t WHERE b = (SELECT MAX(b) FROM t)",
+        );
+    }
+
+    #[test]
+    fn test_rendering_table_samples() {
+        assert_round_trip(
+            "SELECT * FROM t TABLESAMPLE SYSTEM (10) REPEATABLE (7) WHERE a = 1;",
+            "SELECT * FROM t TABLESAMPLE SYSTEM (10) REPEATABLE (7) WHERE a = 1",
+        );
+    }
+
+    #[test]
+    fn test_rendering_cursor_commands() {
+        assert_round_trip(
+            "DECLARE c CURSOR FOR SELECT * FROM t;",
+            "DECLARE c CURSOR FOR SELECT * FROM t",
+        );
+        assert_round_trip("FETCH 10 c;", "FETCH 10 FROM c");
+        assert_round_trip("CLOSE c;", "CLOSE c");
+        assert_round_trip("USE   db;", "USE db");
+        assert_round_trip(
+            "COPY db.t(a,b) FROM 'it''s.csv' (HEADER, FORMAT csv);",
+            "COPY db.t (a, b) FROM 'it''s.csv' (FORMAT csv, HEADER)",
+        );
+        assert_round_trip("SHOW databases;", "SHOW DATABASES");
+        assert_round_trip("SHOW tables FROM db;", "SHOW TABLES FROM db");
+        assert_round_trip("COPY t FROM 't.csv';", "COPY t FROM 't.csv' (FORMAT csv)");
+        assert_round_trip("BEGIN TRANSACTION;", "BEGIN");
+        assert_round_trip("ROLLBACK TO s;", "ROLLBACK TO SAVEPOINT s");
+        assert_round_trip(
+            "EXPLAIN SELECT a FROM t WHERE a=1;",
+            "EXPLAIN SELECT a FROM t WHERE a = 1",
+        );
+    }
+
+    #[test]
+    fn test_rendering_only_necessary_parentheses() {
+        assert_round_trip(
+            "SELECT (a + 1) * 2, a - (b - c), (a - b) - c FROM t;",
+            "SELECT (a + 1) * 2, a - (b - c), a - b - c FROM t",
+        );
+        assert_round_trip(
+            "SELECT * FROM t WHERE (a = 1) AND (b != 2 OR NOT (c >= 3));",
+            "SELECT * FROM t WHERE a = 1 AND (b <> 2 OR NOT c >= 3)",
+        );
+        assert_round_trip(
+            "SELECT * FROM t WHERE name NOT LIKE 'it''s%' OR (a = 1) LIKE b;",
+            "SELECT * FROM t WHERE name NOT LIKE 'it''s%' OR (a = 1) LIKE b",
+        );
+        assert_round_trip(
+            "SELECT * FROM t WHERE a NOT BETWEEN (b AND c) AND 2*3 AND d;",
+            "SELECT * FROM t WHERE a NOT BETWEEN (b AND c) AND 2 * 3 AND d",
+        );
+        assert_round_trip(
+            "SELECT * FROM t WHERE a+1 NOT IN (1,(2), b=c);",
+            "SELECT * FROM t WHERE a + 1 NOT IN (1, 2, b = c)",
+        );
+        assert_round_trip(
+            "SELECT CASE a WHEN 1 THEN TRUE ELSE NULL END FROM t;",
+            "SELECT CASE a WHEN 1 THEN TRUE ELSE NULL END FROM t",
+        );
+        assert_round_trip(
+            "SELECT (a + 1)::text, CAST(b AS boolean) FROM t;",
+            "SELECT CAST(a + 1 AS text), CAST(b AS boolean) FROM t",
+        );
+        assert_round_trip(
+            "SELECT -a, - -1, a - -b, -(a + b), -a::text FROM t;",
+            "SELECT -a, --1, a - -b, -(a + b), -CAST(a AS text) FROM t",
+        );
+    }
+}
@@ -0,0 +1,541 @@
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::iter::Peekable;
+use core::str::CharIndices;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    OpeningParenthesis,
+    ClosingParenthesis,
+    Comma,
+    Dot,
+    Semicolon,
+
+    SelectKeyword,
+    CreateKeyword,
+    InsertKeyword,
+
+    TableKeyword,
+    DatabaseKeyword,
+
+    FromKeyword,
+    ValuesKeyword,
+    IntoKeyword,
+    NotKeyword,
+    NullKeyword,
+    IntegerKeyword,
+    SerialKeyword,
+    WhereKeyword,
+    CaseKeyword,
+    WhenKeyword,
+    ThenKeyword,
+    ElseKeyword,
+    EndKeyword,
+    TrueKeyword,
+    FalseKeyword,
+    AndKeyword,
+    OrKeyword,
+    LikeKeyword,
+    InKeyword,
+    BetweenKeyword,
+    DeclareKeyword,
+    CursorKeyword,
+    ForKeyword,
+    FetchKeyword,
+    CloseKeyword,
+    TablesampleKeyword,
+    SystemKeyword,
+    RepeatableKeyword,
+    ReturningKeyword,
+    OnKeyword,
+    ConflictKeyword,
+    DoKeyword,
+    NothingKeyword,
+    UpdateKeyword,
+    SetKeyword,
+    PrimaryKeyword,
+    KeyKeyword,
+    WithKeyword,
+    CastKeyword,
+    AsKeyword,
+    ExplainKeyword,
+    BeginKeyword,
+    CommitKeyword,
+    RollbackKeyword,
+    TransactionKeyword,
+    SavepointKeyword,
+    ReleaseKeyword,
+    ToKeyword,
+    UseKeyword,
+    CopyKeyword,
+    ShowKeyword,
+    DistinctKeyword,
+    VacuumKeyword,
+
+    Asterisk,
+    EqualSign,
+    NotEqualSign,
+    LessThanSign,
+    LessThanOrEqualSign,
+    GreaterThanSign,
+    GreaterThanOrEqualSign,
+    PlusSign,
+    MinusSign,
+    Slash,
+    DoubleColon,
+    /// `->`, which reads a member or element of a `json` value.
+    Arrow,
+
+    Identifier(String),
+    NumericLiteral(String),
+    StringLiteral(String),
+    /// The hex digits of a `X'DEADBEEF'` literal of binary data.
+    HexStringLiteral(String),
+    /// A `$n` placeholder, or the n-th `?` placeholder, for a value that's bound when the
+    /// statement is executed. Placeholders are numbered from 1.
+    Parameter(usize),
+}
+
+/// The keywords and the tokens they are read as. Keywords are case-sensitive.
+pub const KEYWORDS: &[(&str, Token)] = &[
+    ("CREATE", Token::CreateKeyword),
+    ("INSERT", Token::InsertKeyword),
+    ("SELECT", Token::SelectKeyword),
+    ("TABLE", Token::TableKeyword),
+    ("DATABASE", Token::DatabaseKeyword),
+    ("WHERE", Token::WhereKeyword),
+    ("FROM", Token::FromKeyword),
+    ("VALUES", Token::ValuesKeyword),
+    ("INTO", Token::IntoKeyword),
+    ("NOT", Token::NotKeyword),
+    ("NULL", Token::NullKeyword),
+    ("CASE", Token::CaseKeyword),
+    ("WHEN", Token::WhenKeyword),
+    ("THEN", Token::ThenKeyword),
+    ("ELSE", Token::ElseKeyword),
+    ("END", Token::EndKeyword),
+    ("TRUE", Token::TrueKeyword),
+    ("FALSE", Token::FalseKeyword),
+    ("AND", Token::AndKeyword),
+    ("OR", Token::OrKeyword),
+    ("LIKE", Token::LikeKeyword),
+    ("IN", Token::InKeyword),
+    ("BETWEEN", Token::BetweenKeyword),
+    ("DECLARE", Token::DeclareKeyword),
+    ("CURSOR", Token::CursorKeyword),
+    ("FOR", Token::ForKeyword),
+    ("FETCH", Token::FetchKeyword),
+    ("CLOSE", Token::CloseKeyword),
+    ("TABLESAMPLE", Token::TablesampleKeyword),
+    ("SYSTEM", Token::SystemKeyword),
+    ("REPEATABLE", Token::RepeatableKeyword),
+    ("RETURNING", Token::ReturningKeyword),
+    ("ON", Token::OnKeyword),
+    ("CONFLICT", Token::ConflictKeyword),
+    ("DO", Token::DoKeyword),
+    ("NOTHING", Token::NothingKeyword),
+    ("UPDATE", Token::UpdateKeyword),
+    ("SET", Token::SetKeyword),
+    ("PRIMARY", Token::PrimaryKeyword),
+    ("KEY", Token::KeyKeyword),
+    ("WITH", Token::WithKeyword),
+    ("CAST", Token::CastKeyword),
+    ("AS", Token::AsKeyword),
+    ("EXPLAIN", Token::ExplainKeyword),
+    ("BEGIN", Token::BeginKeyword),
+    ("COMMIT", Token::CommitKeyword),
+    ("ROLLBACK", Token::RollbackKeyword),
+    ("TRANSACTION", Token::TransactionKeyword),
+    ("SAVEPOINT", Token::SavepointKeyword),
+    ("RELEASE", Token::ReleaseKeyword),
+    ("TO", Token::ToKeyword),
+    ("USE", Token::UseKeyword),
+    ("COPY", Token::CopyKeyword),
+    ("SHOW", Token::ShowKeyword),
+    ("DISTINCT", Token::DistinctKeyword),
+    ("VACUUM", Token::VacuumKeyword),
+    ("integer", Token::IntegerKeyword),
+    ("serial", Token::SerialKeyword),
+];
+
+pub fn tokenize(input: &str) -> Vec<Token> {
+    tokenize_with_offsets(input)
+        .into_iter()
+        .map(|(token, _offset)| token)
+        .collect()
+}
+
+/// Tokenizes `input` like `tokenize`, pairing every token with the byte offset where it starts.
+pub fn tokenize_with_offsets(input: &str) -> Vec<(Token, usize)> {
+    let mut cursor = input.char_indices().peekable();
+    let mut tokens = vec![];
+    let mut positional_parameters = 0;
+
+    while let Some((offset, character)) = cursor.next() {
+        if character.is_whitespace() {
+            continue;
+        }
+
+        let token = if character == '(' {
+            Token::OpeningParenthesis
+        } else if character == ')' {
+            Token::ClosingParenthesis
+        } else if character == ',' {
+            Token::Comma
+        } else if character == '.' {
+            Token::Dot
+        } else if character == ';' {
+            Token::Semicolon
+        } else if character == '*' {
+            Token::Asterisk
+        } else if character == '=' {
+            Token::EqualSign
+        } else if character == '<' {
+            match cursor.next_if(|(_, next)| *next == '=' || *next == '>') {
+                Some((_, '=')) => Token::LessThanOrEqualSign,
+                Some(_) => Token::NotEqualSign,
+                None => Token::LessThanSign,
+            }
+        } else if character == '>' {
+            match cursor.next_if(|(_, next)| *next == '=') {
+                Some(_) => Token::GreaterThanOrEqualSign,
+                None => Token::GreaterThanSign,
+            }
+        } else if character == '!' && cursor.next_if(|(_, next)| *next == '=').is_some() {
+            Token::NotEqualSign
+        } else if character == '\'' {
+            Token::StringLiteral(read_string_literal(&mut cursor))
+        } else if matches!(character, 'x' | 'X')
+            && cursor.next_if(|(_, next)| *next == '\'').is_some()
+        {
+            Token::HexStringLiteral(read_string_literal(&mut cursor))
+        } else if character == '+' {
+            Token::PlusSign
+        } else if character == '-' {
+            match cursor.next_if(|(_, next)| *next == '>') {
+                Some(_) => Token::Arrow,
+                None => Token::MinusSign,
+            }
+        } else if character == '/' {
+            Token::Slash
+        } else if character == ':' && cursor.next_if(|(_, next)| *next == ':').is_some() {
+            Token::DoubleColon
+        } else if character == '?' {
+            positional_parameters += 1;
+            Token::Parameter(positional_parameters)
+        } else if character == '$' && cursor.peek().is_some_and(|(_, next)| next.is_ascii_digit()) {
+            let mut digits = String::new();
+            while let Some((_, digit)) = cursor.next_if(|(_, next)| next.is_ascii_digit()) {
+                digits.push(digit);
+            }
+
+            match digits.parse() {
+                Ok(index) => Token::Parameter(index),
+                Err(_) => Token::Identifier(format!("${}", digits)),
+            }
+        } else {
+            let mut token = String::from(character);
+
+            while let Some((_, next_charcter)) = cursor.peek() {
+                if !next_charcter.is_ascii_whitespace() && !is_special_character(*next_charcter) {
+                    let (_, character) = cursor.next().expect("Could not read a peeked character");
+                    token.push(character);
+                } else {
+                    break;
+                }
+            }
+
+            // The digits after the point of a numeric literal like `12.50` belong to the literal.
+            if token.chars().all(|i| i.is_ascii_digit())
+                && cursor.next_if(|(_, next)| *next == '.').is_some()
+            {
+                token.push('.');
+                while let Some((_, digit)) = cursor.next_if(|(_, next)| next.is_ascii_digit()) {
+                    token.push(digit);
+                }
+            }
+
+            match KEYWORDS.iter().find(|(keyword, _token)| *keyword == token) {
+                Some((_keyword, keyword_token)) => keyword_token.clone(),
+
+                None => {
+                    if token.chars().all(|i| i.is_numeric() || i == '.') {
+                        Token::NumericLiteral(token)
+                    } else {
+                        Token::Identifier(token)
+                    }
+                }
+            }
+        };
+
+        tokens.push((token, offset));
+    }
+
+    return tokens;
+}
+
+/// Reads the rest of a `'` quoted string, where `''` stands for a single quote. An unterminated
+/// string runs until the end of the input.
+fn read_string_literal(cursor: &mut Peekable<CharIndices>) -> String {
+    let mut string = String::new();
+
+    while let Some((_, character)) = cursor.next() {
+        if character == '\'' && cursor.next_if(|(_, next)| *next == '\'').is_none() {
+            break;
+        }
+
+        string.push(character);
+    }
+
+    string
+}
+
+/// Returns whether `character` is a token of its own and therefore ends any preceding token.
+fn is_special_character(character: char) -> bool {
+    matches!(
+        character,
+        '(' | ')' | ',' | '.' | ';' | '*' | '=' | '<' | '>' | '!' | '+' | '-' | '/' | ':' | '?'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenizing_special_characters() {
+        assert_eq!(vec![Token::Asterisk, Token::EqualSign], tokenize("*="),);
+        assert_eq!(
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::DoubleColon,
+                Token::Identifier("text".to_string()),
+            ],
+            tokenize("a::text"),
+        );
+        assert_eq!(
+            vec![
+                Token::Identifier("doc".to_string()),
+                Token::Arrow,
+                Token::StringLiteral("tags".to_string()),
+                Token::Arrow,
+                Token::MinusSign,
+                Token::NumericLiteral("1".to_string()),
+            ],
+            tokenize("doc->'tags' -> -1"),
+        );
+    }
+
+    #[test]
+    fn test_tokenizing_arithmetic_operators() {
+        assert_eq!(
+            vec![
+                Token::Identifier("age".to_string()),
+                Token::PlusSign,
+                Token::NumericLiteral("1".to_string()),
+                Token::MinusSign,
+                Token::Identifier("a".to_string()),
+                Token::Asterisk,
+                Token::Identifier("b".to_string()),
+                Token::Slash,
+                Token::NumericLiteral("2".to_string()),
+            ],
+            tokenize("age+1 - a*b / 2"),
+        )
+    }
+
+    #[test]
+    fn test_tokenizing_with_offsets() {
+        assert_eq!(
+            vec![
+                (Token::SelectKeyword, 0),
+                (Token::StringLiteral("é''".to_string()), 7),
+                (Token::LessThanOrEqualSign, 16),
+                (Token::Identifier("b".to_string()), 20),
+                (Token::Semicolon, 21),
+            ],
+            tokenize_with_offsets("SELECT 'é''''' <=\n b;"),
+        )
+    }
+
+    #[test]
+    fn test_tokenizing_parameters() {
+        assert_eq!(
+            vec![
+                Token::Parameter(1),
+                Token::Comma,
+                Token::Parameter(2),
+                Token::EqualSign,
+                Token::Parameter(12),
+                Token::Comma,
+                Token::Identifier("$".to_string()),
+                Token::Parameter(3),
+            ],
+            tokenize("?, ?=$12, $ ?"),
+        )
+    }
+
+    #[test]
+    fn test_tokenizing_comparison_operators() {
+        assert_eq!(
+            vec![
+                Token::Identifier("a".to_string()),
+                Token::LessThanSign,
+                Token::NumericLiteral("1".to_string()),
+                Token::LessThanOrEqualSign,
+                Token::GreaterThanSign,
+                Token::GreaterThanOrEqualSign,
+                Token::NotEqualSign,
+                Token::NotEqualSign,
+                Token::EqualSign,
+            ],
+            tokenize("a<1 <= > >= <> != ="),
+        )
+    }
+
+    #[test]
+    fn test_tokenizing_string_literals() {
+        assert_eq!(
+            vec![
+                Token::Identifier("name".to_string()),
+                Token::LikeKeyword,
+                Token::StringLiteral("it's a (test);".to_string()),
+                Token::Semicolon,
+            ],
+            tokenize("name LIKE 'it''s a (test);';"),
+        )
+    }
+
+    #[test]
+    fn test_tokenizing_create_database_input() {
+        assert_eq!(
+            vec![
+                Token::CreateKeyword,
+                Token::DatabaseKeyword,
+                Token::Identifier("my_database".to_string()),
+                Token::Semicolon
+            ],
+            tokenize("CREATE DATABASE my_database;"),
+        )
+    }
+
+    #[test]
+    fn test_tokenizing_create_table_without_any_columns_input() {
+        assert_eq!(
+            vec![
+                Token::CreateKeyword,
+                Token::TableKeyword,
+                Token::Identifier("my_table".to_string()),
+                Token::OpeningParenthesis,
+                Token::ClosingParenthesis,
+                Token::Semicolon
+            ],
+            tokenize("CREATE TABLE my_table ();"),
+        )
+    }
+
+    #[test]
+    fn test_tokenizing_create_table_with_columns_input() {
+        assert_eq!(
+            vec![
+                Token::CreateKeyword,
+                Token::TableKeyword,
+                Token::Identifier("users".to_string()),
+                Token::OpeningParenthesis,
+                Token::Identifier("birth_year".to_string()),
+                Token::IntegerKeyword,
+                Token::Comma,
+                Token::Identifier("age".to_string()),
+                Token::IntegerKeyword,
+                Token::NotKeyword,
+                Token::NullKeyword,
+                Token::ClosingParenthesis,
+                Token::Semicolon
+            ],
+            tokenize("CREATE TABLE users (birth_year integer, age integer NOT NULL);"),
+        )
+    }
+
+    #[test]
+    fn test_tokenizing_insert_input() {
+        assert_eq!(
+            vec![
+                Token::InsertKeyword,
+                Token::IntoKeyword,
+                Token::Identifier("users".to_string()),
+                Token::ValuesKeyword,
+                Token::OpeningParenthesis,
+                Token::NumericLiteral("12".to_string()),
+                Token::Comma,
+                Token::NumericLiteral("31".to_string()),
+                Token::ClosingParenthesis,
+                Token::Semicolon
+            ],
+            tokenize("INSERT INTO users VALUES (12, 31);"),
+        )
+    }
+
+    #[test]
+    fn test_tokenizing_numeric_literals() {
+        assert_eq!(
+            vec![
+                Token::NumericLiteral("12.50".to_string()),
+                Token::Comma,
+                Token::NumericLiteral("3.".to_string()),
+                Token::Comma,
+                Token::Identifier("t1".to_string()),
+                Token::Dot,
+                Token::Identifier("a".to_string()),
+            ],
+            tokenize("12.50, 3., t1.a"),
+        )
+    }
+
+    #[test]
+    fn test_tokenizing_hex_string_literals() {
+        assert_eq!(
+            vec![
+                Token::HexStringLiteral("DEADbeef".to_string()),
+                Token::Comma,
+                Token::HexStringLiteral(String::new()),
+                Token::Comma,
+                Token::Identifier("x".to_string()),
+            ],
+            tokenize("X'DEADbeef', x'', x"),
+        )
+    }
+
+    #[test]
+    fn test_tokenizing_select_all_input() {
+        assert_eq!(
+            vec![
+                Token::SelectKeyword,
+                Token::Asterisk,
+                Token::FromKeyword,
+                Token::Identifier("users".to_string()),
+                Token::Semicolon
+            ],
+            tokenize("SELECT * FROM users;"),
+        )
+    }
+
+    #[test]
+    fn test_tokenizing_select_column_where_input() {
+        assert_eq!(
+            vec![
+                Token::SelectKeyword,
+                Token::Asterisk,
+                Token::FromKeyword,
+                Token::Identifier("users".to_string()),
+                Token::WhereKeyword,
+                Token::Identifier("user_id".to_string()),
+                Token::EqualSign,
+                Token::NumericLiteral("2".to_string()),
+                Token::Semicolon
+            ],
+            tokenize("SELECT * FROM users WHERE user_id = 2;"),
+        )
+    }
+}
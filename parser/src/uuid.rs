@@ -0,0 +1,113 @@
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{self, Display, Formatter};
+
+use super::hex;
+
+/// A 128-bit universally unique identifier, written in the canonical form
+/// `a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Uuid([u8; 16]);
+
+/// The number of hex digits in every group of the canonical form.
+const GROUP_LENGTHS: [usize; 5] = [8, 4, 4, 4, 12];
+
+impl Uuid {
+    pub fn from_bytes(bytes: [u8; 16]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 16] {
+        &self.0
+    }
+
+    /// Returns a random (version 4) UUID made from `random_bits`, apart from the bits that hold
+    /// the version and the variant.
+    pub fn new_v4(random_bits: u128) -> Self {
+        let mut bytes = random_bits.to_be_bytes();
+
+        bytes[6] = (bytes[6] & 0x0F) | 0x40;
+        bytes[8] = (bytes[8] & 0x3F) | 0x80;
+
+        Self(bytes)
+    }
+
+    /// Parses the canonical form, in either case. The hyphens and surrounding braces may be left
+    /// out, like in `{A0EEBC999C0B4EF8BB6D6BB9BD380A11}`.
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text
+            .strip_prefix('{')
+            .and_then(|text| text.strip_suffix('}'))
+            .unwrap_or(text);
+
+        let digits: String = if text.len() == 32 {
+            text.to_string()
+        } else {
+            let groups: Vec<&str> = text.split('-').collect();
+            let is_canonical = groups.len() == GROUP_LENGTHS.len()
+                && groups
+                    .iter()
+                    .zip(GROUP_LENGTHS)
+                    .all(|(group, length)| group.len() == length);
+            if !is_canonical {
+                return None;
+            }
+
+            groups.concat()
+        };
+
+        Some(Self(hex::decode(&digits)?.try_into().ok()?))
+    }
+}
+
+impl Display for Uuid {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let digits = hex::encode(&self.0);
+        let mut offset = 0;
+
+        for (index, length) in GROUP_LENGTHS.iter().enumerate() {
+            if index > 0 {
+                write!(f, "-")?;
+            }
+            write!(f, "{}", &digits[offset..offset + length])?;
+            offset += length;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsing_and_formatting() {
+        let uuid = Uuid::parse("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11").unwrap();
+        assert_eq!(0xA0, uuid.as_bytes()[0]);
+        assert_eq!(0x11, uuid.as_bytes()[15]);
+        assert_eq!("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11", uuid.to_string());
+
+        assert_eq!(
+            Some(uuid),
+            Uuid::parse("{A0EEBC999C0B4EF8BB6D6BB9BD380A11}")
+        );
+        assert_eq!(None, Uuid::parse("a0eebc99-9c0b4ef8-bb6d-6bb9bd380a11"));
+        assert_eq!(None, Uuid::parse("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a1"));
+        assert_eq!(None, Uuid::parse("g0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11"));
+        assert_eq!(None, Uuid::parse(""));
+    }
+
+    #[test]
+    fn test_random_uuids() {
+        let first = Uuid::new_v4(0);
+        let second = Uuid::new_v4(u128::MAX);
+
+        assert_ne!(first, second);
+        for uuid in [first, second] {
+            let text = uuid.to_string();
+            assert_eq!(Some('4'), text.chars().nth(14));
+            assert!("89ab".contains(text.chars().nth(19).unwrap()));
+        }
+    }
+}
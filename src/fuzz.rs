@@ -0,0 +1,66 @@
+//! Entry points for fuzzers, e.g. a `cargo fuzz` target that calls `bsql::fuzz::fuzz_parse(data)`.
+//! They must not panic for any input, so every panic they run into is a bug.
+
+use std::rc::Rc;
+use std::sync::RwLock;
+
+use crate::{parse, Manager, PageManager};
+
+/// Parses `data` as SQL and checks that the canonical SQL of the parsed command parses back into
+/// the same command.
+pub fn fuzz_parse(data: &[u8]) {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    if let Ok(command) = parse(input) {
+        let sql = command.to_sql();
+
+        assert_eq!(
+            Ok(&command),
+            parse(&sql).as_ref(),
+            "{:?} doesn't parse back into the same command",
+            sql
+        );
+    }
+}
+
+/// Executes every `;`-separated statement of `data` against a fresh in-memory database called
+/// `fuzz`, checking the invariants of the storage layer after every statement.
+pub fn fuzz_execute_in_memory(data: &[u8]) {
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+    let mut manager = Manager::new(page_manager);
+    manager.execute("", "CREATE DATABASE fuzz;").unwrap();
+
+    for statement in input.split(';') {
+        let _result = manager.execute("fuzz", &format!("{};", statement));
+        manager.debug_check_invariants();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fuzz_parse() {
+        fuzz_parse(b"SELECT a + 1 * 2, NOT b FROM t WHERE x BETWEEN 1 AND 2 OR y IN (1, 2);");
+        fuzz_parse(b"INSERT INTO db.t (a) VALUES (1), (2) ON CONFLICT (a) DO NOTHING;");
+        fuzz_parse(b"SELECT 'it''s' FROM");
+        fuzz_parse(&[0xff, 0xfe, b';']);
+    }
+
+    #[test]
+    fn test_fuzz_execute_in_memory() {
+        fuzz_execute_in_memory(
+            b"CREATE TABLE t (a integer, b integer); INSERT INTO t VALUES (1, 2), (3, 4); \
+              INSERT INTO t (b) VALUES (5) ON CONFLICT (b) DO UPDATE SET a = 1; \
+              SELECT * FROM t WHERE a = 1; SELECT FROM; ;;",
+        );
+        fuzz_execute_in_memory(&[0xff, 0xfe]);
+    }
+}
@@ -0,0 +1,125 @@
+//! Generators of random catalog objects and values for the property tests that guard the on-disk
+//! byte layouts. Every property runs for `CASES` consecutive seeds, so a failing case can be
+//! reproduced from the seed in the panic message.
+
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use std::rc::Rc;
+
+use super::{
+    ColumnDefinition, DataType, InternalPage, LockedPage, Random, SharedInternalPage, Value,
+};
+
+pub const CASES: u64 = 256;
+
+/// Checks `property` with a generator for every seed in `0..CASES`.
+pub fn check(property: impl Fn(&mut Random)) {
+    for seed in 0..CASES {
+        let mut random = Random::new(seed);
+
+        if catch_unwind(AssertUnwindSafe(|| property(&mut random))).is_err() {
+            panic!("The property doesn't hold for seed {}", seed);
+        }
+    }
+}
+
+/// Returns a number in `range`.
+pub fn between(random: &mut Random, range: std::ops::RangeInclusive<usize>) -> usize {
+    let length = (range.end() - range.start() + 1) as u64;
+
+    range.start() + (random.next_u64() % length) as usize
+}
+
+pub fn boolean(random: &mut Random) -> bool {
+    random.next_u64() % 2 == 0
+}
+
+/// Returns a name of `1..=max_length` characters which are valid in identifiers.
+pub fn name(random: &mut Random, max_length: usize) -> String {
+    const CHARACTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789_";
+
+    (0..between(random, 1..=max_length))
+        .map(|_| CHARACTERS[between(random, 0..=CHARACTERS.len() - 1)] as char)
+        .collect()
+}
+
+/// Returns `count` different names, see `name`.
+pub fn names(random: &mut Random, count: usize, max_length: usize) -> Vec<String> {
+    let mut names: Vec<String> = Vec::with_capacity(count);
+
+    while names.len() < count {
+        let name = name(random, max_length);
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    names
+}
+
+pub fn data_type(random: &mut Random) -> DataType {
+    match between(random, 0..=5) {
+        0 => DataType::Integer,
+        1 => DataType::Text,
+        2 => DataType::Bytea,
+        3 => DataType::Uuid,
+        4 => DataType::Json,
+        _ => {
+            let precision = between(random, 1..=38) as u8;
+            DataType::Numeric {
+                precision,
+                scale: between(random, 0..=precision as usize) as u8,
+            }
+        }
+    }
+}
+
+pub fn value(random: &mut Random, data_type: &DataType) -> Value {
+    data_type.random_value(random)
+}
+
+/// Returns up to `max_count` column definitions with different ids and names.
+pub fn column_definitions(random: &mut Random, max_count: usize) -> Vec<ColumnDefinition> {
+    let count = between(random, 1..=max_count);
+    let mut column_ids: Vec<u8> = Vec::with_capacity(count);
+
+    while column_ids.len() < count {
+        let column_id = between(random, 0..=254) as u8;
+        if !column_ids.contains(&column_id) {
+            column_ids.push(column_id);
+        }
+    }
+
+    names(random, count, 32)
+        .into_iter()
+        .zip(column_ids)
+        .map(|(name, column_id)| {
+            ColumnDefinition::new(column_id, data_type(random), name).with_not_null(boolean(random))
+        })
+        .collect()
+}
+
+/// Returns a copy of the bytes of `page`, to check that everything can be read back from them.
+pub fn copy_page(page: &SharedInternalPage) -> SharedInternalPage {
+    let page = page.read().unwrap();
+
+    Rc::new(LockedPage::new(InternalPage {
+        metadata: page.metadata,
+        data: page.data,
+    }))
+}
+
+/// Returns a record with a value for every column, where the values of columns that aren't
+/// `NOT NULL` are sometimes `NULL`.
+pub fn record(random: &mut Random, column_definitions: &[ColumnDefinition]) -> Vec<Option<Value>> {
+    column_definitions
+        .iter()
+        .map(|column_definition| {
+            if !column_definition.is_not_null() && between(random, 0..=3) == 0 {
+                return None;
+            }
+
+            Some(value(random, column_definition.data_type()))
+        })
+        .collect()
+}
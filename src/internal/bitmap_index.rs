@@ -1,45 +1,49 @@
+/// A bitmap of `capacity` flags, stored in `capacity / 8 + 1` bytes.
 #[derive(Debug)]
-pub struct BitmapIndex<'a, const N: usize>
-where
-    [(); N / 8 + 1]:,
-{
+pub struct BitmapIndex<'a> {
     bitmap: &'a mut [u8],
+    capacity: u16,
 }
 
-impl<'a, const N: usize> BitmapIndex<'a, N>
-where
-    [(); N / 8 + 1]:,
-{
-    pub fn from_raw(bytes: &'a mut [u8]) -> Option<Self> {
-        if bytes.len() == { N / 8 + 1 } {
-            Some(Self { bitmap: bytes })
+impl<'a> BitmapIndex<'a> {
+    pub fn from_raw(bytes: &'a mut [u8], capacity: u16) -> Option<Self> {
+        if bytes.len() == Self::size(capacity) {
+            Some(Self {
+                bitmap: bytes,
+                capacity,
+            })
         } else {
             None
         }
     }
 
+    /// Returns the number of bytes of a bitmap with `capacity` flags.
+    pub fn size(capacity: u16) -> usize {
+        capacity as usize / 8 + 1
+    }
+
     /// Find the first available flag, set it and return its index.
-    pub fn consume(&mut self) -> Option<u8> {
+    pub fn consume(&mut self) -> Option<u16> {
         let index = self.find_available_index()?;
         self.set(index);
 
         return Some(index);
     }
 
-    pub fn set(&mut self, index: u8) {
-        if index <= N as u8 {
+    pub fn set(&mut self, index: u16) {
+        if index <= self.capacity {
             self.bitmap[(index / 8) as usize] |= 1 << (index % 8);
         }
     }
 
-    pub fn unset(&mut self, index: u8) {
-        if index <= N as u8 {
-            self.bitmap[(index / 8) as usize] &= 0 << (index % 8);
+    pub fn unset(&mut self, index: u16) {
+        if index <= self.capacity {
+            self.bitmap[(index / 8) as usize] &= !(1 << (index % 8));
         }
     }
 
-    pub fn is_set(&self, index: u8) -> bool {
-        if index <= N as u8 {
+    pub fn is_set(&self, index: u16) -> bool {
+        if index <= self.capacity {
             self.bitmap[(index / 8) as usize] & (1 << (index % 8)) != 0
         } else {
             false
@@ -51,27 +55,32 @@ where
     }
 
     /// Returns the number of unset bits.
-    pub fn available(&self) -> u8 {
-        N as u8 - self.count()
+    pub fn available(&self) -> u16 {
+        self.capacity - self.count()
     }
 
     /// Returns the number of set bits.
-    pub fn count(&self) -> u8 {
-        self.bitmap.iter().map(|byte| byte.count_ones() as u8).sum()
+    pub fn count(&self) -> u16 {
+        self.bitmap
+            .iter()
+            .map(|byte| byte.count_ones() as u16)
+            .sum()
     }
 
-    /// Returns a `Vec<u8>` with all bits that are set.
-    pub fn indices(&self) -> Vec<u8> {
-        (0..=255).filter(|index| self.is_set(*index)).collect()
+    /// Returns a `Vec<u16>` with all bits that are set.
+    pub fn indices(&self) -> Vec<u16> {
+        (0..=self.capacity)
+            .filter(|index| self.is_set(*index))
+            .collect()
     }
 
-    fn find_available_index(&self) -> Option<u8> {
+    fn find_available_index(&self) -> Option<u16> {
         for (index, byte) in self.bitmap.iter().enumerate() {
             if *byte == u8::MAX {
                 continue;
             } else {
-                let first_free_index = (!byte).trailing_zeros() as u8;
-                return Some(index as u8 * 8 + first_free_index);
+                let first_free_index = (!byte).trailing_zeros() as u16;
+                return Some(index as u16 * 8 + first_free_index);
             }
         }
 
@@ -86,8 +95,8 @@ mod tests {
     #[test]
     fn test_that_it_works() {
         let mut array = [0; 32];
-        let mut bitmap_index: BitmapIndex<255> =
-            BitmapIndex::from_raw(&mut array).expect("Failed to build BitmapIndex");
+        let mut bitmap_index =
+            BitmapIndex::from_raw(&mut array, 255).expect("Failed to build BitmapIndex");
 
         assert_eq!(false, bitmap_index.is_set(0));
 
@@ -110,8 +119,8 @@ mod tests {
     #[test]
     fn test_that_unset_works() {
         let mut array = [0; 32];
-        let mut bitmap_index: BitmapIndex<255> =
-            BitmapIndex::from_raw(&mut array).expect("Failed to build BitmapIndex");
+        let mut bitmap_index =
+            BitmapIndex::from_raw(&mut array, 255).expect("Failed to build BitmapIndex");
 
         assert_eq!(false, bitmap_index.is_set(0));
 
@@ -120,13 +129,19 @@ mod tests {
 
         bitmap_index.unset(0);
         assert_eq!(false, bitmap_index.is_set(0));
+
+        // Unsetting a flag leaves the others in the same byte set.
+        bitmap_index.set(1);
+        bitmap_index.set(2);
+        bitmap_index.unset(1);
+        assert_eq!(vec![2], bitmap_index.indices());
     }
 
     #[test]
     fn test_returns_all_set_bit_indices() {
         let mut array = [0; 32];
-        let mut bitmap_index: BitmapIndex<255> =
-            BitmapIndex::from_raw(&mut array).expect("Failed to build BitmapIndex");
+        let mut bitmap_index =
+            BitmapIndex::from_raw(&mut array, 255).expect("Failed to build BitmapIndex");
 
         assert!(bitmap_index.indices().is_empty());
 
@@ -143,8 +158,8 @@ mod tests {
     #[test]
     fn test_does_nothing_when_setting_the_same_bit_twice() {
         let mut array = [0; 32];
-        let mut bitmap_index: BitmapIndex<255> =
-            BitmapIndex::from_raw(&mut array).expect("Failed to build BitmapIndex");
+        let mut bitmap_index =
+            BitmapIndex::from_raw(&mut array, 255).expect("Failed to build BitmapIndex");
         assert_eq!(false, bitmap_index.is_set(0));
 
         bitmap_index.set(0);
@@ -157,13 +172,13 @@ mod tests {
     #[test]
     fn test_consume_finds_available_slots_in_the_middle_of_the_index() {
         let mut array = [0; 32];
-        let mut bitmap_index: BitmapIndex<255> =
-            BitmapIndex::from_raw(&mut array).expect("Failed to build BitmapIndex");
+        let mut bitmap_index =
+            BitmapIndex::from_raw(&mut array, 255).expect("Failed to build BitmapIndex");
         (0..=128).for_each(|index| {
-            bitmap_index.set(index.into());
+            bitmap_index.set(index);
         });
         (130..255).for_each(|index| {
-            bitmap_index.set(index.into());
+            bitmap_index.set(index);
         });
 
         assert_eq!(1, bitmap_index.available());
@@ -173,8 +188,8 @@ mod tests {
     #[test]
     fn test_consume_return_available_value() {
         let mut array = [0; 32];
-        let mut bitmap_index: BitmapIndex<255> =
-            BitmapIndex::from_raw(&mut array).expect("Failed to build BitmapIndex");
+        let mut bitmap_index =
+            BitmapIndex::from_raw(&mut array, 255).expect("Failed to build BitmapIndex");
 
         for _ in 0..=u8::MAX {
             assert!(bitmap_index.consume().is_some());
@@ -183,12 +198,32 @@ mod tests {
         assert!(bitmap_index.consume().is_none());
     }
 
+    #[test]
+    fn test_bitmaps_with_more_than_255_flags() {
+        let mut array = [0; 513];
+        assert!(BitmapIndex::from_raw(&mut array, 255).is_none());
+        let mut bitmap_index =
+            BitmapIndex::from_raw(&mut array, 4096).expect("Failed to build BitmapIndex");
+
+        for _ in 0..300 {
+            assert!(bitmap_index.consume().is_some());
+        }
+        bitmap_index.unset(3);
+        assert_eq!(3797, bitmap_index.available());
+        assert_eq!(Some(3), bitmap_index.consume());
+        assert_eq!(Some(300), bitmap_index.consume());
+
+        bitmap_index.set(4095);
+        assert!(bitmap_index.is_set(4095));
+        assert_eq!(Some(&4095), bitmap_index.indices().last());
+    }
+
     #[test]
     fn test_from_raw_works() {
         let mut raw_data = [0; 32];
         raw_data[2] = 0xFF;
-        let bitmap_index: BitmapIndex<255> =
-            BitmapIndex::from_raw(&mut raw_data).expect("Failed to load from bytes");
+        let bitmap_index =
+            BitmapIndex::from_raw(&mut raw_data, 255).expect("Failed to load from bytes");
 
         assert_eq!(8, bitmap_index.count());
     }
@@ -198,16 +233,15 @@ mod tests {
         let mut my_bigger_array = [0; 64];
 
         {
-            let mut bitmap_index: BitmapIndex<255> =
-                BitmapIndex::from_raw(&mut my_bigger_array[0..32])
-                    .expect("Failed to load from bytes");
+            let mut bitmap_index = BitmapIndex::from_raw(&mut my_bigger_array[0..32], 255)
+                .expect("Failed to load from bytes");
 
             bitmap_index.set(0);
             assert_eq!(1, bitmap_index.count());
         }
 
         {
-            let bitmap_index: BitmapIndex<255> = BitmapIndex::from_raw(&mut my_bigger_array[0..32])
+            let bitmap_index = BitmapIndex::from_raw(&mut my_bigger_array[0..32], 255)
                 .expect("Failed to load from bytes");
 
             assert_eq!(1, bitmap_index.count());
@@ -0,0 +1,655 @@
+use std::ops::{Bound, RangeBounds};
+use std::rc::Rc;
+use std::sync::RwLock;
+
+use super::{Error, PageId, PageManager, SharedInternalPage};
+
+const NODE_KIND_OFFSET: usize = 33;
+const ENTRY_COUNT_RANGE: std::ops::Range<usize> = 34..38;
+const NEXT_LEAF_PAGE_ID_RANGE: std::ops::Range<usize> = 38..42;
+
+const LEAF: u8 = 0;
+const INTERNAL: u8 = 1;
+
+const LEAF_ENTRY_SIZE: usize = 16;
+const INTERNAL_ENTRY_SIZE: usize = 12;
+const CHILD_SIZE: usize = 4;
+
+const LEAF_CAPACITY: usize = 4096 / LEAF_ENTRY_SIZE;
+const INTERNAL_CAPACITY: usize = (4096 - CHILD_SIZE) / INTERNAL_ENTRY_SIZE;
+
+/// A B-tree of pages that maps `i64` keys to `u64` values, e.g. the values of a `PRIMARY KEY`
+/// column to the ids of their records.
+///
+/// The metadata of every node has its kind at 33, the number of entries at 34..38 and, for
+/// leaves, the id of the next leaf (0 for the last one) at 38..42, so that the leaves can be
+/// scanned in order. Leaves store their entries as the key followed by the value, ordered by key.
+/// Internal nodes store the id of their first child followed by every key with the id of the child
+/// that has the keys from there on.
+///
+/// The root always stays on the same page: when it's split its entries move to new pages, and
+/// when it's left with a single child the child moves into it. Pages of nodes that were merged
+/// into their sibling aren't used anymore until the database is compacted.
+pub struct BTree {
+    page_manager: Rc<RwLock<PageManager>>,
+    root: SharedInternalPage,
+
+    leaf_capacity: usize,
+    internal_capacity: usize,
+}
+
+enum Node {
+    Leaf {
+        entries: Vec<(i64, u64)>,
+        next_leaf_page_id: PageId,
+    },
+    Internal {
+        keys: Vec<i64>,
+        children: Vec<PageId>,
+    },
+}
+
+impl BTree {
+    /// Makes the page the root of an empty tree.
+    pub fn initialize(page_manager: Rc<RwLock<PageManager>>, root: SharedInternalPage) -> Self {
+        Self::write_node(
+            &root,
+            &Node::Leaf {
+                entries: vec![],
+                next_leaf_page_id: 0,
+            },
+        );
+
+        Self::load(page_manager, root)
+    }
+
+    pub fn load(page_manager: Rc<RwLock<PageManager>>, root: SharedInternalPage) -> Self {
+        Self {
+            page_manager,
+            root,
+            leaf_capacity: LEAF_CAPACITY,
+            internal_capacity: INTERNAL_CAPACITY,
+        }
+    }
+
+    /// Returns the value of `key`, if the tree has it.
+    pub fn get(&self, key: i64) -> Result<Option<u64>, Error> {
+        let (entries, _next_leaf_page_id) =
+            self.leaf_entries(self.find_leaf(Bound::Included(key))?);
+
+        Ok(entries
+            .binary_search_by_key(&key, |(entry_key, _value)| *entry_key)
+            .ok()
+            .map(|position| entries[position].1))
+    }
+
+    /// Sets the value of `key`, replacing the value it had before.
+    pub fn insert(&mut self, key: i64, value: u64) -> Result<(), Error> {
+        let Some((separator, right)) = self.insert_into(&self.root.clone(), key, value)? else {
+            return Ok(());
+        };
+
+        // The root was split: its left half moves to a new page, so that the root can point at
+        // both halves.
+        let left = Self::read_node(&self.root);
+        let (left_page_id, left_page) = self.page_manager.write().unwrap().create_page();
+        Self::write_node(&left_page, &left);
+        Self::write_node(
+            &self.root,
+            &Node::Internal {
+                keys: vec![separator],
+                children: vec![left_page_id, right],
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Removes `key` from the tree and returns the value it had.
+    pub fn remove(&mut self, key: i64) -> Result<Option<u64>, Error> {
+        let root = self.root.clone();
+        let (value, _underflow) = self.remove_from(&root, key)?;
+
+        // A root with a single child is replaced by the child, so the tree gets less deep.
+        if let Node::Internal { keys, children } = Self::read_node(&root) {
+            if keys.is_empty() {
+                let child = Self::read_node(&self.fetch_page(children[0])?);
+                Self::write_node(&root, &child);
+            }
+        }
+
+        Ok(value)
+    }
+
+    /// Returns the entries with keys in `range`, ordered by key.
+    pub fn range(&self, range: impl RangeBounds<i64>) -> Result<Vec<(i64, u64)>, Error> {
+        let mut entries = vec![];
+        let mut leaf = self.find_leaf(range.start_bound().cloned())?;
+
+        loop {
+            let (leaf_entries, next_leaf_page_id) = self.leaf_entries(leaf);
+            for (key, value) in leaf_entries {
+                let is_after_start = match range.start_bound() {
+                    Bound::Included(start) => key >= *start,
+                    Bound::Excluded(start) => key > *start,
+                    Bound::Unbounded => true,
+                };
+                let is_before_end = match range.end_bound() {
+                    Bound::Included(end) => key <= *end,
+                    Bound::Excluded(end) => key < *end,
+                    Bound::Unbounded => true,
+                };
+
+                if !is_before_end {
+                    return Ok(entries);
+                }
+                if is_after_start {
+                    entries.push((key, value));
+                }
+            }
+
+            if next_leaf_page_id == 0 {
+                return Ok(entries);
+            }
+            leaf = self.fetch_page(next_leaf_page_id)?;
+        }
+    }
+
+    /// Returns the number of pages of the nodes in the tree.
+    pub fn page_count(&self) -> Result<u32, Error> {
+        let mut page_count = 0;
+        let mut pages = vec![self.root.clone()];
+
+        while let Some(page) = pages.pop() {
+            page_count += 1;
+            if let Node::Internal { children, .. } = Self::read_node(&page) {
+                for child in children {
+                    pages.push(self.fetch_page(child)?);
+                }
+            }
+        }
+
+        Ok(page_count)
+    }
+
+    /// Returns the leaf that has the keys from `start` on.
+    fn find_leaf(&self, start: Bound<i64>) -> Result<SharedInternalPage, Error> {
+        let mut page = self.root.clone();
+
+        loop {
+            let Node::Internal { keys, children } = Self::read_node(&page) else {
+                return Ok(page);
+            };
+
+            let position = match start {
+                Bound::Included(key) | Bound::Excluded(key) => {
+                    keys.partition_point(|separator| *separator <= key)
+                }
+                Bound::Unbounded => 0,
+            };
+            page = self.fetch_page(children[position])?;
+        }
+    }
+
+    fn leaf_entries(&self, page: SharedInternalPage) -> (Vec<(i64, u64)>, PageId) {
+        match Self::read_node(&page) {
+            Node::Leaf {
+                entries,
+                next_leaf_page_id,
+            } => (entries, next_leaf_page_id),
+            Node::Internal { .. } => unreachable!("Expected a leaf"),
+        }
+    }
+
+    /// Inserts the entry into the subtree of `page`. When the node has to be split, its right half
+    /// moves to a new page and the first key of that half is returned with the id of the page.
+    fn insert_into(
+        &mut self,
+        page: &SharedInternalPage,
+        key: i64,
+        value: u64,
+    ) -> Result<Option<(i64, PageId)>, Error> {
+        let (mut keys, mut children) = match Self::read_node(page) {
+            Node::Leaf {
+                mut entries,
+                next_leaf_page_id,
+            } => {
+                match entries.binary_search_by_key(&key, |(entry_key, _value)| *entry_key) {
+                    Ok(position) => entries[position].1 = value,
+                    Err(position) => entries.insert(position, (key, value)),
+                }
+
+                if entries.len() <= self.leaf_capacity {
+                    Self::write_node(
+                        page,
+                        &Node::Leaf {
+                            entries,
+                            next_leaf_page_id,
+                        },
+                    );
+                    return Ok(None);
+                }
+
+                let right_entries = entries.split_off(entries.len() / 2);
+                let separator = right_entries[0].0;
+                let (right_page_id, right_page) = self.page_manager.write().unwrap().create_page();
+                Self::write_node(
+                    &right_page,
+                    &Node::Leaf {
+                        entries: right_entries,
+                        next_leaf_page_id,
+                    },
+                );
+                Self::write_node(
+                    page,
+                    &Node::Leaf {
+                        entries,
+                        next_leaf_page_id: right_page_id,
+                    },
+                );
+                return Ok(Some((separator, right_page_id)));
+            }
+            Node::Internal {
+                mut keys,
+                mut children,
+            } => {
+                let position = keys.partition_point(|separator| *separator <= key);
+                let child = self.fetch_page(children[position])?;
+                let Some((separator, right_page_id)) = self.insert_into(&child, key, value)? else {
+                    return Ok(None);
+                };
+
+                keys.insert(position, separator);
+                children.insert(position + 1, right_page_id);
+                (keys, children)
+            }
+        };
+
+        if keys.len() <= self.internal_capacity {
+            Self::write_node(page, &Node::Internal { keys, children });
+            return Ok(None);
+        }
+
+        // The middle key moves up to the parent, the keys after it move to the new node.
+        let middle = keys.len() / 2;
+        let right_keys = keys.split_off(middle + 1);
+        let separator = keys.pop().unwrap();
+        let right_children = children.split_off(middle + 1);
+
+        let (right_page_id, right_page) = self.page_manager.write().unwrap().create_page();
+        Self::write_node(
+            &right_page,
+            &Node::Internal {
+                keys: right_keys,
+                children: right_children,
+            },
+        );
+        Self::write_node(page, &Node::Internal { keys, children });
+
+        Ok(Some((separator, right_page_id)))
+    }
+
+    /// Removes `key` from the subtree of `page`. Returns the removed value and whether the node is
+    /// left with fewer entries than it should have, so that the parent can rebalance it.
+    fn remove_from(
+        &mut self,
+        page: &SharedInternalPage,
+        key: i64,
+    ) -> Result<(Option<u64>, bool), Error> {
+        match Self::read_node(page) {
+            Node::Leaf {
+                mut entries,
+                next_leaf_page_id,
+            } => {
+                let Ok(position) =
+                    entries.binary_search_by_key(&key, |(entry_key, _value)| *entry_key)
+                else {
+                    return Ok((None, false));
+                };
+
+                let (_key, value) = entries.remove(position);
+                let underflow = entries.len() < self.leaf_capacity / 2;
+                Self::write_node(
+                    page,
+                    &Node::Leaf {
+                        entries,
+                        next_leaf_page_id,
+                    },
+                );
+
+                Ok((Some(value), underflow))
+            }
+            Node::Internal {
+                mut keys,
+                mut children,
+            } => {
+                let position = keys.partition_point(|separator| *separator <= key);
+                let child = self.fetch_page(children[position])?;
+                let (value, underflow) = self.remove_from(&child, key)?;
+
+                if underflow {
+                    self.rebalance(&mut keys, &mut children, position)?;
+                }
+                let underflow = keys.len() < self.internal_capacity / 2;
+                Self::write_node(page, &Node::Internal { keys, children });
+
+                Ok((value, underflow))
+            }
+        }
+    }
+
+    /// Gives the child at `position` more entries, by moving an entry over from one of its
+    /// siblings or by merging it with one of them.
+    fn rebalance(
+        &mut self,
+        keys: &mut Vec<i64>,
+        children: &mut Vec<PageId>,
+        position: usize,
+    ) -> Result<(), Error> {
+        // Merging always moves the right node into the left one.
+        let (left_position, right_position) = if position + 1 < children.len() {
+            (position, position + 1)
+        } else {
+            (position - 1, position)
+        };
+        let left_page = self.fetch_page(children[left_position])?;
+        let right_page = self.fetch_page(children[right_position])?;
+        let separator = &mut keys[left_position];
+
+        match (Self::read_node(&left_page), Self::read_node(&right_page)) {
+            (
+                Node::Leaf {
+                    entries: mut left_entries,
+                    ..
+                },
+                Node::Leaf {
+                    entries: mut right_entries,
+                    next_leaf_page_id,
+                },
+            ) => {
+                if left_entries.len() + right_entries.len() > self.leaf_capacity {
+                    // Borrow a single entry from whichever sibling has more to spare.
+                    if left_entries.len() > right_entries.len() {
+                        right_entries.insert(0, left_entries.pop().unwrap());
+                    } else {
+                        left_entries.push(right_entries.remove(0));
+                    }
+                    *separator = right_entries[0].0;
+
+                    Self::write_node(
+                        &right_page,
+                        &Node::Leaf {
+                            entries: right_entries,
+                            next_leaf_page_id,
+                        },
+                    );
+                    Self::write_node(
+                        &left_page,
+                        &Node::Leaf {
+                            entries: left_entries,
+                            next_leaf_page_id: children[right_position],
+                        },
+                    );
+                    return Ok(());
+                }
+
+                left_entries.extend(right_entries);
+                Self::write_node(
+                    &left_page,
+                    &Node::Leaf {
+                        entries: left_entries,
+                        next_leaf_page_id,
+                    },
+                );
+            }
+            (
+                Node::Internal {
+                    keys: mut left_keys,
+                    children: mut left_children,
+                },
+                Node::Internal {
+                    keys: mut right_keys,
+                    children: mut right_children,
+                },
+            ) => {
+                if left_keys.len() + right_keys.len() >= self.internal_capacity {
+                    // The separator moves down into one sibling, and a key of the other sibling
+                    // takes its place.
+                    if left_keys.len() > right_keys.len() {
+                        right_keys.insert(0, *separator);
+                        right_children.insert(0, left_children.pop().unwrap());
+                        *separator = left_keys.pop().unwrap();
+                    } else {
+                        left_keys.push(*separator);
+                        left_children.push(right_children.remove(0));
+                        *separator = right_keys.remove(0);
+                    }
+
+                    Self::write_node(
+                        &right_page,
+                        &Node::Internal {
+                            keys: right_keys,
+                            children: right_children,
+                        },
+                    );
+                    Self::write_node(
+                        &left_page,
+                        &Node::Internal {
+                            keys: left_keys,
+                            children: left_children,
+                        },
+                    );
+                    return Ok(());
+                }
+
+                left_keys.push(*separator);
+                left_keys.extend(right_keys);
+                left_children.extend(right_children);
+                Self::write_node(
+                    &left_page,
+                    &Node::Internal {
+                        keys: left_keys,
+                        children: left_children,
+                    },
+                );
+            }
+            _ => unreachable!("Siblings are always at the same depth"),
+        }
+
+        keys.remove(left_position);
+        children.remove(right_position);
+
+        Ok(())
+    }
+
+    fn fetch_page(&self, page_id: PageId) -> Result<SharedInternalPage, Error> {
+        Ok(self
+            .page_manager
+            .read()
+            .unwrap()
+            .fetch_page(page_id)?
+            .expect("The pages of the B-tree exist"))
+    }
+
+    fn read_node(page: &SharedInternalPage) -> Node {
+        let page = page.read().unwrap();
+        let entry_count =
+            u32::from_be_bytes(page.metadata[ENTRY_COUNT_RANGE].try_into().unwrap()) as usize;
+        let page_id = |offset: usize| {
+            PageId::from_be_bytes(page.data[offset..offset + CHILD_SIZE].try_into().unwrap())
+        };
+        let key =
+            |offset: usize| i64::from_be_bytes(page.data[offset..offset + 8].try_into().unwrap());
+
+        match page.metadata[NODE_KIND_OFFSET] {
+            INTERNAL => {
+                let mut children = vec![page_id(0)];
+                let mut keys = Vec::with_capacity(entry_count);
+                for position in 0..entry_count {
+                    let offset = CHILD_SIZE + position * INTERNAL_ENTRY_SIZE;
+                    keys.push(key(offset));
+                    children.push(page_id(offset + 8));
+                }
+
+                Node::Internal { keys, children }
+            }
+            _ => Node::Leaf {
+                entries: (0..entry_count)
+                    .map(|position| {
+                        let offset = position * LEAF_ENTRY_SIZE;
+                        (
+                            key(offset),
+                            u64::from_be_bytes(
+                                page.data[offset + 8..offset + LEAF_ENTRY_SIZE]
+                                    .try_into()
+                                    .unwrap(),
+                            ),
+                        )
+                    })
+                    .collect(),
+                next_leaf_page_id: PageId::from_be_bytes(
+                    page.metadata[NEXT_LEAF_PAGE_ID_RANGE].try_into().unwrap(),
+                ),
+            },
+        }
+    }
+
+    fn write_node(page: &SharedInternalPage, node: &Node) {
+        let mut page = page.write().unwrap();
+
+        match node {
+            Node::Leaf {
+                entries,
+                next_leaf_page_id,
+            } => {
+                page.metadata[NODE_KIND_OFFSET] = LEAF;
+                page.metadata[ENTRY_COUNT_RANGE]
+                    .copy_from_slice(&(entries.len() as u32).to_be_bytes());
+                page.metadata[NEXT_LEAF_PAGE_ID_RANGE]
+                    .copy_from_slice(&next_leaf_page_id.to_be_bytes());
+
+                for (position, (key, value)) in entries.iter().enumerate() {
+                    let offset = position * LEAF_ENTRY_SIZE;
+                    page.data[offset..offset + 8].copy_from_slice(&key.to_be_bytes());
+                    page.data[offset + 8..offset + LEAF_ENTRY_SIZE]
+                        .copy_from_slice(&value.to_be_bytes());
+                }
+            }
+            Node::Internal { keys, children } => {
+                page.metadata[NODE_KIND_OFFSET] = INTERNAL;
+                page.metadata[ENTRY_COUNT_RANGE]
+                    .copy_from_slice(&(keys.len() as u32).to_be_bytes());
+                page.metadata[NEXT_LEAF_PAGE_ID_RANGE].fill(0);
+
+                page.data[0..CHILD_SIZE].copy_from_slice(&children[0].to_be_bytes());
+                for (position, (key, child)) in keys.iter().zip(&children[1..]).enumerate() {
+                    let offset = CHILD_SIZE + position * INTERNAL_ENTRY_SIZE;
+                    page.data[offset..offset + 8].copy_from_slice(&key.to_be_bytes());
+                    page.data[offset + 8..offset + INTERNAL_ENTRY_SIZE]
+                        .copy_from_slice(&child.to_be_bytes());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a tree with small nodes, so that it gets a few levels deep with few entries.
+    fn small_tree(page_manager: &Rc<RwLock<PageManager>>) -> BTree {
+        let (_page_id, root) = page_manager.write().unwrap().create_page();
+        let mut tree = BTree::initialize(page_manager.clone(), root);
+        tree.leaf_capacity = 4;
+        tree.internal_capacity = 3;
+
+        tree
+    }
+
+    #[test]
+    fn test_inserting_and_getting_keys() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut tree = small_tree(&page_manager);
+
+        // Keys in a scrambled order, to split nodes in the middle as well as at the ends.
+        let keys: Vec<i64> = (0..200).map(|key| (key * 7919) % 200 - 100).collect();
+        for key in keys.iter() {
+            tree.insert(*key, (*key * 10) as u64).unwrap();
+        }
+        tree.insert(5, 7).unwrap();
+
+        assert_eq!(Some(7), tree.get(5).unwrap());
+        assert_eq!(Some(990), tree.get(99).unwrap());
+        assert_eq!(Some((-100i64 * 10) as u64), tree.get(-100).unwrap());
+        assert_eq!(None, tree.get(100).unwrap());
+        assert_eq!(200, tree.range(..).unwrap().len());
+        assert!(tree.page_count().unwrap() > 50);
+
+        let loaded_tree = BTree::load(page_manager, tree.root.clone());
+        assert_eq!(Some(7), loaded_tree.get(5).unwrap());
+    }
+
+    #[test]
+    fn test_scanning_ranges() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut tree = small_tree(&page_manager);
+        for key in (0..100).rev() {
+            tree.insert(key * 2, key as u64).unwrap();
+        }
+
+        assert_eq!(vec![(10, 5), (12, 6), (14, 7)], tree.range(9..15).unwrap());
+        assert_eq!(vec![(10, 5), (12, 6)], tree.range(10..=12).unwrap());
+        assert_eq!(
+            vec![(12, 6)],
+            tree.range((Bound::Excluded(10), Bound::Excluded(14)))
+                .unwrap()
+        );
+        assert_eq!(vec![(0, 0), (2, 1)], tree.range(..4).unwrap());
+        assert_eq!(vec![(196, 98), (198, 99)], tree.range(195..).unwrap());
+        assert_eq!(Vec::<(i64, u64)>::new(), tree.range(300..).unwrap());
+
+        let keys: Vec<i64> = tree
+            .range(..)
+            .unwrap()
+            .into_iter()
+            .map(|(key, _value)| key)
+            .collect();
+        assert_eq!((0..100).map(|key| key * 2).collect::<Vec<i64>>(), keys);
+    }
+
+    #[test]
+    fn test_removing_keys() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut tree = small_tree(&page_manager);
+        for key in 0..300 {
+            tree.insert(key, key as u64).unwrap();
+        }
+        let page_count = tree.page_count().unwrap();
+
+        assert_eq!(None, tree.remove(300).unwrap());
+        for key in (0..300).filter(|key| key % 3 != 0) {
+            assert_eq!(Some(key as u64), tree.remove(key).unwrap());
+        }
+        assert_eq!(None, tree.get(1).unwrap());
+        assert_eq!(Some(3), tree.get(3).unwrap());
+        assert_eq!(
+            (0..300)
+                .step_by(3)
+                .map(|key| (key, key as u64))
+                .collect::<Vec<_>>(),
+            tree.range(..).unwrap()
+        );
+        assert!(tree.page_count().unwrap() < page_count);
+
+        // Removing every key leaves a single, empty leaf.
+        for key in (0..300).step_by(3) {
+            assert_eq!(Some(key as u64), tree.remove(key).unwrap());
+        }
+        assert_eq!(Vec::<(i64, u64)>::new(), tree.range(..).unwrap());
+        assert_eq!(1, tree.page_count().unwrap());
+
+        tree.insert(42, 1).unwrap();
+        assert_eq!(vec![(42, 1)], tree.range(..).unwrap());
+    }
+}
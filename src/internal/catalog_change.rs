@@ -0,0 +1,32 @@
+/// Receives every change to the catalog, see `Manager::subscribe_to_catalog_changes`.
+pub type CatalogChangeListener = Box<dyn FnMut(&CatalogChange)>;
+
+/// A change made to the catalog by a DDL statement. `schema_version` is the version of the
+/// catalog after the change, so anything cached for an older version is stale.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CatalogChange {
+    pub schema_version: u64,
+    pub kind: CatalogChangeKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CatalogChangeKind {
+    DatabaseCreated {
+        database_name: String,
+    },
+    TableCreated {
+        database_name: String,
+        table_name: String,
+    },
+    SequenceCreated {
+        database_name: String,
+        sequence_name: String,
+    },
+    ColumnAdded {
+        database_name: String,
+        table_name: String,
+        column_name: String,
+    },
+    /// A rollback undid DDL statements, so the catalog is back to how it was before them.
+    RolledBack,
+}
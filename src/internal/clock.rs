@@ -0,0 +1,84 @@
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// The time that statements are timed with and that random numbers are seeded from, see
+/// `Manager::set_clock`.
+pub trait Clock {
+    /// Returns the time since a fixed point in the past, which never goes backwards. Only the
+    /// difference between two calls means anything.
+    fn elapsed(&self) -> Duration;
+
+    /// Returns the time since the Unix epoch.
+    fn since_epoch(&self) -> Duration;
+}
+
+/// The clock of the operating system.
+pub struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    pub fn new() -> Self {
+        Self {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for SystemClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clock for SystemClock {
+    fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn since_epoch(&self) -> Duration {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or(Duration::ZERO)
+    }
+}
+
+/// A clock that always returns zero, for platforms without a clock like `wasm32-unknown-unknown`,
+/// where asking the system for the time panics. Statements take no time, and random numbers are
+/// seeded the same way every time.
+pub struct StoppedClock;
+
+impl Clock for StoppedClock {
+    fn elapsed(&self) -> Duration {
+        Duration::ZERO
+    }
+
+    fn since_epoch(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// Returns the clock of the operating system, or a `StoppedClock` on platforms without one.
+pub fn default_clock() -> Rc<dyn Clock> {
+    if cfg!(all(target_arch = "wasm32", target_os = "unknown")) {
+        Rc::new(StoppedClock)
+    } else {
+        Rc::new(SystemClock::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clocks() {
+        let clock = SystemClock::new();
+        let start = clock.elapsed();
+        assert!(clock.elapsed() >= start);
+        assert!(clock.since_epoch() > Duration::ZERO);
+
+        assert_eq!(Duration::ZERO, StoppedClock.elapsed());
+        assert_eq!(Duration::ZERO, StoppedClock.since_epoch());
+    }
+}
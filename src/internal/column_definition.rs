@@ -5,30 +5,77 @@ pub struct ColumnDefinition {
     column_id: u8,
     data_type: DataType,
     name: String,
+    not_null: bool,
+    primary_key: bool,
+    serial: bool,
 }
 
+/// Set on the stored type id of columns that are `NOT NULL`.
+const NOT_NULL_FLAG: u8 = 0x80;
+/// Set on the stored type id of the `PRIMARY KEY` column.
+const PRIMARY_KEY_FLAG: u8 = 0x40;
+/// Set on the stored type id of `serial` columns.
+const SERIAL_FLAG: u8 = 0x20;
+const FLAGS: u8 = NOT_NULL_FLAG | PRIMARY_KEY_FLAG | SERIAL_FLAG;
+
 impl ColumnDefinition {
     pub fn new(column_id: u8, data_type: DataType, name: String) -> Self {
         Self {
             column_id,
             data_type,
             name,
+            not_null: false,
+            primary_key: false,
+            serial: false,
         }
     }
 
+    pub fn with_not_null(mut self, not_null: bool) -> Self {
+        self.not_null = not_null;
+        self
+    }
+
+    pub fn with_primary_key(mut self, primary_key: bool) -> Self {
+        self.primary_key = primary_key;
+        self
+    }
+
+    pub fn with_serial(mut self, serial: bool) -> Self {
+        self.serial = serial;
+        self
+    }
+
     pub fn from_raw_bytes(bytes: &[u8]) -> Option<ColumnDefinition> {
+        let (data_type, modifiers_length) = DataType::from_type_id(bytes[1] & !FLAGS, &bytes[2..])?;
+
         Some(Self {
             column_id: bytes[0],
-            data_type: DataType::from_type_id(bytes[1])?,
-            name: String::from_utf8(bytes[2..].to_vec()).ok()?,
+            data_type,
+            name: String::from_utf8(bytes[2 + modifiers_length..].to_vec()).ok()?,
+            not_null: bytes[1] & NOT_NULL_FLAG != 0,
+            primary_key: bytes[1] & PRIMARY_KEY_FLAG != 0,
+            serial: bytes[1] & SERIAL_FLAG != 0,
         })
     }
 
     pub fn to_raw_bytes(&self) -> Vec<u8> {
-        let mut column_definition = Vec::with_capacity(2 + self.name.len());
-        column_definition.push(2 + self.name.len() as u8);
+        let modifiers = self.data_type().bsql_type_modifiers();
+        let length = 2 + modifiers.len() + self.name.len();
+        let mut column_definition = Vec::with_capacity(1 + length);
+        column_definition.push(length as u8);
         column_definition.push(self.column_id);
-        column_definition.push(self.data_type().bsql_type_id());
+        let mut type_id = self.data_type().bsql_type_id();
+        if self.not_null {
+            type_id |= NOT_NULL_FLAG;
+        }
+        if self.primary_key {
+            type_id |= PRIMARY_KEY_FLAG;
+        }
+        if self.serial {
+            type_id |= SERIAL_FLAG;
+        }
+        column_definition.push(type_id);
+        column_definition.extend_from_slice(&modifiers);
         column_definition.extend_from_slice(self.name.as_bytes());
 
         return column_definition;
@@ -45,11 +92,26 @@ impl ColumnDefinition {
     pub fn name(&self) -> &String {
         &self.name
     }
+
+    pub fn is_not_null(&self) -> bool {
+        self.not_null
+    }
+
+    pub fn is_primary_key(&self) -> bool {
+        self.primary_key
+    }
+
+    /// Returns whether the column is `serial`, which means that records get the next value of the
+    /// table's counter when they're inserted without a value for it.
+    pub fn is_serial(&self) -> bool {
+        self.serial
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::internal::arbitrary;
 
     #[test]
     fn test_serializing_and_deserializing_into_bytes() {
@@ -61,4 +123,78 @@ mod tests {
         let deserialized_column = ColumnDefinition::from_raw_bytes(&serialized_column[1..]);
         assert_eq!(Some(column_definition), deserialized_column);
     }
+
+    #[test]
+    fn test_serialization_round_trips() {
+        arbitrary::check(|random| {
+            for column_definition in arbitrary::column_definitions(random, 8) {
+                let serialized_column = column_definition.to_raw_bytes();
+
+                assert_eq!(serialized_column.len() - 1, serialized_column[0] as usize);
+                assert_eq!(
+                    Some(column_definition),
+                    ColumnDefinition::from_raw_bytes(&serialized_column[1..])
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn test_serializing_numeric_columns() {
+        let column_definition = ColumnDefinition::new(
+            2,
+            DataType::Numeric {
+                precision: 10,
+                scale: 2,
+            },
+            "price".to_string(),
+        );
+
+        let serialized_column: Vec<u8> = column_definition.to_raw_bytes();
+        assert_eq!(
+            vec![9, 2, 3, 10, 2, 112, 114, 105, 99, 101],
+            serialized_column
+        );
+
+        let deserialized_column = ColumnDefinition::from_raw_bytes(&serialized_column[1..]);
+        assert_eq!(Some(column_definition), deserialized_column);
+    }
+
+    #[test]
+    fn test_serializing_not_null_columns() {
+        let column_definition =
+            ColumnDefinition::new(1, DataType::Integer, "id".to_string()).with_not_null(true);
+
+        let serialized_column: Vec<u8> = column_definition.to_raw_bytes();
+        assert_eq!(vec![4, 1, 0x81, 105, 100], serialized_column);
+
+        let deserialized_column = ColumnDefinition::from_raw_bytes(&serialized_column[1..]);
+        assert_eq!(Some(column_definition), deserialized_column);
+    }
+
+    #[test]
+    fn test_serializing_primary_key_columns() {
+        let column_definition = ColumnDefinition::new(1, DataType::Integer, "id".to_string())
+            .with_not_null(true)
+            .with_primary_key(true);
+
+        let serialized_column: Vec<u8> = column_definition.to_raw_bytes();
+        assert_eq!(vec![4, 1, 0xC1, 105, 100], serialized_column);
+
+        let deserialized_column = ColumnDefinition::from_raw_bytes(&serialized_column[1..]);
+        assert_eq!(Some(column_definition), deserialized_column);
+    }
+
+    #[test]
+    fn test_serializing_serial_columns() {
+        let column_definition = ColumnDefinition::new(1, DataType::Integer, "id".to_string())
+            .with_not_null(true)
+            .with_serial(true);
+
+        let serialized_column: Vec<u8> = column_definition.to_raw_bytes();
+        assert_eq!(vec![4, 1, 0xA1, 105, 100], serialized_column);
+
+        let deserialized_column = ColumnDefinition::from_raw_bytes(&serialized_column[1..]);
+        assert_eq!(Some(column_definition), deserialized_column);
+    }
 }
@@ -0,0 +1,185 @@
+use std::io::BufRead;
+
+use super::Error;
+
+/// Reads the records of a CSV file one at a time, so that files don't have to fit in memory.
+///
+/// Fields are separated by commas and can be quoted with `"`, where `""` is a quote inside a quoted
+/// field and quoted fields can span lines. Like in PostgreSQL, an empty field that isn't quoted is
+/// `NULL` and read as `None`, while `""` is an empty string. Empty lines are skipped.
+pub struct CsvReader<R: BufRead> {
+    reader: R,
+    line_number: usize,
+}
+
+impl<R: BufRead> CsvReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line_number: 0,
+        }
+    }
+
+    /// Returns the line that the last record ended on, counting from 1.
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    /// Reads the next line without its line ending, or `None` at the end of the file.
+    fn read_line(&mut self) -> Result<Option<String>, Error> {
+        let mut line = String::new();
+        let read = self
+            .reader
+            .read_line(&mut line)
+            .map_err(|error| Error::CouldNotReadFile(error.to_string()))?;
+        if read == 0 {
+            return Ok(None);
+        }
+
+        self.line_number += 1;
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+
+        Ok(Some(line))
+    }
+
+    fn read_record(&mut self) -> Result<Option<Vec<Option<String>>>, Error> {
+        let mut line = loop {
+            match self.read_line()? {
+                Some(line) if line.is_empty() => continue,
+                Some(line) => break line,
+                None => return Ok(None),
+            }
+        };
+
+        let mut fields = vec![];
+        let mut field = String::new();
+        let mut quoted = false;
+        let mut in_quotes = false;
+        let mut position = 0;
+
+        loop {
+            let Some(character) = line[position..].chars().next() else {
+                if in_quotes {
+                    // The quoted field continues on the next line.
+                    let next_line = self.read_line()?.ok_or_else(|| {
+                        Error::BadCopyFileFormat(format!(
+                            "unterminated quoted field on line {}",
+                            self.line_number
+                        ))
+                    })?;
+                    field.push('\n');
+                    line = next_line;
+                    position = 0;
+                    continue;
+                }
+
+                fields.push((quoted || !field.is_empty()).then_some(field));
+                return Ok(Some(fields));
+            };
+            position += character.len_utf8();
+
+            match (in_quotes, character) {
+                (true, '"') if line[position..].starts_with('"') => {
+                    field.push('"');
+                    position += 1;
+                }
+                (true, '"') => in_quotes = false,
+                (true, character) => field.push(character),
+
+                (false, ',') => {
+                    fields.push((quoted || !field.is_empty()).then_some(field));
+                    field = String::new();
+                    quoted = false;
+                }
+                (false, '"') if !quoted && field.is_empty() => {
+                    quoted = true;
+                    in_quotes = true;
+                }
+                (false, '"') => {
+                    return Err(Error::BadCopyFileFormat(format!(
+                        "unexpected quote on line {}",
+                        self.line_number
+                    )))
+                }
+                (false, _) if quoted => {
+                    return Err(Error::BadCopyFileFormat(format!(
+                        "unexpected character after a quoted field on line {}",
+                        self.line_number
+                    )))
+                }
+                (false, character) => field.push(character),
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for CsvReader<R> {
+    type Item = Result<Vec<Option<String>>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(input: &str) -> Result<Vec<Vec<Option<String>>>, Error> {
+        CsvReader::new(input.as_bytes()).collect()
+    }
+
+    fn text(value: &str) -> Option<String> {
+        Some(value.to_string())
+    }
+
+    #[test]
+    fn test_reading_records() {
+        assert_eq!(
+            Ok(vec![
+                vec![text("1"), text("alice")],
+                vec![text("2"), None],
+                vec![None, text("")],
+            ]),
+            read("1,alice\r\n2,\n\n,\"\"\n")
+        );
+    }
+
+    #[test]
+    fn test_reading_quoted_fields() {
+        assert_eq!(
+            Ok(vec![
+                vec![text("a, \"b\""), text("c")],
+                vec![text("two\nlines"), text("d")],
+            ]),
+            read("\"a, \"\"b\"\"\",c\n\"two\nlines\",d")
+        );
+    }
+
+    #[test]
+    fn test_reading_malformed_records() {
+        assert_eq!(
+            Err(Error::BadCopyFileFormat(
+                "unterminated quoted field on line 2".to_string()
+            )),
+            read("1,2\n\"3,4\n")
+        );
+        assert_eq!(
+            Err(Error::BadCopyFileFormat(
+                "unexpected character after a quoted field on line 1".to_string()
+            )),
+            read("\"1\"2,3\n")
+        );
+        assert_eq!(
+            Err(Error::BadCopyFileFormat(
+                "unexpected quote on line 1".to_string()
+            )),
+            read("1\"2,3\n")
+        );
+    }
+}
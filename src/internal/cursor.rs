@@ -0,0 +1,111 @@
+use super::row_result::RowValues;
+use super::{Error, RowResult};
+
+/// The rows of a cursor, which are read from the table as they are fetched.
+pub type CursorRows = Box<dyn Iterator<Item = Result<RowValues, Error>>>;
+
+/// Evaluates the select list of the cursor's query for the rows of a fetch.
+pub type CursorProjection = Box<dyn Fn(&RowResult) -> Result<RowResult, Error>>;
+
+/// The result of a query that is handed out a few rows at a time, see `DECLARE ... CURSOR`.
+///
+/// The cursor holds an iterator over the table instead of the rows, so only the pages of the rows
+/// that are fetched are read. Changes to those pages after the cursor was declared are seen by
+/// the fetches that read them.
+pub struct Cursor {
+    columns: Vec<String>,
+    rows: CursorRows,
+    projection: Option<CursorProjection>,
+}
+
+impl Cursor {
+    pub fn new(columns: Vec<String>, rows: CursorRows) -> Self {
+        Self {
+            columns,
+            rows,
+            projection: None,
+        }
+    }
+
+    /// A cursor over the rows of a query that has already been executed.
+    pub fn from_row_result(row_result: RowResult) -> Self {
+        Self::new(
+            row_result.columns().clone(),
+            Box::new(row_result.rows().to_vec().into_iter().map(Ok)),
+        )
+    }
+
+    /// Applies `projection` to the rows of every fetch, before they're returned.
+    pub fn with_projection(mut self, projection: CursorProjection) -> Self {
+        self.projection = Some(projection);
+        self
+    }
+
+    /// Returns the next `count` rows, or fewer once the cursor runs out of rows.
+    pub fn fetch(&mut self, count: usize) -> Result<RowResult, Error> {
+        let rows = RowResult::new(
+            self.columns.clone(),
+            self.rows
+                .by_ref()
+                .take(count)
+                .collect::<Result<Vec<RowValues>, Error>>()?,
+        );
+
+        match &self.projection {
+            Some(projection) => projection(&rows),
+            None => Ok(rows),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::Value;
+
+    #[test]
+    fn test_fetching_rows() {
+        let mut cursor = Cursor::new(
+            vec!["id".to_string()],
+            Box::new((0..5).map(|id| Ok(vec![Some(Value::Integer(id))]))),
+        );
+
+        assert_eq!(2, cursor.fetch(2).unwrap().rows().len());
+
+        let row_result = cursor.fetch(10).unwrap();
+        assert_eq!(&vec!["id".to_string()], row_result.columns());
+        assert_eq!(
+            vec![
+                vec![Some(Value::Integer(2))],
+                vec![Some(Value::Integer(3))],
+                vec![Some(Value::Integer(4))],
+            ],
+            row_result.rows()
+        );
+
+        assert!(cursor.fetch(1).unwrap().rows().is_empty());
+    }
+
+    #[test]
+    fn test_fetching_projected_rows() {
+        let mut cursor = Cursor::new(
+            vec!["id".to_string()],
+            Box::new((0..3).map(|id| match id {
+                2 => Err(Error::DivisionByZero),
+                id => Ok(vec![Some(Value::Integer(id))]),
+            })),
+        )
+        .with_projection(Box::new(|rows| {
+            Ok(RowResult::new(
+                vec!["count".to_string()],
+                vec![vec![Some(Value::Integer(rows.rows().len() as i64))]],
+            ))
+        }));
+
+        assert_eq!(
+            vec![vec![Some(Value::Integer(2))]],
+            cursor.fetch(2).unwrap().rows()
+        );
+        assert_eq!(Err(Error::DivisionByZero), cursor.fetch(2));
+    }
+}
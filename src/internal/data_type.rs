@@ -1,22 +1,118 @@
-use super::Value;
+use super::parser::{CastType, DataTypeIdentifier};
+use super::Error;
+use super::{Decimal, Random, Uuid, Value};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum DataType {
     Integer,
+    Text,
+    /// A `numeric(precision, scale)`, whose values have `scale` digits after the point and at most
+    /// `precision` digits in total.
+    Numeric {
+        precision: u8,
+        scale: u8,
+    },
+    /// Binary data, which is rendered in the hex format, e.g. `\xdeadbeef`.
+    Bytea,
+    Uuid,
+    /// Text that is valid JSON, see `json::Json`.
+    Json,
 }
 
 impl std::fmt::Display for DataType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             DataType::Integer => write!(f, "integer"),
+            DataType::Text => write!(f, "text"),
+            DataType::Numeric { precision, scale } => write!(f, "numeric({},{})", precision, scale),
+            DataType::Bytea => write!(f, "bytea"),
+            DataType::Uuid => write!(f, "uuid"),
+            DataType::Json => write!(f, "json"),
+        }
+    }
+}
+
+impl From<DataTypeIdentifier> for DataType {
+    fn from(value: DataTypeIdentifier) -> Self {
+        match value {
+            // `serial` columns are integer columns with a flag, see `ColumnDefinition::is_serial`.
+            DataTypeIdentifier::Integer | DataTypeIdentifier::Serial => DataType::Integer,
+            DataTypeIdentifier::Text => DataType::Text,
+            DataTypeIdentifier::Numeric { precision, scale } => {
+                DataType::Numeric { precision, scale }
+            }
+            DataTypeIdentifier::Bytea => DataType::Bytea,
+            DataTypeIdentifier::Uuid => DataType::Uuid,
+            DataTypeIdentifier::Json => DataType::Json,
         }
     }
 }
 
 impl DataType {
-    pub fn from_type_id(type_id: u8) -> Option<Self> {
+    /// Converts `text` to a value of the type, the way `CAST(text AS type)` does.
+    pub fn parse_text(&self, text: String) -> Result<Value, Error> {
+        match self {
+            DataType::Integer => Value::Text(text).cast(CastType::Integer),
+            DataType::Text => Ok(Value::Text(text)),
+            DataType::Numeric { .. } => Value::Text(text).cast(CastType::Numeric),
+            DataType::Bytea => Value::Text(text).cast(CastType::Bytea),
+            DataType::Uuid => Value::Text(text).cast(CastType::Uuid),
+            DataType::Json => Value::Text(text).cast(CastType::Json),
+        }
+    }
+
+    /// Converts `value` to the way it's stored in a column of this type. Numbers are rounded to
+    /// the scale of `numeric` columns, numbers without a fraction are converted for `integer`
+    /// columns, text is read as a UUID for `uuid` columns and checked to be valid JSON for `json`
+    /// columns, and other values are returned as they are (see `DataType::accepts`).
+    pub fn coerce(&self, value: Value) -> Result<Value, Error> {
+        let (precision, scale, value) = match (self, value) {
+            (DataType::Numeric { precision, scale }, value) => (precision, scale, value),
+            (DataType::Integer, Value::Decimal(decimal)) => {
+                return match decimal.rescale(0) {
+                    Some(integer) if integer == decimal => integer
+                        .to_integer()
+                        .map(Value::Integer)
+                        .ok_or(Error::IntegerOutOfRange),
+                    _ => Ok(Value::Decimal(decimal)),
+                };
+            }
+            (DataType::Uuid, value @ Value::Text(_)) => return value.cast(CastType::Uuid),
+            (DataType::Json, value @ Value::Text(_)) => return value.cast(CastType::Json),
+            (_, value) => return Ok(value),
+        };
+
+        let decimal = match value {
+            Value::Integer(value) => Decimal::from(value),
+            Value::Decimal(value) => value,
+            value => return Ok(value),
+        };
+
+        match decimal.rescale(*scale) {
+            Some(decimal) if decimal.fits_precision(*precision) => Ok(Value::Decimal(decimal)),
+            _ => Err(Error::NumericValueOutOfRange),
+        }
+    }
+
+    /// Reads the type from its stored type id and the modifiers that follow it. Returns the type
+    /// together with the number of bytes of modifiers.
+    pub fn from_type_id(type_id: u8, modifiers: &[u8]) -> Option<(Self, usize)> {
         match type_id {
-            1 => Some(DataType::Integer),
+            1 => Some((DataType::Integer, 0)),
+            2 => Some((DataType::Text, 0)),
+            3 => match modifiers {
+                [precision, scale, ..] => Some((
+                    DataType::Numeric {
+                        precision: *precision,
+                        scale: *scale,
+                    },
+                    2,
+                )),
+                _ => None,
+            },
+            4 => Some((DataType::Bytea, 0)),
+            5 => Some((DataType::Uuid, 0)),
+            6 => Some((DataType::Json, 0)),
 
             _ => None,
         }
@@ -24,19 +120,106 @@ impl DataType {
 
     pub fn to_bsql_value(data_type: &Self, data: &[u8]) -> Option<Value> {
         match data_type {
-            DataType::Integer => Some(Value::Integer(*data.first()?)),
+            // Records of the older `RecordFormat`s store integers in a single unsigned byte.
+            DataType::Integer => match data {
+                [value] => Some(Value::Integer(*value as i64)),
+                _ => Some(Value::Integer(i64::from_be_bytes(data.try_into().ok()?))),
+            },
+            DataType::Text => Some(Value::Text(String::from_utf8(data.to_vec()).ok()?)),
+            DataType::Numeric { scale, .. } => Some(Value::Decimal(Decimal::new(
+                i128::from_be_bytes(data.try_into().ok()?),
+                *scale,
+            ))),
+            DataType::Bytea => Some(Value::Bytes(data.to_vec())),
+            DataType::Uuid => Some(Value::Uuid(Uuid::from_bytes(data.try_into().ok()?))),
+            DataType::Json => Some(Value::Json(String::from_utf8(data.to_vec()).ok()?)),
+        }
+    }
+
+    /// Returns an arbitrary value of this type.
+    pub fn random_value(&self, random: &mut Random) -> Value {
+        match self {
+            DataType::Integer => Value::Integer(random.next_u64() as i64),
+            DataType::Text => Value::Text(
+                (0..random.next_u64() % 64)
+                    .map(|_| char::from(b' ' + (random.next_u64() % 95) as u8))
+                    .collect(),
+            ),
+            DataType::Numeric { precision, scale } => {
+                let digits = ((random.next_u64() as u128) << 64 | random.next_u64() as u128)
+                    % 10u128.pow(*precision as u32);
+                let sign = if random.next_u64() % 2 == 0 { 1 } else { -1 };
+                Value::Decimal(Decimal::new(sign * digits as i128, *scale))
+            }
+            DataType::Bytea => Value::Bytes(
+                (0..random.next_u64() % 64)
+                    .map(|_| random.next_u64() as u8)
+                    .collect(),
+            ),
+            DataType::Uuid => Value::Uuid(Uuid::new_v4(random.next_u128())),
+            DataType::Json => Value::Json(format!(
+                "{{\"id\": {}, \"tags\": [{}]}}",
+                random.next_u64() as i64,
+                random.next_u64() % 10
+            )),
         }
     }
 
+    /// Returns whether `value` can be stored in a column of this type.
+    /// Returns whether `value` can be stored in a column of this type as it is.
+    pub fn accepts(&self, value: &Value) -> bool {
+        matches!(
+            (self, value),
+            (DataType::Integer, Value::Integer(_))
+                | (DataType::Text, Value::Text(_))
+                | (DataType::Numeric { .. }, Value::Decimal(_))
+                | (DataType::Bytea, Value::Bytes(_))
+                | (DataType::Uuid, Value::Uuid(_))
+                | (DataType::Json, Value::Json(_))
+        )
+    }
+
     pub fn bsql_type_id(&self) -> u8 {
         match self {
             DataType::Integer => 1,
+            DataType::Text => 2,
+            DataType::Numeric { .. } => 3,
+            DataType::Bytea => 4,
+            DataType::Uuid => 5,
+            DataType::Json => 6,
         }
     }
 
-    pub fn bsql_size(&self) -> u8 {
+    /// Returns the modifiers that are stored after the type id, see `from_type_id`.
+    pub fn bsql_type_modifiers(&self) -> Vec<u8> {
         match self {
-            DataType::Integer => 1,
+            DataType::Numeric { precision, scale } => vec![*precision, *scale],
+            _ => vec![],
+        }
+    }
+
+    /// Returns the size of the values in records, or `None` when the values have different sizes
+    /// and are stored with `RecordFormat::LengthPrefixed`.
+    pub fn bsql_size(&self) -> Option<u8> {
+        match self {
+            DataType::Integer => Some(8),
+            DataType::Text => None,
+            DataType::Numeric { .. } => Some(16),
+            DataType::Bytea => None,
+            DataType::Uuid => Some(16),
+            DataType::Json => None,
+        }
+    }
+
+    /// Returns the alignment of the values in records, see `RecordFormat::Aligned`.
+    pub fn bsql_alignment(&self) -> u8 {
+        match self {
+            DataType::Integer => 8,
+            DataType::Text => 1,
+            DataType::Numeric { .. } => 8,
+            DataType::Bytea => 1,
+            DataType::Uuid => 1,
+            DataType::Json => 1,
         }
     }
 }
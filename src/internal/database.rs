@@ -1,33 +1,42 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use std::sync::RwLock;
 
+use super::row_result::RowValues;
 use crate::PageManager;
 
 use super::{
-    ColumnDefinition, DataType, Error, PageId, RowResult, SharedInternalPage, TableManager, Value,
+    ColumnDefinition, DataType, Error, PageId, Random, SharedInternalPage, TableManager, TablePage,
+    Value,
 };
 
 type TableId = u64;
+/// The page ids of the tables of a database by name, see `Database::table_page_ids_by_name`.
+pub type TablePageIds = Rc<RefCell<HashMap<String, PageId>>>;
 
 const TABLE_MANAGER_PAGE_IDS_OFFSET: usize = 64;
+const PAGE_QUOTA_RANGE: std::ops::Range<usize> = 2048..2052;
+/// The number of sequences, followed by the name and the last value of every sequence.
+const SEQUENCES_OFFSET: usize = 2052;
+/// The length of a sequence name, followed by the name itself.
+const SEQUENCE_NAME_LENGTH: usize = 64;
+const SEQUENCE_ENTRY_LENGTH: usize = SEQUENCE_NAME_LENGTH + 8;
+/// The number of sequences that fit in the metadata page of a database.
+const MAX_SEQUENCES: usize = (4096 - SEQUENCES_OFFSET - 1) / SEQUENCE_ENTRY_LENGTH;
 
 pub struct Database {
     page_manager: Rc<RwLock<PageManager>>,
 
     page: SharedInternalPage,
     next_table_id: TableId,
+
+    /// Read when the database is loaded, unless it's passed to `load_with_table_page_ids`, and
+    /// kept up to date by `create_table`.
+    table_page_ids_by_name: TablePageIds,
 }
 
 impl Database {
-    pub fn new(page_manager: Rc<RwLock<PageManager>>, name: &str) -> Result<Self, Error> {
-        let (_page_id, shared_page) = {
-            let mut page_manager = page_manager.write().unwrap();
-            page_manager.create_page()
-        };
-
-        return Self::initialize(page_manager, shared_page, name);
-    }
-
     pub fn initialize(
         page_manager: Rc<RwLock<PageManager>>,
         shared_page: SharedInternalPage,
@@ -44,21 +53,54 @@ impl Database {
 
             page: shared_page,
             next_table_id: 0,
+
+            table_page_ids_by_name: Rc::new(RefCell::new(HashMap::new())),
         })
     }
 
     pub fn load(
         page_manager: Rc<RwLock<PageManager>>,
         shared_page: SharedInternalPage,
+    ) -> Result<Self, Error> {
+        let database = Self::load_with_table_page_ids(
+            page_manager,
+            shared_page,
+            Rc::new(RefCell::new(HashMap::new())),
+        )?;
+        let table_page_ids_by_name = database
+            .table_managers()?
+            .iter()
+            .map(|table_manager| table_manager.name())
+            .zip(database.table_manager_page_ids())
+            .collect();
+        *database.table_page_ids_by_name.borrow_mut() = table_page_ids_by_name;
+
+        Ok(database)
+    }
+
+    /// Like `load`, but takes the page ids of the tables from an earlier `table_page_ids`, so the
+    /// metadata pages of the tables aren't read. The tables that this database creates are added
+    /// to them.
+    pub fn load_with_table_page_ids(
+        page_manager: Rc<RwLock<PageManager>>,
+        shared_page: SharedInternalPage,
+        table_page_ids_by_name: TablePageIds,
     ) -> Result<Self, Error> {
         Ok(Self {
             page_manager,
 
             page: shared_page,
             next_table_id: 0,
+
+            table_page_ids_by_name,
         })
     }
 
+    /// Returns the page ids of the tables by name, which are shared with this database.
+    pub fn table_page_ids(&self) -> TablePageIds {
+        self.table_page_ids_by_name.clone()
+    }
+
     pub fn name(&self) -> String {
         let page = self.page.read().ok().unwrap();
 
@@ -67,20 +109,43 @@ impl Database {
         String::from_utf8(name_bytes.to_vec()).unwrap()
     }
 
-    pub fn table_names(&self) -> Vec<String> {
-        self.table_managers()
+    /// Returns the maximum number of pages the database may use, if a quota has been set.
+    pub fn page_quota(&self) -> Option<u32> {
+        let page = self.page.read().unwrap();
+        let quota = u32::from_le_bytes(page.metadata[PAGE_QUOTA_RANGE].try_into().unwrap());
+
+        if quota == 0 {
+            None
+        } else {
+            Some(quota)
+        }
+    }
+
+    pub fn set_page_quota(&mut self, quota: Option<u32>) {
+        let mut page = self.page.write().unwrap();
+        page.metadata[PAGE_QUOTA_RANGE].copy_from_slice(&quota.unwrap_or(0).to_le_bytes());
+    }
+
+    /// Returns the number of pages used by the database, including its own metadata page.
+    pub fn page_count(&self) -> Result<u32, Error> {
+        let mut page_count = 1;
+        for table_manager in self.table_managers()? {
+            page_count += table_manager.page_count()?;
+        }
+
+        Ok(page_count)
+    }
+
+    pub fn table_names(&self) -> Result<Vec<String>, Error> {
+        Ok(self
+            .table_managers()?
             .iter()
             .map(|t| t.name().clone())
-            .collect()
+            .collect())
     }
 
     pub fn column_definitions(&self, table_name: &str) -> Result<Vec<ColumnDefinition>, Error> {
-        Ok(self
-            .table_managers()
-            .iter()
-            .find(|t| t.name() == table_name)
-            .ok_or(Error::TableDoesNotExist(table_name.to_string()))?
-            .column_definitions())
+        Ok(self.table_manager(table_name)?.column_definitions())
     }
 
     pub fn create_table(
@@ -89,6 +154,8 @@ impl Database {
         columns: Vec<(String, DataType)>,
     ) -> Result<TableId, Error> {
         if !self.table_exists(table_name) {
+            self.ensure_page_available()?;
+
             let table_id = self.next_table_id;
 
             let (page_id, shared_page) = {
@@ -106,6 +173,9 @@ impl Database {
             table_manager_page_ids.push(page_id);
 
             Self::write_metadata_page(self.page.clone(), &self.name(), table_manager_page_ids);
+            self.table_page_ids_by_name
+                .borrow_mut()
+                .insert(table_name.to_string(), page_id);
 
             self.next_table_id += 1;
 
@@ -115,74 +185,564 @@ impl Database {
         }
     }
 
-    pub fn add_column(
+    /// Makes the column `NOT NULL`, which fails if the table has records where it's `NULL`.
+    pub fn set_not_null(&mut self, table_name: &str, column_name: &str) -> Result<(), Error> {
+        self.table_manager(table_name)?.set_not_null(column_name)
+    }
+
+    /// Makes the column the `PRIMARY KEY` of the table, see `TableManager::set_primary_key`.
+    pub fn set_primary_key(&mut self, table_name: &str, column_name: &str) -> Result<(), Error> {
+        if !self.table_exists(table_name) {
+            return Err(Error::TableDoesNotExist(table_name.to_string()));
+        }
+        self.ensure_page_available()?;
+
+        self.table_manager(table_name)?.set_primary_key(column_name)
+    }
+
+    /// Makes the column `serial`, see `TableManager::set_serial`.
+    pub fn set_serial(&mut self, table_name: &str, column_name: &str) -> Result<(), Error> {
+        self.table_manager(table_name)?.set_serial(column_name)
+    }
+
+    /// Sets the fill factor of the table, see `TableManager::set_fill_factor`.
+    pub fn set_fill_factor(&mut self, table_name: &str, fill_factor: u8) -> Result<(), Error> {
+        self.table_manager(table_name)?.set_fill_factor(fill_factor)
+    }
+
+    /// Vacuums the table, or every table of the database when `table_name` is `None`, see
+    /// `TableManager::vacuum`. Returns the number of freed pages. Fails with
+    /// `Error::PageQuotaExceeded` before any pages of a table are freed when its records would need
+    /// more pages than the quota leaves, e.g. because its fill factor was lowered.
+    pub fn vacuum(&mut self, table_name: Option<&str>) -> Result<u32, Error> {
+        let mut table_managers = self.table_managers()?;
+        if let Some(table_name) = table_name {
+            table_managers.retain(|t| t.name() == table_name);
+            if table_managers.is_empty() {
+                return Err(Error::TableDoesNotExist(table_name.to_string()));
+            }
+        }
+
+        let mut freed_page_count = 0;
+        for table_manager in table_managers.iter_mut() {
+            let max_data_pages = match self.page_quota() {
+                Some(quota) => {
+                    Some(quota.saturating_sub(self.page_count()? - table_manager.data_page_count()))
+                }
+                None => None,
+            };
+            freed_page_count += table_manager
+                .vacuum(max_data_pages)?
+                .ok_or_else(|| Error::PageQuotaExceeded(self.name()))?;
+        }
+
+        Ok(freed_page_count)
+    }
+
+    /// Returns the row with the given record id.
+    pub fn select_record(
+        &self,
+        table_name: &str,
+        record_id: u64,
+    ) -> Result<Option<RowValues>, Error> {
+        Ok(self
+            .table_manager(table_name)?
+            .get_record(record_id)?
+            .and_then(|row_result| row_result.rows().first().cloned()))
+    }
+
+    /// Returns the name of the `PRIMARY KEY` column of the table, if it has one.
+    pub fn primary_key(&self, table_name: &str) -> Result<Option<String>, Error> {
+        Ok(self
+            .table_manager(table_name)?
+            .primary_key()
+            .map(|column_definition| column_definition.name().clone()))
+    }
+
+    /// Returns the row whose primary key is `key` together with its record id, using the primary
+    /// key index instead of scanning the table.
+    pub fn find_by_primary_key(
+        &self,
+        table_name: &str,
+        key: &Value,
+    ) -> Result<Option<(u64, RowValues)>, Error> {
+        let table_manager = self.table_manager(table_name)?;
+
+        let Some(record_id) = table_manager.find_by_primary_key(key)? else {
+            return Ok(None);
+        };
+        Ok(table_manager
+            .get_record(record_id)?
+            .and_then(|row_result| Some((record_id, row_result.rows().first()?.clone()))))
+    }
+
+    pub fn insert_row(
         &mut self,
         table_name: &str,
-        column_name: &str,
-        data_type: DataType,
-    ) -> Result<String, Error> {
-        let mut table_managers = self.table_managers();
-        let table_manager = table_managers
-            .iter_mut()
-            .find(|t| t.name() == table_name)
-            .ok_or(Error::TableDoesNotExist(table_name.to_string()))?;
+        values: Vec<Option<Value>>,
+    ) -> Result<u64, Error> {
+        self.insert(table_name, None, |table_manager| {
+            Ok((table_manager.column_definitions(), values))
+        })
+    }
 
-        table_manager.add_column(column_name, data_type)?;
+    /// Inserts a row with values for only some of the columns, the other columns are `NULL`.
+    pub fn insert_row_into_columns(
+        &mut self,
+        table_name: &str,
+        column_names: &[String],
+        values: Vec<Option<Value>>,
+    ) -> Result<u64, Error> {
+        self.insert(table_name, None, |table_manager| {
+            table_manager.resolve_columns(column_names, values)
+        })
+    }
 
-        return Ok("ALTER TABLE".to_string());
+    /// Inserts rows with values for only some of the columns like `insert_row_into_columns`, but
+    /// writes them together with `TableManager::insert_records`. Nothing is inserted when one of
+    /// the rows can't be. Returns the record ids of the rows.
+    pub fn insert_rows_into_columns(
+        &mut self,
+        table_name: &str,
+        column_names: &[String],
+        rows: Vec<Vec<Option<Value>>>,
+    ) -> Result<Vec<u64>, Error> {
+        let mut table_manager = self.table_manager(table_name)?;
+        let primary_key = table_manager.primary_key();
+
+        // The primary key index doesn't have the keys of the rows before they're written, so
+        // duplicates among them are found with the keys that were checked so far.
+        let mut keys: HashSet<i64> = HashSet::new();
+        let mut records = Vec::with_capacity(rows.len());
+        for values in rows {
+            let (column_definitions, values) =
+                table_manager.resolve_columns(column_names, values)?;
+            let (column_definitions, values) =
+                Self::check_record(&mut table_manager, None, column_definitions, values)?;
+
+            if let Some(primary_key) = &primary_key {
+                let key = column_definitions
+                    .iter()
+                    .position(|column_definition| {
+                        column_definition.column_id() == primary_key.column_id()
+                    })
+                    .and_then(|position| values[position].clone());
+                if let Some(Value::Integer(key)) = key {
+                    if !keys.insert(key) {
+                        return Err(Error::UniqueViolation(primary_key.name().clone()));
+                    }
+                }
+            }
+
+            records.push((column_definitions, values));
+        }
+
+        let max_new_pages = match self.page_quota() {
+            Some(quota) => Some(quota.saturating_sub(self.page_count()?)),
+            None => None,
+        };
+        match table_manager.insert_records(records, max_new_pages)? {
+            Some(record_ids) => Ok(record_ids),
+            None => {
+                self.ensure_page_available()?;
+                Self::ensure_table_page_available(&table_manager)?;
+                Err(Error::InsertFailed)
+            }
+        }
     }
 
-    pub fn insert_row(&mut self, table_name: &str, values: Vec<Value>) -> Result<u64, Error> {
-        let mut table_managers = self.table_managers();
+    /// Replaces the record with `row`, which has a value for every column of the table. The
+    /// record is moved to a slot in a page for all of the columns (with its `NULL` values in the
+    /// record's bitmap), so it may get a new record id.
+    pub fn update_row(
+        &mut self,
+        table_name: &str,
+        record_id: u64,
+        row: RowValues,
+    ) -> Result<u64, Error> {
+        self.insert(table_name, Some(record_id), |table_manager| {
+            Ok((table_manager.column_definitions(), row))
+        })
+    }
 
-        table_managers
-            .iter_mut()
-            .find(|t| t.name() == table_name)
-            .ok_or(Error::TableDoesNotExist(table_name.to_string()))?
-            .insert_record(values)
-            .ok_or(Error::InsertFailed)
+    /// Inserts a record for the columns and values returned by `columns`. When the record replaces
+    /// `replaced_record_id` it may have the same primary key, and the replaced record is deleted.
+    fn insert(
+        &mut self,
+        table_name: &str,
+        replaced_record_id: Option<u64>,
+        columns: impl FnOnce(
+            &TableManager,
+        ) -> Result<(Vec<ColumnDefinition>, Vec<Option<Value>>), Error>,
+    ) -> Result<u64, Error> {
+        let mut table_manager = self.table_manager(table_name)?;
+
+        let (column_definitions, values) = columns(&table_manager)?;
+        let (column_definitions, values) = Self::check_record(
+            &mut table_manager,
+            replaced_record_id,
+            column_definitions,
+            values,
+        )?;
+        if !table_manager.has_writable_page(&column_definitions, &values)? {
+            self.ensure_page_available()?;
+            Self::ensure_table_page_available(&table_manager)?;
+        }
+
+        match replaced_record_id {
+            Some(record_id) => table_manager.update_record(record_id, column_definitions, values),
+            None => table_manager.insert_record_into_columns(column_definitions, values),
+        }?
+        .ok_or(Error::InsertFailed)
     }
 
-    pub fn select_all_columns(&self, table_name: &str) -> Result<RowResult, Error> {
-        Ok(self
-            .table_managers()
-            .iter()
-            .find(|t| t.name() == table_name)
-            .ok_or(Error::TableDoesNotExist(table_name.to_string()))?
-            .get_records())
+    /// Converts the values of a record to the types of their columns, assigns the values of the
+    /// `serial` columns, and checks the `NOT NULL` and `PRIMARY KEY` constraints and the size of
+    /// the record. Returns the columns and values to store.
+    fn check_record(
+        table_manager: &mut TableManager,
+        replaced_record_id: Option<u64>,
+        column_definitions: Vec<ColumnDefinition>,
+        values: Vec<Option<Value>>,
+    ) -> Result<(Vec<ColumnDefinition>, Vec<Option<Value>>), Error> {
+        // Values are converted to the types of their columns before they're stored, e.g. numbers
+        // are rounded to the scale of their `numeric` columns.
+        let values = values
+            .into_iter()
+            .enumerate()
+            .map(
+                |(position, value)| match (column_definitions.get(position), value) {
+                    (Some(column_definition), Some(value)) => {
+                        let data_type = column_definition.data_type();
+                        let value = data_type.coerce(value)?;
+                        if !data_type.accepts(&value) {
+                            return Err(Error::ColumnTypeMismatch {
+                                column: column_definition.name().clone(),
+                                data_type: data_type.clone(),
+                            });
+                        }
+                        Ok(Some(value))
+                    }
+                    (_, value) => Ok(value),
+                },
+            )
+            .collect::<Result<Vec<Option<Value>>, Error>>()?;
+        let (column_definitions, values) =
+            table_manager.assign_serial_values(column_definitions, values)?;
+
+        if let Some(column_definition) = table_manager
+            .column_definitions()
+            .into_iter()
+            .filter(|column_definition| column_definition.is_not_null())
+            .find(|column_definition| {
+                !column_definitions
+                    .iter()
+                    .zip(&values)
+                    .any(|(other, value)| other == column_definition && value.is_some())
+            })
+        {
+            return Err(Error::NotNullViolation(column_definition.name().clone()));
+        }
+
+        if let Some(primary_key) = table_manager.primary_key() {
+            let key = column_definitions
+                .iter()
+                .position(|column_definition| {
+                    column_definition.column_id() == primary_key.column_id()
+                })
+                .and_then(|position| values.get(position)?.as_ref());
+            let existing_record_id = match key {
+                Some(key) => table_manager.find_by_primary_key(key)?,
+                None => None,
+            };
+
+            if existing_record_id.is_some_and(|record_id| Some(record_id) != replaced_record_id) {
+                return Err(Error::UniqueViolation(primary_key.name().clone()));
+            }
+        }
+
+        TablePage::check_record_size(&column_definitions, &values)?;
+
+        Ok((column_definitions, values))
+    }
+
+    /// Returns the names of the table's columns together with an iterator over its rows, see
+    /// `TableManager::scan`.
+    pub fn scan(
+        &self,
+        table_name: &str,
+    ) -> Result<(Vec<String>, impl Iterator<Item = Result<RowValues, Error>>), Error> {
+        let table_manager = self.table_manager(table_name)?;
+
+        Ok((table_manager.column_names(), table_manager.scan()))
+    }
+
+    /// Returns the names of the table's columns together with an iterator over the rows that
+    /// `predicate` returns true for, see `TableManager::scan_filtered`.
+    pub fn scan_filtered<P>(
+        &self,
+        table_name: &str,
+        predicate: P,
+    ) -> Result<(Vec<String>, impl Iterator<Item = Result<RowValues, Error>>), Error>
+    where
+        P: FnMut(&[String], &[Option<Value>]) -> Result<bool, Error>,
+    {
+        let table_manager = self.table_manager(table_name)?;
+
+        Ok((
+            table_manager.column_names(),
+            table_manager.scan_filtered(predicate),
+        ))
     }
 
-    pub fn select_columns_by_name(
+    /// Returns every row of the table together with its record id.
+    pub fn select_records_with_ids(
         &self,
         table_name: &str,
-        column_names: Vec<&str>,
-    ) -> Result<RowResult, Error> {
-        self.table_managers()
+    ) -> Result<Vec<(u64, RowValues)>, Error> {
+        self.table_manager(table_name)?.get_records_with_ids()
+    }
+
+    /// Copies the database and its tables to `page_manager`, see `TableManager::copy_into`.
+    /// Returns the page id of the copy.
+    pub fn copy_into(&self, page_manager: &Rc<RwLock<PageManager>>) -> Result<PageId, Error> {
+        let (page_id, shared_page) = page_manager.write().unwrap().create_page_from(&self.page);
+
+        let table_manager_page_ids = self
+            .table_managers()?
             .iter()
-            .find(|t| t.name() == table_name)
-            .ok_or(Error::TableDoesNotExist(table_name.to_string()))?
-            .get_records_for_columns(&column_names)
+            .map(|table_manager| table_manager.copy_into(page_manager))
+            .collect::<Result<Vec<PageId>, Error>>()?;
+        Self::write_metadata_page(shared_page, &self.name(), table_manager_page_ids);
+
+        Ok(page_id)
     }
 
-    fn table_exists(&self, table_name: &str) -> bool {
-        self.table_managers()
+    /// Returns the number of pages with records of the table, see `TableManager::data_page_count`.
+    pub fn data_page_count(&self, table_name: &str) -> Result<u32, Error> {
+        Ok(self.table_manager(table_name)?.data_page_count())
+    }
+
+    /// Returns the number of rows of the table, without reading its pages.
+    pub fn row_count(&self, table_name: &str) -> Result<u64, Error> {
+        Ok(self.table_manager(table_name)?.row_count())
+    }
+
+    /// Returns the name, the number of rows and the number of pages of every table.
+    pub fn table_sizes(&self) -> Result<Vec<(String, u64, u32)>, Error> {
+        self.table_managers()?
+            .iter()
+            .map(|t| Ok((t.name(), t.row_count(), t.page_count()?)))
+            .collect()
+    }
+
+    /// Counts the rows of every table again, see `TableManager::recount_rows`.
+    pub fn recount_rows(&mut self) -> Result<(), Error> {
+        for mut table_manager in self.table_managers()? {
+            table_manager.recount_rows()?;
+        }
+
+        Ok(())
+    }
+
+    /// Builds the free space list of every table again, see
+    /// `TableManager::build_free_space_list`.
+    pub fn build_free_space_lists(&mut self) -> Result<(), Error> {
+        for mut table_manager in self.table_managers()? {
+            table_manager.build_free_space_list()?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the names of the sequences of the database, in the order they were created.
+    pub fn sequence_names(&self) -> Vec<String> {
+        self.sequences()
+            .into_iter()
+            .map(|(sequence_name, _last_value)| sequence_name)
+            .collect()
+    }
+
+    /// Creates a sequence whose first value is `start`.
+    pub fn create_sequence(&mut self, sequence_name: &str, start: i64) -> Result<(), Error> {
+        if sequence_name.len() >= SEQUENCE_NAME_LENGTH - 1 {
+            return Err(Error::SequenceNameTooLong);
+        }
+
+        let mut sequences = self.sequences();
+        if sequences
             .iter()
-            .find(|t| t.name() == table_name)
-            .is_some()
+            .any(|(name, _last_value)| name == sequence_name)
+        {
+            return Err(Error::SequenceAlreadyExists(sequence_name.to_string()));
+        }
+        if sequences.len() >= MAX_SEQUENCES {
+            return Err(Error::TooManySequences(self.name()));
+        }
+        let last_value = start.checked_sub(1).ok_or(Error::IntegerOutOfRange)?;
+
+        sequences.push((sequence_name.to_string(), last_value));
+        self.write_sequences(&sequences);
+
+        Ok(())
     }
 
-    fn table_managers(&self) -> Vec<TableManager> {
+    /// Advances the sequence and returns its new value. The value is stored in the metadata page
+    /// of the database, so it's never handed out twice once the page is written.
+    pub fn next_sequence_value(&mut self, sequence_name: &str) -> Result<i64, Error> {
+        let mut sequences = self.sequences();
+        let (_name, last_value) = sequences
+            .iter_mut()
+            .find(|(name, _last_value)| name == sequence_name)
+            .ok_or(Error::SequenceDoesNotExist(sequence_name.to_string()))?;
+
+        *last_value = last_value.checked_add(1).ok_or(Error::IntegerOutOfRange)?;
+        let value = *last_value;
+        self.write_sequences(&sequences);
+
+        Ok(value)
+    }
+
+    /// Advances the sequence to `last_value` unless it's already past it. Sequences that don't
+    /// exist are skipped.
+    pub fn advance_sequence(&mut self, sequence_name: &str, last_value: i64) {
+        let mut sequences = self.sequences();
+        let Some(sequence) = sequences
+            .iter_mut()
+            .find(|(name, _last_value)| name == sequence_name)
+        else {
+            return;
+        };
+
+        if sequence.1 < last_value {
+            sequence.1 = last_value;
+            self.write_sequences(&sequences);
+        }
+    }
+
+    /// Returns the names of the columns together with an iterator over the rows of a random
+    /// selection of the table's pages, see `TableSample` and `TableManager::scan_sampled`.
+    pub fn scan_sample(
+        &self,
+        table_name: &str,
+        percentage: u8,
+        random: &mut Random,
+    ) -> Result<(Vec<String>, impl Iterator<Item = Result<RowValues, Error>>), Error> {
+        let table_manager = self.table_manager(table_name)?;
+
+        Ok((
+            table_manager.column_names(),
+            table_manager.scan_sampled(percentage, random),
+        ))
+    }
+
+    /// Returns an iterator over the values of the named columns of every row, see
+    /// `TableManager::scan_columns`.
+    pub fn scan_columns(
+        &self,
+        table_name: &str,
+        column_names: &[&str],
+    ) -> Result<impl Iterator<Item = Result<RowValues, Error>>, Error> {
+        self.table_manager(table_name)?.scan_columns(column_names)
+    }
+
+    /// Asserts that the table names are unique, checks the invariants of every table and that the
+    /// page ids of the tables are up to date. Only checked in debug builds.
+    pub fn debug_check_invariants(&self) {
+        let table_names = self.table_names().unwrap();
+
+        for (index, table_name) in table_names.iter().enumerate() {
+            debug_assert!(
+                !table_names[..index].contains(table_name),
+                "Table {} exists twice in database {}",
+                table_name,
+                self.name()
+            );
+        }
+
+        for table_manager in self.table_managers().unwrap() {
+            table_manager.debug_check_invariants();
+        }
+
+        let table_page_ids_by_name: HashMap<String, PageId> = table_names
+            .into_iter()
+            .zip(self.table_manager_page_ids())
+            .collect();
+        debug_assert_eq!(
+            table_page_ids_by_name,
+            *self.table_page_ids_by_name.borrow(),
+            "The page ids of the tables of database {} are out of date",
+            self.name()
+        );
+
+        let sequence_names = self.sequence_names();
+        for (index, sequence_name) in sequence_names.iter().enumerate() {
+            debug_assert!(
+                !sequence_names[..index].contains(sequence_name),
+                "Sequence {} exists twice in database {}",
+                sequence_name,
+                self.name()
+            );
+        }
+    }
+
+    /// Returns an error if allocating one more page would exceed the page quota.
+    fn ensure_page_available(&self) -> Result<(), Error> {
+        match self.page_quota() {
+            Some(quota) if self.page_count()? >= quota => {
+                Err(Error::PageQuotaExceeded(self.name()))
+            }
+
+            _ => Ok(()),
+        }
+    }
+
+    /// Returns an error if the table can't have one more page, see `TableManager::can_add_page`.
+    fn ensure_table_page_available(table_manager: &TableManager) -> Result<(), Error> {
+        match table_manager.can_add_page() {
+            true => Ok(()),
+            false => Err(Error::TableHasTooManyPages(table_manager.name())),
+        }
+    }
+
+    fn table_exists(&self, table_name: &str) -> bool {
+        self.table_page_ids_by_name
+            .borrow()
+            .contains_key(table_name)
+    }
+
+    /// Loads the table named `table_name`, without reading the metadata pages of the other
+    /// tables.
+    fn table_manager(&self, table_name: &str) -> Result<TableManager, Error> {
+        let page_id = *self
+            .table_page_ids_by_name
+            .borrow()
+            .get(table_name)
+            .ok_or(Error::TableDoesNotExist(table_name.to_string()))?;
+        let shared_page = self
+            .page_manager
+            .read()
+            .unwrap()
+            .fetch_page(page_id)?
+            .expect("The pages of the tables exist");
+
+        TableManager::load(self.page_manager.clone(), shared_page)
+    }
+
+    fn table_managers(&self) -> Result<Vec<TableManager>, Error> {
         let page_manager = self.page_manager.read().unwrap();
         let mut table_managers = Vec::new();
 
         for page_id in &self.table_manager_page_ids() {
-            let shared_page = page_manager.fetch_page(*page_id).unwrap();
+            let shared_page = page_manager
+                .fetch_page(*page_id)?
+                .expect("The pages of the tables exist");
 
-            let table_manager = TableManager::load(self.page_manager.clone(), shared_page).unwrap();
+            let table_manager = TableManager::load(self.page_manager.clone(), shared_page)?;
             table_managers.push(table_manager);
         }
 
-        return table_managers;
+        Ok(table_managers)
     }
 
     fn table_manager_page_ids(&self) -> Vec<PageId> {
@@ -203,6 +763,42 @@ impl Database {
         return page_ids;
     }
 
+    fn sequences(&self) -> Vec<(String, i64)> {
+        let page = self.page.read().unwrap();
+        let number_of_sequences = page.metadata[SEQUENCES_OFFSET] as usize;
+
+        (0..number_of_sequences)
+            .map(|index| {
+                let start = SEQUENCES_OFFSET + 1 + index * SEQUENCE_ENTRY_LENGTH;
+                let name_length = page.metadata[start] as usize;
+                let name =
+                    String::from_utf8(page.metadata[start + 1..start + 1 + name_length].to_vec())
+                        .unwrap();
+                let value_start = start + SEQUENCE_NAME_LENGTH;
+                let last_value = i64::from_be_bytes(
+                    page.metadata[value_start..value_start + 8]
+                        .try_into()
+                        .unwrap(),
+                );
+
+                (name, last_value)
+            })
+            .collect()
+    }
+
+    fn write_sequences(&mut self, sequences: &[(String, i64)]) {
+        let mut page = self.page.write().unwrap();
+        page.metadata[SEQUENCES_OFFSET] = sequences.len() as u8;
+
+        for (index, (name, last_value)) in sequences.iter().enumerate() {
+            let start = SEQUENCES_OFFSET + 1 + index * SEQUENCE_ENTRY_LENGTH;
+            page.metadata[start] = name.len() as u8;
+            page.metadata[start + 1..start + 1 + name.len()].copy_from_slice(name.as_bytes());
+            let value_start = start + SEQUENCE_NAME_LENGTH;
+            page.metadata[value_start..value_start + 8].copy_from_slice(&last_value.to_be_bytes());
+        }
+    }
+
     fn write_metadata_page(shared_page: SharedInternalPage, name: &str, page_ids: Vec<PageId>) {
         let mut page = shared_page.write().unwrap();
 
@@ -228,16 +824,76 @@ impl Database {
 
 #[cfg(test)]
 mod tests {
+    use std::cell::Cell;
+    use std::io;
     use std::rc::Rc;
     use std::sync::RwLock;
 
     use super::*;
-    use crate::internal::InternalPage;
+    use crate::internal::{
+        arbitrary, Decimal, InternalPage, LockedPage, MemoryBackend, StorageBackend,
+    };
+
+    fn create_database(
+        page_manager: Rc<RwLock<PageManager>>,
+        name: &str,
+    ) -> Result<Database, Error> {
+        let (_page_id, shared_page) = page_manager.write().unwrap().create_page();
+        Database::initialize(page_manager, shared_page, name)
+    }
+
+    #[test]
+    fn test_loading_metadata_round_trips() {
+        arbitrary::check(|random| {
+            let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+            let database_name = arbitrary::name(random, 62);
+            let mut database = create_database(page_manager.clone(), &database_name).unwrap();
+
+            let quota = match arbitrary::boolean(random) {
+                true => Some(random.next_u64() as u32),
+                false => None,
+            };
+            database.set_page_quota(quota);
+
+            let table_count = arbitrary::between(random, 0..=8);
+            for table_name in arbitrary::names(random, table_count, 62) {
+                let columns = arbitrary::column_definitions(random, 4)
+                    .into_iter()
+                    .map(|column_definition| {
+                        (
+                            column_definition.name().clone(),
+                            column_definition.data_type().clone(),
+                        )
+                    })
+                    .collect();
+
+                // The quota is random, so creating the table is allowed to fail.
+                let _result = database.create_table(&table_name, columns);
+            }
+
+            let sequence_count = arbitrary::between(random, 0..=MAX_SEQUENCES);
+            for sequence_name in arbitrary::names(random, sequence_count, 62) {
+                database
+                    .create_sequence(&sequence_name, random.next_u64() as i64)
+                    .unwrap_or_else(|error| assert_eq!(Error::IntegerOutOfRange, error));
+            }
+
+            let loaded_database =
+                Database::load(page_manager, arbitrary::copy_page(&database.page)).unwrap();
+            assert_eq!(database_name, loaded_database.name());
+            assert_eq!(quota, loaded_database.page_quota());
+            assert_eq!(
+                database.table_names().unwrap(),
+                loaded_database.table_names().unwrap()
+            );
+            assert_eq!(database.sequence_names(), loaded_database.sequence_names());
+        });
+    }
 
     #[test]
     fn test_creating_new_table_without_columns() {
         let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut database = Database::new(page_manager, "test").unwrap();
+        let mut database = create_database(page_manager, "test").unwrap();
 
         let result = database.create_table("foobar", vec![]);
 
@@ -247,7 +903,7 @@ mod tests {
     #[test]
     fn test_creating_new_table_with_columns() {
         let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut database = Database::new(page_manager, "test").unwrap();
+        let mut database = create_database(page_manager, "test").unwrap();
 
         let result =
             database.create_table("foobar", vec![("hello".to_string(), DataType::Integer)]);
@@ -258,7 +914,7 @@ mod tests {
     #[test]
     fn test_creating_table_that_already_exists() {
         let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut database = Database::new(page_manager, "test").unwrap();
+        let mut database = create_database(page_manager, "test").unwrap();
         let table_name = "new_database";
         assert!(database.create_table(table_name, vec![]).is_ok());
 
@@ -270,28 +926,16 @@ mod tests {
         );
     }
 
-    #[test]
-    fn test_adding_column_to_table() {
-        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut database = Database::new(page_manager, "test").unwrap();
-        let table_name = "new_database";
-        assert!(database.create_table(table_name, vec![]).is_ok());
-
-        let result = database.add_column(table_name, "age", DataType::Integer);
-
-        assert!(result.is_ok(), "Failed to add column to table");
-    }
-
     #[test]
     fn inserting_row_to_a_table() {
         let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut database = Database::new(page_manager, "test").unwrap();
+        let mut database = create_database(page_manager, "test").unwrap();
         let table_name = "new_table";
         assert!(database
             .create_table(table_name, vec![("age".to_string(), DataType::Integer)])
             .is_ok());
 
-        let result = database.insert_row(table_name, vec![Value::Integer(3)]);
+        let result = database.insert_row(table_name, vec![Some(Value::Integer(3))]);
 
         assert!(result.is_ok(), "Failed to insert row to table");
     }
@@ -299,13 +943,16 @@ mod tests {
     #[test]
     fn inserting_row_with_different_len_values() {
         let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut database = Database::new(page_manager, "test").unwrap();
+        let mut database = create_database(page_manager, "test").unwrap();
         let table_name = "new_table";
         assert!(database
             .create_table(table_name, vec![("age".to_string(), DataType::Integer)])
             .is_ok());
 
-        let result = database.insert_row(table_name, vec![Value::Integer(3), Value::Integer(5)]);
+        let result = database.insert_row(
+            table_name,
+            vec![Some(Value::Integer(3)), Some(Value::Integer(5))],
+        );
 
         assert_eq!(result.is_ok(), false);
     }
@@ -313,71 +960,77 @@ mod tests {
     #[test]
     fn select_all_from_table() {
         let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut database = Database::new(page_manager, "test").unwrap();
+        let mut database = create_database(page_manager, "test").unwrap();
         let table_name = "new_table";
         assert!(database
             .create_table(table_name, vec![("age".to_string(), DataType::Integer)])
             .is_ok());
         assert!(database
-            .insert_row(table_name, vec![Value::Integer(5)])
+            .insert_row(table_name, vec![Some(Value::Integer(5))])
             .is_ok());
 
-        let result = database
-            .select_all_columns(table_name)
+        let (_columns, rows) = database
+            .scan(table_name)
             .expect("Failed to select from database");
+        let result: Vec<RowValues> = rows.collect::<Result<_, _>>().unwrap();
 
-        assert_eq!(1, *result.count());
-        assert_eq!(vec![vec![Some(Value::Integer(5))]], result.rows());
+        assert_eq!(vec![vec![Some(Value::Integer(5))]], result);
     }
 
     #[test]
     fn select_all_from_table_with_different_columns_over_time() {
         let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut database = Database::new(page_manager, "test").unwrap();
+        let mut database = create_database(page_manager, "test").unwrap();
         let table_name = "new_table";
         assert!(database
             .create_table(table_name, vec![("age".to_string(), DataType::Integer)])
             .is_ok());
         assert!(database
-            .insert_row(table_name, vec![Value::Integer(1)])
+            .insert_row(table_name, vec![Some(Value::Integer(1))])
             .is_ok());
 
         assert!(database
-            .add_column(table_name, "month", DataType::Integer)
+            .table_manager(table_name)
+            .unwrap()
+            .add_column("month", DataType::Integer)
             .is_ok());
         assert!(database
-            .insert_row(table_name, vec![Value::Integer(2), Value::Integer(3)])
+            .insert_row(
+                table_name,
+                vec![Some(Value::Integer(2)), Some(Value::Integer(3))]
+            )
             .is_ok());
 
         // Order is not guaranteed
-        let result = database
-            .select_all_columns(table_name)
+        let (_columns, rows) = database
+            .scan(table_name)
             .expect("Failed to select from database");
+        let result: Vec<RowValues> = rows.collect::<Result<_, _>>().unwrap();
 
-        assert_eq!(2, *result.count());
-        assert!(result.rows().contains(&vec![Some(Value::Integer(1)), None]));
-        assert!(result.rows().contains(&vec![Some(Value::Integer(1)), None]));
+        assert_eq!(2, result.len());
+        assert!(result.contains(&vec![Some(Value::Integer(1)), None]));
+        assert!(result.contains(&vec![Some(Value::Integer(1)), None]));
     }
 
     #[test]
     fn select_all_from_empty_table() {
         let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut database = Database::new(page_manager, "test").unwrap();
+        let mut database = create_database(page_manager, "test").unwrap();
         let table_name = "new_table";
         assert!(database.create_table(table_name, vec![]).is_ok());
 
-        let result = database
-            .select_all_columns(table_name)
+        let (_columns, rows) = database
+            .scan(table_name)
             .expect("Failed to select from database");
+        let result: Vec<RowValues> = rows.collect::<Result<_, _>>().unwrap();
 
-        assert_eq!(0, *result.count());
-        assert!(result.rows().is_empty());
+        assert!(result.is_empty());
     }
 
     #[test]
     fn select_from_table() {
         let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut database = Database::new(page_manager, "test").unwrap();
+        let mut database = create_database(page_manager, "test").unwrap();
         let table_name = "new_table";
         assert!(database
             .create_table(
@@ -389,41 +1042,52 @@ mod tests {
             )
             .is_ok());
         assert!(database
-            .insert_row(table_name, vec![Value::Integer(5), Value::Integer(3)])
+            .insert_row(
+                table_name,
+                vec![Some(Value::Integer(5)), Some(Value::Integer(3))]
+            )
             .is_ok());
 
-        let result = database
-            .select_columns_by_name(table_name, vec!["birthday"])
-            .expect("Failed to select from database");
+        let result: Vec<RowValues> = database
+            .scan_columns(table_name, &["birthday"])
+            .expect("Failed to select from database")
+            .collect::<Result<_, _>>()
+            .unwrap();
 
-        assert_eq!(1, *result.count());
-        assert_eq!(vec![vec![Some(Value::Integer(3))]], result.rows());
+        assert_eq!(vec![vec![Some(Value::Integer(3))]], result);
     }
 
     #[test]
     fn select_with_column_that_doesnt_exist() {
         let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut database = Database::new(page_manager, "test").unwrap();
+        let mut database = create_database(page_manager, "test").unwrap();
         let table_name = "new_table";
         assert!(database.create_table(table_name, vec![]).is_ok());
 
-        let result = database.select_columns_by_name(table_name, vec!["lol123"]);
+        let result = database.scan_columns(table_name, &["lol123"]);
 
-        assert_eq!(Err(Error::ColumnDoesNotExist("lol123".to_string())), result);
+        assert_eq!(
+            Some(Error::ColumnDoesNotExist("lol123".to_string())),
+            result.err()
+        );
     }
 
     #[test]
     fn test_inserting_record_and_getting_it_back_out() {
         let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut table_manager = TableManager::new(page_manager, "test").unwrap();
-        table_manager.add_column("day", DataType::Integer).unwrap();
-
-        let record_id = table_manager
-            .insert_record(vec![Value::Integer(29)])
+        let mut database = create_database(page_manager, "test").unwrap();
+        database
+            .create_table("test", vec![("day".to_string(), DataType::Integer)])
+            .unwrap();
+        let table_manager = database.table_manager("test").unwrap();
+
+        let record_id = database
+            .insert_row("test", vec![Some(Value::Integer(29))])
             .expect("Failed to insert a row");
 
         let record = table_manager
             .get_record(record_id)
+            .unwrap()
             .expect("Failed to retrive record by interal record id");
 
         assert_eq!(1, *record.count());
@@ -436,10 +1100,157 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_inserting_values_of_other_types() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut database = create_database(page_manager, "test").unwrap();
+        database
+            .create_table(
+                "people",
+                vec![
+                    ("age".to_string(), DataType::Integer),
+                    ("name".to_string(), DataType::Text),
+                ],
+            )
+            .unwrap();
+
+        // Numbers without a fraction fit in integer columns.
+        database
+            .insert_row(
+                "people",
+                vec![
+                    Some(Value::Decimal(Decimal::parse("36.00").unwrap())),
+                    Some(Value::Text("Ada".to_string())),
+                ],
+            )
+            .unwrap();
+        assert_eq!(
+            vec![vec![
+                Some(Value::Integer(36)),
+                Some(Value::Text("Ada".to_string()))
+            ]],
+            database
+                .scan("people")
+                .unwrap()
+                .1
+                .collect::<Result<Vec<RowValues>, _>>()
+                .unwrap()
+        );
+
+        assert_eq!(
+            Err(Error::ColumnTypeMismatch {
+                column: "age".to_string(),
+                data_type: DataType::Integer
+            }),
+            database.insert_row(
+                "people",
+                vec![
+                    Some(Value::Decimal(Decimal::parse("36.5").unwrap())),
+                    Some(Value::Text("Ada".to_string())),
+                ],
+            )
+        );
+        assert_eq!(
+            Err(Error::ColumnTypeMismatch {
+                column: "name".to_string(),
+                data_type: DataType::Text
+            }),
+            database.insert_row_into_columns(
+                "people",
+                &["name".to_string()],
+                vec![Some(Value::Integer(1))],
+            )
+        );
+        assert_eq!(
+            Err(Error::IntegerOutOfRange),
+            database.insert_row(
+                "people",
+                vec![Some(Value::Decimal(Decimal::new(10i128.pow(30), 0))), None],
+            )
+        );
+    }
+
+    #[test]
+    fn test_inserting_beyond_the_page_quota() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut database = create_database(page_manager, "test").unwrap();
+        let table_name = "new_table";
+        assert!(database
+            .create_table(table_name, vec![("age".to_string(), DataType::Integer)])
+            .is_ok());
+        database.set_page_quota(Some(3));
+
+        // A data page holds 254 records with a single integer.
+        for _ in 0..254 {
+            database
+                .insert_row(table_name, vec![Some(Value::Integer(3))])
+                .expect("Failed to fill the first data page");
+        }
+        assert_eq!(3, database.page_count().unwrap());
+
+        assert_eq!(
+            Err(Error::PageQuotaExceeded("test".to_string())),
+            database.insert_row(table_name, vec![Some(Value::Integer(3))])
+        );
+        assert_eq!(
+            Err(Error::PageQuotaExceeded("test".to_string())),
+            database.create_table("other_table", vec![])
+        );
+
+        database.set_page_quota(None);
+        assert!(database
+            .insert_row(table_name, vec![Some(Value::Integer(3))])
+            .is_ok());
+    }
+
+    #[test]
+    fn test_inserting_rows_in_a_batch() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut database = create_database(page_manager, "test").unwrap();
+        database
+            .create_table("t", vec![("id".to_string(), DataType::Integer)])
+            .unwrap();
+        database.set_primary_key("t", "id").unwrap();
+        let column_names = vec!["id".to_string()];
+        let rows = |ids: std::ops::Range<i64>| {
+            ids.map(|id| vec![Some(Value::Integer(id))])
+                .collect::<Vec<RowValues>>()
+        };
+
+        assert_eq!(
+            Ok(3),
+            database
+                .insert_rows_into_columns("t", &column_names, rows(0..3))
+                .map(|record_ids| record_ids.len())
+        );
+        // Duplicate keys are found among the rows of the batch, too.
+        assert_eq!(
+            Err(Error::UniqueViolation("id".to_string())),
+            database.insert_rows_into_columns(
+                "t",
+                &column_names,
+                [rows(3..5), rows(4..5)].concat()
+            )
+        );
+        assert_eq!(Ok(3), database.row_count("t"));
+
+        // The rows that were written before the quota ran out are deleted again.
+        database.set_page_quota(Some(database.page_count().unwrap() + 1));
+        assert_eq!(
+            Err(Error::PageQuotaExceeded("test".to_string())),
+            database.insert_rows_into_columns("t", &column_names, rows(3..1000))
+        );
+        assert_eq!(Ok(3), database.row_count("t"));
+        assert_eq!(
+            Ok(3),
+            database.scan("t").map(|(_columns, rows)| rows.count())
+        );
+    }
+
     #[test]
     fn test_initialize_and_load() {
         let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let page = Rc::new(RwLock::new(InternalPage::new()));
+        let page = Rc::new(LockedPage::new(InternalPage::new()));
 
         {
             let mut database =
@@ -451,14 +1262,70 @@ mod tests {
                 .expect("Failed to create table");
 
             assert_eq!("my database name", database.name());
-            assert_eq!(vec!["my table"], database.table_names());
+            assert_eq!(vec!["my table"], database.table_names().unwrap());
         }
 
         {
             let database = Database::load(page_manager, page).expect("Failed to load database");
 
             assert_eq!("my database name", database.name());
-            assert_eq!(vec!["my table"], database.table_names());
+            assert_eq!(vec!["my table"], database.table_names().unwrap());
+        }
+    }
+
+    /// Stores the pages in memory, but fails to read them back while `failing` is set.
+    struct UnreadableBackend {
+        backend: MemoryBackend,
+        failing: Rc<Cell<bool>>,
+    }
+
+    impl StorageBackend for UnreadableBackend {
+        fn read_all(&self) -> io::Result<Vec<Vec<u8>>> {
+            self.backend.read_all()
         }
+
+        fn read_page(&self, page_id: PageId) -> io::Result<Vec<u8>> {
+            if self.failing.get() {
+                return Err(io::Error::new(io::ErrorKind::Other, "disk gone"));
+            }
+
+            self.backend.read_page(page_id)
+        }
+
+        fn write_pages(&self, pages: Vec<(PageId, Vec<u8>)>) -> io::Result<()> {
+            self.backend.write_pages(pages)
+        }
+    }
+
+    #[test]
+    fn test_unreadable_pages_are_reported() {
+        let failing = Rc::new(Cell::new(false));
+        let page_manager = Rc::new(RwLock::new(PageManager::with_backend(Box::new(
+            UnreadableBackend {
+                backend: MemoryBackend::new(),
+                failing: failing.clone(),
+            },
+        ))));
+        let mut database = create_database(page_manager.clone(), "db").unwrap();
+        database
+            .create_table("t", vec![("id".to_string(), DataType::Integer)])
+            .unwrap();
+        let record_id = database
+            .insert_row("t", vec![Some(Value::Integer(1))])
+            .unwrap();
+
+        // Released pages are read back when they're needed, which fails now.
+        page_manager.write().unwrap().shrink_to_fit();
+        failing.set(true);
+        let error = || Error::StorageFailed("disk gone".to_string());
+        assert_eq!(Err(error()), database.select_record("t", record_id));
+        assert_eq!(Some(error()), database.scan("t").err());
+        assert_eq!(Err(error()), database.table_sizes());
+
+        failing.set(false);
+        assert_eq!(
+            Ok(Some(vec![Some(Value::Integer(1))])),
+            database.select_record("t", record_id)
+        );
     }
 }
@@ -1,19 +1,563 @@
-use super::Token;
+use alloc::string::String;
+use core::fmt::{self, Display, Formatter};
+
+use super::parser::{self, CastType};
+use super::{DataType, Token};
 
 #[derive(Debug, PartialEq)]
 pub enum Error {
+    // Syntax errors
     MissingToken,
-    UnexpectedToken { actual: Token },
+    UnexpectedToken {
+        actual: Token,
+    },
+    AggregateNotAllowed(String),
+    ColumnNotAggregated(String),
+    SubqueryReturnedMoreThanOneColumn,
+    MissingParameter(usize),
+    /// More parameters were supplied than the query uses.
+    TooManyParameters {
+        supplied: usize,
+        required: usize,
+    },
 
+    // Catalog errors
+    NoActiveDatabase,
     DatabaseDoesNotExist(String),
     DatabaseAlreadyExists(String),
     TableDoesNotExist(String),
     TableAlreadyExists(String),
     ColumnDoesNotExist(String),
     ColumnAlreadyExist(String),
-    TooManyColumnsInUse,
-    InsertFailed,
-
+    FunctionDoesNotExist(String),
+    InvalidFunctionArguments(String),
     TableNameTooLong,
     DatabaseNameTooLong,
+    CursorDoesNotExist(String),
+    CursorAlreadyExists(String),
+    MultiplePrimaryKeys(String),
+    TypeDoesNotExist(String),
+    MissingFromClauseEntry(String),
+    SequenceDoesNotExist(String),
+    SequenceAlreadyExists(String),
+    SequenceNameTooLong,
+
+    // Data errors
+    SubqueryReturnedMoreThanOneRow,
+    /// An `INSERT ... ON CONFLICT DO UPDATE` would update a row that the same statement already
+    /// inserted or updated.
+    RowAffectedTwice,
+    IntegerOutOfRange,
+    NumericValueOutOfRange,
+    TypeMismatch,
+    /// A value that can't be stored in a column of the column's type.
+    ColumnTypeMismatch {
+        column: String,
+        data_type: DataType,
+    },
+    DivisionByZero,
+    InvalidCast {
+        value: String,
+        data_type: CastType,
+    },
+    BadCopyFileFormat(String),
+    /// A `NULL` in the column that can't be converted, see `FromRow`.
+    UnexpectedNull(String),
+    /// The value of a storage option like `fillfactor` is outside of the range it allows.
+    OptionValueOutOfRange {
+        option: String,
+        value: u64,
+        min: u64,
+        max: u64,
+    },
+    /// The percentage of `TABLESAMPLE` is greater than 100.
+    SamplePercentageOutOfRange(u64),
+
+    // Constraint errors
+    InsertFailed,
+    NotNullViolation(String),
+    UniqueViolation(String),
+
+    // Storage errors
+    TooManyColumnsInUse,
+    /// The metadata page of the table has no room for the id of another page.
+    TableHasTooManyPages(String),
+    PageQuotaExceeded(String),
+    StorageUnavailable,
+    /// Reading or writing the pages of the storage backend failed, with the message of the
+    /// `io::Error`.
+    StorageFailed(String),
+    QueryMemoryLimitExceeded {
+        used: usize,
+        limit: usize,
+    },
+    CouldNotReadFile(String),
+    /// `COPY ... FROM` names a file outside of the directory that it may read from, see
+    /// `Manager::set_copy_directory`.
+    CopyFileNotAllowed(String),
+    CouldNotWriteFile(String),
+    RowTooBig {
+        size: usize,
+        limit: usize,
+    },
+    /// Page 0 of the file doesn't start with the header of bsql.
+    NotADatabaseFile,
+    /// The file was written with a newer format version than this version of bsql can read.
+    UnsupportedFormatVersion(u32),
+    /// The pages of the backup don't match the checksum it was written with.
+    CorruptBackup(String),
+    /// The metadata page of the database has no room for another sequence.
+    TooManySequences(String),
+
+    // Transaction errors
+    NoTransactionInProgress,
+    SavepointDoesNotExist(String),
+    /// The command can't run inside a transaction, e.g. `VACUUM`.
+    ActiveSqlTransaction(String),
+
+    // Internal errors
+    UnresolvedSubquery,
+}
+
+/// The class of an `Error`, for callers that want to handle whole groups of errors the same way.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorCategory {
+    /// The statement couldn't be parsed or isn't valid SQL.
+    Syntax,
+    /// The statement refers to databases, tables, columns or functions that don't exist (or
+    /// tries to create ones that already do).
+    Catalog,
+    /// A value couldn't be computed, e.g. because of overflows or mismatching types.
+    Data,
+    /// The statement would leave a table in a state that isn't allowed.
+    Constraint,
+    /// The storage layer ran out of room, or a query ran out of memory.
+    Storage,
+    /// The statement can't be used in the current state of the transaction.
+    Transaction,
+    /// Something went wrong inside bsql itself.
+    Internal,
+}
+
+impl Error {
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            Error::MissingToken
+            | Error::UnexpectedToken { .. }
+            | Error::AggregateNotAllowed(_)
+            | Error::ColumnNotAggregated(_)
+            | Error::SubqueryReturnedMoreThanOneColumn
+            | Error::MissingParameter(_)
+            | Error::TooManyParameters { .. } => ErrorCategory::Syntax,
+
+            Error::NoActiveDatabase
+            | Error::DatabaseDoesNotExist(_)
+            | Error::DatabaseAlreadyExists(_)
+            | Error::TableDoesNotExist(_)
+            | Error::TableAlreadyExists(_)
+            | Error::ColumnDoesNotExist(_)
+            | Error::ColumnAlreadyExist(_)
+            | Error::FunctionDoesNotExist(_)
+            | Error::InvalidFunctionArguments(_)
+            | Error::TableNameTooLong
+            | Error::DatabaseNameTooLong
+            | Error::CursorDoesNotExist(_)
+            | Error::CursorAlreadyExists(_)
+            | Error::MultiplePrimaryKeys(_)
+            | Error::TypeDoesNotExist(_)
+            | Error::MissingFromClauseEntry(_)
+            | Error::SequenceDoesNotExist(_)
+            | Error::SequenceAlreadyExists(_)
+            | Error::SequenceNameTooLong => ErrorCategory::Catalog,
+
+            Error::SubqueryReturnedMoreThanOneRow
+            | Error::RowAffectedTwice
+            | Error::IntegerOutOfRange
+            | Error::NumericValueOutOfRange
+            | Error::TypeMismatch
+            | Error::ColumnTypeMismatch { .. }
+            | Error::DivisionByZero
+            | Error::InvalidCast { .. }
+            | Error::BadCopyFileFormat(_)
+            | Error::UnexpectedNull(_)
+            | Error::OptionValueOutOfRange { .. }
+            | Error::SamplePercentageOutOfRange(_) => ErrorCategory::Data,
+
+            Error::InsertFailed | Error::NotNullViolation(_) | Error::UniqueViolation(_) => {
+                ErrorCategory::Constraint
+            }
+
+            Error::TooManyColumnsInUse
+            | Error::TableHasTooManyPages(_)
+            | Error::PageQuotaExceeded(_)
+            | Error::StorageUnavailable
+            | Error::StorageFailed(_)
+            | Error::QueryMemoryLimitExceeded { .. }
+            | Error::CouldNotReadFile(_)
+            | Error::CopyFileNotAllowed(_)
+            | Error::CouldNotWriteFile(_)
+            | Error::RowTooBig { .. }
+            | Error::NotADatabaseFile
+            | Error::UnsupportedFormatVersion(_)
+            | Error::CorruptBackup(_)
+            | Error::TooManySequences(_) => ErrorCategory::Storage,
+
+            Error::NoTransactionInProgress
+            | Error::SavepointDoesNotExist(_)
+            | Error::ActiveSqlTransaction(_) => ErrorCategory::Transaction,
+
+            Error::UnresolvedSubquery => ErrorCategory::Internal,
+        }
+    }
+
+    /// Returns a stable, SQLSTATE-like code for the error. The first two characters are the
+    /// class of the error and follow the classes used by SQLSTATE.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Error::MissingToken | Error::UnexpectedToken { .. } => "42601",
+            Error::AggregateNotAllowed(_) | Error::ColumnNotAggregated(_) => "42803",
+            Error::SubqueryReturnedMoreThanOneColumn => "42601",
+            Error::MissingParameter(_) => "42P02",
+            Error::TooManyParameters { .. } => "08P01",
+
+            Error::NoActiveDatabase | Error::DatabaseDoesNotExist(_) => "3D000",
+            Error::DatabaseAlreadyExists(_) => "42P04",
+            Error::TableDoesNotExist(_) => "42P01",
+            Error::TableAlreadyExists(_) => "42P07",
+            Error::ColumnDoesNotExist(_) => "42703",
+            Error::ColumnAlreadyExist(_) => "42701",
+            Error::FunctionDoesNotExist(_) | Error::InvalidFunctionArguments(_) => "42883",
+            Error::TableNameTooLong | Error::DatabaseNameTooLong => "42622",
+            Error::CursorDoesNotExist(_) => "34000",
+            Error::CursorAlreadyExists(_) => "42P03",
+            Error::MultiplePrimaryKeys(_) => "42P16",
+            Error::TypeDoesNotExist(_) => "42704",
+            Error::MissingFromClauseEntry(_) => "42P01",
+            Error::SequenceDoesNotExist(_) => "42P01",
+            Error::SequenceAlreadyExists(_) => "42P07",
+            Error::SequenceNameTooLong => "42622",
+
+            Error::SubqueryReturnedMoreThanOneRow | Error::RowAffectedTwice => "21000",
+            Error::IntegerOutOfRange | Error::NumericValueOutOfRange => "22003",
+            Error::TypeMismatch | Error::ColumnTypeMismatch { .. } => "42804",
+            Error::DivisionByZero => "22012",
+            Error::InvalidCast { .. } => "22P02",
+            Error::BadCopyFileFormat(_) => "22P04",
+            Error::UnexpectedNull(_) => "22004",
+            Error::OptionValueOutOfRange { .. } => "22023",
+            Error::SamplePercentageOutOfRange(_) => "2202H",
+
+            Error::InsertFailed => "23000",
+            Error::NotNullViolation(_) => "23502",
+            Error::UniqueViolation(_) => "23505",
+
+            Error::TooManyColumnsInUse => "54011",
+            Error::TableHasTooManyPages(_) => "54000",
+            Error::PageQuotaExceeded(_) => "53100",
+            Error::StorageUnavailable | Error::StorageFailed(_) => "58030",
+            Error::QueryMemoryLimitExceeded { .. } => "53200",
+            Error::CouldNotReadFile(_) => "58P01",
+            Error::CopyFileNotAllowed(_) => "42501",
+            Error::CouldNotWriteFile(_) => "58030",
+            Error::RowTooBig { .. } => "54000",
+            Error::NotADatabaseFile => "XX001",
+            Error::UnsupportedFormatVersion(_) => "0A000",
+            Error::CorruptBackup(_) => "XX001",
+            Error::TooManySequences(_) => "54000",
+
+            Error::NoTransactionInProgress => "25P01",
+            Error::SavepointDoesNotExist(_) => "3B001",
+            Error::ActiveSqlTransaction(_) => "25001",
+
+            Error::UnresolvedSubquery => "XX000",
+        }
+    }
+}
+
+impl Display for Error {
+    /// Writes a message for people, in the style of the messages of PostgreSQL.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::MissingToken => write!(f, "syntax error at end of input"),
+            Error::UnexpectedToken { actual } => {
+                write!(f, "syntax error at or near \"{}\"", actual.to_sql())
+            }
+            Error::AggregateNotAllowed(name) => {
+                write!(f, "aggregate function {} is not allowed here", name)
+            }
+            Error::ColumnNotAggregated(column) => write!(
+                f,
+                "column \"{}\" must appear in the GROUP BY clause or be used in an aggregate \
+                 function",
+                column
+            ),
+            Error::SubqueryReturnedMoreThanOneColumn => {
+                write!(f, "subquery must return only one column")
+            }
+            Error::MissingParameter(index) => write!(f, "there is no parameter ${}", index),
+            Error::TooManyParameters { supplied, required } => write!(
+                f,
+                "bind message supplies {} parameters, but the query requires {}",
+                supplied, required
+            ),
+
+            Error::NoActiveDatabase => write!(f, "no database is selected"),
+            Error::DatabaseDoesNotExist(name) => write!(f, "database \"{}\" does not exist", name),
+            Error::DatabaseAlreadyExists(name) => {
+                write!(f, "database \"{}\" already exists", name)
+            }
+            Error::TableDoesNotExist(name) => write!(f, "relation \"{}\" does not exist", name),
+            Error::TableAlreadyExists(name) => write!(f, "relation \"{}\" already exists", name),
+            Error::ColumnDoesNotExist(name) => write!(f, "column \"{}\" does not exist", name),
+            Error::ColumnAlreadyExist(name) => write!(f, "column \"{}\" already exists", name),
+            Error::FunctionDoesNotExist(name) => write!(f, "function {} does not exist", name),
+            Error::InvalidFunctionArguments(name) => {
+                write!(f, "invalid arguments for function {}", name)
+            }
+            Error::TableNameTooLong => write!(f, "table name is too long"),
+            Error::DatabaseNameTooLong => write!(f, "database name is too long"),
+            Error::CursorDoesNotExist(name) => write!(f, "cursor \"{}\" does not exist", name),
+            Error::CursorAlreadyExists(name) => write!(f, "cursor \"{}\" already exists", name),
+            Error::MultiplePrimaryKeys(table) => write!(
+                f,
+                "multiple primary keys for table \"{}\" are not allowed",
+                table
+            ),
+            Error::TypeDoesNotExist(name) => write!(f, "type \"{}\" does not exist", name),
+            Error::MissingFromClauseEntry(name) => {
+                write!(f, "missing FROM-clause entry for table \"{}\"", name)
+            }
+            Error::SequenceDoesNotExist(name) => {
+                write!(f, "sequence \"{}\" does not exist", name)
+            }
+            Error::SequenceAlreadyExists(name) => {
+                write!(f, "sequence \"{}\" already exists", name)
+            }
+            Error::SequenceNameTooLong => write!(f, "sequence name is too long"),
+
+            Error::SubqueryReturnedMoreThanOneRow => {
+                write!(
+                    f,
+                    "more than one row returned by a subquery used as an expression"
+                )
+            }
+            Error::RowAffectedTwice => write!(
+                f,
+                "ON CONFLICT DO UPDATE command cannot affect row a second time"
+            ),
+            Error::IntegerOutOfRange => write!(f, "integer out of range"),
+            Error::NumericValueOutOfRange => write!(f, "numeric field overflow"),
+            Error::TypeMismatch => write!(f, "operator does not exist for these types"),
+            Error::ColumnTypeMismatch { column, data_type } => write!(
+                f,
+                "value for column \"{}\" is not of type {}",
+                column, data_type
+            ),
+            Error::DivisionByZero => write!(f, "division by zero"),
+            Error::InvalidCast { value, data_type } => write!(
+                f,
+                "invalid input syntax for type {}: \"{}\"",
+                data_type.to_sql(),
+                value
+            ),
+            Error::BadCopyFileFormat(details) => write!(f, "bad COPY file format: {}", details),
+            Error::UnexpectedNull(column) => write!(f, "column \"{}\" is null", column),
+            Error::OptionValueOutOfRange {
+                option,
+                value,
+                min,
+                max,
+            } => write!(
+                f,
+                "value {} out of bounds for option \"{}\", valid values are between \"{}\" and \
+                 \"{}\"",
+                value, option, min, max
+            ),
+            Error::SamplePercentageOutOfRange(percentage) => write!(
+                f,
+                "sample percentage {} must be between 0 and 100",
+                percentage
+            ),
+
+            Error::InsertFailed => write!(f, "could not insert the row"),
+            Error::NotNullViolation(column) => write!(
+                f,
+                "null value in column \"{}\" violates not-null constraint",
+                column
+            ),
+            Error::UniqueViolation(column) => write!(
+                f,
+                "duplicate key value violates unique constraint on column \"{}\"",
+                column
+            ),
+
+            Error::TooManyColumnsInUse => write!(f, "table has no room for more columns"),
+            Error::TableHasTooManyPages(table) => {
+                write!(f, "table \"{}\" has no room for more pages", table)
+            }
+            Error::PageQuotaExceeded(database) => {
+                write!(f, "database \"{}\" exceeds its page quota", database)
+            }
+            Error::StorageUnavailable => write!(f, "the storage is unavailable"),
+            Error::StorageFailed(error) => write!(f, "could not access the storage: {}", error),
+            Error::QueryMemoryLimitExceeded { used, limit } => write!(
+                f,
+                "query uses {} bytes of memory, which exceeds the limit of {} bytes",
+                used, limit
+            ),
+            Error::CouldNotReadFile(file) => write!(f, "could not read file \"{}\"", file),
+            Error::CopyFileNotAllowed(file) => write!(
+                f,
+                "COPY is not allowed to read file \"{}\" outside of the copy directory",
+                file
+            ),
+            Error::CouldNotWriteFile(file) => write!(f, "could not write file \"{}\"", file),
+            Error::RowTooBig { size, limit } => write!(
+                f,
+                "row size {} exceeds the maximum of {} bytes",
+                size, limit
+            ),
+            Error::NotADatabaseFile => write!(f, "file is not a bsql database"),
+            Error::UnsupportedFormatVersion(version) => write!(
+                f,
+                "file has format version {}, which this version of bsql can't read",
+                version
+            ),
+            Error::CorruptBackup(file) => {
+                write!(f, "backup \"{}\" doesn't match its checksum", file)
+            }
+            Error::TooManySequences(database) => {
+                write!(
+                    f,
+                    "database \"{}\" has no room for more sequences",
+                    database
+                )
+            }
+
+            Error::NoTransactionInProgress => write!(f, "there is no transaction in progress"),
+            Error::SavepointDoesNotExist(name) => {
+                write!(f, "savepoint \"{}\" does not exist", name)
+            }
+            Error::ActiveSqlTransaction(command) => {
+                write!(f, "{} cannot run inside a transaction block", command)
+            }
+
+            Error::UnresolvedSubquery => write!(f, "subquery was not resolved"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<parser::Error> for Error {
+    fn from(error: parser::Error) -> Self {
+        match error {
+            parser::Error::MissingToken => Error::MissingToken,
+            parser::Error::UnexpectedToken { actual } => Error::UnexpectedToken { actual },
+            parser::Error::MissingParameter(index) => Error::MissingParameter(index),
+            parser::Error::TypeDoesNotExist(name) => Error::TypeDoesNotExist(name),
+            parser::Error::IntegerOutOfRange => Error::IntegerOutOfRange,
+            parser::Error::OptionValueOutOfRange {
+                option,
+                value,
+                min,
+                max,
+            } => Error::OptionValueOutOfRange {
+                option,
+                value,
+                min,
+                max,
+            },
+            parser::Error::SamplePercentageOutOfRange(percentage) => {
+                Error::SamplePercentageOutOfRange(percentage)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_categories_and_codes() {
+        let error = Error::UnexpectedToken {
+            actual: Token::Comma,
+        };
+        assert_eq!(ErrorCategory::Syntax, error.category());
+        assert_eq!("42601", error.code());
+
+        let error = Error::TableDoesNotExist("users".to_string());
+        assert_eq!(ErrorCategory::Catalog, error.category());
+        assert_eq!("42P01", error.code());
+
+        let error = Error::ColumnTypeMismatch {
+            column: "age".to_string(),
+            data_type: DataType::Integer,
+        };
+        assert_eq!(ErrorCategory::Data, error.category());
+        assert_eq!("42804", error.code());
+
+        let error = Error::SamplePercentageOutOfRange(150);
+        assert_eq!(ErrorCategory::Data, error.category());
+        assert_eq!("2202H", error.code());
+
+        let error = Error::PageQuotaExceeded("db".to_string());
+        assert_eq!(ErrorCategory::Storage, error.category());
+        assert_eq!("53100", error.code());
+    }
+
+    #[test]
+    fn test_error_messages() {
+        assert_eq!(
+            "syntax error at or near \"rating\"",
+            Error::UnexpectedToken {
+                actual: Token::Identifier("rating".to_string())
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "relation \"users\" does not exist",
+            Error::TableDoesNotExist("users".to_string()).to_string()
+        );
+        assert_eq!(
+            "table \"users\" has no room for more pages",
+            Error::TableHasTooManyPages("users".to_string()).to_string()
+        );
+        assert_eq!(
+            "ON CONFLICT DO UPDATE command cannot affect row a second time",
+            Error::RowAffectedTwice.to_string()
+        );
+        assert_eq!(
+            "invalid input syntax for type uuid: \"a0eebc99\"",
+            Error::InvalidCast {
+                value: "a0eebc99".to_string(),
+                data_type: CastType::Uuid,
+            }
+            .to_string()
+        );
+        assert_eq!(
+            "value 5 out of bounds for option \"fillfactor\", valid values are between \"10\" \
+             and \"100\"",
+            Error::OptionValueOutOfRange {
+                option: "fillfactor".to_string(),
+                value: 5,
+                min: 10,
+                max: 100,
+            }
+            .to_string()
+        );
+
+        // The errors of the parser keep their message when they're converted.
+        let error = parser::Error::UnexpectedToken {
+            actual: Token::Comma,
+        };
+        assert_eq!(error.to_string(), Error::from(error).to_string());
+        let error = parser::Error::SamplePercentageOutOfRange(150);
+        assert_eq!(error.to_string(), Error::from(error).to_string());
+
+        // The errors can be boxed like any other error.
+        let error: Box<dyn std::error::Error> = Box::new(Error::DivisionByZero);
+        assert_eq!("division by zero", error.to_string());
+    }
 }
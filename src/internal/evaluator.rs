@@ -0,0 +1,1571 @@
+use std::cell::RefCell;
+use std::cmp::Ordering;
+
+use super::{
+    default_clock,
+    json::{self, Json},
+    parser::{ArithmeticOperation, CastType, CompareOperation, LogicalOperation},
+    row_result::RowValues,
+    Decimal, Error, Expression, Random, RowResult, Uuid, Value,
+};
+
+const AGGREGATE_FUNCTIONS: [&str; 5] = ["COUNT", "MAX", "MIN", "STRING_AGG", "ARRAY_AGG"];
+
+thread_local! {
+    /// The generator behind `GEN_RANDOM_UUID()`, which is seeded once for every thread.
+    static RANDOM: RefCell<Random> = RefCell::new(Random::from_clock(&*default_clock()));
+}
+
+/// Replaces the generator behind `GEN_RANDOM_UUID()` for the current thread, see
+/// `Manager::set_clock`.
+pub fn seed_random(random: Random) {
+    RANDOM.with(|current| *current.borrow_mut() = random);
+}
+
+/// Evaluates the `expressions` of a select list against every row in `row_result`.
+/// When the select list contains an aggregate function the rows are collapsed into a single row.
+pub fn project(row_result: &RowResult, expressions: &[Expression]) -> Result<RowResult, Error> {
+    let columns: Vec<String> = expressions
+        .iter()
+        .flat_map(|expression| match expression {
+            Expression::Wildcard => row_result.columns().clone(),
+            _ => vec![expression.column_name()],
+        })
+        .collect();
+
+    if expressions.iter().any(is_aggregate) {
+        let row = expressions
+            .iter()
+            .map(|expression| evaluate_aggregate(expression, row_result))
+            .collect::<Result<RowValues, Error>>()?;
+
+        return Ok(RowResult::new(columns, vec![row]));
+    }
+
+    let mut rows = Vec::with_capacity(row_result.rows().len());
+
+    for row in row_result.rows() {
+        let mut values = Vec::with_capacity(columns.len());
+
+        for expression in expressions {
+            match expression {
+                Expression::Wildcard => values.extend(row.iter().cloned()),
+                _ => values.push(evaluate(expression, row_result.columns(), row)?),
+            }
+        }
+
+        rows.push(values);
+    }
+
+    Ok(RowResult::new(columns, rows))
+}
+
+/// Returns the rows of `row_result` for which `condition` evaluates to true.
+pub fn filter(row_result: &RowResult, condition: &Expression) -> Result<RowResult, Error> {
+    let mut rows = Vec::new();
+
+    for row in row_result.rows() {
+        if matches(condition, row_result.columns(), row)? {
+            rows.push(row.clone());
+        }
+    }
+
+    Ok(RowResult::new(row_result.columns().clone(), rows))
+}
+
+/// Returns whether `condition` evaluates to true for a single row.
+pub fn matches(
+    condition: &Expression,
+    columns: &[String],
+    row: &[Option<Value>],
+) -> Result<bool, Error> {
+    is_true(evaluate(condition, columns, row)?)
+}
+
+/// Evaluates a (non-aggregate) expression for a single row.
+pub fn evaluate(
+    expression: &Expression,
+    columns: &[String],
+    row: &[Option<Value>],
+) -> Result<Option<Value>, Error> {
+    match expression {
+        Expression::Null => Ok(None),
+        Expression::Literal(literal) => Ok(Some(literal.clone().into())),
+
+        Expression::Identifier(name) => columns
+            .iter()
+            .position(|column| column == name)
+            .map(|index| row[index].clone())
+            .ok_or(Error::ColumnDoesNotExist(name.clone())),
+
+        Expression::Function { name, .. } if is_aggregate(expression) => {
+            Err(Error::AggregateNotAllowed(name.clone()))
+        }
+        Expression::Function { name, arguments } => {
+            if let Some(Expression::Distinct(_)) = arguments.first() {
+                return Err(Error::InvalidFunctionArguments(name.clone()));
+            }
+
+            let arguments = arguments
+                .iter()
+                .map(|argument| evaluate(argument, columns, row))
+                .collect::<Result<Vec<Option<Value>>, Error>>()?;
+
+            call_scalar_function(name, arguments)
+        }
+
+        Expression::Comparison {
+            lhs,
+            operation,
+            rhs,
+        } => {
+            let lhs = evaluate(lhs, columns, row)?;
+            let rhs = evaluate(rhs, columns, row)?;
+
+            match (lhs, rhs) {
+                (Some(lhs), Some(rhs)) => {
+                    Ok(Some(Value::Boolean(compare(&lhs, *operation, &rhs)?)))
+                }
+                _ => Ok(None),
+            }
+        }
+
+        Expression::Arithmetic {
+            lhs,
+            operation,
+            rhs,
+        } => {
+            let lhs = evaluate(lhs, columns, row)?;
+            let rhs = evaluate(rhs, columns, row)?;
+
+            match (lhs, rhs) {
+                (Some(lhs), Some(rhs)) => calculate(&lhs, *operation, &rhs).map(Some),
+                _ => Ok(None),
+            }
+        }
+
+        Expression::Logical {
+            lhs,
+            operation,
+            rhs,
+        } => {
+            let lhs = as_boolean(evaluate(lhs, columns, row)?)?;
+            let rhs = as_boolean(evaluate(rhs, columns, row)?)?;
+
+            Ok(combine(*operation, lhs, rhs).map(Value::Boolean))
+        }
+
+        Expression::Not(expression) => {
+            let value = as_boolean(evaluate(expression, columns, row)?)?;
+            Ok(value.map(|value| Value::Boolean(!value)))
+        }
+
+        Expression::Negation(expression) => match evaluate(expression, columns, row)? {
+            Some(Value::Integer(value)) => value
+                .checked_neg()
+                .map(|value| Some(Value::Integer(value)))
+                .ok_or(Error::IntegerOutOfRange),
+            Some(Value::Decimal(value)) => value
+                .checked_neg()
+                .map(|value| Some(Value::Decimal(value)))
+                .ok_or(Error::NumericValueOutOfRange),
+            Some(_) => Err(Error::TypeMismatch),
+            None => Ok(None),
+        },
+
+        Expression::Like {
+            expression,
+            pattern,
+            negated,
+        } => {
+            let value = evaluate(expression, columns, row)?;
+            let pattern = evaluate(pattern, columns, row)?;
+
+            match (value, pattern) {
+                (Some(Value::Text(value)), Some(Value::Text(pattern))) => Ok(Some(Value::Boolean(
+                    like_matches(&value, &pattern) != *negated,
+                ))),
+                (None, _) | (_, None) => Ok(None),
+
+                _ => Err(Error::TypeMismatch),
+            }
+        }
+
+        Expression::Between {
+            expression,
+            low,
+            high,
+            negated,
+        } => {
+            let value = evaluate(expression, columns, row)?;
+            let low = evaluate(low, columns, row)?;
+            let high = evaluate(high, columns, row)?;
+
+            let compare_to = |bound: Option<Value>, operation| match (&value, bound) {
+                (Some(value), Some(bound)) => compare(value, operation, &bound).map(Some),
+                _ => Ok(None),
+            };
+            let is_above_low = compare_to(low, CompareOperation::GreaterThanOrEqual)?;
+            let is_below_high = compare_to(high, CompareOperation::LessThanOrEqual)?;
+
+            Ok(combine(LogicalOperation::And, is_above_low, is_below_high)
+                .map(|result| Value::Boolean(result != *negated)))
+        }
+
+        Expression::InList {
+            expression,
+            list,
+            negated,
+        } => {
+            let Some(value) = evaluate(expression, columns, row)? else {
+                return Ok(None);
+            };
+
+            // Like a chain of `OR`ed equalities, a `NULL` in the list makes a missing value
+            // unknown rather than false.
+            let mut result = Some(false);
+            for item in list {
+                match evaluate(item, columns, row)? {
+                    Some(item) if compare(&value, CompareOperation::Equality, &item)? => {
+                        result = Some(true);
+                        break;
+                    }
+                    Some(_) => (),
+                    None => result = None,
+                }
+            }
+
+            Ok(result.map(|result| Value::Boolean(result != *negated)))
+        }
+
+        Expression::Case {
+            operand,
+            branches,
+            else_result,
+        } => {
+            let operand = match operand {
+                Some(operand) => Some(evaluate(operand, columns, row)?),
+                None => None,
+            };
+
+            for (condition, result) in branches {
+                let condition = evaluate(condition, columns, row)?;
+
+                let is_match = match (&operand, condition) {
+                    (Some(Some(operand)), Some(condition)) => {
+                        compare(operand, CompareOperation::Equality, &condition)?
+                    }
+                    (Some(_), _) => false,
+                    (None, condition) => is_true(condition)?,
+                };
+
+                if is_match {
+                    return evaluate(result, columns, row);
+                }
+            }
+
+            match else_result {
+                Some(else_result) => evaluate(else_result, columns, row),
+                None => Ok(None),
+            }
+        }
+
+        Expression::Cast {
+            expression,
+            data_type,
+        } => evaluate(expression, columns, row)?
+            .map(|value| value.cast(*data_type))
+            .transpose(),
+
+        Expression::Wildcard => Err(Error::ColumnDoesNotExist("*".to_string())),
+        Expression::Subquery(_) => Err(Error::UnresolvedSubquery),
+        Expression::Distinct(_) => {
+            unreachable!("The parser only produces DISTINCT as the first argument of a function")
+        }
+    }
+}
+
+/// Calls a built-in (non-aggregate) function. Except for `CONCAT`, which skips `NULL` arguments,
+/// the functions return `NULL` when any of their arguments is `NULL`.
+fn call_scalar_function(name: &str, arguments: Vec<Option<Value>>) -> Result<Option<Value>, Error> {
+    let function_name = name.to_uppercase();
+    let invalid_arguments = || Error::InvalidFunctionArguments(name.to_string());
+
+    if function_name == "CONCAT" {
+        if arguments.is_empty() {
+            return Err(invalid_arguments());
+        }
+
+        return Ok(Some(Value::Text(
+            arguments
+                .into_iter()
+                .flatten()
+                .map(|value| value.to_string())
+                .collect(),
+        )));
+    }
+
+    if function_name == "GEN_RANDOM_UUID" {
+        if !arguments.is_empty() {
+            return Err(invalid_arguments());
+        }
+
+        let uuid = RANDOM.with(|random| Uuid::new_v4(random.borrow_mut().next_u128()));
+        return Ok(Some(Value::Uuid(uuid)));
+    }
+
+    if !["UPPER", "LOWER", "LENGTH", "SUBSTR", "JSON_EXTRACT"].contains(&function_name.as_str()) {
+        return Err(Error::FunctionDoesNotExist(name.to_string()));
+    }
+    let Some(arguments) = arguments.into_iter().collect::<Option<Vec<Value>>>() else {
+        return Ok(None);
+    };
+
+    match (&function_name[..], &arguments[..]) {
+        ("UPPER", [Value::Text(text)]) => Ok(Some(Value::Text(text.to_uppercase()))),
+        ("LOWER", [Value::Text(text)]) => Ok(Some(Value::Text(text.to_lowercase()))),
+        ("LENGTH", [Value::Text(text)]) => i64::try_from(text.chars().count())
+            .map(|length| Some(Value::Integer(length)))
+            .map_err(|_| Error::IntegerOutOfRange),
+        ("LENGTH", [Value::Bytes(bytes)]) => i64::try_from(bytes.len())
+            .map(|length| Some(Value::Integer(length)))
+            .map_err(|_| Error::IntegerOutOfRange),
+
+        // Positions start at 1, and a start before the first character shortens the count.
+        ("SUBSTR", [Value::Text(text), Value::Integer(start), count @ ..]) => {
+            let end = match count {
+                [] => i64::MAX,
+                [Value::Integer(count)] if *count >= 0 => start.saturating_add(*count),
+                _ => return Err(invalid_arguments()),
+            };
+
+            Ok(Some(Value::Text(
+                text.chars()
+                    .enumerate()
+                    .filter(|(index, _character)| (*start..end).contains(&(*index as i64 + 1)))
+                    .map(|(_index, character)| character)
+                    .collect(),
+            )))
+        }
+
+        // `JSON_EXTRACT(doc, 'tags', 0)` is `doc -> 'tags' -> 0`. Text reads a member of an object
+        // and an integer reads an element of an array, counting from the end when it's negative.
+        ("JSON_EXTRACT", [Value::Json(json), path @ ..]) if !path.is_empty() => {
+            let mut json = Json::parse(json).ok_or_else(invalid_arguments)?;
+
+            for key in path {
+                let member = match key {
+                    Value::Text(key) => json.get(key),
+                    Value::Integer(index) => json.get_index(*index),
+                    _ => return Err(invalid_arguments()),
+                };
+                let Some(member) = member else {
+                    return Ok(None);
+                };
+                json = member.clone();
+            }
+
+            Ok(Some(Value::Json(json.to_string())))
+        }
+
+        _ => Err(invalid_arguments()),
+    }
+}
+
+/// Compares values of the same type. Integers are compared with numerics as numerics, and text
+/// is read as a UUID or JSON when it's compared with one. JSON values are compared without their
+/// insignificant whitespace.
+fn compare(lhs: &Value, operation: CompareOperation, rhs: &Value) -> Result<bool, Error> {
+    let ordering = match (lhs, rhs) {
+        (Value::Integer(lhs), Value::Decimal(rhs)) => Decimal::from(*lhs).cmp(rhs),
+        (Value::Decimal(lhs), Value::Integer(rhs)) => lhs.cmp(&Decimal::from(*rhs)),
+        (Value::Uuid(_), Value::Text(_)) => {
+            let rhs = rhs.clone().cast(CastType::Uuid)?;
+            return compare(lhs, operation, &rhs);
+        }
+        (Value::Text(_), Value::Uuid(_)) => {
+            let lhs = lhs.clone().cast(CastType::Uuid)?;
+            return compare(&lhs, operation, rhs);
+        }
+        (Value::Json(lhs), Value::Json(rhs)) => json::normalize(lhs).cmp(&json::normalize(rhs)),
+        (Value::Json(_), Value::Text(_)) => {
+            let rhs = rhs.clone().cast(CastType::Json)?;
+            return compare(lhs, operation, &rhs);
+        }
+        (Value::Text(_), Value::Json(_)) => {
+            let lhs = lhs.clone().cast(CastType::Json)?;
+            return compare(&lhs, operation, rhs);
+        }
+        _ if std::mem::discriminant(lhs) != std::mem::discriminant(rhs) => {
+            return Err(Error::TypeMismatch);
+        }
+        _ => lhs.partial_cmp(rhs).ok_or(Error::TypeMismatch)?,
+    };
+
+    match operation {
+        CompareOperation::Equality => Ok(ordering == Ordering::Equal),
+        CompareOperation::Inequality => Ok(ordering != Ordering::Equal),
+        CompareOperation::LessThan => Ok(ordering == Ordering::Less),
+        CompareOperation::LessThanOrEqual => Ok(ordering != Ordering::Greater),
+        CompareOperation::GreaterThan => Ok(ordering == Ordering::Greater),
+        CompareOperation::GreaterThanOrEqual => Ok(ordering != Ordering::Less),
+    }
+}
+
+/// Matches `value` against a `LIKE` pattern. Whenever a character doesn't match, the last `%`
+/// is retried with one more character of the value.
+fn like_matches(value: &str, pattern: &str) -> bool {
+    let value: Vec<char> = value.chars().collect();
+    let pattern: Vec<char> = pattern.chars().collect();
+
+    let (mut value_index, mut pattern_index) = (0, 0);
+    let mut last_wildcard: Option<(usize, usize)> = None;
+
+    while value_index < value.len() {
+        match pattern.get(pattern_index) {
+            Some('%') => {
+                last_wildcard = Some((pattern_index, value_index));
+                pattern_index += 1;
+                continue;
+            }
+            Some(character) if *character == '_' || *character == value[value_index] => {
+                value_index += 1;
+                pattern_index += 1;
+                continue;
+            }
+
+            _ => (),
+        }
+
+        let Some((wildcard_index, wildcard_value_index)) = last_wildcard else {
+            return false;
+        };
+        last_wildcard = Some((wildcard_index, wildcard_value_index + 1));
+        pattern_index = wildcard_index + 1;
+        value_index = wildcard_value_index + 1;
+    }
+
+    pattern[pattern_index..]
+        .iter()
+        .all(|character| *character == '%')
+}
+
+/// Calculates with integers, or with numerics when either operand is a numeric.
+fn calculate(lhs: &Value, operation: ArithmeticOperation, rhs: &Value) -> Result<Value, Error> {
+    let (lhs, rhs) = match (lhs, rhs) {
+        (Value::Integer(lhs), Value::Integer(rhs)) => (lhs, rhs),
+
+        _ => {
+            return match (as_decimal(lhs), as_decimal(rhs)) {
+                (Some(lhs), Some(rhs)) => calculate_decimal(lhs, operation, rhs),
+                _ => Err(Error::TypeMismatch),
+            }
+        }
+    };
+
+    let result = match operation {
+        ArithmeticOperation::Addition => lhs.checked_add(*rhs),
+        ArithmeticOperation::Subtraction => lhs.checked_sub(*rhs),
+        ArithmeticOperation::Multiplication => lhs.checked_mul(*rhs),
+        ArithmeticOperation::Division if *rhs == 0 => return Err(Error::DivisionByZero),
+        ArithmeticOperation::Division => lhs.checked_div(*rhs),
+    };
+
+    result.map(Value::Integer).ok_or(Error::IntegerOutOfRange)
+}
+
+fn calculate_decimal(
+    lhs: Decimal,
+    operation: ArithmeticOperation,
+    rhs: Decimal,
+) -> Result<Value, Error> {
+    let result = match operation {
+        ArithmeticOperation::Addition => lhs.checked_add(&rhs),
+        ArithmeticOperation::Subtraction => lhs.checked_sub(&rhs),
+        ArithmeticOperation::Multiplication => lhs.checked_mul(&rhs),
+        ArithmeticOperation::Division if rhs.is_zero() => return Err(Error::DivisionByZero),
+        ArithmeticOperation::Division => lhs.checked_div(&rhs),
+    };
+
+    result
+        .map(Value::Decimal)
+        .ok_or(Error::NumericValueOutOfRange)
+}
+
+fn as_decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::Integer(value) => Some(Decimal::from(*value)),
+        Value::Decimal(value) => Some(*value),
+
+        _ => None,
+    }
+}
+
+/// Combines two conditions using three-valued logic, where `None` is unknown.
+fn combine(operation: LogicalOperation, lhs: Option<bool>, rhs: Option<bool>) -> Option<bool> {
+    match (operation, lhs, rhs) {
+        (LogicalOperation::And, Some(false), _) | (LogicalOperation::And, _, Some(false)) => {
+            Some(false)
+        }
+        (LogicalOperation::And, Some(true), Some(true)) => Some(true),
+        (LogicalOperation::Or, Some(true), _) | (LogicalOperation::Or, _, Some(true)) => Some(true),
+        (LogicalOperation::Or, Some(false), Some(false)) => Some(false),
+
+        _ => None,
+    }
+}
+
+fn as_boolean(value: Option<Value>) -> Result<Option<bool>, Error> {
+    match value {
+        Some(Value::Boolean(value)) => Ok(Some(value)),
+        None => Ok(None),
+
+        Some(_) => Err(Error::TypeMismatch),
+    }
+}
+
+/// Interprets the result of a condition, where `NULL` counts as false.
+fn is_true(value: Option<Value>) -> Result<bool, Error> {
+    Ok(as_boolean(value)?.unwrap_or(false))
+}
+
+fn evaluate_aggregate(
+    expression: &Expression,
+    row_result: &RowResult,
+) -> Result<Option<Value>, Error> {
+    let Expression::Function { name, arguments } = expression else {
+        return match expression {
+            Expression::Null | Expression::Literal(_) => evaluate(expression, &[], &[]),
+            _ => Err(Error::ColumnNotAggregated(expression.column_name())),
+        };
+    };
+
+    let function_name = name.to_uppercase();
+
+    // `STRING_AGG(expression, delimiter)` is the only aggregate with a second argument.
+    let (argument, delimiter) = match (&function_name[..], &arguments[..]) {
+        ("STRING_AGG", [argument, delimiter]) => (argument, Some(delimiter)),
+        ("STRING_AGG", _) => return Err(Error::InvalidFunctionArguments(name.clone())),
+        (_, [argument]) => (argument, None),
+        _ => return Err(Error::InvalidFunctionArguments(name.clone())),
+    };
+
+    if function_name == "COUNT" && *argument == Expression::Wildcard {
+        return count_value(row_result.rows().len());
+    }
+
+    let (argument, distinct) = match argument {
+        Expression::Distinct(argument) => (argument.as_ref(), true),
+        argument => (argument, false),
+    };
+
+    let mut values = row_result
+        .rows()
+        .iter()
+        .map(|row| evaluate(argument, row_result.columns(), row))
+        .collect::<Result<Vec<Option<Value>>, Error>>()?;
+
+    if distinct {
+        values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+        values.dedup();
+    }
+
+    // Only `ARRAY_AGG` keeps the `NULL` values.
+    if function_name == "ARRAY_AGG" {
+        return Ok((!values.is_empty()).then(|| Value::Text(array_literal(&values))));
+    }
+    let values: Vec<Value> = values.into_iter().flatten().collect();
+
+    match &function_name[..] {
+        "COUNT" => count_value(values.len()),
+        "STRING_AGG" => {
+            let delimiter = match delimiter.map(|delimiter| evaluate(delimiter, &[], &[])) {
+                Some(Ok(Some(Value::Text(delimiter)))) => delimiter,
+                Some(Ok(None)) | None => String::new(),
+                Some(Ok(Some(_))) => return Err(Error::TypeMismatch),
+                Some(Err(error)) => return Err(error),
+            };
+
+            Ok((!values.is_empty()).then(|| {
+                Value::Text(
+                    values
+                        .iter()
+                        .map(Value::to_string)
+                        .collect::<Vec<String>>()
+                        .join(&delimiter),
+                )
+            }))
+        }
+        "MAX" => Ok(values
+            .into_iter()
+            .reduce(|max, value| if value > max { value } else { max })),
+        "MIN" => Ok(values
+            .into_iter()
+            .reduce(|min, value| if value < min { value } else { min })),
+
+        _ => Err(Error::FunctionDoesNotExist(name.clone())),
+    }
+}
+
+/// Formats the values the way PostgreSQL writes an array, e.g. `{1,NULL,"a b"}`, since there's no
+/// array type. Text that would be ambiguous is quoted.
+fn array_literal(values: &[Option<Value>]) -> String {
+    let elements: Vec<String> = values
+        .iter()
+        .map(|value| match value {
+            None => "NULL".to_string(),
+            Some(Value::Text(text))
+                if text.is_empty()
+                    || text.eq_ignore_ascii_case("null")
+                    || text
+                        .chars()
+                        .any(|c| c.is_whitespace() || "{},\\\"".contains(c)) =>
+            {
+                format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+            }
+            Some(value) => value.to_string(),
+        })
+        .collect();
+
+    format!("{{{}}}", elements.join(","))
+}
+
+fn count_value(count: usize) -> Result<Option<Value>, Error> {
+    i64::try_from(count)
+        .map(|count| Some(Value::Integer(count)))
+        .map_err(|_| Error::IntegerOutOfRange)
+}
+
+fn is_aggregate(expression: &Expression) -> bool {
+    match expression {
+        Expression::Function { name, .. } => {
+            AGGREGATE_FUNCTIONS.contains(&name.to_uppercase().as_str())
+        }
+
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::parser::LiteralValue;
+
+    fn row_result() -> RowResult {
+        RowResult::new(
+            vec!["id".to_string(), "age".to_string()],
+            vec![
+                vec![Some(Value::Integer(1)), Some(Value::Integer(30))],
+                vec![Some(Value::Integer(2)), None],
+                vec![Some(Value::Integer(3)), Some(Value::Integer(18))],
+            ],
+        )
+    }
+
+    #[test]
+    fn test_projecting_identifiers_and_literals() {
+        let result = project(
+            &row_result(),
+            &[
+                Expression::Identifier("age".to_string()),
+                Expression::Literal(LiteralValue::Integer(7)),
+            ],
+        )
+        .expect("Failed to project rows");
+
+        assert_eq!(
+            &vec!["age".to_string(), "?column?".to_string()],
+            result.columns()
+        );
+        assert_eq!(
+            vec![
+                vec![Some(Value::Integer(30)), Some(Value::Integer(7))],
+                vec![None, Some(Value::Integer(7))],
+                vec![Some(Value::Integer(18)), Some(Value::Integer(7))],
+            ],
+            result.rows()
+        );
+    }
+
+    #[test]
+    fn test_aggregating_rows() {
+        let function = |name: &str, argument: Expression| Expression::Function {
+            name: name.to_string(),
+            arguments: vec![argument],
+        };
+
+        let result = project(
+            &row_result(),
+            &[
+                function("MAX", Expression::Identifier("id".to_string())),
+                function("MIN", Expression::Identifier("age".to_string())),
+                function("COUNT", Expression::Identifier("age".to_string())),
+                function("COUNT", Expression::Wildcard),
+            ],
+        )
+        .expect("Failed to aggregate rows");
+
+        assert_eq!(
+            vec![vec![
+                Some(Value::Integer(3)),
+                Some(Value::Integer(18)),
+                Some(Value::Integer(2)),
+                Some(Value::Integer(3)),
+            ]],
+            result.rows()
+        );
+    }
+
+    #[test]
+    fn test_aggregating_values_into_strings_and_arrays() {
+        let row_result = RowResult::new(
+            vec!["name".to_string()],
+            vec![
+                vec![Some(Value::Text("b".to_string()))],
+                vec![None],
+                vec![Some(Value::Text("a, \"c\"".to_string()))],
+                vec![Some(Value::Text("b".to_string()))],
+            ],
+        );
+        let name = || Expression::Identifier("name".to_string());
+        let separator = || Expression::Literal(LiteralValue::Text("; ".to_string()));
+        let function = |name: &str, arguments: Vec<Expression>| Expression::Function {
+            name: name.to_string(),
+            arguments,
+        };
+
+        let result = project(
+            &row_result,
+            &[
+                function("string_agg", vec![name(), separator()]),
+                function(
+                    "STRING_AGG",
+                    vec![Expression::Distinct(Box::new(name())), separator()],
+                ),
+                function("ARRAY_AGG", vec![name()]),
+            ],
+        )
+        .expect("Failed to aggregate rows");
+        assert_eq!(
+            &vec![
+                "string_agg".to_string(),
+                "string_agg".to_string(),
+                "array_agg".to_string()
+            ],
+            result.columns()
+        );
+        assert_eq!(
+            vec![vec![
+                Some(Value::Text("b; a, \"c\"; b".to_string())),
+                Some(Value::Text("a, \"c\"; b".to_string())),
+                Some(Value::Text("{b,NULL,\"a, \\\"c\\\"\",b}".to_string())),
+            ]],
+            result.rows()
+        );
+
+        let empty = RowResult::new(vec!["name".to_string()], vec![]);
+        assert_eq!(
+            vec![vec![None, None]],
+            project(
+                &empty,
+                &[
+                    function("STRING_AGG", vec![name(), separator()]),
+                    function("ARRAY_AGG", vec![name()]),
+                ],
+            )
+            .expect("Failed to aggregate rows")
+            .rows()
+        );
+        assert_eq!(
+            Err(Error::InvalidFunctionArguments("STRING_AGG".to_string())),
+            project(&row_result, &[function("STRING_AGG", vec![name()])])
+        );
+    }
+
+    #[test]
+    fn test_aggregating_distinct_values() {
+        let function = |name: &str, argument: Expression| Expression::Function {
+            name: name.to_string(),
+            arguments: vec![argument],
+        };
+        let distinct =
+            |name: &str| Expression::Distinct(Box::new(Expression::Identifier(name.to_string())));
+        let row_result = RowResult::new(
+            vec!["user_id".to_string()],
+            vec![
+                vec![Some(Value::Integer(2))],
+                vec![Some(Value::Integer(1))],
+                vec![None],
+                vec![Some(Value::Integer(2))],
+            ],
+        );
+
+        let result = project(
+            &row_result,
+            &[
+                function("COUNT", distinct("user_id")),
+                function("COUNT", Expression::Identifier("user_id".to_string())),
+                function("MAX", distinct("user_id")),
+            ],
+        )
+        .expect("Failed to aggregate rows");
+        assert_eq!(
+            vec![vec![
+                Some(Value::Integer(2)),
+                Some(Value::Integer(3)),
+                Some(Value::Integer(2)),
+            ]],
+            result.rows()
+        );
+
+        assert_eq!(
+            Err(Error::InvalidFunctionArguments("UPPER".to_string())),
+            project(&row_result, &[function("UPPER", distinct("user_id"))])
+        );
+    }
+
+    #[test]
+    fn test_evaluating_arithmetic() {
+        let arithmetic = |lhs: Expression, operation, rhs: i64| Expression::Arithmetic {
+            lhs: Box::new(lhs),
+            operation,
+            rhs: Box::new(Expression::Literal(LiteralValue::Integer(rhs))),
+        };
+
+        let result = project(
+            &row_result(),
+            &[
+                arithmetic(
+                    Expression::Identifier("age".to_string()),
+                    ArithmeticOperation::Addition,
+                    1,
+                ),
+                arithmetic(
+                    arithmetic(
+                        Expression::Identifier("id".to_string()),
+                        ArithmeticOperation::Multiplication,
+                        10,
+                    ),
+                    ArithmeticOperation::Division,
+                    4,
+                ),
+            ],
+        )
+        .expect("Failed to project rows");
+
+        assert_eq!(
+            vec![
+                vec![Some(Value::Integer(31)), Some(Value::Integer(2))],
+                vec![None, Some(Value::Integer(5))],
+                vec![Some(Value::Integer(19)), Some(Value::Integer(7))],
+            ],
+            result.rows()
+        );
+    }
+
+    #[test]
+    fn test_evaluating_invalid_arithmetic() {
+        let arithmetic = |operation, rhs: i64| Expression::Arithmetic {
+            lhs: Box::new(Expression::Identifier("id".to_string())),
+            operation,
+            rhs: Box::new(Expression::Literal(LiteralValue::Integer(rhs))),
+        };
+
+        assert_eq!(
+            Err(Error::DivisionByZero),
+            project(
+                &row_result(),
+                &[arithmetic(ArithmeticOperation::Division, 0)]
+            )
+        );
+        assert_eq!(
+            Err(Error::IntegerOutOfRange),
+            project(
+                &row_result(),
+                &[arithmetic(ArithmeticOperation::Multiplication, i64::MAX)]
+            )
+        );
+        assert_eq!(
+            Err(Error::IntegerOutOfRange),
+            project(
+                &row_result(),
+                &[arithmetic(ArithmeticOperation::Subtraction, i64::MIN)]
+            )
+        );
+    }
+
+    #[test]
+    fn test_evaluating_numeric_arithmetic() {
+        let numeric =
+            |text: &str| Expression::Literal(LiteralValue::Decimal(Decimal::parse(text).unwrap()));
+        let arithmetic = |lhs: Expression, operation, rhs: Expression| Expression::Arithmetic {
+            lhs: Box::new(lhs),
+            operation,
+            rhs: Box::new(rhs),
+        };
+        let evaluate_to_string = |expression: &Expression| {
+            evaluate(expression, &[], &[]).map(|value| value.unwrap().to_string())
+        };
+
+        assert_eq!(
+            Ok("0.3".to_string()),
+            evaluate_to_string(&arithmetic(
+                numeric("0.1"),
+                ArithmeticOperation::Addition,
+                numeric("0.2")
+            ))
+        );
+        assert_eq!(
+            Ok("7.50".to_string()),
+            evaluate_to_string(&arithmetic(
+                Expression::Literal(LiteralValue::Integer(3)),
+                ArithmeticOperation::Multiplication,
+                numeric("2.50")
+            ))
+        );
+        assert_eq!(
+            Err(Error::DivisionByZero),
+            evaluate_to_string(&arithmetic(
+                numeric("1.5"),
+                ArithmeticOperation::Division,
+                numeric("0.0")
+            ))
+        );
+        assert_eq!(
+            Err(Error::TypeMismatch),
+            evaluate_to_string(&arithmetic(
+                numeric("1.5"),
+                ArithmeticOperation::Addition,
+                Expression::Literal(LiteralValue::Text("1".to_string()))
+            ))
+        );
+
+        let compare = |lhs: Expression, operation, rhs: Expression| {
+            evaluate(
+                &Expression::Comparison {
+                    lhs: Box::new(lhs),
+                    operation,
+                    rhs: Box::new(rhs),
+                },
+                &[],
+                &[],
+            )
+        };
+        assert_eq!(
+            Ok(Some(Value::Boolean(true))),
+            compare(
+                numeric("2.00"),
+                CompareOperation::Equality,
+                Expression::Literal(LiteralValue::Integer(2))
+            )
+        );
+        assert_eq!(
+            Ok(Some(Value::Boolean(true))),
+            compare(numeric("1.05"), CompareOperation::LessThan, numeric("1.5"))
+        );
+    }
+
+    #[test]
+    fn test_evaluating_negations() {
+        let negation = |expression: Expression| Expression::Negation(Box::new(expression));
+
+        let result = project(
+            &row_result(),
+            &[negation(Expression::Arithmetic {
+                lhs: Box::new(Expression::Identifier("age".to_string())),
+                operation: ArithmeticOperation::Subtraction,
+                rhs: Box::new(Expression::Identifier("age".to_string())),
+            })],
+        )
+        .expect("Failed to project rows");
+        assert_eq!(
+            vec![
+                vec![Some(Value::Integer(0))],
+                vec![None],
+                vec![Some(Value::Integer(0))]
+            ],
+            result.rows()
+        );
+
+        let result = project(
+            &row_result(),
+            &[negation(Expression::Identifier("id".to_string()))],
+        )
+        .expect("Failed to project rows");
+        assert_eq!(
+            vec![
+                vec![Some(Value::Integer(-1))],
+                vec![Some(Value::Integer(-2))],
+                vec![Some(Value::Integer(-3))]
+            ],
+            result.rows()
+        );
+        assert_eq!(
+            Err(Error::IntegerOutOfRange),
+            project(
+                &row_result(),
+                &[negation(Expression::Literal(LiteralValue::Integer(
+                    i64::MIN
+                )))]
+            )
+        );
+        assert_eq!(
+            Err(Error::TypeMismatch),
+            project(
+                &row_result(),
+                &[negation(Expression::Literal(LiteralValue::Boolean(true)))]
+            )
+        );
+    }
+
+    #[test]
+    fn test_filtering_rows() {
+        let condition = Expression::Comparison {
+            lhs: Box::new(Expression::Identifier("age".to_string())),
+            operation: CompareOperation::Equality,
+            rhs: Box::new(Expression::Literal(LiteralValue::Integer(18))),
+        };
+
+        let result = filter(&row_result(), &condition).expect("Failed to filter rows");
+
+        assert_eq!(
+            vec![vec![Some(Value::Integer(3)), Some(Value::Integer(18))]],
+            result.rows()
+        );
+    }
+
+    #[test]
+    fn test_filtering_with_range_comparisons() {
+        let ids_where = |operation: CompareOperation| {
+            let condition = Expression::Comparison {
+                lhs: Box::new(Expression::Identifier("age".to_string())),
+                operation,
+                rhs: Box::new(Expression::Literal(LiteralValue::Integer(18))),
+            };
+
+            filter(&row_result(), &condition)
+                .expect("Failed to filter rows")
+                .rows()
+                .iter()
+                .map(|row| row[0].clone())
+                .collect::<Vec<Option<Value>>>()
+        };
+
+        // The row without an age never matches.
+        assert_eq!(
+            vec![Some(Value::Integer(1))],
+            ids_where(CompareOperation::Inequality)
+        );
+        assert!(ids_where(CompareOperation::LessThan).is_empty());
+        assert_eq!(
+            vec![Some(Value::Integer(3))],
+            ids_where(CompareOperation::LessThanOrEqual)
+        );
+        assert_eq!(
+            vec![Some(Value::Integer(1))],
+            ids_where(CompareOperation::GreaterThan)
+        );
+        assert_eq!(
+            vec![Some(Value::Integer(1)), Some(Value::Integer(3))],
+            ids_where(CompareOperation::GreaterThanOrEqual)
+        );
+    }
+
+    #[test]
+    fn test_filtering_with_boolean_operators() {
+        let equals = |name: &str, value: i64| Expression::Comparison {
+            lhs: Box::new(Expression::Identifier(name.to_string())),
+            operation: CompareOperation::Equality,
+            rhs: Box::new(Expression::Literal(LiteralValue::Integer(value))),
+        };
+
+        // id = 1 OR NOT age = 18, where the row without an age is unknown and filtered out.
+        let condition = Expression::Logical {
+            lhs: Box::new(equals("id", 1)),
+            operation: LogicalOperation::Or,
+            rhs: Box::new(Expression::Not(Box::new(equals("age", 18)))),
+        };
+        let result = filter(&row_result(), &condition).expect("Failed to filter rows");
+        assert_eq!(
+            vec![vec![Some(Value::Integer(1)), Some(Value::Integer(30))]],
+            result.rows()
+        );
+
+        // id = 2 AND age = 30 is unknown for the row without an age, which is filtered out.
+        let condition = Expression::Logical {
+            lhs: Box::new(equals("id", 2)),
+            operation: LogicalOperation::And,
+            rhs: Box::new(equals("age", 30)),
+        };
+        let result = filter(&row_result(), &condition).expect("Failed to filter rows");
+        assert!(result.rows().is_empty());
+
+        // ... but (age = 30 AND id = 2) OR id = 2 is true for it.
+        let condition = Expression::Logical {
+            lhs: Box::new(Expression::Logical {
+                lhs: Box::new(equals("age", 30)),
+                operation: LogicalOperation::And,
+                rhs: Box::new(equals("id", 2)),
+            }),
+            operation: LogicalOperation::Or,
+            rhs: Box::new(equals("id", 2)),
+        };
+        let result = filter(&row_result(), &condition).expect("Failed to filter rows");
+        assert_eq!(vec![vec![Some(Value::Integer(2)), None]], result.rows());
+    }
+
+    #[test]
+    fn test_filtering_with_non_boolean_condition() {
+        let condition = Expression::Identifier("age".to_string());
+
+        assert_eq!(Err(Error::TypeMismatch), filter(&row_result(), &condition));
+    }
+
+    #[test]
+    fn test_matching_like_patterns() {
+        assert!(like_matches("John", "Jo%"));
+        assert!(like_matches("Jo", "Jo%"));
+        assert!(like_matches("Joanna", "%an%a"));
+        assert!(like_matches("Jim", "J_m"));
+        assert!(like_matches("", "%"));
+
+        assert!(!like_matches("Jim", "Jo%"));
+        assert!(!like_matches("Joe", "J_"));
+        assert!(!like_matches("Anna", "%an%a"));
+    }
+
+    #[test]
+    fn test_evaluating_like_expressions() {
+        let like = |value: Expression, negated: bool| Expression::Like {
+            expression: Box::new(value),
+            pattern: Box::new(Expression::Literal(LiteralValue::Text("Jo%".to_string()))),
+            negated,
+        };
+        let text = |value: &str| Expression::Literal(LiteralValue::Text(value.to_string()));
+
+        assert_eq!(
+            Ok(Some(Value::Boolean(true))),
+            evaluate(&like(text("John"), false), &[], &[])
+        );
+        assert_eq!(
+            Ok(Some(Value::Boolean(false))),
+            evaluate(&like(text("John"), true), &[], &[])
+        );
+        assert_eq!(Ok(None), evaluate(&like(Expression::Null, false), &[], &[]));
+        assert_eq!(
+            Err(Error::TypeMismatch),
+            evaluate(
+                &like(Expression::Literal(LiteralValue::Integer(1)), false),
+                &[],
+                &[]
+            )
+        );
+    }
+
+    #[test]
+    fn test_filtering_with_between() {
+        let between = |low: i64, high: i64, negated: bool| Expression::Between {
+            expression: Box::new(Expression::Identifier("age".to_string())),
+            low: Box::new(Expression::Literal(LiteralValue::Integer(low))),
+            high: Box::new(Expression::Literal(LiteralValue::Integer(high))),
+            negated,
+        };
+        let ids_where = |condition: Expression| {
+            filter(&row_result(), &condition)
+                .expect("Failed to filter rows")
+                .rows()
+                .iter()
+                .map(|row| row[0].clone())
+                .collect::<Vec<Option<Value>>>()
+        };
+
+        // The row without an age never matches.
+        assert_eq!(
+            vec![Some(Value::Integer(1)), Some(Value::Integer(3))],
+            ids_where(between(18, 30, false))
+        );
+        assert_eq!(
+            vec![Some(Value::Integer(3))],
+            ids_where(between(10, 29, false))
+        );
+        assert_eq!(
+            vec![Some(Value::Integer(1))],
+            ids_where(between(10, 29, true))
+        );
+        assert!(ids_where(between(30, 18, false)).is_empty());
+    }
+
+    #[test]
+    fn test_evaluating_in_lists() {
+        let integer = |value: i64| Expression::Literal(LiteralValue::Integer(value));
+        let in_list = |list: Vec<Expression>, negated: bool| Expression::InList {
+            expression: Box::new(Expression::Identifier("id".to_string())),
+            list,
+            negated,
+        };
+        let evaluate_row = |expression: &Expression, id: Option<Value>| {
+            evaluate(expression, &["id".to_string()], &[id])
+        };
+
+        let condition = in_list(vec![integer(1), integer(3)], false);
+        assert_eq!(
+            Ok(Some(Value::Boolean(true))),
+            evaluate_row(&condition, Some(Value::Integer(3)))
+        );
+        assert_eq!(
+            Ok(Some(Value::Boolean(false))),
+            evaluate_row(&condition, Some(Value::Integer(2)))
+        );
+        assert_eq!(Ok(None), evaluate_row(&condition, None));
+
+        let condition = in_list(vec![integer(1), Expression::Null], true);
+        assert_eq!(
+            Ok(Some(Value::Boolean(false))),
+            evaluate_row(&condition, Some(Value::Integer(1)))
+        );
+        assert_eq!(Ok(None), evaluate_row(&condition, Some(Value::Integer(2))));
+    }
+
+    #[test]
+    fn test_evaluating_case_expressions() {
+        let case = Expression::Case {
+            operand: None,
+            branches: vec![(
+                Expression::Comparison {
+                    lhs: Box::new(Expression::Identifier("age".to_string())),
+                    operation: CompareOperation::Equality,
+                    rhs: Box::new(Expression::Literal(LiteralValue::Integer(18))),
+                },
+                Expression::Literal(LiteralValue::Integer(1)),
+            )],
+            else_result: Some(Box::new(Expression::Literal(LiteralValue::Integer(0)))),
+        };
+
+        let result = project(&row_result(), &[case]).expect("Failed to project rows");
+
+        assert_eq!(&vec!["case".to_string()], result.columns());
+        assert_eq!(
+            vec![
+                vec![Some(Value::Integer(0))],
+                vec![Some(Value::Integer(0))],
+                vec![Some(Value::Integer(1))],
+            ],
+            result.rows()
+        );
+    }
+
+    #[test]
+    fn test_evaluating_case_expressions_with_operand() {
+        let case = Expression::Case {
+            operand: Some(Box::new(Expression::Identifier("id".to_string()))),
+            branches: vec![(
+                Expression::Literal(LiteralValue::Integer(2)),
+                Expression::Literal(LiteralValue::Boolean(true)),
+            )],
+            else_result: None,
+        };
+
+        let result = project(&row_result(), &[case]).expect("Failed to project rows");
+
+        assert_eq!(
+            vec![vec![None], vec![Some(Value::Boolean(true))], vec![None]],
+            result.rows()
+        );
+    }
+
+    #[test]
+    fn test_evaluating_casts() {
+        let cast = |value: Value, data_type| {
+            evaluate(
+                &Expression::Cast {
+                    expression: Box::new(Expression::Literal(value.into())),
+                    data_type,
+                },
+                &[],
+                &[],
+            )
+        };
+        let text = |value: &str| Value::Text(value.to_string());
+
+        assert_eq!(
+            Ok(Some(text("30"))),
+            cast(Value::Integer(30), CastType::Text)
+        );
+        assert_eq!(
+            Ok(Some(Value::Boolean(false))),
+            cast(Value::Integer(0), CastType::Boolean)
+        );
+        assert_eq!(
+            Ok(Some(Value::Integer(1))),
+            cast(Value::Boolean(true), CastType::Integer)
+        );
+        assert_eq!(
+            Ok(Some(text("false"))),
+            cast(Value::Boolean(false), CastType::Text)
+        );
+        assert_eq!(
+            Ok(Some(Value::Integer(42))),
+            cast(text(" 42 "), CastType::Integer)
+        );
+        assert_eq!(
+            Ok(Some(Value::Boolean(true))),
+            cast(text("Yes"), CastType::Boolean)
+        );
+
+        assert_eq!(
+            Ok(Some(Value::Integer(-256))),
+            cast(text("-256"), CastType::Integer)
+        );
+        assert_eq!(
+            Err(Error::IntegerOutOfRange),
+            cast(text("9223372036854775808"), CastType::Integer)
+        );
+        assert_eq!(
+            Err(Error::InvalidCast {
+                value: "forty".to_string(),
+                data_type: CastType::Integer
+            }),
+            cast(text("forty"), CastType::Integer)
+        );
+        assert_eq!(
+            Err(Error::InvalidCast {
+                value: "maybe".to_string(),
+                data_type: CastType::Boolean
+            }),
+            cast(text("maybe"), CastType::Boolean)
+        );
+
+        assert_eq!(
+            Ok(Some(Value::Decimal(Decimal::new(-50, 2)))),
+            cast(text(" -0.50 "), CastType::Numeric)
+        );
+        assert_eq!(
+            Ok(Some(Value::Integer(3))),
+            cast(Value::Decimal(Decimal::new(25, 1)), CastType::Integer)
+        );
+        assert_eq!(
+            Err(Error::InvalidCast {
+                value: "1,5".to_string(),
+                data_type: CastType::Numeric
+            }),
+            cast(text("1,5"), CastType::Numeric)
+        );
+
+        assert_eq!(
+            Ok(Some(Value::Bytes(vec![0xDE, 0xAD]))),
+            cast(text("\\xDEad"), CastType::Bytea)
+        );
+        assert_eq!(
+            Ok(Some(Value::Bytes(b"hi".to_vec()))),
+            cast(text("hi"), CastType::Bytea)
+        );
+        assert_eq!(
+            Ok(Some(text("\\x00ff"))),
+            cast(Value::Bytes(vec![0x00, 0xFF]), CastType::Text)
+        );
+        assert_eq!(
+            Err(Error::InvalidCast {
+                value: "\\xABC".to_string(),
+                data_type: CastType::Bytea
+            }),
+            cast(text("\\xABC"), CastType::Bytea)
+        );
+        assert_eq!(
+            Err(Error::TypeMismatch),
+            cast(Value::Integer(1), CastType::Bytea)
+        );
+
+        let uuid = Uuid::parse("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11").unwrap();
+        assert_eq!(
+            Ok(Some(Value::Uuid(uuid))),
+            cast(
+                text(" A0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11 "),
+                CastType::Uuid
+            )
+        );
+        assert_eq!(
+            Ok(Some(text("a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11"))),
+            cast(Value::Uuid(uuid), CastType::Text)
+        );
+        assert_eq!(
+            Err(Error::TypeMismatch),
+            cast(Value::Uuid(uuid), CastType::Integer)
+        );
+
+        assert_eq!(
+            Ok(Some(Value::Json(" [1, {\"a\": null}] ".to_string()))),
+            cast(text(" [1, {\"a\": null}] "), CastType::Json)
+        );
+        assert_eq!(
+            Ok(Some(text("{\"a\":1}"))),
+            cast(Value::Json("{\"a\":1}".to_string()), CastType::Text)
+        );
+        assert_eq!(
+            Err(Error::InvalidCast {
+                value: "{'a': 1}".to_string(),
+                data_type: CastType::Json
+            }),
+            cast(text("{'a': 1}"), CastType::Json)
+        );
+        assert_eq!(
+            Err(Error::TypeMismatch),
+            cast(Value::Integer(1), CastType::Json)
+        );
+
+        // Casting `NULL` results in `NULL`.
+        let result = project(
+            &row_result(),
+            &[Expression::Cast {
+                expression: Box::new(Expression::Identifier("age".to_string())),
+                data_type: CastType::Text,
+            }],
+        )
+        .expect("Failed to project rows");
+        assert_eq!(&vec!["age".to_string()], result.columns());
+        assert_eq!(
+            vec![vec![Some(text("30"))], vec![None], vec![Some(text("18"))]],
+            result.rows()
+        );
+    }
+
+    #[test]
+    fn test_evaluating_string_functions() {
+        let call = |name: &str, arguments: Vec<Expression>| {
+            evaluate(
+                &Expression::Function {
+                    name: name.to_string(),
+                    arguments,
+                },
+                &[],
+                &[],
+            )
+        };
+        let text = |value: &str| Expression::Literal(LiteralValue::Text(value.to_string()));
+        let integer = |value: i64| Expression::Literal(LiteralValue::Integer(value));
+        let text_value = |value: &str| Some(Value::Text(value.to_string()));
+
+        assert_eq!(Ok(text_value("ÅSA")), call("upper", vec![text("åsa")]));
+        assert_eq!(Ok(text_value("åsa")), call("LOWER", vec![text("ÅSA")]));
+        assert_eq!(
+            Ok(Some(Value::Integer(3))),
+            call("length", vec![text("åsa")])
+        );
+        assert_eq!(
+            Ok(text_value("a1true")),
+            call(
+                "concat",
+                vec![
+                    text("a"),
+                    integer(1),
+                    Expression::Null,
+                    Expression::Literal(LiteralValue::Boolean(true))
+                ]
+            )
+        );
+        assert_eq!(
+            Ok(text_value("ell")),
+            call("substr", vec![text("hello"), integer(2), integer(3)])
+        );
+        assert_eq!(
+            Ok(text_value("llo")),
+            call("substr", vec![text("hello"), integer(3)])
+        );
+        assert_eq!(
+            Ok(text_value("h")),
+            call("substr", vec![text("hello"), integer(0), integer(2)])
+        );
+        assert_eq!(
+            Ok(text_value("he")),
+            call("substr", vec![text("hello"), integer(-1), integer(4)])
+        );
+        assert_eq!(
+            Err(Error::InvalidFunctionArguments("substr".to_string())),
+            call("substr", vec![text("hello"), integer(1), integer(-1)])
+        );
+
+        assert_eq!(Ok(None), call("upper", vec![Expression::Null]));
+        assert_eq!(
+            Err(Error::InvalidFunctionArguments("upper".to_string())),
+            call("upper", vec![integer(1)])
+        );
+        assert_eq!(
+            Err(Error::InvalidFunctionArguments("length".to_string())),
+            call("length", vec![text("a"), text("b")])
+        );
+        assert_eq!(
+            Err(Error::FunctionDoesNotExist("reverse".to_string())),
+            call("reverse", vec![text("a")])
+        );
+    }
+
+    #[test]
+    fn test_evaluating_json_extract() {
+        let json_extract = |json: &str, path: Vec<LiteralValue>| {
+            evaluate(
+                &Expression::Function {
+                    name: "JSON_EXTRACT".to_string(),
+                    arguments: [Expression::Cast {
+                        expression: Box::new(Expression::Literal(LiteralValue::Text(
+                            json.to_string(),
+                        ))),
+                        data_type: CastType::Json,
+                    }]
+                    .into_iter()
+                    .chain(path.into_iter().map(Expression::Literal))
+                    .collect(),
+                },
+                &[],
+                &[],
+            )
+        };
+        let key = |key: &str| LiteralValue::Text(key.to_string());
+        let json = |json: &str| Some(Value::Json(json.to_string()));
+        let document = r#"{"name": "Ada", "tags": ["math",  {"year":1843}]}"#;
+
+        assert_eq!(
+            Ok(json("\"Ada\"")),
+            json_extract(document, vec![key("name")])
+        );
+        assert_eq!(
+            Ok(json("{\"year\": 1843}")),
+            json_extract(document, vec![key("tags"), LiteralValue::Integer(-1)])
+        );
+        assert_eq!(
+            Ok(json("\"math\"")),
+            json_extract(document, vec![key("tags"), LiteralValue::Integer(0)])
+        );
+        assert_eq!(Ok(None), json_extract(document, vec![key("age")]));
+        assert_eq!(
+            Ok(None),
+            json_extract(document, vec![key("name"), key("first")])
+        );
+        assert_eq!(
+            Ok(None),
+            json_extract(document, vec![key("tags"), LiteralValue::Integer(2)])
+        );
+        assert_eq!(
+            Err(Error::InvalidFunctionArguments("JSON_EXTRACT".to_string())),
+            json_extract(document, vec![LiteralValue::Boolean(true)])
+        );
+        assert_eq!(
+            Err(Error::InvalidFunctionArguments("JSON_EXTRACT".to_string())),
+            json_extract(document, vec![])
+        );
+    }
+
+    #[test]
+    fn test_mixing_aggregates_and_columns() {
+        let result = project(
+            &row_result(),
+            &[
+                Expression::Identifier("id".to_string()),
+                Expression::Function {
+                    name: "MAX".to_string(),
+                    arguments: vec![Expression::Identifier("age".to_string())],
+                },
+            ],
+        );
+
+        assert_eq!(Err(Error::ColumnNotAggregated("id".to_string())), result);
+    }
+}
@@ -0,0 +1,179 @@
+use super::row_result::RowValues;
+use super::{Decimal, Error, Uuid, Value};
+
+/// A type that a row of a `RowResult` can be converted into, see `Manager::query_as`. The values
+/// are taken from the row by the names of their columns, e.g.:
+///
+/// ```ignore
+/// impl FromRow for User {
+///     fn from_row(row: &Row) -> Result<Self, Error> {
+///         Ok(User {
+///             id: row.get("id")?,
+///             name: row.get("name")?,
+///         })
+///     }
+/// }
+/// ```
+///
+/// Tuples of up to four values are converted from the columns in order.
+pub trait FromRow: Sized {
+    fn from_row(row: &Row) -> Result<Self, Error>;
+}
+
+/// A type that the value of a column can be converted into, see `Row::get`. Converting `NULL`
+/// only works for `Option`s.
+pub trait FromValue: Sized {
+    /// Returns `None` when the value has another type.
+    fn from_value(value: Option<Value>) -> Option<Self>;
+}
+
+/// A row of a `RowResult` together with the names of its columns.
+pub struct Row<'a> {
+    columns: &'a [String],
+    values: &'a RowValues,
+}
+
+impl<'a> Row<'a> {
+    pub fn new(columns: &'a [String], values: &'a RowValues) -> Self {
+        Self { columns, values }
+    }
+
+    /// Returns the value of the column named `column_name`, converted to `T`. Fails when there is
+    /// no such column, when the value has another type or when it's `NULL` and `T` isn't an
+    /// `Option`.
+    pub fn get<T: FromValue>(&self, column_name: &str) -> Result<T, Error> {
+        let index = self
+            .columns
+            .iter()
+            .position(|column| column == column_name)
+            .ok_or(Error::ColumnDoesNotExist(column_name.to_string()))?;
+
+        self.get_at(index)
+    }
+
+    /// Like `get`, but returns the value of the column at `index`.
+    pub fn get_at<T: FromValue>(&self, index: usize) -> Result<T, Error> {
+        let column_name = self
+            .columns
+            .get(index)
+            .ok_or(Error::ColumnDoesNotExist(format!("${}", index + 1)))?;
+        let value = self.values.get(index).cloned().flatten();
+
+        match value {
+            None => T::from_value(None).ok_or(Error::UnexpectedNull(column_name.clone())),
+            value => T::from_value(value).ok_or(Error::TypeMismatch),
+        }
+    }
+}
+
+impl FromValue for Value {
+    fn from_value(value: Option<Value>) -> Option<Self> {
+        value
+    }
+}
+
+impl<T: FromValue> FromValue for Option<T> {
+    fn from_value(value: Option<Value>) -> Option<Self> {
+        match value {
+            None => Some(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+macro_rules! impl_from_value {
+    ($type:ty, $variant:ident) => {
+        impl FromValue for $type {
+            fn from_value(value: Option<Value>) -> Option<Self> {
+                match value {
+                    Some(Value::$variant(value)) => Some(value),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+impl_from_value!(i64, Integer);
+impl_from_value!(bool, Boolean);
+impl_from_value!(Decimal, Decimal);
+impl_from_value!(Vec<u8>, Bytes);
+impl_from_value!(Uuid, Uuid);
+
+impl FromValue for String {
+    /// Converts `text` and `json` values, which are both kept as text.
+    fn from_value(value: Option<Value>) -> Option<Self> {
+        match value {
+            Some(Value::Text(value) | Value::Json(value)) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_from_row_for_tuple {
+    ($($type:ident: $index:tt),+) => {
+        impl<$($type: FromValue),+> FromRow for ($($type,)+) {
+            fn from_row(row: &Row) -> Result<Self, Error> {
+                Ok(($(row.get_at::<$type>($index)?,)+))
+            }
+        }
+    };
+}
+
+impl_from_row_for_tuple!(A: 0);
+impl_from_row_for_tuple!(A: 0, B: 1);
+impl_from_row_for_tuple!(A: 0, B: 1, C: 2);
+impl_from_row_for_tuple!(A: 0, B: 1, C: 2, D: 3);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Drink {
+        id: i64,
+        name: String,
+        price: Option<Decimal>,
+    }
+
+    impl FromRow for Drink {
+        fn from_row(row: &Row) -> Result<Self, Error> {
+            Ok(Drink {
+                id: row.get("id")?,
+                name: row.get("name")?,
+                price: row.get("price")?,
+            })
+        }
+    }
+
+    #[test]
+    fn test_converting_rows() {
+        let columns = vec!["id".to_string(), "name".to_string(), "price".to_string()];
+        let values = vec![
+            Some(Value::Integer(1)),
+            Some(Value::Text("gin".to_string())),
+            None,
+        ];
+        let row = Row::new(&columns, &values);
+
+        assert_eq!(
+            Ok(Drink {
+                id: 1,
+                name: "gin".to_string(),
+                price: None,
+            }),
+            Drink::from_row(&row)
+        );
+        assert_eq!(Ok((1, "gin".to_string())), <(i64, String)>::from_row(&row));
+
+        assert_eq!(
+            Err(Error::ColumnDoesNotExist("missing".to_string())),
+            row.get::<i64>("missing")
+        );
+        assert_eq!(Err(Error::TypeMismatch), row.get::<bool>("id"));
+        assert_eq!(
+            Err(Error::UnexpectedNull("price".to_string())),
+            row.get::<Decimal>("price")
+        );
+    }
+}
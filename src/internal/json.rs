@@ -0,0 +1,344 @@
+use std::fmt::Display;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// How deeply arrays and objects can be nested, so that parsing can't overflow the stack.
+const MAX_DEPTH: usize = 128;
+
+/// A parsed JSON document. Numbers keep the text they were written with, and objects keep the
+/// order of their members.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Boolean(bool),
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// Parses a JSON document, which may be surrounded by whitespace. Returns `None` when the
+    /// text isn't valid JSON.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut characters = text.chars().peekable();
+        let json = parse_value(&mut characters, 0)?;
+
+        skip_whitespace(&mut characters);
+        characters.peek().is_none().then_some(json)
+    }
+
+    /// Returns the member of an object with the given key. When a key is used more than once,
+    /// the last member wins.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        let Json::Object(members) = self else {
+            return None;
+        };
+
+        members
+            .iter()
+            .rev()
+            .find(|(member_key, _value)| member_key == key)
+            .map(|(_key, value)| value)
+    }
+
+    /// Returns the element of an array at `index`, where negative indices count from the end.
+    pub fn get_index(&self, index: i64) -> Option<&Json> {
+        let Json::Array(elements) = self else {
+            return None;
+        };
+
+        let index = if index < 0 {
+            elements.len().checked_sub(index.unsigned_abs() as usize)?
+        } else {
+            index as usize
+        };
+        elements.get(index)
+    }
+}
+
+/// Writes the document without insignificant whitespace, other than a space after every `,` and
+/// `:`, e.g. `{"tags": ["a", "b"]}`.
+impl Display for Json {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Json::Null => write!(f, "null"),
+            Json::Boolean(value) => write!(f, "{}", value),
+            Json::Number(number) => write!(f, "{}", number),
+            Json::String(string) => write_string(f, string),
+            Json::Array(elements) => {
+                write!(f, "[")?;
+                for (index, element) in elements.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", element)?;
+                }
+                write!(f, "]")
+            }
+            Json::Object(members) => {
+                write!(f, "{{")?;
+                for (index, (key, value)) in members.iter().enumerate() {
+                    if index > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write_string(f, key)?;
+                    write!(f, ": {}", value)?;
+                }
+                write!(f, "}}")
+            }
+        }
+    }
+}
+
+/// Returns the document in the format of `Json`'s `Display`, or the text itself when it isn't
+/// valid JSON.
+pub fn normalize(text: &str) -> String {
+    Json::parse(text).map_or_else(|| text.to_string(), |json| json.to_string())
+}
+
+fn write_string(f: &mut std::fmt::Formatter<'_>, string: &str) -> std::fmt::Result {
+    write!(f, "\"")?;
+    for character in string.chars() {
+        match character {
+            '"' => write!(f, "\\\"")?,
+            '\\' => write!(f, "\\\\")?,
+            '\n' => write!(f, "\\n")?,
+            '\r' => write!(f, "\\r")?,
+            '\t' => write!(f, "\\t")?,
+            character if character.is_control() => write!(f, "\\u{:04x}", character as u32)?,
+            character => write!(f, "{}", character)?,
+        }
+    }
+    write!(f, "\"")
+}
+
+fn skip_whitespace(characters: &mut Peekable<Chars>) {
+    while characters
+        .next_if(|character| matches!(character, ' ' | '\t' | '\n' | '\r'))
+        .is_some()
+    {}
+}
+
+fn parse_value(characters: &mut Peekable<Chars>, depth: usize) -> Option<Json> {
+    if depth > MAX_DEPTH {
+        return None;
+    }
+    skip_whitespace(characters);
+
+    match characters.peek()? {
+        'n' => parse_keyword(characters, "null", Json::Null),
+        't' => parse_keyword(characters, "true", Json::Boolean(true)),
+        'f' => parse_keyword(characters, "false", Json::Boolean(false)),
+        '"' => parse_string(characters).map(Json::String),
+        '[' => {
+            characters.next();
+            let mut elements = vec![];
+
+            skip_whitespace(characters);
+            if characters.next_if_eq(&']').is_some() {
+                return Some(Json::Array(elements));
+            }
+
+            loop {
+                elements.push(parse_value(characters, depth + 1)?);
+                skip_whitespace(characters);
+
+                match characters.next()? {
+                    ',' => continue,
+                    ']' => return Some(Json::Array(elements)),
+                    _ => return None,
+                }
+            }
+        }
+        '{' => {
+            characters.next();
+            let mut members = vec![];
+
+            skip_whitespace(characters);
+            if characters.next_if_eq(&'}').is_some() {
+                return Some(Json::Object(members));
+            }
+
+            loop {
+                skip_whitespace(characters);
+                let key = parse_string(characters)?;
+                skip_whitespace(characters);
+                characters.next_if_eq(&':')?;
+                members.push((key, parse_value(characters, depth + 1)?));
+                skip_whitespace(characters);
+
+                match characters.next()? {
+                    ',' => continue,
+                    '}' => return Some(Json::Object(members)),
+                    _ => return None,
+                }
+            }
+        }
+        '-' | '0'..='9' => parse_number(characters).map(Json::Number),
+
+        _ => None,
+    }
+}
+
+fn parse_keyword(characters: &mut Peekable<Chars>, keyword: &str, json: Json) -> Option<Json> {
+    for expected in keyword.chars() {
+        characters.next_if_eq(&expected)?;
+    }
+
+    Some(json)
+}
+
+/// Parses a number like `-12.5e3`. Leading zeros, and a `.` or exponent without digits, aren't
+/// allowed.
+fn parse_number(characters: &mut Peekable<Chars>) -> Option<String> {
+    let mut number = String::new();
+    let push_digits = |characters: &mut Peekable<Chars>, number: &mut String| {
+        let length = number.len();
+        while let Some(digit) = characters.next_if(char::is_ascii_digit) {
+            number.push(digit);
+        }
+        number.len() > length
+    };
+
+    if let Some(minus) = characters.next_if_eq(&'-') {
+        number.push(minus);
+    }
+    if let Some(zero) = characters.next_if_eq(&'0') {
+        number.push(zero);
+    } else if !push_digits(characters, &mut number) {
+        return None;
+    }
+
+    if let Some(point) = characters.next_if_eq(&'.') {
+        number.push(point);
+        if !push_digits(characters, &mut number) {
+            return None;
+        }
+    }
+
+    if let Some(exponent) = characters.next_if(|character| matches!(character, 'e' | 'E')) {
+        number.push(exponent);
+        if let Some(sign) = characters.next_if(|character| matches!(character, '+' | '-')) {
+            number.push(sign);
+        }
+        if !push_digits(characters, &mut number) {
+            return None;
+        }
+    }
+
+    Some(number)
+}
+
+fn parse_string(characters: &mut Peekable<Chars>) -> Option<String> {
+    characters.next_if_eq(&'"')?;
+    let mut string = String::new();
+
+    loop {
+        match characters.next()? {
+            '"' => return Some(string),
+            '\\' => match characters.next()? {
+                '"' => string.push('"'),
+                '\\' => string.push('\\'),
+                '/' => string.push('/'),
+                'b' => string.push('\u{8}'),
+                'f' => string.push('\u{c}'),
+                'n' => string.push('\n'),
+                'r' => string.push('\r'),
+                't' => string.push('\t'),
+                'u' => {
+                    let code_unit = parse_code_unit(characters)?;
+                    let code_point = if (0xD800..0xDC00).contains(&code_unit) {
+                        // A high surrogate has to be followed by an escaped low surrogate.
+                        characters.next_if_eq(&'\\')?;
+                        characters.next_if_eq(&'u')?;
+                        let low_surrogate = parse_code_unit(characters)?;
+                        if !(0xDC00..0xE000).contains(&low_surrogate) {
+                            return None;
+                        }
+                        0x10000 + ((code_unit - 0xD800) << 10) + (low_surrogate - 0xDC00)
+                    } else {
+                        code_unit
+                    };
+                    string.push(char::from_u32(code_point)?);
+                }
+                _ => return None,
+            },
+            character if character.is_control() => return None,
+            character => string.push(character),
+        }
+    }
+}
+
+fn parse_code_unit(characters: &mut Peekable<Chars>) -> Option<u32> {
+    let mut code_unit = 0;
+    for _ in 0..4 {
+        code_unit = code_unit * 16 + characters.next()?.to_digit(16)?;
+    }
+
+    Some(code_unit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parsing_and_formatting() {
+        let json = Json::parse(r#" {"name":"Ada","tags":["a",true,null],"age":36.5e0} "#).unwrap();
+        assert_eq!(
+            r#"{"name": "Ada", "tags": ["a", true, null], "age": 36.5e0}"#,
+            json.to_string()
+        );
+
+        let json = Json::parse(r#"["\"quoted\"\n", "\u00e9\ud83d\ude00"]"#).unwrap();
+        assert_eq!(
+            Json::Array(vec![
+                Json::String("\"quoted\"\n".to_string()),
+                Json::String("é😀".to_string())
+            ]),
+            json
+        );
+        assert_eq!(r#"["\"quoted\"\n", "é😀"]"#, json.to_string());
+
+        for invalid in [
+            "",
+            "{",
+            "[1,]",
+            "{\"a\" 1}",
+            "01",
+            "1.",
+            "-",
+            "tru",
+            "\"a\nb\"",
+            "\"\\ud83d\"",
+            "[1] 2",
+        ] {
+            assert_eq!(None, Json::parse(invalid), "{:?} isn't valid JSON", invalid);
+        }
+
+        assert_eq!(None, Json::parse(&"[".repeat(MAX_DEPTH + 2)));
+    }
+
+    #[test]
+    fn test_getting_members_and_elements() {
+        let json = Json::parse(r#"{"a": 1, "b": [10, 20, 30], "a": 2}"#).unwrap();
+
+        assert_eq!(Some(&Json::Number("2".to_string())), json.get("a"));
+        assert_eq!(None, json.get("c"));
+        assert_eq!(None, json.get_index(0));
+
+        let array = json.get("b").unwrap();
+        assert_eq!(Some(&Json::Number("10".to_string())), array.get_index(0));
+        assert_eq!(Some(&Json::Number("30".to_string())), array.get_index(-1));
+        assert_eq!(None, array.get_index(3));
+        assert_eq!(None, array.get_index(-4));
+        assert_eq!(None, array.get("a"));
+    }
+
+    #[test]
+    fn test_normalizing() {
+        assert_eq!(r#"{"a": [1, 2]}"#, normalize(" {\"a\":[1,\n2]} "));
+        assert_eq!("not json", normalize("not json"));
+    }
+}
@@ -1,322 +1,4915 @@
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
 use std::rc::Rc;
 use std::sync::RwLock;
+use std::time::Duration;
 
+use super::page_manager::Snapshot;
+use super::parser::{
+    ColumnDeclaration, CompareOperation, DataTypeIdentifier, LiteralValue, LogicalOperation,
+};
+use super::row_result::RowValues;
 use super::{
-    parse, ColumnDefinition, Command, DataType, Database, Error, PageId, PageManager, QueryResult,
-    Value,
+    default_clock, evaluator, normalize, parameter_count, parse_with_parameters, split_statements,
+    CatalogChange, CatalogChangeKind, CatalogChangeListener, Clock, ColumnDefinition, Command,
+    ConflictAction, CsvReader, Cursor, DataType, Database, Error, Expression, FromRow, OnConflict,
+    PageId, PageManager, QueryLog, QueryLogEntry, QueryResult, Random, Row, RowResult, Session,
+    StatementStatistics, TablePageIds, TableSample, Value, Warning, STATEMENT_STATISTICS_VIEW,
 };
 use crate::internal::SharedInternalPage;
 
+/// Page 0 starts its data with this magic number followed by the `FORMAT_VERSION` of the file as a
+/// big-endian `u32`. Files from before the header have zeros there, which is read as version 0.
+const FORMAT_MAGIC: &[u8; 8] = b"bsql\0fmt";
+const FORMAT_MAGIC_RANGE: std::ops::Range<usize> = 0..8;
+const FORMAT_VERSION_RANGE: std::ops::Range<usize> = 8..12;
+/// Files written by `backup` store a checksum of their pages after the header, see
+/// `backup_checksum`. Other files have zeros there.
+const BACKUP_CHECKSUM_RANGE: std::ops::Range<usize> = 12..20;
+
+/// The version of the on-disk format that this version of bsql writes. Bump it when the layout of
+/// the pages changes, and add a migration from the previous version to `MIGRATIONS`.
+pub const FORMAT_VERSION: u32 = 4;
+
+/// Migrates the pages of a file from one format version to the next one.
+type Migration = fn(&Manager) -> Result<(), Error>;
+
+/// The migration from the version at its index to the next one. The header is updated after every
+/// migration, so they only have to change the pages whose layout changed.
+const MIGRATIONS: &[Migration] = &[
+    // Version 0 didn't have a header, but the layout of the pages is the same.
+    |_manager| Ok(()),
+    // Version 2 keeps the number of rows of every table in its metadata page.
+    |manager| {
+        for mut database in manager.databases()? {
+            database.recount_rows()?;
+        }
+
+        Ok(())
+    },
+    // Version 3 lets tables have more than 255 pages, with the high byte of their number of pages
+    // in a byte of the metadata page that is zero in older files.
+    |_manager| Ok(()),
+    // Version 4 links the pages of every table that have room for more records into a list.
+    |manager| {
+        for mut database in manager.databases()? {
+            database.build_free_space_lists()?;
+        }
+
+        Ok(())
+    },
+];
+
+/// The names of the databases together with the names of their tables and sequences, see
+/// `Manager::catalog_names`.
+type CatalogNames = Vec<(String, Vec<String>, Vec<String>)>;
+
+/// The number of records of a file that `COPY` parses before it writes them together, see
+/// `Database::insert_rows_into_columns`.
+const COPY_BATCH_SIZE: usize = 1000;
+
 pub struct Manager {
     page_manager: Rc<RwLock<PageManager>>,
     page: SharedInternalPage,
+
+    auto_shrink_threshold: Option<usize>,
+
+    /// The memory used by the rows that the current statement materialized, see
+    /// `set_query_memory_limit`.
+    query_memory_usage: Cell<usize>,
+    query_memory_limit: Option<usize>,
+
+    /// The directory that `COPY ... FROM` reads files from, see `set_copy_directory`.
+    copy_directory: Option<PathBuf>,
+
+    /// The warnings raised by the last call to `execute`.
+    warnings: Vec<Warning>,
+
+    query_log: Option<QueryLog>,
+    slow_query_threshold: Option<Duration>,
+
+    /// Times the statements, see `set_clock`.
+    clock: Rc<dyn Clock>,
+
+    /// The statistics of every statement that was executed successfully, by normalized statement.
+    statement_statistics: HashMap<String, StatementStatistics>,
+
+    /// The open cursors by name. They're closed by the statements that remove or move the pages
+    /// they scan: `ROLLBACK` and `VACUUM`.
+    cursors: HashMap<String, Cursor>,
+
+    /// The page id of every database by name, together with the page ids of its tables, so a
+    /// statement doesn't read the metadata pages of every database and table to find the one it
+    /// uses. Loaded when the manager is opened, updated by the statements that create databases
+    /// and tables, and loaded again after a rollback.
+    catalog: RefCell<HashMap<String, (PageId, TablePageIds)>>,
+
+    /// The last value that every sequence handed out, by database and sequence name. The values
+    /// are stored in the metadata pages of the databases, which a rollback restores, so they're
+    /// advanced again afterwards to not hand out a value twice.
+    sequence_values: RefCell<HashMap<(String, String), i64>>,
+
+    /// Bumped by every DDL statement, see `schema_version`.
+    schema_version: u64,
+    catalog_change_listeners: Vec<CatalogChangeListener>,
 }
 
 impl Manager {
+    /// Like `open`, but panics when the pages can't be read by this version of bsql.
     pub fn new(page_manager: Rc<RwLock<PageManager>>) -> Self {
-        let mut read_from_memory = false;
-        let shared_page = {
-            let mut page_manager = page_manager.write().unwrap();
-            if let Some(page) = page_manager.fetch_page(0) {
-                read_from_memory = true;
-                page
-            } else {
-                let (_page_id, page) = page_manager.create_page();
-                page
-            }
-        };
+        Self::open(page_manager)
+            .unwrap_or_else(|error| panic!("Could not open the database file: {}", error))
+    }
 
-        if !read_from_memory {
-            Self::write_metadata_page(page_manager.clone(), shared_page.clone(), vec![]);
+    /// Loads the databases from the pages of `page_manager`, which start with page 0, or starts
+    /// without any databases when there are no pages yet. Files of older format versions are
+    /// migrated to `FORMAT_VERSION` first. Fails with `Error::NotADatabaseFile` when page 0 doesn't
+    /// have the header of bsql, and with `Error::UnsupportedFormatVersion` when the file was
+    /// written by a newer version of bsql.
+    pub fn open(page_manager: Rc<RwLock<PageManager>>) -> Result<Self, Error> {
+        let existing_page = page_manager.read().unwrap().fetch_page(0)?;
+        let is_new_file = existing_page.is_none();
+        let shared_page = existing_page.unwrap_or_else(|| {
+            let (_page_id, page) = page_manager.write().unwrap().create_page();
+            Self::write_format_version(&page, FORMAT_VERSION);
+            Self::write_metadata_page(page.clone(), vec![]);
+            page
+        });
+        if is_new_file {
+            page_manager.read().unwrap().commit()?;
         }
 
-        Self {
+        let manager = Self {
             page_manager,
             page: shared_page,
-        }
-    }
 
-    pub fn execute(&mut self, database_name: &str, query: &str) -> Result<QueryResult, Error> {
-        match parse(query)? {
-            Command::CreateDatabase { database_name } => self.create_database(&database_name),
+            auto_shrink_threshold: None,
 
-            Command::CreateTable {
-                table_name,
-                column_definitions,
-            } => {
-                let columns: Vec<(String, DataType)> = column_definitions
-                    .into_iter()
-                    .map(|(c, dt)| (c, dt.into()))
-                    .collect();
+            query_memory_usage: Cell::new(0),
+            query_memory_limit: None,
 
-                self.create_table(database_name, &table_name, columns)
-            }
+            copy_directory: None,
 
-            Command::InsertInto { table_name, values } => self.insert_row(
-                database_name,
-                &table_name,
-                values.into_iter().map(|value| value.into()).collect(),
-            ),
+            warnings: vec![],
 
-            Command::Select {
-                identifiers,
-                table_name,
-                ..
-            } => {
-                match &identifiers
-                    .iter()
-                    .map(|i| i.as_str())
-                    .collect::<Vec<&str>>()[..]
-                {
-                    ["*"] => self.select_all(database_name, &table_name),
+            query_log: None,
+            slow_query_threshold: None,
 
-                    _ => self.select(database_name, &table_name, identifiers.clone()),
-                }
-            }
+            clock: default_clock(),
+
+            statement_statistics: HashMap::new(),
+
+            cursors: HashMap::new(),
+
+            catalog: RefCell::new(HashMap::new()),
+
+            sequence_values: RefCell::new(HashMap::new()),
+
+            schema_version: 0,
+            catalog_change_listeners: vec![],
+        };
+        if !is_new_file {
+            manager.migrate()?;
         }
-    }
+        manager.load_catalog()?;
 
-    pub fn database_names(&self) -> Vec<String> {
-        self.databases()
-            .iter()
-            .map(|d| d.name().to_string())
-            .collect()
+        Ok(manager)
     }
 
-    pub fn database_table_names(&self, database_name: &str) -> Result<Vec<String>, Error> {
-        self.databases()
+    /// Returns the format version in the header of page 0, see `FORMAT_MAGIC`.
+    fn read_format_version(shared_page: &SharedInternalPage) -> Result<u32, Error> {
+        let page = shared_page.read().unwrap();
+        if page.data[FORMAT_MAGIC_RANGE] == *FORMAT_MAGIC {
+            return Ok(u32::from_be_bytes(
+                page.data[FORMAT_VERSION_RANGE].try_into().unwrap(),
+            ));
+        }
+
+        if page.data[FORMAT_MAGIC_RANGE.start..FORMAT_VERSION_RANGE.end]
             .iter()
-            .find(|d| d.name() == database_name)
-            .ok_or(Error::DatabaseDoesNotExist(database_name.to_string()))
-            .map(|database| database.table_names())
+            .all(|byte| *byte == 0)
+        {
+            Ok(0)
+        } else {
+            Err(Error::NotADatabaseFile)
+        }
     }
 
-    pub fn table_definition(
-        &self,
-        database_name: &str,
-        table_name: &str,
-    ) -> Result<Vec<ColumnDefinition>, Error> {
-        self.databases()
-            .iter()
-            .find(|d| d.name() == database_name)
-            .ok_or(Error::DatabaseDoesNotExist(database_name.to_string()))
-            .and_then(|database| database.column_definitions(table_name))
+    fn write_format_version(shared_page: &SharedInternalPage, version: u32) {
+        let mut page = shared_page.write().unwrap();
+        page.data[FORMAT_MAGIC_RANGE].copy_from_slice(FORMAT_MAGIC);
+        page.data[FORMAT_VERSION_RANGE].copy_from_slice(&version.to_be_bytes());
     }
 
-    pub fn database_exists(&self, key: &str) -> bool {
-        self.databases().iter().find(|d| d.name() == key).is_some()
+    /// Runs the `MIGRATIONS` from the format version of the file up to `FORMAT_VERSION`.
+    fn migrate(&self) -> Result<(), Error> {
+        let version = Self::read_format_version(&self.page)?;
+        if version > FORMAT_VERSION {
+            return Err(Error::UnsupportedFormatVersion(version));
+        }
+
+        for (from_version, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+            migration(self)?;
+            Self::write_format_version(&self.page, from_version as u32 + 1);
+            self.page_manager.read().unwrap().commit()?;
+        }
+
+        Ok(())
     }
 
-    fn create_table(
-        &mut self,
-        database_name: &str,
-        table_name: &str,
-        columns: Vec<(String, DataType)>,
-    ) -> Result<QueryResult, Error> {
-        let mut databases = self.databases();
-        let Some(database) = databases.iter_mut().find(|d| d.name() == database_name) else {
-            return Err(Error::DatabaseDoesNotExist(database_name.to_string()));
-        };
+    /// Hands the manager to a `Session` whose statements run against `database_name`. A session
+    /// keeps track of the database that `USE` switches to, so callers don't have to.
+    pub fn session(self, database_name: &str) -> Session {
+        Session::new(self, database_name)
+    }
 
-        let result = database
-            .create_table(table_name, columns)
-            .map(|_table_id| QueryResult::CommandSuccessMessage("CREATE TABLE".to_string()));
+    /// Executes every statement of `query` in order and returns the result of the last one, see
+    /// `execute_all`.
+    pub fn execute(&mut self, database_name: &str, query: &str) -> Result<QueryResult, Error> {
+        let mut results = self.execute_all(database_name, query);
 
-        {
-            let page_manager = self.page_manager.read().unwrap();
-            page_manager.commit();
-        }
+        results.pop().unwrap()
+    }
 
-        return result;
+    /// Executes every statement of `query` in order, e.g. `CREATE TABLE a (x integer); INSERT INTO
+    /// a VALUES (1);`, and returns their results. Stops at the first statement that fails, so its
+    /// error is the last result. Statements before it stay executed.
+    pub fn execute_all(
+        &mut self,
+        database_name: &str,
+        query: &str,
+    ) -> Vec<Result<QueryResult, Error>> {
+        self.execute_statements(database_name, query, &[])
     }
 
-    fn add_column(
+    /// Executes every statement of `query` like `execute`, binding the `$n` and `?` placeholders
+    /// of each statement to the n-th of the `parameters`, e.g. `SELECT * FROM users WHERE id = $1;`.
+    /// Fails without executing anything when there are more `parameters` than the query uses.
+    pub fn execute_with_parameters(
         &mut self,
         database_name: &str,
-        table_name: &str,
-        column_name: &str,
-        data_type: DataType,
+        query: &str,
+        parameters: &[Value],
     ) -> Result<QueryResult, Error> {
-        let mut databases = self.databases();
-        let database = databases
-            .iter_mut()
-            .find(|d| d.name() == database_name)
-            .ok_or(Error::DatabaseDoesNotExist(database_name.to_string()))?;
+        let required = parameter_count(query);
+        if parameters.len() > required {
+            return Err(Error::TooManyParameters {
+                supplied: parameters.len(),
+                required,
+            });
+        }
 
-        let query_result = database
-            .add_column(table_name, column_name, data_type)
-            .map(|command| QueryResult::CommandSuccessMessage(command));
+        let parameters: Vec<LiteralValue> = parameters.iter().cloned().map(Into::into).collect();
+        let mut results = self.execute_statements(database_name, query, &parameters);
 
-        {
-            let page_manager = self.page_manager.read().unwrap();
-            page_manager.commit();
-        }
+        results.pop().unwrap()
+    }
 
-        return query_result;
+    /// Executes `query` like `execute` and converts the rows it returns into `T`, e.g.
+    /// `manager.query_as::<(i64, String)>("db", "SELECT id, name FROM users")`. Statements that
+    /// don't return rows give no values.
+    pub fn query_as<T: FromRow>(
+        &mut self,
+        database_name: &str,
+        query: &str,
+    ) -> Result<Vec<T>, Error> {
+        match self.execute(database_name, query)? {
+            QueryResult::RowResult(result) => result
+                .rows()
+                .iter()
+                .map(|values| T::from_row(&Row::new(result.columns(), values)))
+                .collect(),
+            _ => Ok(vec![]),
+        }
     }
 
-    fn create_database(&mut self, name: &str) -> Result<QueryResult, Error> {
-        if !self.database_exists(name) {
-            let mut database_page_ids = self.database_page_ids();
+    fn execute_statements(
+        &mut self,
+        database_name: &str,
+        query: &str,
+        parameters: &[LiteralValue],
+    ) -> Vec<Result<QueryResult, Error>> {
+        self.warnings.clear();
 
-            {
-                let mut page_manager = self.page_manager.write().unwrap();
-                let (page_id, shared_page) = page_manager.create_page();
+        let mut statements = split_statements(query);
+        if statements.is_empty() {
+            // Executed anyway so that the parse error ends up in the query log.
+            statements.push(query);
+        }
 
-                Database::initialize(self.page_manager.clone(), shared_page.clone(), name)?;
+        // `USE` changes the database of the statements after it.
+        let mut database_name = database_name.to_string();
 
-                database_page_ids.push(page_id);
+        let mut results = vec![];
+        for statement in statements {
+            let result = self.execute_statement(&database_name, statement, parameters);
+            if let Ok(QueryResult::DatabaseChanged {
+                database_name: new_database_name,
+            }) = &result
+            {
+                database_name = new_database_name.clone();
             }
 
-            Self::write_metadata_page(
-                self.page_manager.clone(),
-                self.page.clone(),
-                database_page_ids,
-            );
+            let failed = result.is_err();
+            results.push(result);
 
-            {
-                let page_manager = self.page_manager.read().unwrap();
-                page_manager.commit();
+            if failed {
+                break;
             }
-
-            Ok(QueryResult::CommandSuccessMessage(
-                "CREATE DATABASE".to_string(),
-            ))
-        } else {
-            Err(Error::DatabaseAlreadyExists(name.to_string()))
         }
+
+        results
     }
 
-    fn insert_row(
+    fn execute_statement(
         &mut self,
         database_name: &str,
-        table_name: &str,
-        values: Vec<Value>,
+        query: &str,
+        parameters: &[LiteralValue],
     ) -> Result<QueryResult, Error> {
-        let mut databases = self.databases();
-        let database = databases
-            .iter_mut()
-            .find(|d| d.name() == database_name)
-            .ok_or(Error::DatabaseDoesNotExist(database_name.to_string()))?;
+        self.query_memory_usage.set(0);
 
-        let query_result = database
-            .insert_row(table_name, values)
-            .map(|_record_id| QueryResult::InsertSuccess { count: 1 });
+        let start = self.clock.elapsed();
+        let result = self.execute_query(database_name, query, parameters);
+        let duration = self.clock.elapsed().saturating_sub(start);
+        let entry = QueryLogEntry::new(database_name, query, duration, &result);
 
-        {
-            let page_manager = self.page_manager.read().unwrap();
-            page_manager.commit();
+        if result.is_ok() {
+            self.statement_statistics
+                .entry(normalize(query))
+                .or_insert_with_key(|query| StatementStatistics::new(query.clone()))
+                .record(duration, entry.rows);
         }
 
-        return query_result;
+        if let Some(query_log) = self.query_log.as_mut() {
+            if self
+                .slow_query_threshold
+                .map_or(true, |threshold| duration >= threshold)
+            {
+                query_log(&entry);
+            }
+        }
+
+        result
     }
 
-    fn select_all(&self, database_name: &str, table_name: &str) -> Result<QueryResult, Error> {
-        let databases = self.databases();
-        let database = databases
-            .iter()
-            .find(|d| d.name() == database_name)
-            .ok_or(Error::DatabaseDoesNotExist(database_name.to_string()))?;
+    /// Calls `query_log` for every query that `execute` runs, including the ones that fail.
+    /// `None` disables the query log.
+    pub fn set_query_log(&mut self, query_log: Option<QueryLog>) {
+        self.query_log = query_log;
+    }
 
-        return Ok(QueryResult::RowResult(
-            database.select_all_columns(table_name)?,
-        ));
+    /// Only log queries that take at least `threshold` to execute. `None` logs every query.
+    pub fn set_slow_query_threshold(&mut self, threshold: Option<Duration>) {
+        self.slow_query_threshold = threshold;
     }
 
-    fn select(
-        &self,
+    /// Times the statements with `clock` and seeds random numbers from it, e.g. with a
+    /// `StoppedClock` on platforms where asking the system for the time panics.
+    pub fn set_clock(&mut self, clock: Rc<dyn Clock>) {
+        evaluator::seed_random(Random::from_clock(&*clock));
+        self.clock = clock;
+    }
+
+    /// Returns the statistics of the statements that were executed successfully, the statements
+    /// that took the most time in total first. Also available in SQL as the
+    /// `bsql_stat_statements` view.
+    pub fn statement_statistics(&self) -> Vec<StatementStatistics> {
+        let mut statement_statistics: Vec<StatementStatistics> =
+            self.statement_statistics.values().cloned().collect();
+        statement_statistics.sort_by(|a, b| {
+            b.total_time
+                .cmp(&a.total_time)
+                .then_with(|| a.query.cmp(&b.query))
+        });
+
+        statement_statistics
+    }
+
+    pub fn reset_statement_statistics(&mut self) {
+        self.statement_statistics.clear();
+    }
+
+    /// Returns the version of the catalog, which changes whenever a database, table or column is
+    /// created, or a rollback undoes that. Prepared statements and cached plans are only valid for the version they were
+    /// made for.
+    pub fn schema_version(&self) -> u64 {
+        self.schema_version
+    }
+
+    /// Calls `listener` for every change to the catalog from now on.
+    pub fn subscribe_to_catalog_changes(&mut self, listener: CatalogChangeListener) {
+        self.catalog_change_listeners.push(listener);
+    }
+
+    fn notify_catalog_change(&mut self, kind: CatalogChangeKind) {
+        self.schema_version += 1;
+
+        let change = CatalogChange {
+            schema_version: self.schema_version,
+            kind,
+        };
+        for listener in self.catalog_change_listeners.iter_mut() {
+            listener(&change);
+        }
+    }
+
+    fn execute_query(
+        &mut self,
         database_name: &str,
-        table_name: &str,
-        columns: Vec<String>,
+        query: &str,
+        parameters: &[LiteralValue],
     ) -> Result<QueryResult, Error> {
-        let databases = self.databases();
-        let database = databases
-            .iter()
-            .find(|d| d.name() == database_name)
-            .ok_or(Error::DatabaseDoesNotExist(database_name.to_string()))?;
+        let command = parse_with_parameters(query, parameters)?;
+        let result = self.execute_command(database_name, command);
 
-        return database
-            .select_columns_by_name(
-                table_name,
-                columns.iter().map(|i| i.as_str()).collect::<Vec<&str>>(),
-            )
-            .map(|rows| QueryResult::RowResult(rows));
+        if let Some(threshold) = self.auto_shrink_threshold {
+            if self.page_manager.read().unwrap().loaded_page_count() > threshold {
+                self.shrink_to_fit();
+            }
+        }
+
+        return result;
     }
 
-    fn databases(&self) -> Vec<Database> {
+    /// Asserts that the catalog is consistent, i.e. that database and table names are unique and
+    /// that every page they refer to exists and is only used once. Only checked in debug builds.
+    /// Queries don't run it, because it reads every page, so tests and `fuzz` call it explicitly.
+    pub fn debug_check_invariants(&self) {
+        let database_names = self.database_names().unwrap();
+        for (index, database_name) in database_names.iter().enumerate() {
+            debug_assert!(
+                !database_names[..index].contains(database_name),
+                "Database {} exists twice",
+                database_name
+            );
+        }
+
         let database_page_ids = self.database_page_ids();
-        let mut databases = Vec::new();
+        for (index, page_id) in database_page_ids.iter().enumerate() {
+            debug_assert!(
+                !database_page_ids[..index].contains(page_id),
+                "Page {} is used by two databases",
+                page_id
+            );
+        }
 
-        for database_page_id in &database_page_ids {
-            let page_manager = self.page_manager.read().unwrap();
-            let page = page_manager.fetch_page(*database_page_id).unwrap();
+        for database in self.databases().unwrap() {
+            database.debug_check_invariants();
+        }
+    }
 
-            let database = Database::load(self.page_manager.clone(), page).unwrap();
-            databases.push(database);
+    /// Checks that the pages of the databases can still be read and written, for health probes.
+    /// Also available in SQL as `bsql_health()`, which returns `TRUE` when the check passes.
+    pub fn check_health(&self) -> Result<(), Error> {
+        let Ok(page_manager) = self.page_manager.read() else {
+            return Err(Error::StorageUnavailable);
+        };
+
+        if page_manager.is_healthy() && matches!(page_manager.fetch_page(0), Ok(Some(_))) {
+            Ok(())
+        } else {
+            Err(Error::StorageUnavailable)
         }
+    }
 
-        return databases;
+    /// Returns whether a transaction was begun and not yet committed or rolled back.
+    pub fn in_transaction(&self) -> bool {
+        self.page_manager.read().unwrap().in_transaction()
     }
 
-    fn database_page_ids(&self) -> Vec<PageId> {
-        let page = self.page.read().unwrap();
-        let number_of_database_page_ids = page.metadata[0] as usize;
-        let mut database_page_ids: Vec<PageId> = Vec::new();
+    /// Returns the warnings raised by the last call to `execute` or `execute_all`.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
 
-        for index in 0..number_of_database_page_ids {
-            let start_index = index as usize * 4 + 1;
-            let end_index = start_index + 4;
-            let page_id_bytes: &[u8; 4] =
-                &page.metadata[start_index..end_index].try_into().unwrap();
+    /// Writes the pages that have been modified to disk and waits until they're stored durably,
+    /// see `PageManager::flush`. Returns the number of pages that were written.
+    pub fn flush(&self) -> Result<usize, Error> {
+        self.page_manager.read().unwrap().flush()
+    }
 
-            let database_page_id = PageId::from_le_bytes(*page_id_bytes);
-            database_page_ids.push(database_page_id);
-        }
+    /// Releases every page that isn't in use back to the system. The pages are read back from
+    /// disk the next time they are needed.
+    pub fn shrink_to_fit(&mut self) {
+        let mut page_manager = self.page_manager.write().unwrap();
+        page_manager.shrink_to_fit();
+    }
 
-        return database_page_ids;
+    /// Automatically shrink memory usage after a query leaves more than `threshold` pages loaded
+    /// into memory. `None` disables automatic shrinking.
+    pub fn set_auto_shrink_threshold(&mut self, threshold: Option<usize>) {
+        self.auto_shrink_threshold = threshold;
     }
 
-    fn write_metadata_page(
-        page_manager: Rc<RwLock<PageManager>>,
-        shared_page: SharedInternalPage,
-        database_page_ids: Vec<PageId>,
-    ) {
-        {
-            let mut page = shared_page.write().unwrap();
-            let number_of_database_page_ids = database_page_ids.len();
-            page.metadata[0] = number_of_database_page_ids as u8;
+    /// Fails statements whose scanned, filtered and projected rows (including the ones of their
+    /// subqueries) take up more than `limit` bytes together. `None` removes the limit.
+    pub fn set_query_memory_limit(&mut self, limit: Option<usize>) {
+        self.query_memory_limit = limit;
+    }
 
-            let database_page_ids_bytes: Vec<u8> = database_page_ids
-                .iter()
-                .map(|dpid| dpid.to_le_bytes())
-                .flatten()
-                .collect();
+    pub fn query_memory_limit(&self) -> Option<usize> {
+        self.query_memory_limit
+    }
 
-            page.metadata[1..database_page_ids_bytes.len() + 1]
-                .copy_from_slice(&database_page_ids_bytes);
+    /// Lets `COPY ... FROM` read the files inside of `directory`, with relative paths resolved
+    /// against it. Paths that lead outside of it, also through `..` or symbolic links, fail with
+    /// `Error::CopyFileNotAllowed`. `None`, the default, doesn't let `COPY` read any files, so
+    /// statements from untrusted clients can't read the files of the server.
+    pub fn set_copy_directory(&mut self, directory: Option<PathBuf>) {
+        self.copy_directory = directory;
+    }
+
+    pub fn copy_directory(&self) -> Option<&PathBuf> {
+        self.copy_directory.as_ref()
+    }
+
+    /// Returns the bytes of memory used by the rows that the last statement materialized.
+    pub fn query_memory_usage(&self) -> usize {
+        self.query_memory_usage.get()
+    }
+
+    /// Counts `rows` towards the memory used by the current statement.
+    fn account_memory(&self, rows: RowResult) -> Result<RowResult, Error> {
+        self.account_bytes(rows.memory_usage())?;
+        Ok(rows)
+    }
+
+    /// Collects the rows of a scan, and counts every row towards the memory used by the current
+    /// statement as soon as it's read, so the scan stops at the row that exceeds the limit.
+    fn collect_accounted(
+        &self,
+        columns: Vec<String>,
+        rows: impl Iterator<Item = Result<RowValues, Error>>,
+    ) -> Result<RowResult, Error> {
+        let mut collected = vec![];
+        for row in rows {
+            let row = row?;
+            self.account_bytes(RowResult::row_memory_usage(&row))?;
+            collected.push(row);
         }
 
-        {
-            let page_manager = page_manager.read().unwrap();
-            page_manager.commit();
+        Ok(RowResult::new(columns, collected))
+    }
+
+    fn account_bytes(&self, bytes: usize) -> Result<(), Error> {
+        let used = self.query_memory_usage.get() + bytes;
+        self.query_memory_usage.set(used);
+
+        match self.query_memory_limit {
+            Some(limit) if used > limit => Err(Error::QueryMemoryLimitExceeded { used, limit }),
+            _ => Ok(()),
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    fn execute_command(
+        &mut self,
+        database_name: &str,
+        command: Command,
+    ) -> Result<QueryResult, Error> {
+        match command {
+            Command::CreateDatabase { database_name } => self.create_database(&database_name),
 
-    #[test]
-    fn test_list_databases() {
-        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut manager = Manager::new(page_manager);
-        manager.create_database("hello").unwrap();
-        manager.create_database("world").unwrap();
+            Command::CreateTable {
+                database_name: qualified_database_name,
+                table_name,
+                column_definitions,
+                fill_factor,
+            } => {
+                let database_name =
+                    Self::resolve_database_name(database_name, qualified_database_name)?;
+                self.create_table(&database_name, &table_name, column_definitions, fill_factor)
+            }
 
-        assert_eq!(
-            vec!["hello".to_string(), "world".to_string()],
-            manager.database_names()
+            Command::InsertInto {
+                database_name: qualified_database_name,
+                table_name,
+                column_names,
+                values,
+                on_conflict,
+                returning,
+            } => {
+                let on_conflict = match on_conflict {
+                    Some(OnConflict {
+                        columns,
+                        action: ConflictAction::DoUpdate(assignments),
+                    }) => Some(OnConflict {
+                        columns,
+                        action: ConflictAction::DoUpdate(
+                            assignments
+                                .into_iter()
+                                .map(|(column_name, expression)| {
+                                    Ok((
+                                        column_name,
+                                        self.resolve_subqueries(database_name, expression)?,
+                                    ))
+                                })
+                                .collect::<Result<Vec<(String, Expression)>, Error>>()?,
+                        ),
+                    }),
+                    on_conflict => on_conflict,
+                };
+                let returning = returning
+                    .map(|expressions| {
+                        expressions
+                            .into_iter()
+                            .map(|expression| self.resolve_subqueries(database_name, expression))
+                            .collect::<Result<Vec<Expression>, Error>>()
+                    })
+                    .transpose()?;
+
+                self.insert_row(
+                    &Self::resolve_database_name(database_name, qualified_database_name)?,
+                    &table_name,
+                    column_names,
+                    values
+                        .into_iter()
+                        .map(|row| {
+                            row.into_iter()
+                                .map(|value| value.map(Value::from))
+                                .collect()
+                        })
+                        .collect(),
+                    on_conflict,
+                    returning,
+                )
+            }
+
+            Command::Select {
+                expressions,
+                database_name: qualified_database_name,
+                table_name,
+                table_sample,
+                where_clause,
+            } => self
+                .select(
+                    database_name,
+                    qualified_database_name,
+                    table_name.as_deref(),
+                    expressions,
+                    table_sample,
+                    where_clause,
+                )
+                .map(QueryResult::RowResult),
+
+            Command::DeclareCursor { cursor_name, query } => {
+                if self.cursors.contains_key(&cursor_name) {
+                    return Err(Error::CursorAlreadyExists(cursor_name));
+                }
+
+                let cursor = self.declare_cursor(database_name, *query)?;
+                self.cursors.insert(cursor_name, cursor);
+
+                Ok(QueryResult::CommandSuccessMessage(
+                    "DECLARE CURSOR".to_string(),
+                ))
+            }
+
+            Command::Fetch { cursor_name, count } => {
+                let row_result = self
+                    .cursors
+                    .get_mut(&cursor_name)
+                    .ok_or(Error::CursorDoesNotExist(cursor_name))?
+                    .fetch(count)?;
+
+                self.account_memory(row_result).map(QueryResult::RowResult)
+            }
+
+            Command::CloseCursor { cursor_name } => self
+                .cursors
+                .remove(&cursor_name)
+                .map(|_cursor| QueryResult::CommandSuccessMessage("CLOSE CURSOR".to_string()))
+                .ok_or(Error::CursorDoesNotExist(cursor_name)),
+
+            Command::Begin => {
+                let page_manager = self.page_manager.read().unwrap();
+                if page_manager.in_transaction() {
+                    self.warnings.push(Warning::TransactionAlreadyInProgress);
+                } else {
+                    page_manager.begin_transaction();
+                }
+
+                Ok(QueryResult::CommandSuccessMessage("BEGIN".to_string()))
+            }
+
+            Command::Commit => {
+                let page_manager = self.page_manager.read().unwrap();
+                if page_manager.in_transaction() {
+                    page_manager.commit_transaction()?;
+                } else {
+                    self.warnings.push(Warning::NoTransactionInProgress);
+                }
+
+                Ok(QueryResult::CommandSuccessMessage("COMMIT".to_string()))
+            }
+
+            Command::Rollback => {
+                if self.page_manager.read().unwrap().in_transaction() {
+                    let catalog_names = self.catalog_names()?;
+                    self.page_manager.read().unwrap().rollback_transaction();
+                    self.reload_catalog(catalog_names)?;
+                } else {
+                    self.warnings.push(Warning::NoTransactionInProgress);
+                }
+
+                Ok(QueryResult::CommandSuccessMessage("ROLLBACK".to_string()))
+            }
+
+            Command::Savepoint { name } => {
+                if !self.page_manager.read().unwrap().create_savepoint(&name) {
+                    return Err(Error::NoTransactionInProgress);
+                }
+
+                Ok(QueryResult::CommandSuccessMessage("SAVEPOINT".to_string()))
+            }
+
+            Command::RollbackToSavepoint { name } => {
+                if !self.page_manager.read().unwrap().in_transaction() {
+                    return Err(Error::NoTransactionInProgress);
+                }
+                let catalog_names = self.catalog_names()?;
+                if !self
+                    .page_manager
+                    .read()
+                    .unwrap()
+                    .rollback_to_savepoint(&name)
+                {
+                    return Err(Error::SavepointDoesNotExist(name));
+                }
+                self.reload_catalog(catalog_names)?;
+
+                Ok(QueryResult::CommandSuccessMessage("ROLLBACK".to_string()))
+            }
+
+            Command::ReleaseSavepoint { name } => {
+                let page_manager = self.page_manager.read().unwrap();
+                if !page_manager.in_transaction() {
+                    return Err(Error::NoTransactionInProgress);
+                }
+                if !page_manager.release_savepoint(&name) {
+                    return Err(Error::SavepointDoesNotExist(name));
+                }
+
+                Ok(QueryResult::CommandSuccessMessage("RELEASE".to_string()))
+            }
+
+            Command::Use {
+                database_name: new_database_name,
+            } => {
+                if !self.database_exists(&new_database_name) {
+                    return Err(Error::DatabaseDoesNotExist(new_database_name));
+                }
+
+                Ok(QueryResult::DatabaseChanged {
+                    database_name: new_database_name,
+                })
+            }
+
+            Command::Copy {
+                database_name: qualified_database_name,
+                table_name,
+                column_names,
+                filename,
+                header,
+            } => self.copy_from(
+                &Self::resolve_database_name(database_name, qualified_database_name)?,
+                &table_name,
+                column_names,
+                &filename,
+                header,
+            ),
+
+            Command::ShowDatabases => Ok(QueryResult::RowResult(RowResult::new(
+                vec!["Database name".to_string()],
+                self.database_names()?
+                    .into_iter()
+                    .map(|database_name| vec![Some(Value::Text(database_name))])
+                    .collect(),
+            ))),
+
+            Command::ShowTables {
+                database_name: qualified_database_name,
+            } => {
+                let database_name =
+                    Self::resolve_database_name(database_name, qualified_database_name)?;
+
+                Ok(QueryResult::RowResult(RowResult::new(
+                    vec!["Table name".to_string()],
+                    self.database_table_names(&database_name)?
+                        .into_iter()
+                        .map(|table_name| vec![Some(Value::Text(table_name))])
+                        .collect(),
+                )))
+            }
+
+            Command::Explain { query } => self
+                .explain(database_name, *query)
+                .map(QueryResult::RowResult),
+
+            Command::CreateSequence {
+                database_name: qualified_database_name,
+                sequence_name,
+                start,
+            } => {
+                let database_name =
+                    Self::resolve_database_name(database_name, qualified_database_name)?;
+                self.create_sequence(&database_name, &sequence_name, start.unwrap_or(1))
+            }
+
+            Command::Vacuum {
+                database_name: qualified_database_name,
+                table_name,
+            } => {
+                self.vacuum(
+                    &Self::resolve_database_name(database_name, qualified_database_name)?,
+                    table_name.as_deref(),
+                )?;
+
+                Ok(QueryResult::CommandSuccessMessage("VACUUM".to_string()))
+            }
+        }
+    }
+
+    /// Returns the database a statement refers to, which is either the database the statement
+    /// qualifies its table with or the active database.
+    fn resolve_database_name(
+        active_database_name: &str,
+        qualified_database_name: Option<String>,
+    ) -> Result<String, Error> {
+        match qualified_database_name {
+            Some(database_name) => Ok(database_name),
+            None if active_database_name.is_empty() => Err(Error::NoActiveDatabase),
+            None => Ok(active_database_name.to_string()),
+        }
+    }
+
+    pub fn database_names(&self) -> Result<Vec<String>, Error> {
+        Ok(self
+            .databases()?
+            .iter()
+            .map(|d| d.name().to_string())
+            .collect())
+    }
+
+    pub fn database_table_names(&self, database_name: &str) -> Result<Vec<String>, Error> {
+        self.database(database_name)?.table_names()
+    }
+
+    /// Returns the name, the number of rows and the number of pages of every table of the
+    /// database, see `Database::table_sizes`.
+    pub fn table_sizes(&self, database_name: &str) -> Result<Vec<(String, u64, u32)>, Error> {
+        self.database(database_name)?.table_sizes()
+    }
+
+    pub fn table_definition(
+        &self,
+        database_name: &str,
+        table_name: &str,
+    ) -> Result<Vec<ColumnDefinition>, Error> {
+        self.database(database_name)
+            .and_then(|database| database.column_definitions(table_name))
+    }
+
+    /// Limits the number of pages `database_name` may use. Inserts and table creations that would
+    /// need a page beyond the quota fail with `Error::PageQuotaExceeded`.
+    pub fn set_page_quota(&mut self, database_name: &str, quota: Option<u32>) -> Result<(), Error> {
+        let mut database = self.database(database_name)?;
+
+        database.set_page_quota(quota);
+
+        {
+            let page_manager = self.page_manager.read().unwrap();
+            page_manager.commit()?;
+        }
+
+        Ok(())
+    }
+
+    pub fn page_quota(&self, database_name: &str) -> Result<Option<u32>, Error> {
+        self.database(database_name)
+            .map(|database| database.page_quota())
+    }
+
+    /// Inserts `count` rows of random values into the table, which is handy for creating test
+    /// data. The same `seed` always generates the same rows.
+    pub fn seed(
+        &mut self,
+        database_name: &str,
+        table_name: &str,
+        count: usize,
+        seed: u64,
+    ) -> Result<QueryResult, Error> {
+        let mut database = self.database(database_name)?;
+
+        let column_definitions = database.column_definitions(table_name)?;
+        let mut random = Random::new(seed);
+
+        let mut result = Ok(QueryResult::InsertSuccess { count });
+        for _ in 0..count {
+            let values = column_definitions
+                .iter()
+                .map(|column_definition| {
+                    Some(column_definition.data_type().random_value(&mut random))
+                })
+                .collect();
+
+            if let Err(error) = database.insert_row(table_name, values) {
+                result = Err(error);
+                break;
+            }
+        }
+
+        {
+            let page_manager = self.page_manager.read().unwrap();
+            page_manager.commit()?;
+        }
+
+        result
+    }
+
+    /// Compacts the pages of the table, or of every table of the database when `table_name` is
+    /// `None`, and puts the pages that are left over on the free list. Like in PostgreSQL, this
+    /// can't be done inside a transaction. Returns the number of freed pages. When vacuuming one of
+    /// the tables fails, the tables that were vacuumed before it are restored too.
+    pub fn vacuum(&mut self, database_name: &str, table_name: Option<&str>) -> Result<u32, Error> {
+        if self.page_manager.read().unwrap().in_transaction() {
+            return Err(Error::ActiveSqlTransaction("VACUUM".to_string()));
+        }
+
+        let mut database = self.database(database_name)?;
+        let snapshot = self.page_manager.read().unwrap().snapshot();
+        let freed_page_count = match database.vacuum(table_name) {
+            Ok(freed_page_count) => freed_page_count,
+            Err(error) => {
+                self.undo_statement(&snapshot)?;
+                return Err(error);
+            }
+        };
+        self.cursors.clear();
+
+        {
+            let page_manager = self.page_manager.read().unwrap();
+            page_manager.commit()?;
+        }
+
+        Ok(freed_page_count)
+    }
+
+    /// Copies every database to `page_manager`, which has to be empty, leaving out the pages that
+    /// aren't used anymore. The pages are ordered by database and by table, with the catalog
+    /// pages of a database or table before the pages they refer to.
+    pub fn compact_into(&self, page_manager: Rc<RwLock<PageManager>>) -> Result<Manager, Error> {
+        Self::copy_databases_into(&self.databases()?, page_manager)
+    }
+
+    /// Like `compact_into`, but only copies `databases`.
+    fn copy_databases_into(
+        databases: &[Database],
+        page_manager: Rc<RwLock<PageManager>>,
+    ) -> Result<Manager, Error> {
+        let compacted = Manager::open(page_manager.clone())?;
+
+        let database_page_ids = databases
+            .iter()
+            .map(|database| database.copy_into(&page_manager))
+            .collect::<Result<Vec<PageId>, Error>>()?;
+        Self::write_metadata_page(compacted.page.clone(), database_page_ids);
+        compacted.load_catalog()?;
+
+        Ok(compacted)
+    }
+
+    /// Writes a compacted copy of every database to a new database file at `path`, see
+    /// `compact_into`, which can be opened like any other database file or be restored with
+    /// `restore`. The checksum of its pages is written with it, see `BACKUP_CHECKSUM_RANGE`. The
+    /// copy is written next to `path` first and then moved there, so `path` never holds a partial
+    /// backup. Can't be done inside a transaction, because the backup would include its
+    /// uncommitted changes.
+    pub fn backup(&self, path: &str) -> Result<(), Error> {
+        self.write_backup(path, self.databases()?)
+    }
+
+    /// Like `backup`, but only writes the databases named `database_names` to the backup, which
+    /// `restore` then recreates on their own.
+    pub fn backup_databases(&self, path: &str, database_names: &[&str]) -> Result<(), Error> {
+        let databases = database_names
+            .iter()
+            .map(|database_name| self.database(database_name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        self.write_backup(path, databases)
+    }
+
+    fn write_backup(&self, path: &str, databases: Vec<Database>) -> Result<(), Error> {
+        if self.page_manager.read().unwrap().in_transaction() {
+            return Err(Error::ActiveSqlTransaction("BACKUP".to_string()));
+        }
+
+        let partial_path = format!("{}.partial", path);
+        let _result = std::fs::remove_file(&partial_path);
+        let page_manager = Rc::new(RwLock::new(PageManager::new(&partial_path)));
+        if !page_manager.read().unwrap().is_healthy() {
+            return Err(Error::CouldNotWriteFile(path.to_string()));
+        }
+
+        Self::copy_databases_into(&databases, page_manager.clone())?;
+        {
+            let page_manager = page_manager.read().unwrap();
+            let checksum = Self::backup_checksum(&page_manager)?;
+            page_manager
+                .fetch_page(0)?
+                .expect("The backup has a header page")
+                .write()
+                .unwrap()
+                .data[BACKUP_CHECKSUM_RANGE]
+                .copy_from_slice(&checksum.to_be_bytes());
+            page_manager
+                .flush()
+                .map_err(|_error| Error::CouldNotWriteFile(path.to_string()))?;
+        }
+
+        std::fs::rename(&partial_path, path).map_err(|_error| {
+            let _result = std::fs::remove_file(&partial_path);
+            Error::CouldNotWriteFile(path.to_string())
+        })
+    }
+
+    /// Recreates the databases of a backup written by `backup`, with their tables and rows, and
+    /// returns their names. Nothing is restored when the backup can't be read, was written with
+    /// another format version, doesn't match its checksum or has a database that already exists.
+    pub fn restore(&mut self, path: &str) -> Result<Vec<String>, Error> {
+        if self.page_manager.read().unwrap().in_transaction() {
+            return Err(Error::ActiveSqlTransaction("RESTORE".to_string()));
+        }
+        if !std::path::Path::new(path).is_file() {
+            return Err(Error::CouldNotReadFile(path.to_string()));
+        }
+
+        let page_manager = Rc::new(RwLock::new(PageManager::new(path)));
+        {
+            let page_manager = page_manager.read().unwrap();
+            let page = page_manager.fetch_page(0)?.ok_or(Error::NotADatabaseFile)?;
+            let version = Self::read_format_version(&page)?;
+            if version != FORMAT_VERSION {
+                return Err(Error::UnsupportedFormatVersion(version));
+            }
+
+            let checksum = u64::from_be_bytes(
+                page.read().unwrap().data[BACKUP_CHECKSUM_RANGE]
+                    .try_into()
+                    .unwrap(),
+            );
+            if checksum != Self::backup_checksum(&page_manager)? {
+                return Err(Error::CorruptBackup(path.to_string()));
+            }
+        }
+        let backup = Manager::open(page_manager)?;
+
+        let databases = backup.databases()?;
+        if let Some(database) = databases.iter().find(|d| self.database_exists(&d.name())) {
+            return Err(Error::DatabaseAlreadyExists(database.name()));
+        }
+
+        let mut database_page_ids = self.database_page_ids();
+        for database in databases.iter() {
+            database_page_ids.push(database.copy_into(&self.page_manager)?);
+        }
+        Self::write_metadata_page(self.page.clone(), database_page_ids);
+        self.page_manager.read().unwrap().commit()?;
+
+        self.load_catalog()?;
+
+        let database_names: Vec<String> = databases.iter().map(|d| d.name()).collect();
+        for database_name in database_names.iter() {
+            self.notify_catalog_change(CatalogChangeKind::DatabaseCreated {
+                database_name: database_name.clone(),
+            });
+        }
+
+        Ok(database_names)
+    }
+
+    /// Returns the checksum of every page of a backup, leaving out where it's stored in page 0. Uses
+    /// FNV-1a, which unlike `DefaultHasher` doesn't change between versions of Rust.
+    fn backup_checksum(page_manager: &PageManager) -> Result<u64, Error> {
+        let mut checksum: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut hash = |bytes: &[u8]| {
+            for byte in bytes {
+                checksum ^= *byte as u64;
+                checksum = checksum.wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+
+        for page_id in 0..page_manager.page_count() as PageId {
+            let Some(page) = page_manager.fetch_page(page_id)? else {
+                continue;
+            };
+            let page = page.read().unwrap();
+
+            hash(&page.metadata);
+            if page_id == 0 {
+                hash(&page.data[..BACKUP_CHECKSUM_RANGE.start]);
+                hash(&page.data[BACKUP_CHECKSUM_RANGE.end..]);
+            } else {
+                hash(&page.data);
+            }
+        }
+
+        Ok(checksum)
+    }
+
+    pub fn database_exists(&self, key: &str) -> bool {
+        self.database(key).is_ok()
+    }
+
+    fn create_table(
+        &mut self,
+        database_name: &str,
+        table_name: &str,
+        column_definitions: Vec<ColumnDeclaration>,
+        fill_factor: Option<u8>,
+    ) -> Result<QueryResult, Error> {
+        let not_null_columns: Vec<String> = column_definitions
+            .iter()
+            .filter(|column| column.not_null)
+            .map(|column| column.name.clone())
+            .collect();
+        let primary_key_columns: Vec<String> = column_definitions
+            .iter()
+            .filter(|column| column.primary_key)
+            .map(|column| column.name.clone())
+            .collect();
+        let serial_columns: Vec<String> = column_definitions
+            .iter()
+            .filter(|column| column.data_type == DataTypeIdentifier::Serial)
+            .map(|column| column.name.clone())
+            .collect();
+        if primary_key_columns.len() > 1 {
+            return Err(Error::MultiplePrimaryKeys(table_name.to_string()));
+        }
+        let primary_key = primary_key_columns.into_iter().next();
+        let columns: Vec<(String, DataType)> = column_definitions
+            .into_iter()
+            .map(|column| (column.name, column.data_type.into()))
+            .collect();
+        // The primary key index only has integer keys.
+        if columns.iter().any(|(column_name, data_type)| {
+            Some(column_name) == primary_key.as_ref() && *data_type != DataType::Integer
+        }) {
+            return Err(Error::TypeMismatch);
+        }
+
+        let mut database = self.database(database_name)?;
+
+        let result = database
+            .create_table(table_name, columns)
+            .and_then(|_table_id| {
+                not_null_columns
+                    .iter()
+                    .try_for_each(|column_name| database.set_not_null(table_name, column_name))
+            })
+            .and_then(|_| {
+                serial_columns
+                    .iter()
+                    .try_for_each(|column_name| database.set_serial(table_name, column_name))
+            })
+            .and_then(|_| match &primary_key {
+                Some(column_name) => database.set_primary_key(table_name, column_name),
+                None => Ok(()),
+            })
+            .and_then(|_| match fill_factor {
+                Some(fill_factor) => database.set_fill_factor(table_name, fill_factor),
+                None => Ok(()),
+            })
+            .map(|_| QueryResult::CommandSuccessMessage("CREATE TABLE".to_string()));
+
+        {
+            let page_manager = self.page_manager.read().unwrap();
+            page_manager.commit()?;
+        }
+
+        if result.is_ok() {
+            self.notify_catalog_change(CatalogChangeKind::TableCreated {
+                database_name: database_name.to_string(),
+                table_name: table_name.to_string(),
+            });
+        }
+
+        return result;
+    }
+
+    fn create_sequence(
+        &mut self,
+        database_name: &str,
+        sequence_name: &str,
+        start: i64,
+    ) -> Result<QueryResult, Error> {
+        let mut database = self.database(database_name)?;
+        database.create_sequence(sequence_name, start)?;
+
+        {
+            let page_manager = self.page_manager.read().unwrap();
+            page_manager.commit()?;
+        }
+
+        self.notify_catalog_change(CatalogChangeKind::SequenceCreated {
+            database_name: database_name.to_string(),
+            sequence_name: sequence_name.to_string(),
+        });
+
+        Ok(QueryResult::CommandSuccessMessage(
+            "CREATE SEQUENCE".to_string(),
+        ))
+    }
+
+    fn create_database(&mut self, name: &str) -> Result<QueryResult, Error> {
+        if !self.database_exists(name) {
+            let mut database_page_ids = self.database_page_ids();
+
+            {
+                let mut page_manager = self.page_manager.write().unwrap();
+                let (page_id, shared_page) = page_manager.create_page();
+
+                let database =
+                    Database::initialize(self.page_manager.clone(), shared_page.clone(), name)?;
+                self.catalog
+                    .borrow_mut()
+                    .insert(name.to_string(), (page_id, database.table_page_ids()));
+
+                database_page_ids.push(page_id);
+            }
+
+            Self::write_metadata_page(self.page.clone(), database_page_ids);
+
+            {
+                let page_manager = self.page_manager.read().unwrap();
+                page_manager.commit()?;
+            }
+
+            self.notify_catalog_change(CatalogChangeKind::DatabaseCreated {
+                database_name: name.to_string(),
+            });
+
+            Ok(QueryResult::CommandSuccessMessage(
+                "CREATE DATABASE".to_string(),
+            ))
+        } else {
+            Err(Error::DatabaseAlreadyExists(name.to_string()))
+        }
+    }
+
+    fn insert_row(
+        &mut self,
+        database_name: &str,
+        table_name: &str,
+        column_names: Option<Vec<String>>,
+        rows: Vec<Vec<Option<Value>>>,
+        on_conflict: Option<OnConflict>,
+        returning: Option<Vec<Expression>>,
+    ) -> Result<QueryResult, Error> {
+        let mut database = self.database(database_name)?;
+
+        let columns: Vec<String> = database
+            .column_definitions(table_name)?
+            .iter()
+            .map(|column_definition| column_definition.name().clone())
+            .collect();
+        // The rows that were inserted or updated, which are only kept for `RETURNING`.
+        let mut affected_rows: Vec<RowValues> = vec![];
+        // The records that were inserted or updated, which `ON CONFLICT DO UPDATE` mustn't
+        // update again.
+        let mut affected_record_ids: HashSet<u64> = HashSet::new();
+        let mut count = 0;
+        // A statement with several rows inserts all of them or none.
+        let snapshot = (rows.len() > 1).then(|| self.page_manager.read().unwrap().snapshot());
+
+        let inserted = match &on_conflict {
+            // Without conflicts to look for, the rows are written together.
+            None => {
+                let inserted_rows: Vec<RowValues> = match returning {
+                    Some(_) => rows
+                        .iter()
+                        .map(|values| Self::inserted_row(&columns, &column_names, values))
+                        .collect(),
+                    None => vec![],
+                };
+                database
+                    .insert_rows_into_columns(
+                        table_name,
+                        column_names.as_ref().unwrap_or(&columns),
+                        rows,
+                    )
+                    .and_then(|record_ids| {
+                        count = record_ids.len();
+                        // The stored rows also have the values of the `serial` columns.
+                        for (record_id, row) in record_ids.into_iter().zip(inserted_rows) {
+                            affected_rows.push(
+                                database
+                                    .select_record(table_name, record_id)?
+                                    .unwrap_or(row),
+                            );
+                        }
+
+                        Ok(())
+                    })
+            }
+            Some(on_conflict) => rows.into_iter().try_for_each(|values| {
+                let row = Self::inserted_row(&columns, &column_names, &values);
+                let conflict =
+                    Self::find_conflict(&database, table_name, &columns, on_conflict, &row)?;
+
+                if let Some((record_id, existing_row)) = conflict {
+                    if let ConflictAction::DoUpdate(assignments) = &on_conflict.action {
+                        if affected_record_ids.contains(&record_id) {
+                            return Err(Error::RowAffectedTwice);
+                        }
+
+                        let row = Self::updated_row(&columns, existing_row, row, assignments)?;
+                        if returning.is_some() {
+                            affected_rows.push(row.clone());
+                        }
+                        affected_record_ids
+                            .insert(database.update_row(table_name, record_id, row)?);
+                        count += 1;
+                    }
+
+                    return Ok(());
+                }
+
+                let record_id = match &column_names {
+                    Some(column_names) => {
+                        database.insert_row_into_columns(table_name, column_names, values)
+                    }
+                    None => database.insert_row(table_name, values),
+                }?;
+                affected_record_ids.insert(record_id);
+                count += 1;
+                if returning.is_some() {
+                    affected_rows.push(
+                        database
+                            .select_record(table_name, record_id)?
+                            .unwrap_or(row),
+                    );
+                }
+
+                Ok(())
+            }),
+        };
+
+        let query_result = inserted.and_then(|_| match returning {
+            Some(expressions) => {
+                evaluator::project(&RowResult::new(columns, affected_rows), &expressions)
+                    .map(QueryResult::RowResult)
+            }
+            None => Ok(QueryResult::InsertSuccess { count }),
+        });
+
+        if let (Err(_), Some(snapshot)) = (&query_result, snapshot) {
+            self.undo_statement(&snapshot)?;
+        }
+        {
+            let page_manager = self.page_manager.read().unwrap();
+            page_manager.commit()?;
+        }
+
+        return query_result;
+    }
+
+    /// Restores the pages to `snapshot`, to undo the changes of a statement that failed after
+    /// some of its rows were written.
+    fn undo_statement(&self, snapshot: &Snapshot) -> Result<(), Error> {
+        self.page_manager.read().unwrap().restore(snapshot);
+        self.load_catalog()?;
+        self.restore_sequence_values()
+    }
+
+    /// Returns the names of the databases together with the names of their tables and sequences,
+    /// which is what DDL statements change.
+    fn catalog_names(&self) -> Result<CatalogNames, Error> {
+        self.databases()?
+            .iter()
+            .map(|database| {
+                Ok((
+                    database.name(),
+                    database.table_names()?,
+                    database.sequence_names(),
+                ))
+            })
+            .collect()
+    }
+
+    /// Loads the catalog again after a rollback and closes the cursors. When the rollback undid
+    /// DDL statements, so the catalog differs from `catalog_names` from before the rollback, the
+    /// schema version is bumped and the listeners are notified.
+    fn reload_catalog(&mut self, catalog_names: CatalogNames) -> Result<(), Error> {
+        self.load_catalog()?;
+        self.restore_sequence_values()?;
+        self.cursors.clear();
+
+        if self.catalog_names()? != catalog_names {
+            self.notify_catalog_change(CatalogChangeKind::RolledBack);
+        }
+
+        Ok(())
+    }
+
+    /// Advances the sequences to the values they handed out before pages were restored, see
+    /// `sequence_values`.
+    fn restore_sequence_values(&self) -> Result<(), Error> {
+        for ((database_name, sequence_name), last_value) in self.sequence_values.borrow().iter() {
+            if !self.catalog.borrow().contains_key(database_name) {
+                continue;
+            }
+
+            self.database(database_name)?
+                .advance_sequence(sequence_name, *last_value);
+        }
+
+        self.page_manager.read().unwrap().commit()
+    }
+
+    /// Inserts every record of the CSV file `filename` into the table, converting the fields to the
+    /// types of their columns. The file is read and written `COPY_BATCH_SIZE` records at a time, so
+    /// it doesn't have to fit in memory. Empty fields are `NULL`. When a record can't be read or inserted, none of them are.
+    /// Only files inside the copy directory can be read, see `set_copy_directory`.
+    fn copy_from(
+        &mut self,
+        database_name: &str,
+        table_name: &str,
+        column_names: Option<Vec<String>>,
+        filename: &str,
+        header: bool,
+    ) -> Result<QueryResult, Error> {
+        let mut database = self.database(database_name)?;
+
+        let column_definitions = database.column_definitions(table_name)?;
+        let columns: Vec<(String, DataType)> = match column_names {
+            Some(column_names) => column_names
+                .into_iter()
+                .map(|column_name| {
+                    column_definitions
+                        .iter()
+                        .find(|column_definition| *column_definition.name() == column_name)
+                        .map(|column_definition| {
+                            (column_name.clone(), column_definition.data_type().clone())
+                        })
+                        .ok_or(Error::ColumnDoesNotExist(column_name))
+                })
+                .collect::<Result<Vec<(String, DataType)>, Error>>()?,
+            None => column_definitions
+                .iter()
+                .map(|column_definition| {
+                    (
+                        column_definition.name().clone(),
+                        column_definition.data_type().clone(),
+                    )
+                })
+                .collect(),
+        };
+
+        let column_names: Vec<String> = columns
+            .iter()
+            .map(|(column_name, _data_type)| column_name.clone())
+            .collect();
+
+        let file = File::open(self.copy_file_path(filename)?)
+            .map_err(|error| Error::CouldNotReadFile(format!("{}: {}", filename, error)))?;
+        let mut records = CsvReader::new(BufReader::new(file));
+        if header {
+            records.next().transpose()?;
+        }
+
+        // COPY inserts all of the records or none.
+        let snapshot = self.page_manager.read().unwrap().snapshot();
+        let mut count = 0;
+        let mut batch = Vec::with_capacity(COPY_BATCH_SIZE);
+        let query_result = loop {
+            if batch.len() == COPY_BATCH_SIZE {
+                match database.insert_rows_into_columns(table_name, &column_names, batch) {
+                    Ok(record_ids) => count += record_ids.len(),
+                    Err(error) => break Err(error),
+                }
+                batch = Vec::with_capacity(COPY_BATCH_SIZE);
+            }
+
+            let record = match records.next() {
+                Some(Ok(record)) => record,
+                Some(Err(error)) => break Err(error),
+                None => {
+                    break database
+                        .insert_rows_into_columns(table_name, &column_names, batch)
+                        .map(|record_ids| {
+                            QueryResult::CommandSuccessMessage(format!(
+                                "COPY {}",
+                                count + record_ids.len()
+                            ))
+                        })
+                }
+            };
+            if record.len() != columns.len() {
+                break Err(Error::BadCopyFileFormat(format!(
+                    "expected {} fields on line {}, found {}",
+                    columns.len(),
+                    records.line_number(),
+                    record.len()
+                )));
+            }
+
+            let values = columns
+                .iter()
+                .zip(record)
+                .map(|((_column_name, data_type), field)| {
+                    field.map(|field| data_type.parse_text(field)).transpose()
+                })
+                .collect::<Result<Vec<Option<Value>>, Error>>();
+            match values {
+                Ok(values) => batch.push(values),
+                Err(error) => break Err(error),
+            }
+        };
+
+        if query_result.is_err() {
+            self.undo_statement(&snapshot)?;
+        }
+        {
+            let page_manager = self.page_manager.read().unwrap();
+            page_manager.commit()?;
+        }
+
+        query_result
+    }
+
+    /// Resolves `filename` against the copy directory, and fails unless the file it leads to is
+    /// inside of it.
+    fn copy_file_path(&self, filename: &str) -> Result<PathBuf, Error> {
+        let not_allowed = || Error::CopyFileNotAllowed(filename.to_string());
+        let directory = self
+            .copy_directory
+            .as_ref()
+            .and_then(|directory| directory.canonicalize().ok())
+            .ok_or_else(not_allowed)?;
+
+        let path = directory
+            .join(filename)
+            .canonicalize()
+            .map_err(|error| Error::CouldNotReadFile(format!("{}: {}", filename, error)))?;
+        if !path.starts_with(&directory) {
+            return Err(not_allowed());
+        }
+
+        Ok(path)
+    }
+
+    /// Returns the record that has the same values as `row` for all of the conflict columns, if
+    /// there is one. `NULL` values never conflict.
+    fn find_conflict(
+        database: &Database,
+        table_name: &str,
+        columns: &[String],
+        on_conflict: &OnConflict,
+        row: &RowValues,
+    ) -> Result<Option<(u64, RowValues)>, Error> {
+        let positions = on_conflict
+            .columns
+            .iter()
+            .map(|column_name| {
+                columns
+                    .iter()
+                    .position(|column| column == column_name)
+                    .ok_or(Error::ColumnDoesNotExist(column_name.clone()))
+            })
+            .collect::<Result<Vec<usize>, Error>>()?;
+
+        if positions.iter().any(|position| row[*position].is_none()) {
+            return Ok(None);
+        }
+
+        // Conflicts on the primary key are found with its index.
+        if let [position] = positions[..] {
+            if database.primary_key(table_name)?.as_ref() == Some(&columns[position]) {
+                return database.find_by_primary_key(table_name, row[position].as_ref().unwrap());
+            }
+        }
+
+        Ok(database
+            .select_records_with_ids(table_name)?
+            .into_iter()
+            .find(|(_record_id, existing_row)| {
+                positions
+                    .iter()
+                    .all(|position| existing_row[*position] == row[*position])
+            }))
+    }
+
+    /// Applies the `SET` assignments of `ON CONFLICT DO UPDATE` to `existing_row`. The assignments
+    /// can refer to the columns of `row`, which conflicted with it, as `excluded.column`.
+    fn updated_row(
+        columns: &[String],
+        existing_row: RowValues,
+        row: RowValues,
+        assignments: &[(String, Expression)],
+    ) -> Result<RowValues, Error> {
+        let scope_columns: Vec<String> = columns
+            .iter()
+            .cloned()
+            .chain(columns.iter().map(|column| format!("excluded.{}", column)))
+            .collect();
+        let scope_row: RowValues = existing_row.iter().cloned().chain(row).collect();
+
+        let mut updated_row = existing_row;
+        for (column_name, expression) in assignments {
+            let position = columns
+                .iter()
+                .position(|column| column == column_name)
+                .ok_or(Error::ColumnDoesNotExist(column_name.clone()))?;
+
+            updated_row[position] = evaluator::evaluate(expression, &scope_columns, &scope_row)?;
+        }
+
+        Ok(updated_row)
+    }
+
+    /// Returns an inserted row with a value for every column of the table, in table order.
+    fn inserted_row(
+        columns: &[String],
+        column_names: &Option<Vec<String>>,
+        values: &[Option<Value>],
+    ) -> RowValues {
+        columns
+            .iter()
+            .enumerate()
+            .map(|(index, column)| match column_names {
+                Some(column_names) => column_names
+                    .iter()
+                    .position(|column_name| column_name == column)
+                    .and_then(|position| values.get(position).cloned().flatten()),
+                None => values.get(index).cloned().flatten(),
+            })
+            .collect()
+    }
+
+    fn select(
+        &self,
+        active_database_name: &str,
+        qualified_database_name: Option<String>,
+        table_name: Option<&str>,
+        expressions: Vec<Expression>,
+        table_sample: Option<TableSample>,
+        where_clause: Option<Expression>,
+    ) -> Result<RowResult, Error> {
+        let (expressions, column_names) =
+            self.resolve_select_list(active_database_name, table_name, expressions)?;
+        let project = |rows: &RowResult| Self::project(rows, &expressions, &column_names);
+        let where_clause =
+            self.resolve_where_clause(active_database_name, table_name, where_clause)?;
+
+        let Some(table_name) = table_name else {
+            // Without a table the select list is evaluated once, for a row without columns.
+            let rows = RowResult::new(vec![], vec![vec![]]);
+            let rows = match where_clause {
+                Some(condition) => evaluator::filter(&rows, &condition)?,
+                None => rows,
+            };
+
+            return self.account_memory(project(&rows)?);
+        };
+
+        if qualified_database_name.is_none() && table_name == STATEMENT_STATISTICS_VIEW {
+            let rows = StatementStatistics::to_row_result(&self.statement_statistics());
+            let rows = match where_clause {
+                Some(condition) => self.account_memory(evaluator::filter(&rows, &condition)?)?,
+                None => rows,
+            };
+
+            return self.account_memory(project(&rows)?);
+        }
+
+        let database_name =
+            Self::resolve_database_name(active_database_name, qualified_database_name)?;
+        let database = self.database(&database_name)?;
+
+        if let Some(TableSample { percentage, seed }) = table_sample {
+            let mut random = seed
+                .map(Random::new)
+                .unwrap_or_else(|| Random::from_clock(&*self.clock));
+            let (columns, rows) = database.scan_sample(table_name, percentage, &mut random)?;
+            let rows = self.collect_accounted(columns, rows)?;
+            let rows = match where_clause {
+                Some(condition) => self.account_memory(evaluator::filter(&rows, &condition)?)?,
+                None => rows,
+            };
+
+            return self.account_memory(project(&rows)?);
+        }
+
+        if let Some(condition) = where_clause {
+            let rows = match Self::primary_key_lookup(&database, table_name, &condition)? {
+                Some(rows) => {
+                    let rows = evaluator::filter(&self.account_memory(rows)?, &condition)?;
+                    self.account_memory(rows)?
+                }
+                // The rows are filtered while the pages are scanned, before they're put in the
+                // order of the table's columns.
+                None => {
+                    let (columns, rows) = database.scan_filtered(table_name, |columns, row| {
+                        evaluator::matches(&condition, columns, row)
+                    })?;
+                    self.collect_accounted(columns, rows)?
+                }
+            };
+            return self.account_memory(project(&rows)?);
+        }
+
+        if let [Expression::Wildcard] = &expressions[..] {
+            let (columns, rows) = database.scan(table_name)?;
+            return self.collect_accounted(columns, rows);
+        }
+
+        // `COUNT(*)` of the whole table is the row count that the table keeps.
+        if let [expression @ Expression::Function { name, arguments }] = &expressions[..] {
+            if name.eq_ignore_ascii_case("COUNT") && arguments[..] == [Expression::Wildcard] {
+                let row_count = i64::try_from(database.row_count(table_name)?)
+                    .map_err(|_| Error::IntegerOutOfRange)?;
+                return Ok(RowResult::new(
+                    vec![expression.column_name()],
+                    vec![vec![Some(Value::Integer(row_count))]],
+                ));
+            }
+        }
+
+        let column_names: Vec<&str> = expressions
+            .iter()
+            .filter_map(|expression| match expression {
+                Expression::Identifier(name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect();
+
+        if column_names.len() == expressions.len() {
+            let rows = database.scan_columns(table_name, &column_names)?;
+            let columns = column_names.iter().map(|name| name.to_string()).collect();
+            self.collect_accounted(columns, rows)
+        } else {
+            let (columns, rows) = database.scan(table_name)?;
+            let rows = self.collect_accounted(columns, rows)?;
+            self.account_memory(project(&rows)?)
+        }
+    }
+
+    /// Resolves the expressions of a select list, see `select`. Returns them together with the
+    /// names of their columns, which are `None` for `*`.
+    fn resolve_select_list(
+        &self,
+        active_database_name: &str,
+        table_name: Option<&str>,
+        expressions: Vec<Expression>,
+    ) -> Result<(Vec<Expression>, Vec<Option<String>>), Error> {
+        // Subqueries refer to the active database, unless they qualify their own tables. The
+        // columns are named before system functions are replaced by their results, so that e.g.
+        // `bsql_health()` keeps its name.
+        let mut column_names = Vec::with_capacity(expressions.len());
+        let expressions = expressions
+            .into_iter()
+            .map(|expression| {
+                let expression = self.resolve_subqueries(active_database_name, expression)?;
+                let expression =
+                    self.resolve_sequence_functions(active_database_name, expression)?;
+                let expression = Self::resolve_qualified_columns(table_name, expression)?;
+                column_names.push(match &expression {
+                    Expression::Wildcard => None,
+                    expression => Some(expression.column_name()),
+                });
+
+                self.resolve_system_functions(expression)
+            })
+            .collect::<Result<Vec<Expression>, Error>>()?;
+
+        Ok((expressions, column_names))
+    }
+
+    fn resolve_where_clause(
+        &self,
+        active_database_name: &str,
+        table_name: Option<&str>,
+        where_clause: Option<Expression>,
+    ) -> Result<Option<Expression>, Error> {
+        where_clause
+            .map(|expression| {
+                let expression = self.resolve_subqueries(active_database_name, expression)?;
+                Self::resolve_qualified_columns(table_name, expression)
+            })
+            .transpose()
+    }
+
+    /// Creates a cursor for a `SELECT` query, which scans the table as the rows are fetched
+    /// instead of executing the query up front. Queries without a table and queries of views are
+    /// executed when the cursor is declared.
+    fn declare_cursor(&self, active_database_name: &str, query: Command) -> Result<Cursor, Error> {
+        let Command::Select {
+            expressions,
+            database_name: qualified_database_name,
+            table_name,
+            table_sample,
+            where_clause,
+        } = query
+        else {
+            unreachable!("The parser only produces cursors for SELECT commands");
+        };
+
+        let table_name = match table_name {
+            Some(table_name)
+                if qualified_database_name.is_some() || table_name != STATEMENT_STATISTICS_VIEW =>
+            {
+                table_name
+            }
+            table_name => {
+                return self
+                    .select(
+                        active_database_name,
+                        qualified_database_name,
+                        table_name.as_deref(),
+                        expressions,
+                        table_sample,
+                        where_clause,
+                    )
+                    .map(Cursor::from_row_result)
+            }
+        };
+
+        let (expressions, column_names) =
+            self.resolve_select_list(active_database_name, Some(&table_name), expressions)?;
+        let where_clause =
+            self.resolve_where_clause(active_database_name, Some(&table_name), where_clause)?;
+
+        let database_name =
+            Self::resolve_database_name(active_database_name, qualified_database_name)?;
+        let database = self.database(&database_name)?;
+
+        let cursor = match (table_sample, where_clause) {
+            (Some(TableSample { percentage, seed }), where_clause) => {
+                let mut random = seed
+                    .map(Random::new)
+                    .unwrap_or_else(|| Random::from_clock(&*self.clock));
+                let (columns, rows) = database.scan_sample(&table_name, percentage, &mut random)?;
+                let filter_columns = columns.clone();
+
+                Cursor::new(
+                    columns,
+                    Box::new(rows.filter_map(move |row| {
+                        let (Ok(row), Some(condition)) = (&row, &where_clause) else {
+                            return Some(row);
+                        };
+
+                        match evaluator::matches(condition, &filter_columns, row) {
+                            Ok(true) => Some(Ok(row.clone())),
+                            Ok(false) => None,
+                            Err(error) => Some(Err(error)),
+                        }
+                    })),
+                )
+            }
+            (None, Some(condition)) => {
+                let (columns, rows) = database
+                    .scan_filtered(&table_name, move |columns, row| {
+                        evaluator::matches(&condition, columns, row)
+                    })?;
+                Cursor::new(columns, Box::new(rows))
+            }
+            (None, None) => {
+                let (columns, rows) = database.scan(&table_name)?;
+                Cursor::new(columns, Box::new(rows))
+            }
+        };
+
+        if let [Expression::Wildcard] = &expressions[..] {
+            return Ok(cursor);
+        }
+
+        Ok(cursor.with_projection(Box::new(move |rows| {
+            Self::project(rows, &expressions, &column_names)
+        })))
+    }
+
+    /// Like `evaluator::project`, but names the columns of the `expressions` that aren't `*` by
+    /// `column_names` instead of by the expressions themselves.
+    fn project(
+        rows: &RowResult,
+        expressions: &[Expression],
+        column_names: &[Option<String>],
+    ) -> Result<RowResult, Error> {
+        let columns = column_names
+            .iter()
+            .flat_map(|column_name| match column_name {
+                Some(column_name) => vec![column_name.clone()],
+                None => rows.columns().clone(),
+            })
+            .collect();
+
+        Ok(RowResult::new(
+            columns,
+            evaluator::project(rows, expressions)?.into_iter().collect(),
+        ))
+    }
+
+    /// Describes how `select` would execute the query: how the table is scanned, how many pages
+    /// that reads, the condition that the rows are filtered with and the select list.
+    fn explain(&self, active_database_name: &str, query: Command) -> Result<RowResult, Error> {
+        let Command::Select {
+            expressions,
+            database_name: qualified_database_name,
+            table_name,
+            table_sample,
+            where_clause,
+        } = query
+        else {
+            unreachable!("The parser only explains SELECT commands");
+        };
+        let where_clause = where_clause
+            .map(|expression| Self::resolve_qualified_columns(table_name.as_deref(), expression))
+            .transpose()?;
+
+        let mut plan = vec![];
+
+        if let Some(table_name) = table_name {
+            if qualified_database_name.is_none() && table_name == STATEMENT_STATISTICS_VIEW {
+                plan.push(format!("View Scan on {}", table_name));
+            } else {
+                let database_name =
+                    Self::resolve_database_name(active_database_name, qualified_database_name)?;
+                let database = self.database(&database_name)?;
+
+                let page_count = database.data_page_count(&table_name)?;
+                let primary_key_condition = match &where_clause {
+                    Some(condition) => {
+                        Self::primary_key_condition(&database, &table_name, condition)?
+                    }
+                    None => None,
+                };
+
+                match (table_sample, primary_key_condition) {
+                    (Some(TableSample { percentage, .. }), _) => {
+                        plan.push(format!("Sample Scan on {} ({}%)", table_name, percentage));
+                        plan.push(format!(
+                            "  Pages: ~{}",
+                            (page_count * percentage as u32 + 99) / 100
+                        ));
+                    }
+                    (None, Some((column_name, key))) => {
+                        plan.push(format!("Primary Key Lookup on {}", table_name));
+                        plan.push(format!("  Key: {} = {}", column_name, key.to_sql()));
+                        // The index page and the page of the record.
+                        plan.push("  Pages: 2".to_string());
+                    }
+                    (None, None) => {
+                        plan.push(format!("Seq Scan on {}", table_name));
+                        plan.push(format!("  Pages: {}", page_count));
+                    }
+                }
+            }
+        } else {
+            plan.push("Result".to_string());
+        }
+
+        if let Some(condition) = where_clause {
+            plan.push(format!("  Filter: {}", condition.to_sql()));
+        }
+        plan.push(format!(
+            "  Output: {}",
+            expressions
+                .iter()
+                .map(Expression::to_sql)
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+
+        Ok(RowResult::new(
+            vec!["QUERY PLAN".to_string()],
+            plan.into_iter()
+                .map(|line| vec![Some(Value::Text(line))])
+                .collect(),
+        ))
+    }
+
+    /// Returns the column and the key when `condition` compares the primary key of the table to
+    /// an integer, e.g. `id = 3`, so that the rows can be found with the primary key index. The
+    /// comparison can also be one of the operands of `AND`, e.g. `id = 3 AND age > 30`, because
+    /// only the row with that key can match the whole condition.
+    fn primary_key_condition<'a>(
+        database: &Database,
+        table_name: &str,
+        condition: &'a Expression,
+    ) -> Result<Option<(&'a String, &'a LiteralValue)>, Error> {
+        if let Expression::Logical {
+            lhs,
+            operation: LogicalOperation::And,
+            rhs,
+        } = condition
+        {
+            return match Self::primary_key_condition(database, table_name, lhs)? {
+                Some(primary_key_condition) => Ok(Some(primary_key_condition)),
+                None => Self::primary_key_condition(database, table_name, rhs),
+            };
+        }
+
+        let Expression::Comparison {
+            lhs,
+            operation: CompareOperation::Equality,
+            rhs,
+        } = condition
+        else {
+            return Ok(None);
+        };
+
+        let (column_name, key) = match (lhs.as_ref(), rhs.as_ref()) {
+            (Expression::Identifier(column_name), Expression::Literal(key))
+            | (Expression::Literal(key), Expression::Identifier(column_name)) => (column_name, key),
+
+            _ => return Ok(None),
+        };
+
+        if !matches!(key, LiteralValue::Integer(_))
+            || database.primary_key(table_name)?.as_ref() != Some(column_name)
+        {
+            return Ok(None);
+        }
+
+        Ok(Some((column_name, key)))
+    }
+
+    /// Returns the rows that can match `condition` when it compares the primary key of the table
+    /// to an integer, see `primary_key_condition`. The rows are found with the primary key index,
+    /// so the table isn't scanned. Returns `None` for any other condition.
+    fn primary_key_lookup(
+        database: &Database,
+        table_name: &str,
+        condition: &Expression,
+    ) -> Result<Option<RowResult>, Error> {
+        let Some((_column_name, key)) =
+            Self::primary_key_condition(database, table_name, condition)?
+        else {
+            return Ok(None);
+        };
+
+        let columns = database
+            .column_definitions(table_name)?
+            .iter()
+            .map(|column_definition| column_definition.name().clone())
+            .collect();
+        let rows = database
+            .find_by_primary_key(table_name, &key.clone().into())?
+            .map(|(_record_id, row)| row)
+            .into_iter()
+            .collect();
+
+        Ok(Some(RowResult::new(columns, rows)))
+    }
+
+    /// Replaces the calls to functions that inspect the state of bsql itself, such as
+    /// `bsql_health()`, with their results.
+    fn resolve_system_functions(&self, expression: Expression) -> Result<Expression, Error> {
+        expression.transform(&mut |expression| match expression {
+            Expression::Function { name, arguments }
+                if name.eq_ignore_ascii_case("bsql_health") =>
+            {
+                if !arguments.is_empty() {
+                    return Err(Error::InvalidFunctionArguments(name));
+                }
+
+                self.check_health()?;
+                Ok(Expression::Literal(LiteralValue::Boolean(true)))
+            }
+
+            expression => Ok(expression),
+        })
+    }
+
+    /// Replaces every `nextval('sequence_name')` with the next value of the sequence in the active
+    /// database. Like subqueries, every call is evaluated once for the whole statement. The new
+    /// value is written right away, so it isn't handed out again after a restart.
+    fn resolve_sequence_functions(
+        &self,
+        database_name: &str,
+        expression: Expression,
+    ) -> Result<Expression, Error> {
+        expression.transform(&mut |expression| match expression {
+            Expression::Function { name, arguments } if name.eq_ignore_ascii_case("nextval") => {
+                let [Expression::Literal(LiteralValue::Text(sequence_name))] = &arguments[..]
+                else {
+                    return Err(Error::InvalidFunctionArguments(name));
+                };
+
+                let database_name = Self::resolve_database_name(database_name, None)?;
+                let mut database = self.database(&database_name)?;
+                let value = database.next_sequence_value(sequence_name)?;
+                self.page_manager.read().unwrap().commit()?;
+                self.sequence_values
+                    .borrow_mut()
+                    .insert((database_name, sequence_name.clone()), value);
+
+                Ok(Expression::Literal(LiteralValue::Integer(value)))
+            }
+
+            expression => Ok(expression),
+        })
+    }
+
+    /// Replaces the `table_name.column` references in `expression` with `column`. Columns can only
+    /// be qualified with the table that's selected from.
+    fn resolve_qualified_columns(
+        table_name: Option<&str>,
+        expression: Expression,
+    ) -> Result<Expression, Error> {
+        expression.transform(&mut |expression| match expression {
+            Expression::Identifier(name) => match name.split_once('.') {
+                Some((qualifier, column_name)) if Some(qualifier) == table_name => {
+                    Ok(Expression::Identifier(column_name.to_string()))
+                }
+                Some((qualifier, _column_name)) => {
+                    Err(Error::MissingFromClauseEntry(qualifier.to_string()))
+                }
+                None => Ok(Expression::Identifier(name)),
+            },
+
+            expression => Ok(expression),
+        })
+    }
+
+    /// Replaces every subquery in `expression` with the single value it evaluates to.
+    fn resolve_subqueries(
+        &self,
+        database_name: &str,
+        expression: Expression,
+    ) -> Result<Expression, Error> {
+        expression.transform(&mut |expression| {
+            let Expression::Subquery(command) = expression else {
+                return Ok(expression);
+            };
+
+            let Command::Select {
+                expressions,
+                database_name: qualified_database_name,
+                table_name,
+                table_sample,
+                where_clause,
+            } = *command
+            else {
+                unreachable!("The parser only produces SELECT subqueries");
+            };
+
+            let row_result = self.select(
+                database_name,
+                qualified_database_name,
+                table_name.as_deref(),
+                expressions,
+                table_sample,
+                where_clause,
+            )?;
+
+            if row_result.columns().len() != 1 {
+                return Err(Error::SubqueryReturnedMoreThanOneColumn);
+            }
+
+            match row_result.rows() {
+                [] => Ok(Expression::Null),
+                [row] => Ok(row[0]
+                    .clone()
+                    .map(|value| Expression::Literal(value.into()))
+                    .unwrap_or(Expression::Null)),
+
+                _ => Err(Error::SubqueryReturnedMoreThanOneRow),
+            }
+        })
+    }
+
+    /// Loads the database named `database_name`, see `catalog`.
+    fn database(&self, database_name: &str) -> Result<Database, Error> {
+        let (page_id, table_page_ids) = self
+            .catalog
+            .borrow()
+            .get(database_name)
+            .cloned()
+            .ok_or(Error::DatabaseDoesNotExist(database_name.to_string()))?;
+        let page = self
+            .page_manager
+            .read()
+            .unwrap()
+            .fetch_page(page_id)?
+            .expect("The pages of the databases exist");
+
+        Database::load_with_table_page_ids(self.page_manager.clone(), page, table_page_ids)
+    }
+
+    /// Loads the page ids of every database and table again, see `catalog`.
+    fn load_catalog(&self) -> Result<(), Error> {
+        *self.catalog.borrow_mut() = self
+            .database_page_ids()
+            .into_iter()
+            .zip(self.databases()?)
+            .map(|(page_id, database)| (database.name(), (page_id, database.table_page_ids())))
+            .collect();
+
+        Ok(())
+    }
+
+    fn databases(&self) -> Result<Vec<Database>, Error> {
+        let database_page_ids = self.database_page_ids();
+        let mut databases = Vec::new();
+
+        for database_page_id in &database_page_ids {
+            let page_manager = self.page_manager.read().unwrap();
+            let page = page_manager
+                .fetch_page(*database_page_id)?
+                .expect("The pages of the databases exist");
+
+            let database = Database::load(self.page_manager.clone(), page)?;
+            databases.push(database);
+        }
+
+        Ok(databases)
+    }
+
+    fn database_page_ids(&self) -> Vec<PageId> {
+        let page = self.page.read().unwrap();
+        let number_of_database_page_ids = page.metadata[0] as usize;
+        let mut database_page_ids: Vec<PageId> = Vec::new();
+
+        for index in 0..number_of_database_page_ids {
+            let start_index = index as usize * 4 + 1;
+            let end_index = start_index + 4;
+            let page_id_bytes: &[u8; 4] =
+                &page.metadata[start_index..end_index].try_into().unwrap();
+
+            let database_page_id = PageId::from_le_bytes(*page_id_bytes);
+            database_page_ids.push(database_page_id);
+        }
+
+        return database_page_ids;
+    }
+
+    /// Writes the page ids of the databases to the metadata page, which is written to the backend
+    /// with the next commit.
+    fn write_metadata_page(shared_page: SharedInternalPage, database_page_ids: Vec<PageId>) {
+        {
+            let mut page = shared_page.write().unwrap();
+            let number_of_database_page_ids = database_page_ids.len();
+            page.metadata[0] = number_of_database_page_ids as u8;
+
+            let database_page_ids_bytes: Vec<u8> = database_page_ids
+                .iter()
+                .map(|dpid| dpid.to_le_bytes())
+                .flatten()
+                .collect();
+
+            page.metadata[1..database_page_ids_bytes.len() + 1]
+                .copy_from_slice(&database_page_ids_bytes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::parser::CastType;
+    use crate::internal::{arbitrary, Decimal, MemoryBackend, StoppedClock, StorageBackend, Token};
+
+    #[test]
+    fn test_loading_the_catalog_round_trips() {
+        arbitrary::check(|random| {
+            let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+            let mut manager = Manager::new(page_manager.clone());
+
+            let database_count = arbitrary::between(random, 0..=8);
+            let database_names = arbitrary::names(random, database_count, 62);
+            for database_name in database_names.iter() {
+                manager.create_database(database_name).unwrap();
+            }
+
+            assert_eq!(
+                database_names,
+                Manager::new(page_manager).database_names().unwrap()
+            );
+        });
+    }
+
+    #[test]
+    fn test_format_version_header() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager.clone());
+        manager.create_database("db").unwrap();
+        let page = page_manager.read().unwrap().fetch_page(0).unwrap().unwrap();
+        assert_eq!(Ok(FORMAT_VERSION), Manager::read_format_version(&page));
+        assert_eq!(MIGRATIONS.len(), FORMAT_VERSION as usize);
+
+        // Files from before the header are migrated to the current version.
+        page.write().unwrap().data[..12].fill(0);
+        let manager = Manager::open(page_manager.clone()).unwrap();
+        assert_eq!(vec!["db".to_string()], manager.database_names().unwrap());
+        assert_eq!(Ok(FORMAT_VERSION), Manager::read_format_version(&page));
+
+        Manager::write_format_version(&page, FORMAT_VERSION + 1);
+        assert_eq!(
+            Some(Error::UnsupportedFormatVersion(FORMAT_VERSION + 1)),
+            Manager::open(page_manager.clone()).err()
+        );
+
+        page.write().unwrap().data[..12].copy_from_slice(b"SQLite forma");
+        assert_eq!(
+            Some(Error::NotADatabaseFile),
+            Manager::open(page_manager).err()
+        );
+    }
+
+    #[test]
+    fn test_list_databases() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.create_database("hello").unwrap();
+        manager.create_database("world").unwrap();
+
+        assert_eq!(
+            vec!["hello".to_string(), "world".to_string()],
+            manager.database_names().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_executing_multiple_statements() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+
+        manager
+            .execute("", "CREATE DATABASE db; CREATE DATABASE other;")
+            .unwrap();
+        assert_eq!(
+            vec!["db".to_string(), "other".to_string()],
+            manager.database_names().unwrap()
+        );
+
+        assert_eq!(
+            vec![
+                Ok(QueryResult::CommandSuccessMessage(
+                    "CREATE TABLE".to_string()
+                )),
+                Ok(QueryResult::InsertSuccess { count: 1 }),
+            ],
+            manager.execute_all(
+                "db",
+                "CREATE TABLE t (x integer);; INSERT INTO t VALUES (1);"
+            )
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "INSERT INTO t VALUES (2); SELECT x FROM t;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(2, row_result.rows().len());
+    }
+
+    #[test]
+    fn test_executing_multiple_statements_stops_at_the_first_error() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+
+        let results = manager.execute_all(
+            "db",
+            "CREATE TABLE t (x integer); SELECT y FROM t; CREATE TABLE u (x integer);",
+        );
+        assert_eq!(2, results.len());
+        assert_eq!(Err(Error::ColumnDoesNotExist("y".to_string())), results[1]);
+        assert_eq!(
+            Ok(vec!["t".to_string()]),
+            manager.database_table_names("db")
+        );
+    }
+
+    #[test]
+    fn test_executing_with_parameters() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (x integer, y integer);")
+            .unwrap();
+
+        for (x, y) in [(1, 10), (2, 20), (3, 30)] {
+            manager
+                .execute_with_parameters(
+                    "db",
+                    "INSERT INTO t VALUES (?, ?);",
+                    &[Value::Integer(x), Value::Integer(y)],
+                )
+                .unwrap();
+        }
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute_with_parameters(
+            "db",
+            "SELECT y FROM t WHERE x >= $1 AND y <> $2;",
+            &[Value::Integer(2), Value::Integer(30)],
+        ) else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(vec![vec![Some(Value::Integer(20))]], row_result.rows());
+
+        assert_eq!(
+            Err(Error::MissingParameter(1)),
+            manager.execute_with_parameters("db", "SELECT y FROM t WHERE x = $1;", &[])
+        );
+        assert_eq!(
+            Err(Error::TooManyParameters {
+                supplied: 2,
+                required: 1
+            }),
+            manager.execute_with_parameters(
+                "db",
+                "INSERT INTO t VALUES ($1, 40);",
+                &[Value::Integer(4), Value::Integer(40)]
+            )
+        );
+        assert_eq!(
+            Err(Error::ColumnTypeMismatch {
+                column: "x".to_string(),
+                data_type: DataType::Integer
+            }),
+            manager.execute_with_parameters(
+                "db",
+                "INSERT INTO t VALUES ($1, 40);",
+                &[Value::Text("4".to_string())]
+            )
+        );
+        assert_eq!(
+            Ok(vec![(3,)]),
+            manager.query_as("db", "SELECT COUNT(*) FROM t;")
+        );
+    }
+
+    #[test]
+    fn test_use_changes_the_database_of_later_statements() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager
+            .execute("", "CREATE DATABASE db; CREATE DATABASE other;")
+            .unwrap();
+
+        assert_eq!(
+            Ok(QueryResult::DatabaseChanged {
+                database_name: "other".to_string()
+            }),
+            manager.execute("db", "USE other;")
+        );
+        manager
+            .execute("db", "USE other; CREATE TABLE t (x integer);")
+            .unwrap();
+        assert_eq!(Ok(vec![]), manager.database_table_names("db"));
+        assert_eq!(
+            Ok(vec!["t".to_string()]),
+            manager.database_table_names("other")
+        );
+
+        assert_eq!(
+            Err(Error::DatabaseDoesNotExist("missing".to_string())),
+            manager.execute("db", "USE missing;")
+        );
+    }
+
+    #[test]
+    fn test_selecting_without_a_table() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (x integer);")
+            .unwrap();
+        manager.execute("db", "INSERT INTO t VALUES (7);").unwrap();
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("", "SELECT 1 + 2, UPPER('a'), (SELECT x FROM db.t);")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &vec![
+                "?column?".to_string(),
+                "upper".to_string(),
+                "?column?".to_string()
+            ],
+            row_result.columns()
+        );
+        assert_eq!(
+            vec![vec![
+                Some(Value::Integer(3)),
+                Some(Value::Text("A".to_string())),
+                Some(Value::Integer(7))
+            ]],
+            row_result.rows()
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute("", "SELECT 1 WHERE 1 = 2;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert!(row_result.rows().is_empty());
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute("", "EXPLAIN SELECT 1;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            vec![
+                vec![Some(Value::Text("Result".to_string()))],
+                vec![Some(Value::Text("  Output: 1".to_string()))],
+            ],
+            row_result.rows()
+        );
+
+        assert_eq!(
+            Err(Error::MissingFromClauseEntry("t".to_string())),
+            manager.execute("db", "SELECT t.x;")
+        );
+        assert_eq!(
+            Err(Error::ColumnDoesNotExist("x".to_string())),
+            manager.execute("db", "SELECT x;")
+        );
+    }
+
+    #[test]
+    fn test_selecting_table_qualified_columns() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE users (id integer PRIMARY KEY, age integer);",
+            )
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO users VALUES (1, 30), (2, 40);")
+            .unwrap();
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute(
+            "db",
+            "SELECT users.age, users.id + 1 FROM db.users WHERE users.age > 35;",
+        ) else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &vec!["age".to_string(), "?column?".to_string()],
+            row_result.columns()
+        );
+        assert_eq!(
+            vec![vec![Some(Value::Integer(40)), Some(Value::Integer(3))]],
+            row_result.rows()
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "EXPLAIN SELECT age FROM users WHERE users.id = 2;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            Some(Value::Text("Primary Key Lookup on users".to_string())),
+            row_result.rows()[0][0]
+        );
+
+        assert_eq!(
+            Err(Error::MissingFromClauseEntry("orders".to_string())),
+            manager.execute("db", "SELECT orders.age FROM users;")
+        );
+        assert_eq!(
+            Err(Error::ColumnDoesNotExist("name".to_string())),
+            manager.execute("db", "SELECT users.name FROM users;")
+        );
+    }
+
+    #[test]
+    fn test_showing_databases_and_tables() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager
+            .execute("", "CREATE DATABASE db; CREATE DATABASE other;")
+            .unwrap();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE a (x integer); CREATE TABLE b (x integer);",
+            )
+            .unwrap();
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute("", "SHOW DATABASES;") else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(&vec!["Database name".to_string()], row_result.columns());
+        assert_eq!(
+            vec![
+                vec![Some(Value::Text("db".to_string()))],
+                vec![Some(Value::Text("other".to_string()))],
+            ],
+            row_result.rows()
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute("db", "SHOW TABLES;") else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(&vec!["Table name".to_string()], row_result.columns());
+        assert_eq!(
+            vec![
+                vec![Some(Value::Text("a".to_string()))],
+                vec![Some(Value::Text("b".to_string()))],
+            ],
+            row_result.rows()
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SHOW TABLES FROM other;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert!(row_result.rows().is_empty());
+
+        assert_eq!(
+            Err(Error::NoActiveDatabase),
+            manager.execute("", "SHOW TABLES;")
+        );
+        assert_eq!(
+            Err(Error::DatabaseDoesNotExist("missing".to_string())),
+            manager.execute("db", "SHOW TABLES FROM missing;")
+        );
+    }
+
+    #[test]
+    fn test_copying_a_csv_file_into_a_table() {
+        let filename =
+            std::env::temp_dir().join(format!("bsql_manager_copy_{}.csv", std::process::id()));
+        std::fs::write(&filename, "id,age,score\n1,30,\n2,\"40\",7\n").unwrap();
+        let filename = filename.to_str().unwrap();
+
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE t (id integer, age integer, score integer);",
+            )
+            .unwrap();
+
+        // COPY can't read any files until it's given a directory to read them from.
+        assert_eq!(
+            Err(Error::CopyFileNotAllowed(filename.to_string())),
+            manager.execute("db", &format!("COPY t FROM '{}';", filename))
+        );
+        manager.set_copy_directory(Some(std::env::temp_dir()));
+
+        assert_eq!(
+            Ok(QueryResult::CommandSuccessMessage("COPY 2".to_string())),
+            manager.execute(
+                "db",
+                &format!("COPY t FROM '{}' (FORMAT csv, HEADER);", filename)
+            )
+        );
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SELECT id, age, score FROM t;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            vec![
+                vec![Some(Value::Integer(1)), Some(Value::Integer(30)), None],
+                vec![
+                    Some(Value::Integer(2)),
+                    Some(Value::Integer(40)),
+                    Some(Value::Integer(7))
+                ],
+            ],
+            row_result.rows()
+        );
+
+        // Without `HEADER` the header is a record, and its fields aren't integers.
+        assert_eq!(
+            Err(Error::InvalidCast {
+                value: "id".to_string(),
+                data_type: CastType::Integer
+            }),
+            manager.execute("db", &format!("COPY t FROM '{}';", filename))
+        );
+        assert_eq!(
+            Err(Error::BadCopyFileFormat(
+                "expected 2 fields on line 1, found 3".to_string()
+            )),
+            manager.execute("db", &format!("COPY t (id, age) FROM '{}';", filename))
+        );
+
+        // The records before a malformed one aren't inserted either.
+        std::fs::write(filename, "3,50,1\n4,60,2\n5,70\n").unwrap();
+        assert_eq!(
+            Err(Error::BadCopyFileFormat(
+                "expected 3 fields on line 3, found 2".to_string()
+            )),
+            manager.execute("db", &format!("COPY t FROM '{}';", filename))
+        );
+        assert_eq!(
+            Ok(vec![(1,), (2,)]),
+            manager.query_as::<(i64,)>("db", "SELECT id FROM t;")
+        );
+        std::fs::remove_file(filename).unwrap();
+
+        assert!(matches!(
+            manager.execute("db", &format!("COPY t FROM '{}';", filename)),
+            Err(Error::CouldNotReadFile(_))
+        ));
+    }
+
+    #[test]
+    fn test_copying_only_reads_the_copy_directory() {
+        let directory =
+            std::env::temp_dir().join(format!("bsql_manager_copy_dir_{}", std::process::id()));
+        std::fs::create_dir_all(&directory).unwrap();
+        std::fs::write(directory.join("inside.csv"), "1\n").unwrap();
+        let outside = directory.with_extension("csv");
+        std::fs::write(&outside, "2\n").unwrap();
+
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.set_copy_directory(Some(directory.clone()));
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (id integer);")
+            .unwrap();
+
+        // Relative paths are resolved against the copy directory.
+        assert_eq!(
+            Ok(QueryResult::CommandSuccessMessage("COPY 1".to_string())),
+            manager.execute("db", "COPY t FROM 'inside.csv';")
+        );
+
+        let escaping_filename = format!("../{}", outside.file_name().unwrap().to_str().unwrap());
+        assert_eq!(
+            Err(Error::CopyFileNotAllowed(escaping_filename.clone())),
+            manager.execute("db", &format!("COPY t FROM '{}';", escaping_filename))
+        );
+        let outside_filename = outside.to_str().unwrap();
+        assert_eq!(
+            Err(Error::CopyFileNotAllowed(outside_filename.to_string())),
+            manager.execute("db", &format!("COPY t FROM '{}';", outside_filename))
+        );
+        assert_eq!(
+            Ok(vec![(1,)]),
+            manager.query_as::<(i64,)>("db", "SELECT id FROM t;")
+        );
+
+        std::fs::remove_dir_all(&directory).unwrap();
+        std::fs::remove_file(&outside).unwrap();
+    }
+
+    #[test]
+    fn test_logging_queries() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+
+        let entries = Rc::new(RwLock::new(vec![]));
+        let log = entries.clone();
+        manager.set_query_log(Some(Box::new(move |entry: &QueryLogEntry| {
+            log.write().unwrap().push((
+                entry.query.to_string(),
+                entry.rows,
+                entry.error.map(|error| error.code()),
+            ))
+        })));
+
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (x integer);")
+            .unwrap();
+        manager.execute("db", "INSERT INTO t VALUES (1);").unwrap();
+        manager.execute("db", "SELECT * FROM t;").unwrap();
+        manager.execute("db", "SELECT * FROM missing;").unwrap_err();
+
+        assert_eq!(
+            vec![
+                ("CREATE DATABASE db;".to_string(), 0, None),
+                ("CREATE TABLE t (x integer);".to_string(), 0, None),
+                ("INSERT INTO t VALUES (1);".to_string(), 1, None),
+                ("SELECT * FROM t;".to_string(), 1, None),
+                ("SELECT * FROM missing;".to_string(), 0, Some("42P01")),
+            ],
+            *entries.read().unwrap()
+        );
+
+        // None of the queries are slow enough to be logged.
+        manager.set_slow_query_threshold(Some(Duration::from_secs(60)));
+        manager.execute("db", "SELECT * FROM t;").unwrap();
+        assert_eq!(5, entries.read().unwrap().len());
+    }
+
+    #[test]
+    fn test_catalog_changes() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+
+        let changes = Rc::new(RwLock::new(vec![]));
+        let listener = changes.clone();
+        manager.subscribe_to_catalog_changes(Box::new(move |change: &CatalogChange| {
+            listener.write().unwrap().push(change.clone())
+        }));
+
+        assert_eq!(0, manager.schema_version());
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (x integer);")
+            .unwrap();
+        manager.execute("db", "INSERT INTO t VALUES (1);").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (x integer);")
+            .unwrap_err();
+        assert_eq!(2, manager.schema_version());
+
+        assert_eq!(
+            vec![
+                CatalogChange {
+                    schema_version: 1,
+                    kind: CatalogChangeKind::DatabaseCreated {
+                        database_name: "db".to_string()
+                    }
+                },
+                CatalogChange {
+                    schema_version: 2,
+                    kind: CatalogChangeKind::TableCreated {
+                        database_name: "db".to_string(),
+                        table_name: "t".to_string()
+                    }
+                },
+            ],
+            *changes.read().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_rolling_back_ddl_changes_the_schema_version() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (x integer);")
+            .unwrap();
+
+        let changes = Rc::new(RwLock::new(vec![]));
+        let listener = changes.clone();
+        manager.subscribe_to_catalog_changes(Box::new(move |change: &CatalogChange| {
+            listener.write().unwrap().push(change.clone())
+        }));
+
+        // A statement prepared for this version is stale once the rollback undid the table.
+        let prepared_schema_version = manager.schema_version();
+        manager.execute("db", "BEGIN;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE u (x integer);")
+            .unwrap();
+        manager.execute("db", "ROLLBACK;").unwrap();
+        assert_eq!(prepared_schema_version + 2, manager.schema_version());
+        assert_eq!(
+            Some(&CatalogChange {
+                schema_version: prepared_schema_version + 2,
+                kind: CatalogChangeKind::RolledBack,
+            }),
+            changes.read().unwrap().last()
+        );
+        assert_eq!(
+            Err(Error::TableDoesNotExist("u".to_string())),
+            manager.execute("db", "SELECT * FROM u;")
+        );
+
+        // Rollbacks that don't undo DDL statements keep the version.
+        let schema_version = manager.schema_version();
+        manager.execute("db", "BEGIN;").unwrap();
+        manager.execute("db", "INSERT INTO t VALUES (1);").unwrap();
+        manager.execute("db", "SAVEPOINT before_sequence;").unwrap();
+        manager
+            .execute("db", "ROLLBACK TO SAVEPOINT before_sequence;")
+            .unwrap();
+        assert_eq!(schema_version, manager.schema_version());
+
+        manager.execute("db", "CREATE SEQUENCE s;").unwrap();
+        manager
+            .execute("db", "ROLLBACK TO SAVEPOINT before_sequence;")
+            .unwrap();
+        assert_eq!(schema_version + 2, manager.schema_version());
+        manager.execute("db", "ROLLBACK;").unwrap();
+        assert_eq!(schema_version + 2, manager.schema_version());
+        assert_eq!(4, changes.read().unwrap().len());
+    }
+
+    #[test]
+    fn test_qualified_tables_without_an_active_database() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("", "CREATE TABLE db.t (x integer);")
+            .unwrap();
+        manager.execute("", "INSERT INTO db.t VALUES (7);").unwrap();
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute("", "SELECT * FROM db.t;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(vec![vec![Some(Value::Integer(7))]], row_result.rows());
+
+        assert_eq!(
+            Err(Error::NoActiveDatabase),
+            manager.execute("", "SELECT * FROM t;")
+        );
+        assert_eq!(
+            Err(Error::NoActiveDatabase),
+            manager.execute("", "SELECT * FROM db.t WHERE x = (SELECT MAX(x) FROM t);")
+        );
+    }
+
+    #[test]
+    fn test_seeding_tables() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE a (x integer, y integer);")
+            .unwrap();
+        manager
+            .execute("db", "CREATE TABLE b (x integer, y integer);")
+            .unwrap();
+
+        assert_eq!(
+            Ok(QueryResult::InsertSuccess { count: 300 }),
+            manager.seed("db", "a", 300, 7)
+        );
+        manager.seed("db", "b", 300, 7).unwrap();
+
+        let (Ok(QueryResult::RowResult(a)), Ok(QueryResult::RowResult(b))) = (
+            manager.execute("db", "SELECT * FROM a;"),
+            manager.execute("db", "SELECT * FROM b;"),
+        ) else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(300, a.rows().len());
+        assert_eq!(a.rows(), b.rows());
+
+        assert_eq!(
+            Err(Error::TableDoesNotExist("c".to_string())),
+            manager.seed("db", "c", 1, 7)
+        );
+    }
+
+    #[test]
+    fn test_sampling_tables() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE numbers (x integer);")
+            .unwrap();
+        manager.seed("db", "numbers", 2000, 7).unwrap();
+
+        let Ok(QueryResult::RowResult(none)) =
+            manager.execute("db", "SELECT * FROM numbers TABLESAMPLE SYSTEM (0);")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(0, none.rows().len());
+
+        let Ok(QueryResult::RowResult(all)) =
+            manager.execute("db", "SELECT * FROM numbers TABLESAMPLE SYSTEM (100);")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(2000, all.rows().len());
+
+        let query = "SELECT x FROM numbers TABLESAMPLE SYSTEM (50) REPEATABLE (42);";
+        let (Ok(QueryResult::RowResult(first)), Ok(QueryResult::RowResult(second))) =
+            (manager.execute("db", query), manager.execute("db", query))
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(first.rows(), second.rows());
+        assert!(first.rows().len() < 2000);
+    }
+
+    #[test]
+    fn test_inserting_into_some_columns() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE users (id integer, age integer);")
+            .unwrap();
+
+        manager
+            .execute("db", "INSERT INTO users VALUES (1, 20);")
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO users (age) VALUES (30);")
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO users (age, id) VALUES (40, 3);")
+            .unwrap();
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute("db", "SELECT * FROM users;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            vec![
+                vec![Some(Value::Integer(1)), Some(Value::Integer(20))],
+                vec![Some(Value::Integer(3)), Some(Value::Integer(40))],
+                vec![None, Some(Value::Integer(30))],
+            ],
+            row_result.rows()
+        );
+
+        assert_eq!(
+            Err(Error::ColumnDoesNotExist("name".to_string())),
+            manager.execute("db", "INSERT INTO users (name) VALUES (1);")
+        );
+        assert_eq!(
+            Err(Error::ColumnAlreadyExist("age".to_string())),
+            manager.execute("db", "INSERT INTO users (age, age) VALUES (1, 2);")
+        );
+        assert_eq!(
+            Err(Error::InsertFailed),
+            manager.execute("db", "INSERT INTO users (age) VALUES (1, 2);")
+        );
+    }
+
+    #[test]
+    fn test_inserting_multiple_rows() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (x integer, y integer);")
+            .unwrap();
+
+        assert_eq!(
+            Ok(QueryResult::InsertSuccess { count: 3 }),
+            manager.execute("db", "INSERT INTO t VALUES (1, 2), (3, 4), (5, 6);")
+        );
+        assert_eq!(
+            Ok(QueryResult::InsertSuccess { count: 2 }),
+            manager.execute("db", "INSERT INTO t (y) VALUES (7), (8);")
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute("db", "SELECT x, y FROM t;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &vec![
+                vec![Some(Value::Integer(1)), Some(Value::Integer(2))],
+                vec![Some(Value::Integer(3)), Some(Value::Integer(4))],
+                vec![Some(Value::Integer(5)), Some(Value::Integer(6))],
+                vec![None, Some(Value::Integer(7))],
+                vec![None, Some(Value::Integer(8))],
+            ],
+            row_result.rows()
+        );
+    }
+
+    #[test]
+    fn test_failed_multi_row_inserts_insert_nothing() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (id integer NOT NULL, y integer);")
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO t VALUES (1, 1);")
+            .unwrap();
+
+        assert_eq!(
+            Err(Error::NotNullViolation("id".to_string())),
+            manager.execute("db", "INSERT INTO t VALUES (2, 2), (3, 3), (NULL, 4);")
+        );
+        assert_eq!(
+            Ok(vec![(1, 1)]),
+            manager.query_as::<(i64, i64)>("db", "SELECT id, y FROM t;")
+        );
+    }
+
+    #[test]
+    fn test_inserting_with_returning() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (x integer, y integer);")
+            .unwrap();
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "INSERT INTO t VALUES (1, 2), (3, 4) RETURNING *;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &vec!["x".to_string(), "y".to_string()],
+            row_result.columns()
+        );
+        assert_eq!(
+            &vec![
+                vec![Some(Value::Integer(1)), Some(Value::Integer(2))],
+                vec![Some(Value::Integer(3)), Some(Value::Integer(4))],
+            ],
+            row_result.rows()
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "INSERT INTO t (y) VALUES (5) RETURNING y + 1, x;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &vec![vec![Some(Value::Integer(6)), None]],
+            row_result.rows()
+        );
+
+        assert_eq!(
+            Ok(QueryResult::InsertSuccess { count: 1 }),
+            manager.execute("db", "INSERT INTO t VALUES (6, 7);")
+        );
+    }
+
+    #[test]
+    fn test_inserting_with_on_conflict() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE counters (id integer, hits integer);")
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO counters VALUES (1, 1), (2, 1);")
+            .unwrap();
+
+        assert_eq!(
+            Ok(QueryResult::InsertSuccess { count: 1 }),
+            manager.execute(
+                "db",
+                "INSERT INTO counters VALUES (1, 5), (3, 1) ON CONFLICT (id) DO NOTHING;"
+            )
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute(
+            "db",
+            "INSERT INTO counters VALUES (2, 4) ON CONFLICT (id) DO UPDATE SET hits = hits + excluded.hits RETURNING *;",
+        ) else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &vec![vec![Some(Value::Integer(2)), Some(Value::Integer(5))]],
+            row_result.rows()
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute(
+            "db",
+            "SELECT id, hits FROM counters WHERE id = 2 OR id = 3;",
+        ) else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(2, row_result.rows().len());
+        assert!(row_result
+            .rows()
+            .contains(&vec![Some(Value::Integer(2)), Some(Value::Integer(5))]));
+        assert!(row_result
+            .rows()
+            .contains(&vec![Some(Value::Integer(3)), Some(Value::Integer(1))]));
+
+        assert_eq!(
+            Err(Error::ColumnDoesNotExist("missing".to_string())),
+            manager.execute(
+                "db",
+                "INSERT INTO counters VALUES (1, 1) ON CONFLICT (missing) DO NOTHING;"
+            )
+        );
+
+        // Like in Postgres, a statement can't update the same row twice, and then none of its
+        // rows are inserted or updated.
+        assert_eq!(
+            Err(Error::RowAffectedTwice),
+            manager.execute(
+                "db",
+                "INSERT INTO counters VALUES (4, 1), (1, 1), (1, 1) ON CONFLICT (id) DO UPDATE SET hits = hits + 1;"
+            )
+        );
+        assert_eq!(
+            Err(Error::RowAffectedTwice),
+            manager.execute(
+                "db",
+                "INSERT INTO counters VALUES (5, 1), (5, 1) ON CONFLICT (id) DO UPDATE SET hits = hits + 1;"
+            )
+        );
+        let mut counters = manager
+            .query_as::<(i64, i64)>("db", "SELECT id, hits FROM counters;")
+            .unwrap();
+        counters.sort();
+        assert_eq!(vec![(1, 1), (2, 5), (3, 1)], counters);
+    }
+
+    #[test]
+    fn test_not_null_columns() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (id integer NOT NULL, age integer);")
+            .unwrap();
+
+        assert_eq!(
+            Ok(QueryResult::InsertSuccess { count: 1 }),
+            manager.execute("db", "INSERT INTO t (id) VALUES (1);")
+        );
+        assert_eq!(
+            Err(Error::NotNullViolation("id".to_string())),
+            manager.execute("db", "INSERT INTO t (age) VALUES (30);")
+        );
+        assert_eq!(
+            Err(Error::NotNullViolation("id".to_string())),
+            manager.execute(
+                "db",
+                "INSERT INTO t VALUES (1, 2) ON CONFLICT (id) DO UPDATE SET id = NULL;"
+            )
+        );
+
+        let Ok(column_definitions) = manager.table_definition("db", "t") else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            vec![true, false],
+            column_definitions
+                .iter()
+                .map(|column_definition| column_definition.is_not_null())
+                .collect::<Vec<bool>>()
+        );
+    }
+
+    #[test]
+    fn test_primary_key_columns() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE t (id integer PRIMARY KEY, age integer);",
+            )
+            .unwrap();
+
+        assert_eq!(
+            Ok(QueryResult::InsertSuccess { count: 2 }),
+            manager.execute("db", "INSERT INTO t VALUES (1, 20), (2, 30);")
+        );
+        assert_eq!(
+            Err(Error::UniqueViolation("id".to_string())),
+            manager.execute("db", "INSERT INTO t VALUES (1, 40);")
+        );
+        assert_eq!(
+            Err(Error::UniqueViolation("id".to_string())),
+            manager.execute("db", "INSERT INTO t VALUES (3, 40), (3, 50);")
+        );
+        assert_eq!(
+            Err(Error::NotNullViolation("id".to_string())),
+            manager.execute("db", "INSERT INTO t (age) VALUES (40);")
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SELECT age FROM t WHERE id = 2;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(&[vec![Some(Value::Integer(30))]], row_result.rows());
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SELECT age FROM t WHERE 3 = id;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert!(row_result.rows().is_empty());
+
+        // The row that is found with the index still has to match the rest of the condition.
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SELECT age FROM t WHERE id = 2 AND age > 25;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(&[vec![Some(Value::Integer(30))]], row_result.rows());
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SELECT age FROM t WHERE age > 35 AND id = 2;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert!(row_result.rows().is_empty());
+
+        assert_eq!(
+            Ok(QueryResult::InsertSuccess { count: 1 }),
+            manager.execute(
+                "db",
+                "INSERT INTO t VALUES (1, 50) ON CONFLICT (id) DO UPDATE SET age = excluded.age;"
+            )
+        );
+        assert_eq!(
+            Err(Error::UniqueViolation("id".to_string())),
+            manager.execute(
+                "db",
+                "INSERT INTO t VALUES (1, 60) ON CONFLICT (id) DO UPDATE SET id = 2;"
+            )
+        );
+        assert_eq!(
+            Ok(QueryResult::InsertSuccess { count: 1 }),
+            manager.execute(
+                "db",
+                "INSERT INTO t VALUES (1, 60) ON CONFLICT (id) DO UPDATE SET id = 3;"
+            )
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SELECT * FROM t WHERE id = 3;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[vec![Some(Value::Integer(3)), Some(Value::Integer(50))]],
+            row_result.rows()
+        );
+        assert_eq!(
+            Ok(QueryResult::InsertSuccess { count: 1 }),
+            manager.execute("db", "INSERT INTO t VALUES (1, 70);")
+        );
+
+        assert_eq!(
+            Err(Error::MultiplePrimaryKeys("u".to_string())),
+            manager.execute(
+                "db",
+                "CREATE TABLE u (id integer PRIMARY KEY, other integer PRIMARY KEY);"
+            )
+        );
+    }
+
+    #[test]
+    fn test_text_columns() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager.clone());
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (id integer PRIMARY KEY, name text);")
+            .unwrap();
+        manager
+            .execute(
+                "db",
+                "INSERT INTO t VALUES (1, 'Ada'), (2, 'Grace'), (3, '');",
+            )
+            .unwrap();
+        manager
+            .execute(
+                "db",
+                "INSERT INTO t VALUES (1, '') ON CONFLICT (id) DO UPDATE SET name = 'Ada Lovelace';",
+            )
+            .unwrap();
+
+        let mut manager = Manager::new(page_manager);
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SELECT name, LENGTH(name) FROM t WHERE name > 'B';")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[vec![
+                Some(Value::Text("Grace".to_string())),
+                Some(Value::Integer(5))
+            ]],
+            row_result.rows()
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SELECT name FROM t WHERE id = 1;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[vec![Some(Value::Text("Ada Lovelace".to_string()))]],
+            row_result.rows()
+        );
+
+        assert_eq!(
+            Err(Error::TypeMismatch),
+            manager.execute("db", "CREATE TABLE u (name text PRIMARY KEY);")
+        );
+        assert!(manager.table_definition("db", "u").is_err());
+        assert_eq!(
+            Err(Error::RowTooBig {
+                size: 4011,
+                limit: 3076
+            }),
+            manager.execute(
+                "db",
+                &format!("INSERT INTO t VALUES (4, '{}');", "x".repeat(4000))
+            )
+        );
+    }
+
+    #[test]
+    fn test_numeric_columns() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager.clone());
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE prices (id integer PRIMARY KEY, price numeric(5, 2));",
+            )
+            .unwrap();
+        manager
+            .execute(
+                "db",
+                "INSERT INTO prices VALUES (1, 19.999), (2, 5), (3, 0.125);",
+            )
+            .unwrap();
+
+        let mut manager = Manager::new(page_manager);
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute(
+            "db",
+            "SELECT price, price * 3, price / 3, -price FROM prices WHERE id = 1;",
+        ) else {
+            panic!("Did not get the expected result");
+        };
+        let values: Vec<String> = row_result.rows()[0]
+            .iter()
+            .map(|value| value.as_ref().unwrap().to_string())
+            .collect();
+        assert_eq!(vec!["20.00", "60.00", "6.666667", "-20.00"], values);
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute(
+            "db",
+            "SELECT id FROM prices WHERE price = 5 OR price < 0.2;",
+        ) else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[vec![Some(Value::Integer(2))], vec![Some(Value::Integer(3))]],
+            row_result.rows()
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SELECT MAX(price) FROM prices;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[vec![Some(Value::Decimal(Decimal::new(2000, 2)))]],
+            row_result.rows()
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SELECT '1.5'::numeric + 1;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[vec![Some(Value::Decimal(Decimal::new(25, 1)))]],
+            row_result.rows()
+        );
+
+        assert_eq!(
+            Err(Error::NumericValueOutOfRange),
+            manager.execute("db", "INSERT INTO prices VALUES (4, 999.995);")
+        );
+    }
+
+    #[test]
+    fn test_bytea_columns() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager.clone());
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE files (id integer PRIMARY KEY, hash bytea);",
+            )
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO files VALUES (1, X'DEADBEEF'), (2, x'');")
+            .unwrap();
+
+        let mut manager = Manager::new(page_manager);
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute(
+            "db",
+            "SELECT hash, LENGTH(hash) FROM files WHERE hash = x'deadbeef';",
+        ) else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[vec![
+                Some(Value::Bytes(vec![0xDE, 0xAD, 0xBE, 0xEF])),
+                Some(Value::Integer(4))
+            ]],
+            row_result.rows()
+        );
+        assert_eq!(
+            "\\xdeadbeef",
+            row_result.rows()[0][0].as_ref().unwrap().to_string()
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SELECT hash FROM files WHERE id = 2;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(&[vec![Some(Value::Bytes(vec![]))]], row_result.rows());
+
+        assert_eq!(
+            Err(Error::UnexpectedToken {
+                actual: Token::HexStringLiteral("ABC".to_string())
+            }),
+            manager.execute("db", "INSERT INTO files VALUES (3, X'ABC');")
+        );
+    }
+
+    #[test]
+    fn test_uuid_columns() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager.clone());
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE sessions (id integer PRIMARY KEY, token uuid);",
+            )
+            .unwrap();
+        manager
+            .execute(
+                "db",
+                "INSERT INTO sessions VALUES (1, 'a0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11'), \
+                 (2, '{B0EEBC999C0B4EF8BB6D6BB9BD380A11}');",
+            )
+            .unwrap();
+
+        let mut manager = Manager::new(page_manager);
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute(
+            "db",
+            "SELECT id, token::text FROM sessions \
+             WHERE token = 'B0EEBC99-9C0B-4EF8-BB6D-6BB9BD380A11';",
+        ) else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[vec![
+                Some(Value::Integer(2)),
+                Some(Value::Text(
+                    "b0eebc99-9c0b-4ef8-bb6d-6bb9bd380a11".to_string()
+                ))
+            ]],
+            row_result.rows()
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SELECT gen_random_uuid(), gen_random_uuid();")
+        else {
+            panic!("Did not get the expected result");
+        };
+        let [Some(Value::Uuid(first)), Some(Value::Uuid(second))] = &row_result.rows()[0][..]
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_ne!(first, second);
+
+        assert_eq!(
+            Err(Error::InvalidCast {
+                value: "not-a-uuid".to_string(),
+                data_type: CastType::Uuid
+            }),
+            manager.execute("db", "INSERT INTO sessions VALUES (3, 'not-a-uuid');")
+        );
+        assert_eq!(
+            Err(Error::InvalidFunctionArguments(
+                "gen_random_uuid".to_string()
+            )),
+            manager.execute("db", "SELECT gen_random_uuid(1);")
+        );
+    }
+
+    #[test]
+    fn test_null_values() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager.clone());
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE people (id integer NOT NULL, name text, age integer);",
+            )
+            .unwrap();
+        assert_eq!(
+            Ok(QueryResult::InsertSuccess { count: 2 }),
+            manager.execute(
+                "db",
+                "INSERT INTO people VALUES (1, NULL, 36), (2, 'Grace', NULL);"
+            )
+        );
+        assert_eq!(
+            Err(Error::NotNullViolation("id".to_string())),
+            manager.execute("db", "INSERT INTO people VALUES (NULL, 'Ada', 36);")
+        );
+
+        let mut manager = Manager::new(page_manager);
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SELECT id, name, age FROM people WHERE id = 1;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[vec![
+                Some(Value::Integer(1)),
+                None,
+                Some(Value::Integer(36))
+            ]],
+            row_result.rows()
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SELECT id, name, age FROM people WHERE id = 2;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[vec![
+                Some(Value::Integer(2)),
+                Some(Value::Text("Grace".to_string())),
+                None
+            ]],
+            row_result.rows()
+        );
+    }
+
+    #[test]
+    fn test_json_columns() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager.clone());
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE events (id integer PRIMARY KEY, payload json);",
+            )
+            .unwrap();
+        manager
+            .execute(
+                "db",
+                "INSERT INTO events VALUES \
+                 (1, '{\"kind\": \"click\", \"tags\": [\"a\", \"b\"]}'), \
+                 (2, '{\"kind\":\"view\",\"tags\":[]}'), \
+                 (3, '[1, 2]');",
+            )
+            .unwrap();
+
+        let mut manager = Manager::new(page_manager);
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute(
+            "db",
+            "SELECT id, payload -> 'tags' -> 0, payload FROM events \
+             WHERE payload -> 'kind' = '\"click\"';",
+        ) else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[vec![
+                Some(Value::Integer(1)),
+                Some(Value::Json("\"a\"".to_string())),
+                Some(Value::Json(
+                    "{\"kind\": \"click\", \"tags\": [\"a\", \"b\"]}".to_string()
+                ))
+            ]],
+            row_result.rows()
+        );
+
+        // Members that don't exist are `NULL`, and JSON is compared without its whitespace.
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute(
+            "db",
+            "SELECT id, json_extract(payload, 'kind')::text FROM events \
+             WHERE payload -> 'tags' = '[ ]' OR payload = '[1,2]';",
+        ) else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[
+                vec![
+                    Some(Value::Integer(2)),
+                    Some(Value::Text("\"view\"".to_string()))
+                ],
+                vec![Some(Value::Integer(3)), None]
+            ],
+            row_result.rows()
+        );
+
+        assert_eq!(
+            Err(Error::InvalidCast {
+                value: "{\"kind\": }".to_string(),
+                data_type: CastType::Json
+            }),
+            manager.execute("db", "INSERT INTO events VALUES (4, '{\"kind\": }');")
+        );
+    }
+
+    #[test]
+    fn test_serial_columns() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager.clone());
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (id serial, age integer);")
+            .unwrap();
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "INSERT INTO t (age) VALUES (30), (40) RETURNING id;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[vec![Some(Value::Integer(1))], vec![Some(Value::Integer(2))]],
+            row_result.rows()
+        );
+
+        // Explicit values are kept and don't move the counter.
+        manager
+            .execute("db", "INSERT INTO t VALUES (10, 50);")
+            .unwrap();
+
+        // The counter is stored in the table, so it continues after reloading.
+        let mut manager = Manager::new(page_manager);
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "INSERT INTO t (age) VALUES (60) RETURNING id, age;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[vec![Some(Value::Integer(3)), Some(Value::Integer(60))]],
+            row_result.rows()
+        );
+
+        let Ok(column_definitions) = manager.table_definition("db", "t") else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            vec![(true, true), (false, false)],
+            column_definitions
+                .iter()
+                .map(|column_definition| (
+                    column_definition.is_serial(),
+                    column_definition.is_not_null()
+                ))
+                .collect::<Vec<(bool, bool)>>()
+        );
+    }
+
+    #[test]
+    fn test_fill_factor() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE t (id integer PRIMARY KEY, hits integer) WITH (fillfactor = 50);",
+            )
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO t VALUES (1, 1), (2, 1);")
+            .unwrap();
+        manager
+            .execute(
+                "db",
+                "INSERT INTO t VALUES (1, 1) ON CONFLICT (id) DO UPDATE SET hits = hits + 1;",
+            )
+            .unwrap();
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute("db", "SELECT * FROM t;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[
+                vec![Some(Value::Integer(1)), Some(Value::Integer(2))],
+                vec![Some(Value::Integer(2)), Some(Value::Integer(1))]
+            ],
+            row_result.rows()
+        );
+    }
+
+    #[test]
+    fn test_compacting_databases() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager.clone());
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE t (id integer PRIMARY KEY, age integer);",
+            )
+            .unwrap();
+        manager
+            .execute("db", "CREATE TABLE u (id serial, age integer);")
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO t VALUES (1, 30), (2, 40);")
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO u (age) VALUES (40);")
+            .unwrap();
+
+        // Updating the row moves it to a page for both columns, which leaves its old page empty.
+        manager
+            .execute("db", "INSERT INTO t (id) VALUES (3);")
+            .unwrap();
+        manager
+            .execute(
+                "db",
+                "INSERT INTO t VALUES (3, 50) ON CONFLICT (id) DO UPDATE SET age = 50;",
+            )
+            .unwrap();
+
+        let compacted_page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut compacted = manager
+            .compact_into(compacted_page_manager.clone())
+            .unwrap();
+        compacted.debug_check_invariants();
+        assert!(
+            compacted_page_manager.read().unwrap().page_count()
+                < page_manager.read().unwrap().page_count()
+        );
+
+        for query in ["SELECT * FROM t;", "SELECT * FROM u;"] {
+            assert_eq!(manager.execute("db", query), compacted.execute("db", query));
+        }
+        assert_eq!(
+            manager.execute("db", "SELECT * FROM t WHERE id = 3;"),
+            compacted.execute("db", "SELECT * FROM t WHERE id = 3;")
+        );
+        let Ok(QueryResult::RowResult(row_result)) =
+            compacted.execute("db", "INSERT INTO u (age) VALUES (41) RETURNING id;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(&[vec![Some(Value::Integer(2))]], row_result.rows());
+    }
+
+    #[test]
+    fn test_vacuuming_beyond_the_page_quota() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager.clone());
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE sparse (id integer PRIMARY KEY, age integer);",
+            )
+            .unwrap();
+        manager
+            .execute("db", "CREATE TABLE packed (id integer);")
+            .unwrap();
+        // Like in `test_vacuuming_tables`, the update leaves the first page of `sparse` empty.
+        manager
+            .execute("db", "INSERT INTO sparse (id) VALUES (1);")
+            .unwrap();
+        manager
+            .execute(
+                "db",
+                "INSERT INTO sparse VALUES (1, 30) ON CONFLICT (id) DO UPDATE SET age = 30;",
+            )
+            .unwrap();
+        manager.seed("db", "packed", 100, 1).unwrap();
+
+        // The records of `packed` need more pages than the quota leaves once they are moved to
+        // pages that are filled to the lowered fill factor.
+        manager
+            .database("db")
+            .unwrap()
+            .set_fill_factor("packed", 10)
+            .unwrap();
+        let page_count = manager.database("db").unwrap().page_count().unwrap();
+        manager.set_page_quota("db", Some(page_count)).unwrap();
+        let table_sizes = manager.table_sizes("db");
+
+        assert_eq!(
+            Err(Error::PageQuotaExceeded("db".to_string())),
+            manager.vacuum("db", None)
+        );
+        // Vacuuming `sparse` is undone too.
+        assert_eq!(table_sizes, manager.table_sizes("db"));
+        assert_eq!(0, page_manager.read().unwrap().free_page_count());
+        manager.debug_check_invariants();
+
+        assert_eq!(Ok(1), manager.vacuum("db", Some("sparse")));
+    }
+
+    #[test]
+    fn test_vacuuming_tables() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager.clone());
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE t (id integer PRIMARY KEY, age integer);",
+            )
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO t (id) VALUES (1);")
+            .unwrap();
+
+        // Updating the row moves it to a page for both columns, which leaves its old page empty.
+        manager
+            .execute(
+                "db",
+                "INSERT INTO t VALUES (1, 30) ON CONFLICT (id) DO UPDATE SET age = 30;",
+            )
+            .unwrap();
+        let expected = manager.execute("db", "SELECT * FROM t WHERE id = 1;");
+
+        assert_eq!(
+            Ok(QueryResult::CommandSuccessMessage("VACUUM".to_string())),
+            manager.execute("db", "VACUUM t;")
+        );
+        assert_eq!(1, page_manager.read().unwrap().free_page_count());
+        manager.debug_check_invariants();
+        assert_eq!(
+            expected,
+            manager.execute("db", "SELECT * FROM t WHERE id = 1;")
+        );
+
+        assert_eq!(Ok(0), manager.vacuum("db", None));
+        assert_eq!(
+            Err(Error::TableDoesNotExist("missing".to_string())),
+            manager.vacuum("db", Some("missing"))
+        );
+
+        manager.execute("db", "BEGIN;").unwrap();
+        assert_eq!(
+            Err(Error::ActiveSqlTransaction("VACUUM".to_string())),
+            manager.execute("db", "VACUUM;")
+        );
+    }
+
+    #[test]
+    fn test_sequences() {
+        let filename =
+            std::env::temp_dir().join(format!("bsql_manager_sequences_{}.db", std::process::id()));
+        let filename = filename.to_str().unwrap();
+
+        {
+            let page_manager = Rc::new(RwLock::new(PageManager::new(filename)));
+            let mut manager = Manager::new(page_manager);
+            manager.execute("", "CREATE DATABASE db;").unwrap();
+            assert_eq!(
+                Ok(QueryResult::CommandSuccessMessage(
+                    "CREATE SEQUENCE".to_string()
+                )),
+                manager.execute("db", "CREATE SEQUENCE orders START WITH 10;")
+            );
+            manager.execute("db", "CREATE SEQUENCE invoices;").unwrap();
+            assert_eq!(
+                Err(Error::SequenceAlreadyExists("orders".to_string())),
+                manager.execute("db", "CREATE SEQUENCE orders;")
+            );
+
+            assert_eq!(
+                Ok(QueryResult::RowResult(RowResult::new(
+                    vec!["?column?".to_string(), "?column?".to_string()],
+                    vec![vec![Some(Value::Integer(10)), Some(Value::Integer(1))]],
+                ))),
+                manager.execute("db", "SELECT nextval('orders'), nextval('invoices');")
+            );
+            manager.execute("db", "SELECT nextval('orders');").unwrap();
+
+            assert_eq!(
+                Err(Error::SequenceDoesNotExist("missing".to_string())),
+                manager.execute("db", "SELECT nextval('missing');")
+            );
+            assert_eq!(
+                Err(Error::InvalidFunctionArguments("nextval".to_string())),
+                manager.execute("db", "SELECT nextval(1);")
+            );
+        }
+
+        // The values that were handed out aren't reused after a restart.
+        let page_manager = Rc::new(RwLock::new(PageManager::new(filename)));
+        let mut manager = Manager::open(page_manager).unwrap();
+        assert_eq!(
+            Ok(QueryResult::RowResult(RowResult::new(
+                vec!["?column?".to_string()],
+                vec![vec![Some(Value::Integer(12))]],
+            ))),
+            manager.execute("db", "SELECT nextval('orders');")
+        );
+
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_sequences_survive_rollbacks() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager.execute("db", "CREATE SEQUENCE orders;").unwrap();
+        let nextval = |manager: &mut Manager, query: &str| match manager.execute("db", query) {
+            Ok(QueryResult::RowResult(row_result)) => row_result.rows()[0][0].clone(),
+            result => panic!("Unexpected result {:?}", result),
+        };
+
+        manager.execute("db", "BEGIN;").unwrap();
+        assert_eq!(
+            Some(Value::Integer(1)),
+            nextval(&mut manager, "SELECT nextval('orders');")
+        );
+        manager.execute("db", "ROLLBACK;").unwrap();
+        assert_eq!(
+            Some(Value::Integer(2)),
+            nextval(&mut manager, "SELECT nextval('orders');")
+        );
+
+        manager.execute("db", "BEGIN;").unwrap();
+        manager.execute("db", "SAVEPOINT before;").unwrap();
+        nextval(&mut manager, "SELECT nextval('orders');");
+        manager
+            .execute("db", "ROLLBACK TO SAVEPOINT before;")
+            .unwrap();
+        assert_eq!(
+            Some(Value::Integer(4)),
+            nextval(&mut manager, "SELECT nextval('orders');")
+        );
+        manager.execute("db", "ROLLBACK;").unwrap();
+
+        // Sequences that were created in the rolled back transaction are gone.
+        manager.execute("db", "BEGIN;").unwrap();
+        manager.execute("db", "CREATE SEQUENCE invoices;").unwrap();
+        nextval(&mut manager, "SELECT nextval('invoices');");
+        manager.execute("db", "ROLLBACK;").unwrap();
+        assert_eq!(
+            Err(Error::SequenceDoesNotExist("invoices".to_string())),
+            manager.execute("db", "SELECT nextval('invoices');")
+        );
+        assert_eq!(
+            Some(Value::Integer(5)),
+            nextval(&mut manager, "SELECT nextval('orders');")
+        );
+    }
+
+    #[test]
+    fn test_backups() {
+        let filename =
+            std::env::temp_dir().join(format!("bsql_manager_backup_{}.db", std::process::id()));
+        let filename = filename.to_str().unwrap();
+
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE t (id integer PRIMARY KEY, name text); \
+                 INSERT INTO t VALUES (1, 'one'), (2, 'two');",
+            )
+            .unwrap();
+
+        assert_eq!(Ok(()), manager.backup(filename));
+        assert!(!std::path::Path::new(&format!("{}.partial", filename)).exists());
+
+        // Changes made after the backup aren't in it.
+        manager
+            .execute("db", "INSERT INTO t VALUES (3, 'three');")
+            .unwrap();
+        let backup_page_manager = Rc::new(RwLock::new(PageManager::new(filename)));
+        let mut backup = Manager::open(backup_page_manager).unwrap();
+        backup.debug_check_invariants();
+        assert_eq!(
+            manager.execute("db", "SELECT * FROM t WHERE id < 3;"),
+            backup.execute("db", "SELECT * FROM t;")
+        );
+        assert_eq!(
+            manager.execute("db", "SELECT name FROM t WHERE id = 2;"),
+            backup.execute("db", "SELECT name FROM t WHERE id = 2;")
+        );
+
+        manager.execute("db", "BEGIN;").unwrap();
+        assert_eq!(
+            Err(Error::ActiveSqlTransaction("BACKUP".to_string())),
+            manager.backup(filename)
+        );
+        manager.execute("db", "ROLLBACK;").unwrap();
+
+        assert_eq!(
+            Err(Error::CouldNotWriteFile(
+                "/bsql-directory-that-does-not-exist/backup.db".to_string()
+            )),
+            manager.backup("/bsql-directory-that-does-not-exist/backup.db")
+        );
+
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_backing_up_selected_databases() {
+        let filename = std::env::temp_dir().join(format!(
+            "bsql_manager_selective_backup_{}.db",
+            std::process::id()
+        ));
+        let filename = filename.to_str().unwrap();
+
+        let mut manager = Manager::new(Rc::new(RwLock::new(PageManager::new(":memory:"))));
+        manager
+            .execute(
+                "",
+                "CREATE DATABASE a; CREATE DATABASE b; CREATE DATABASE c;",
+            )
+            .unwrap();
+        for database_name in ["a", "b", "c"] {
+            manager
+                .execute(
+                    database_name,
+                    "CREATE TABLE t (id integer); INSERT INTO t VALUES (1);",
+                )
+                .unwrap();
+        }
+
+        assert_eq!(
+            Err(Error::DatabaseDoesNotExist("d".to_string())),
+            manager.backup_databases(filename, &["a", "d"])
+        );
+        assert!(!std::path::Path::new(filename).exists());
+
+        assert_eq!(Ok(()), manager.backup_databases(filename, &["c", "a"]));
+        let mut restored = Manager::new(Rc::new(RwLock::new(PageManager::new(":memory:"))));
+        assert_eq!(
+            Ok(vec!["c".to_string(), "a".to_string()]),
+            restored.restore(filename)
+        );
+        restored.debug_check_invariants();
+        assert_eq!(
+            manager.execute("a", "SELECT * FROM t;"),
+            restored.execute("a", "SELECT * FROM t;")
+        );
+        assert!(!restored.database_exists("b"));
+
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_restoring_backups() {
+        let filename =
+            std::env::temp_dir().join(format!("bsql_manager_restore_{}.db", std::process::id()));
+        let filename = filename.to_str().unwrap();
+
+        let mut manager = Manager::new(Rc::new(RwLock::new(PageManager::new(":memory:"))));
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE t (id integer PRIMARY KEY, age integer); \
+                 INSERT INTO t VALUES (1, 30), (2, 40);",
+            )
+            .unwrap();
+        manager.backup(filename).unwrap();
+
+        let mut restored = Manager::new(Rc::new(RwLock::new(PageManager::new(":memory:"))));
+        restored.execute("", "CREATE DATABASE other;").unwrap();
+        assert_eq!(Ok(vec!["db".to_string()]), restored.restore(filename));
+        restored.debug_check_invariants();
+        assert_eq!(
+            vec!["other".to_string(), "db".to_string()],
+            restored.database_names().unwrap()
+        );
+        for query in ["SELECT * FROM t;", "SELECT age FROM t WHERE id = 2;"] {
+            assert_eq!(manager.execute("db", query), restored.execute("db", query));
+        }
+        assert_eq!(
+            Err(Error::DatabaseAlreadyExists("db".to_string())),
+            restored.restore(filename)
+        );
+
+        // A backup whose pages changed since it was written isn't restored.
+        let mut bytes = std::fs::read(filename).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        std::fs::write(filename, &bytes).unwrap();
+        let mut empty = Manager::new(Rc::new(RwLock::new(PageManager::new(":memory:"))));
+        assert_eq!(
+            Err(Error::CorruptBackup(filename.to_string())),
+            empty.restore(filename)
+        );
+
+        // The data of page 0, which starts with the header, follows its 4096 bytes of metadata.
+        bytes[4096 + 8..4096 + 12].copy_from_slice(&(FORMAT_VERSION + 1).to_be_bytes());
+        std::fs::write(filename, &bytes).unwrap();
+        assert_eq!(
+            Err(Error::UnsupportedFormatVersion(FORMAT_VERSION + 1)),
+            empty.restore(filename)
+        );
+        assert!(empty.database_names().unwrap().is_empty());
+
+        std::fs::remove_file(filename).unwrap();
+        assert_eq!(
+            Err(Error::CouldNotReadFile(filename.to_string())),
+            empty.restore(filename)
+        );
+    }
+
+    #[test]
+    fn test_looking_up_databases_by_name() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager.clone());
+        manager.execute("", "CREATE DATABASE a;").unwrap();
+        manager.execute("", "CREATE DATABASE b;").unwrap();
+        manager
+            .execute("b", "CREATE TABLE t (id integer);")
+            .unwrap();
+
+        // The catalog is loaded when the pages are opened again.
+        let mut reopened = Manager::new(page_manager);
+        let mut database_names: Vec<String> = reopened.catalog.borrow().keys().cloned().collect();
+        database_names.sort();
+        assert_eq!(vec!["a".to_string(), "b".to_string()], database_names);
+        assert_eq!(
+            Ok(vec!["t".to_string()]),
+            reopened.database_table_names("b")
+        );
+        assert_eq!(
+            Err(Error::DatabaseDoesNotExist("c".to_string())),
+            reopened.database("c").map(|database| database.name())
+        );
+
+        // The databases and tables that were created in a rolled back transaction can't be found
+        // anymore.
+        reopened.execute("b", "BEGIN;").unwrap();
+        reopened.execute("", "CREATE DATABASE c;").unwrap();
+        reopened
+            .execute("b", "CREATE TABLE u (id integer);")
+            .unwrap();
+        assert_eq!("c", reopened.database("c").unwrap().name());
+        assert_eq!(
+            Ok(QueryResult::InsertSuccess { count: 1 }),
+            reopened.execute("b", "INSERT INTO u VALUES (1);")
+        );
+        reopened.execute("b", "ROLLBACK;").unwrap();
+        assert_eq!(
+            Err(Error::DatabaseDoesNotExist("c".to_string())),
+            reopened.database("c").map(|database| database.name())
+        );
+        assert_eq!(
+            Err(Error::TableDoesNotExist("u".to_string())),
+            reopened.execute("b", "INSERT INTO u VALUES (1);")
+        );
+    }
+
+    #[test]
+    fn test_query_as() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE drinks (id integer, name text);")
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO drinks VALUES (1, 'gin'), (2, NULL);")
+            .unwrap();
+
+        assert_eq!(
+            Ok(vec![(1, Some("gin".to_string())), (2, None)]),
+            manager.query_as::<(i64, Option<String>)>("db", "SELECT id, name FROM drinks;")
+        );
+        assert_eq!(
+            Err(Error::UnexpectedNull("name".to_string())),
+            manager.query_as::<(String,)>("db", "SELECT name FROM drinks;")
+        );
+        assert_eq!(
+            Ok(vec![]),
+            manager.query_as::<(i64,)>("db", "INSERT INTO drinks VALUES (3, NULL);")
+        );
+    }
+
+    #[test]
+    fn test_transactions() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (id integer);")
+            .unwrap();
+        manager.execute("db", "INSERT INTO t VALUES (1);").unwrap();
+
+        let select_ids = |manager: &mut Manager| {
+            let Ok(QueryResult::RowResult(row_result)) = manager.execute("db", "SELECT id FROM t;")
+            else {
+                panic!("Did not get the expected result");
+            };
+            row_result.rows().to_vec()
+        };
+
+        // The changes of a transaction are visible within it and undone by rolling it back,
+        // including the tables it created.
+        manager.execute("db", "BEGIN;").unwrap();
+        manager.execute("db", "INSERT INTO t VALUES (2);").unwrap();
+        manager
+            .execute("db", "CREATE TABLE u (id integer);")
+            .unwrap();
+        assert_eq!(
+            vec![vec![Some(Value::Integer(1))], vec![Some(Value::Integer(2))]],
+            select_ids(&mut manager)
+        );
+        assert_eq!(
+            Ok(QueryResult::CommandSuccessMessage("ROLLBACK".to_string())),
+            manager.execute("db", "ROLLBACK;")
+        );
+        assert_eq!(
+            vec![vec![Some(Value::Integer(1))]],
+            select_ids(&mut manager)
+        );
+        assert_eq!(
+            Ok(vec!["t".to_string()]),
+            manager.database_table_names("db")
+        );
+
+        manager.execute("db", "BEGIN TRANSACTION;").unwrap();
+        manager.execute("db", "BEGIN;").unwrap();
+        assert_eq!(&[Warning::TransactionAlreadyInProgress], manager.warnings());
+        manager.execute("db", "INSERT INTO t VALUES (3);").unwrap();
+        manager.execute("db", "COMMIT;").unwrap();
+        assert_eq!(
+            vec![vec![Some(Value::Integer(1))], vec![Some(Value::Integer(3))]],
+            select_ids(&mut manager)
+        );
+
+        manager.execute("db", "ROLLBACK;").unwrap();
+        assert_eq!(&[Warning::NoTransactionInProgress], manager.warnings());
+        assert_eq!(
+            Err(Error::NoTransactionInProgress),
+            manager.execute("db", "SAVEPOINT s;")
+        );
+        assert_eq!(
+            vec![vec![Some(Value::Integer(1))], vec![Some(Value::Integer(3))]],
+            select_ids(&mut manager)
+        );
+    }
+
+    #[test]
+    fn test_savepoints() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (id integer PRIMARY KEY);")
+            .unwrap();
+
+        manager.execute("db", "BEGIN;").unwrap();
+        manager.execute("db", "INSERT INTO t VALUES (1);").unwrap();
+        manager.execute("db", "SAVEPOINT loaded;").unwrap();
+
+        // A failed statement only has to be rolled back to the savepoint.
+        manager.execute("db", "INSERT INTO t VALUES (2);").unwrap();
+        assert_eq!(
+            Err(Error::UniqueViolation("id".to_string())),
+            manager.execute("db", "INSERT INTO t VALUES (3), (1);")
+        );
+        assert_eq!(
+            Ok(QueryResult::CommandSuccessMessage("ROLLBACK".to_string())),
+            manager.execute("db", "ROLLBACK TO SAVEPOINT loaded;")
+        );
+        manager.execute("db", "INSERT INTO t VALUES (4);").unwrap();
+        manager.execute("db", "RELEASE loaded;").unwrap();
+        assert_eq!(
+            Err(Error::SavepointDoesNotExist("loaded".to_string())),
+            manager.execute("db", "ROLLBACK TO loaded;")
+        );
+        manager.execute("db", "COMMIT;").unwrap();
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute("db", "SELECT id FROM t;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[vec![Some(Value::Integer(1))], vec![Some(Value::Integer(4))]],
+            row_result.rows()
+        );
+    }
+
+    #[test]
+    fn test_explaining_queries() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE t (id integer PRIMARY KEY, age integer);",
+            )
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO t VALUES (1, 30), (2, 40);")
+            .unwrap();
+
+        let plan = |manager: &mut Manager, query: &str| {
+            let Ok(QueryResult::RowResult(row_result)) = manager.execute("db", query) else {
+                panic!("Did not get the expected result");
+            };
+            assert_eq!(&vec!["QUERY PLAN".to_string()], row_result.columns());
+
+            row_result
+                .rows()
+                .iter()
+                .map(|row| row[0].as_ref().unwrap().to_string())
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(
+            vec![
+                "Seq Scan on t",
+                "  Pages: 1",
+                "  Filter: age > 30",
+                "  Output: id, age + 1"
+            ],
+            plan(
+                &mut manager,
+                "EXPLAIN SELECT id, age + 1 FROM t WHERE age > 30;"
+            )
+        );
+        assert_eq!(
+            vec![
+                "Primary Key Lookup on t",
+                "  Key: id = 2",
+                "  Pages: 2",
+                "  Filter: 2 = id",
+                "  Output: *"
+            ],
+            plan(&mut manager, "EXPLAIN SELECT * FROM t WHERE 2 = id;")
+        );
+        assert_eq!(
+            vec![
+                "Primary Key Lookup on t",
+                "  Key: id = 1",
+                "  Pages: 2",
+                "  Filter: age > 20 AND id = 1",
+                "  Output: age"
+            ],
+            plan(
+                &mut manager,
+                "EXPLAIN SELECT age FROM t WHERE age > 20 AND id = 1;"
+            )
+        );
+        assert_eq!(
+            vec![
+                "Seq Scan on t",
+                "  Pages: 1",
+                "  Filter: age > 20 OR id = 1",
+                "  Output: age"
+            ],
+            plan(
+                &mut manager,
+                "EXPLAIN SELECT age FROM t WHERE age > 20 OR id = 1;"
+            )
+        );
+        assert_eq!(
+            vec!["Sample Scan on t (50%)", "  Pages: ~1", "  Output: *"],
+            plan(
+                &mut manager,
+                "EXPLAIN SELECT * FROM t TABLESAMPLE SYSTEM (50);"
+            )
+        );
+
+        assert_eq!(
+            Err(Error::TableDoesNotExist("missing".to_string())),
+            manager.execute("db", "EXPLAIN SELECT * FROM missing;")
+        );
+    }
+
+    #[test]
+    fn test_health_checks() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        assert_eq!(Ok(()), manager.check_health());
+
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (id integer);")
+            .unwrap();
+        manager.execute("db", "INSERT INTO t VALUES (1);").unwrap();
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SELECT bsql_health(), id FROM t;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &vec!["bsql_health".to_string(), "id".to_string()],
+            row_result.columns()
+        );
+        assert_eq!(
+            &[vec![Some(Value::Boolean(true)), Some(Value::Integer(1))]],
+            row_result.rows()
+        );
+
+        assert_eq!(
+            Err(Error::InvalidFunctionArguments("bsql_health".to_string())),
+            manager.execute("db", "SELECT bsql_health(id) FROM t;")
+        );
+
+        // A backend that has stopped accepting writes, like a full or read-only disk.
+        struct ReadOnlyBackend(MemoryBackend);
+
+        impl StorageBackend for ReadOnlyBackend {
+            fn read_all(&self) -> std::io::Result<Vec<Vec<u8>>> {
+                self.0.read_all()
+            }
+
+            fn read_page(&self, page_id: PageId) -> std::io::Result<Vec<u8>> {
+                self.0.read_page(page_id)
+            }
+
+            fn write_pages(&self, pages: Vec<(PageId, Vec<u8>)>) -> std::io::Result<()> {
+                self.0.write_pages(pages)
+            }
+
+            fn is_writable(&self) -> bool {
+                false
+            }
+        }
+
+        let page_manager = Rc::new(RwLock::new(PageManager::with_backend(Box::new(
+            ReadOnlyBackend(MemoryBackend::new()),
+        ))));
+        let mut manager = Manager::new(page_manager);
+        assert_eq!(Err(Error::StorageUnavailable), manager.check_health());
+
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (id integer);")
+            .unwrap();
+        manager.execute("db", "INSERT INTO t VALUES (1);").unwrap();
+        assert_eq!(
+            Err(Error::StorageUnavailable),
+            manager.execute("db", "SELECT bsql_health() FROM t;")
+        );
+    }
+
+    #[test]
+    fn test_statement_statistics() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (id integer);")
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO t VALUES (1), (2);")
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO t VALUES (3), (4);")
+            .unwrap();
+        manager
+            .execute("db", "SELECT * FROM t WHERE id > 1;")
+            .unwrap();
+        assert!(manager.execute("db", "SELECT * FROM missing;").is_err());
+
+        let mut statement_statistics = manager.statement_statistics();
+        statement_statistics.sort_by(|a, b| a.query.cmp(&b.query));
+        assert_eq!(
+            vec![
+                ("CREATE DATABASE db", 1, 0),
+                ("CREATE TABLE t(id integer)", 1, 0),
+                ("INSERT INTO t VALUES (?), (?)", 2, 4),
+                ("SELECT * FROM t WHERE id > ?", 1, 3),
+            ],
+            statement_statistics
+                .iter()
+                .map(|statistics| (statistics.query.as_str(), statistics.calls, statistics.rows))
+                .collect::<Vec<(&str, usize, usize)>>()
+        );
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute(
+            "",
+            "SELECT calls, rows FROM bsql_stat_statements WHERE query LIKE 'INSERT%';",
+        ) else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(
+            &[vec![Some(Value::Integer(2)), Some(Value::Integer(4))]],
+            row_result.rows()
+        );
+
+        manager.reset_statement_statistics();
+        assert!(manager.statement_statistics().is_empty());
+    }
+
+    #[test]
+    fn test_timing_statements_with_a_clock() {
+        /// Advances by a millisecond every time it's read.
+        struct TickingClock(Cell<u64>);
+
+        impl Clock for TickingClock {
+            fn elapsed(&self) -> Duration {
+                self.0.set(self.0.get() + 1);
+                Duration::from_millis(self.0.get())
+            }
+
+            fn since_epoch(&self) -> Duration {
+                Duration::ZERO
+            }
+        }
+
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.set_clock(Rc::new(TickingClock(Cell::new(0))));
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager.execute("", "CREATE DATABASE other;").unwrap();
+        assert_eq!(
+            vec![Duration::from_millis(1), Duration::from_millis(1)],
+            manager
+                .statement_statistics()
+                .iter()
+                .map(|statistics| statistics.total_time)
+                .collect::<Vec<Duration>>()
+        );
+
+        // Without a clock, statements take no time and samples are still taken.
+        manager.set_clock(Rc::new(StoppedClock));
+        manager.reset_statement_statistics();
+        manager
+            .execute(
+                "db",
+                "CREATE TABLE t (id integer); INSERT INTO t VALUES (1);",
+            )
+            .unwrap();
+        assert!(manager
+            .execute("db", "SELECT * FROM t TABLESAMPLE SYSTEM (100);")
+            .is_ok());
+        assert!(manager.execute("db", "SELECT GEN_RANDOM_UUID();").is_ok());
+        assert!(manager
+            .statement_statistics()
+            .iter()
+            .all(|statistics| statistics.total_time == Duration::ZERO));
+    }
+
+    #[test]
+    fn test_fetching_from_cursors() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (x integer);")
+            .unwrap();
+        for x in 0..5 {
+            manager
+                .execute("db", &format!("INSERT INTO t VALUES ({});", x))
+                .unwrap();
+        }
+
+        manager
+            .execute("db", "DECLARE c CURSOR FOR SELECT x FROM t WHERE x > 0;")
+            .unwrap();
+        assert_eq!(
+            Err(Error::CursorAlreadyExists("c".to_string())),
+            manager.execute("db", "DECLARE c CURSOR FOR SELECT * FROM t;")
+        );
+
+        let fetch = |manager: &mut Manager| match manager.execute("db", "FETCH 3 FROM c;") {
+            Ok(QueryResult::RowResult(row_result)) => row_result.rows().to_vec(),
+            _ => panic!("Did not get the expected result"),
+        };
+        assert_eq!(
+            vec![
+                vec![Some(Value::Integer(1))],
+                vec![Some(Value::Integer(2))],
+                vec![Some(Value::Integer(3))],
+            ],
+            fetch(&mut manager)
+        );
+        assert_eq!(vec![vec![Some(Value::Integer(4))]], fetch(&mut manager));
+        assert!(fetch(&mut manager).is_empty());
+
+        manager.execute("db", "CLOSE c;").unwrap();
+        assert_eq!(
+            Err(Error::CursorDoesNotExist("c".to_string())),
+            manager.execute("db", "FETCH 1 FROM c;")
+        );
+
+        // The select list is evaluated for the rows of every fetch.
+        manager
+            .execute("db", "DECLARE c CURSOR FOR SELECT x * 2 FROM t;")
+            .unwrap();
+        match manager.execute("db", "FETCH 2 FROM c;") {
+            Ok(QueryResult::RowResult(row_result)) => {
+                assert_eq!(&vec!["?column?".to_string()], row_result.columns());
+                assert_eq!(
+                    &[vec![Some(Value::Integer(0))], vec![Some(Value::Integer(2))]],
+                    row_result.rows()
+                );
+            }
+            _ => panic!("Did not get the expected result"),
+        }
+        manager.execute("db", "CLOSE c;").unwrap();
+
+        manager
+            .execute("db", "DECLARE c CURSOR FOR SELECT 10 / x FROM t;")
+            .unwrap();
+        assert_eq!(
+            Err(Error::DivisionByZero),
+            manager.execute("db", "FETCH 1 FROM c;")
+        );
+        manager.execute("db", "CLOSE c;").unwrap();
+
+        // A rollback can remove the pages that the cursors scan, so it closes them.
+        manager.execute("db", "BEGIN;").unwrap();
+        manager
+            .execute("db", "DECLARE c CURSOR FOR SELECT * FROM t;")
+            .unwrap();
+        manager.execute("db", "ROLLBACK;").unwrap();
+        assert_eq!(
+            Err(Error::CursorDoesNotExist("c".to_string())),
+            manager.execute("db", "FETCH 1 FROM c;")
+        );
+    }
+
+    #[test]
+    fn test_automatic_shrinking_after_queries() {
+        let filename = std::env::temp_dir().join(format!(
+            "bsql_manager_auto_shrink_{}.db",
+            std::process::id()
+        ));
+        let filename = filename.to_str().unwrap();
+
+        let page_manager = Rc::new(RwLock::new(PageManager::new(filename)));
+        let mut manager = Manager::new(page_manager.clone());
+        manager.set_auto_shrink_threshold(Some(2));
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (x integer);")
+            .unwrap();
+        manager.execute("db", "INSERT INTO t VALUES (7);").unwrap();
+
+        // Only the metadata page that `Manager` holds on to stays loaded.
+        assert_eq!(1, page_manager.read().unwrap().loaded_page_count());
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute("db", "SELECT * FROM t;")
+        else {
+            panic!("Did not get the expected result");
+        };
+        assert_eq!(vec![vec![Some(Value::Integer(7))]], row_result.rows());
+
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_page_quota() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.create_database("hello").unwrap();
+        assert_eq!(Ok(None), manager.page_quota("hello"));
+
+        manager.set_page_quota("hello", Some(1)).unwrap();
+        assert_eq!(Ok(Some(1)), manager.page_quota("hello"));
+
+        assert_eq!(
+            Err(Error::PageQuotaExceeded("hello".to_string())),
+            manager.execute("hello", "CREATE TABLE t (x integer);")
+        );
+        assert_eq!(
+            Err(Error::DatabaseDoesNotExist("world".to_string())),
+            manager.set_page_quota("world", Some(1))
+        );
+    }
+
+    #[test]
+    fn test_query_memory_limit() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (x integer, y integer);")
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO t VALUES (1, 1), (2, 1), (3, 2), (4, 2);")
+            .unwrap();
+
+        let value_size = core::mem::size_of::<Option<Value>>();
+        manager.set_query_memory_limit(Some(5 * value_size));
+
+        manager.execute("db", "SELECT x FROM t;").unwrap();
+        assert_eq!(4 * value_size, manager.query_memory_usage());
+
+        // The scan stops at the row that exceeds the limit, the 3rd row of the 4 here.
+        assert_eq!(
+            Err(Error::QueryMemoryLimitExceeded {
+                used: 6 * value_size,
+                limit: 5 * value_size
+            }),
+            manager.execute("db", "SELECT * FROM t;")
+        );
+        assert_eq!(
+            Err(Error::QueryMemoryLimitExceeded {
+                used: 6 * value_size,
+                limit: 5 * value_size
+            }),
+            manager.execute("db", "SELECT x + y FROM t;")
+        );
+
+        // Only the rows that match the filter are kept, but their 4 values and the 2 projected
+        // values are too much together.
+        assert_eq!(
+            Err(Error::QueryMemoryLimitExceeded {
+                used: 6 * value_size,
+                limit: 5 * value_size
+            }),
+            manager.execute("db", "SELECT x FROM t WHERE y > 1;")
+        );
+
+        manager.set_query_memory_limit(None);
+        manager
+            .execute("db", "SELECT x FROM t WHERE y > 1;")
+            .unwrap();
+        assert_eq!(6 * value_size, manager.query_memory_usage());
+    }
+
+    #[test]
+    fn test_select_with_scalar_subquery() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE users (id integer);")
+            .unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (x integer);")
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO users VALUES (4);")
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO users VALUES (9);")
+            .unwrap();
+        manager.execute("db", "INSERT INTO t VALUES (1);").unwrap();
+        manager.execute("db", "INSERT INTO t VALUES (2);").unwrap();
+
+        let Ok(QueryResult::RowResult(row_result)) =
+            manager.execute("db", "SELECT x, (SELECT MAX(id) FROM users) FROM t;")
+        else {
+            panic!("Did not get the expected result");
+        };
+
+        assert_eq!(
+            &vec!["x".to_string(), "?column?".to_string()],
+            row_result.columns()
+        );
+        assert!(row_result
+            .rows()
+            .contains(&vec![Some(Value::Integer(1)), Some(Value::Integer(9))]));
+        assert!(row_result
+            .rows()
+            .contains(&vec![Some(Value::Integer(2)), Some(Value::Integer(9))]));
+    }
+
+    #[test]
+    fn test_select_with_case_when() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE users (id integer, age integer);")
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO users VALUES (1, 18);")
+            .unwrap();
+        manager
+            .execute("db", "INSERT INTO users VALUES (2, 30);")
+            .unwrap();
+
+        let Ok(QueryResult::RowResult(row_result)) = manager.execute(
+            "db",
+            "SELECT id FROM users WHERE CASE WHEN age = 18 THEN 1 ELSE 0 END = 1;",
+        ) else {
+            panic!("Did not get the expected result");
+        };
+
+        assert_eq!(vec![vec![Some(Value::Integer(1))]], row_result.rows());
+    }
+
+    #[test]
+    fn test_select_with_scalar_subquery_returning_multiple_rows() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager.execute("", "CREATE DATABASE db;").unwrap();
+        manager
+            .execute("db", "CREATE TABLE t (x integer);")
+            .unwrap();
+        manager.execute("db", "INSERT INTO t VALUES (1);").unwrap();
+        manager.execute("db", "INSERT INTO t VALUES (2);").unwrap();
+
+        assert_eq!(
+            Err(Error::SubqueryReturnedMoreThanOneRow),
+            manager.execute("db", "SELECT (SELECT x FROM t) FROM t;")
         );
     }
 }
@@ -1,31 +1,72 @@
+#[cfg(test)]
+mod arbitrary;
 mod bitmap_index;
+mod btree;
+mod catalog_change;
+mod clock;
 mod column_definition;
+mod csv_reader;
+mod cursor;
 mod data_type;
 mod database;
 mod error;
+mod evaluator;
+mod from_row;
+mod json;
 mod manager;
 mod page;
 mod page_manager;
-mod parser;
+mod primary_key_index;
+mod query_log;
 mod query_result;
+mod random;
+mod record_format;
 mod row_result;
+#[cfg(feature = "serde")]
+mod serialization;
+mod session;
+mod statement_statistics;
+mod storage_backend;
 mod table_manager;
 mod table_page;
 mod value;
+mod warning;
+
+use bsql_parser as parser;
 
 use bitmap_index::BitmapIndex;
-use database::Database;
-use page::InternalPage;
+use btree::BTree;
+use clock::default_clock;
+use csv_reader::CsvReader;
+use cursor::Cursor;
+use database::{Database, TablePageIds};
+use page::{InternalPage, LockedPage};
 use page_manager::{PageId, SharedInternalPage};
+use parser::{hex, normalize, parameter_count, ConflictAction, OnConflict, TableSample};
+use primary_key_index::PrimaryKeyIndex;
+use random::Random;
+use record_format::RecordFormat;
+use statement_statistics::STATEMENT_STATISTICS_VIEW;
 use table_manager::TableManager;
 use table_page::TablePage;
 
+pub use catalog_change::{CatalogChange, CatalogChangeKind, CatalogChangeListener};
+pub use clock::{Clock, StoppedClock, SystemClock};
 pub use column_definition::ColumnDefinition;
 pub use data_type::DataType;
-pub use error::Error;
-pub use manager::Manager;
-pub use page_manager::PageManager;
-pub use parser::{parse, Command, Token};
+pub use error::{Error, ErrorCategory};
+pub use from_row::{FromRow, FromValue, Row};
+pub use manager::{Manager, FORMAT_VERSION};
+pub use page_manager::{PageManager, SynchronousMode};
+pub use parser::{
+    locate_syntax_error, parse, parse_statements, parse_with_parameters, split_statements, Command,
+    Decimal, Error as ParseError, Expression, SyntaxErrorLocation, Token, Uuid,
+};
+pub use query_log::{QueryLog, QueryLogEntry};
 pub use query_result::QueryResult;
 pub use row_result::RowResult;
+pub use session::Session;
+pub use statement_statistics::StatementStatistics;
+pub use storage_backend::{FileBackend, MemoryBackend, StorageBackend};
 pub use value::Value;
+pub use warning::Warning;
@@ -1,5 +1,9 @@
+use std::cell::Cell;
+use std::sync::{LockResult, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
 pub type PageData = [u8; 4096];
 
+#[derive(Clone)]
 pub struct InternalPage {
     pub metadata: PageData,
     pub data: PageData,
@@ -13,3 +17,42 @@ impl InternalPage {
         }
     }
 }
+
+/// An `InternalPage` behind a lock, which remembers whether it was locked for writing since the
+/// `PageManager` last wrote it to the backend, see `PageManager::is_dirty`.
+pub struct LockedPage {
+    page: RwLock<InternalPage>,
+    dirty: Cell<bool>,
+}
+
+impl LockedPage {
+    /// Wraps a page that isn't stored in the backend yet, so it starts out dirty.
+    pub fn new(page: InternalPage) -> Self {
+        Self {
+            page: RwLock::new(page),
+            dirty: Cell::new(true),
+        }
+    }
+
+    pub fn read(&self) -> LockResult<RwLockReadGuard<'_, InternalPage>> {
+        self.page.read()
+    }
+
+    /// Locks the page for writing and marks it as dirty, whether or not it's changed.
+    pub fn write(&self) -> LockResult<RwLockWriteGuard<'_, InternalPage>> {
+        self.dirty.set(true);
+        self.page.write()
+    }
+
+    pub fn is_poisoned(&self) -> bool {
+        self.page.is_poisoned()
+    }
+
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.get()
+    }
+
+    pub fn set_dirty(&self, dirty: bool) {
+        self.dirty.set(dirty);
+    }
+}
@@ -1,71 +1,1007 @@
-use std::fs::File;
-use std::io::Write;
+use std::cell::RefCell;
+use std::collections::HashSet;
 use std::rc::Rc;
-use std::sync::RwLock;
+use std::sync::mpsc::Receiver;
 
-use super::InternalPage;
+use super::storage_backend::PAGE_SIZE;
+use super::{Error, FileBackend, InternalPage, LockedPage, StorageBackend};
 
 pub type PageId = u32;
-pub type SharedInternalPage = Rc<RwLock<InternalPage>>;
+pub type SharedInternalPage = Rc<LockedPage>;
+
+/// Freed pages are zeroed except for this marker at the start of their metadata, so that the free
+/// list can be rebuilt when the pages are read from the backend.
+const FREE_PAGE_MARKER: &[u8] = b"bsql free page";
 
 pub struct PageManager {
-    filename: String,
+    /// Where the pages are stored, or `None` when the pages only live in memory (`:memory:`).
+    backend: Option<Box<dyn StorageBackend>>,
+
+    /// Why the pages couldn't be read from the backend, if they couldn't. Nothing is written to
+    /// such a backend, so that the pages that are stored in it aren't overwritten.
+    read_error: Option<String>,
+
+    /// The pages that are loaded into memory. Pages that have been evicted by `shrink_to_fit` are
+    /// `None` and will be read back from the backend the next time they are fetched.
+    pages: RefCell<Vec<Option<SharedInternalPage>>>,
+
+    /// The pages that have been freed with `free_page`, which `create_page` hands out again.
+    free_page_ids: RefCell<Vec<PageId>>,
 
-    pages: Vec<SharedInternalPage>,
+    /// Pages that are being read ahead by the backend, see `prefetch`.
+    prefetching: RefCell<Option<Prefetch>>,
+
+    /// The state of the pages when the current transaction began, see `begin_transaction`.
+    transaction: RefCell<Option<Transaction>>,
+
+    synchronous_mode: SynchronousMode,
 }
 
-impl PageManager {
-    pub fn new(filename: &str) -> Self {
-        let mut pages = Vec::new();
-        let path = std::path::Path::new(filename);
+/// When the `PageManager` waits for written pages to be stored durably by the backend, e.g. with
+/// an fsync of the database file. Syncing less often makes writes faster, but pages that were
+/// written since the last sync can be lost when the system crashes.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum SynchronousMode {
+    /// Never syncs, not even on `flush`. Useful for bulk loads that can be repeated.
+    Off,
+    /// Syncs on `flush` and when a transaction is committed.
+    #[default]
+    Normal,
+    /// Also syncs after every `commit`, i.e. after every statement that modified pages.
+    Full,
+}
 
-        if std::path::Path::exists(path) {
-            let content = std::fs::read(filename).unwrap_or(vec![]);
+struct Transaction {
+    snapshot: Snapshot,
 
-            for chunk in content.chunks(8192) {
-                let (metadata, data) = chunk.split_at(4096);
+    /// The savepoints of the transaction in the order they were created, see `create_savepoint`.
+    savepoints: Vec<(String, Snapshot)>,
+}
 
-                let mut page = InternalPage::new();
-                page.metadata.copy_from_slice(&metadata);
-                page.data.copy_from_slice(&data);
+/// The state of the pages at some point of a transaction or statement, see `snapshot`.
+pub struct Snapshot {
+    page_count: usize,
+    free_page_ids: Vec<PageId>,
 
-                pages.push(Rc::new(RwLock::new(page)));
-            }
+    /// A copy of every page that was loaded into memory, and whether it was dirty. The pages that
+    /// weren't loaded are unchanged in the backend, because nothing is written to it during a
+    /// transaction.
+    loaded_pages: Vec<Option<(InternalPage, bool)>>,
+}
+
+struct Prefetch {
+    pending_page_ids: HashSet<PageId>,
+    receiver: Receiver<(PageId, Vec<u8>)>,
+}
+
+impl PageManager {
+    /// Stores the pages in the file `filename`, or only in memory when it's `:memory:`.
+    pub fn new(filename: &str) -> Self {
+        match filename {
+            ":memory:" => Self::from_backend(None),
+            _ => Self::with_backend(Box::new(FileBackend::new(filename))),
         }
+    }
+
+    /// Stores the pages in `backend`, which is read from to load the pages that it already has.
+    pub fn with_backend(backend: Box<dyn StorageBackend>) -> Self {
+        Self::from_backend(Some(backend))
+    }
+
+    fn from_backend(backend: Option<Box<dyn StorageBackend>>) -> Self {
+        let stored_pages = match &backend {
+            Some(backend) => backend.read_all(),
+            None => Ok(vec![]),
+        };
+        let (pages, read_error): (Vec<InternalPage>, _) = match stored_pages {
+            Ok(pages) => (
+                pages
+                    .iter()
+                    .map(|bytes| Self::page_from_bytes(bytes))
+                    .collect(),
+                None,
+            ),
+            Err(error) => (vec![], Some(error.to_string())),
+        };
+        // Free pages are handed out in order of their page id, see `create_page`.
+        let free_page_ids = (0..pages.len() as PageId)
+            .rev()
+            .filter(|page_id| Self::is_free(&pages[*page_id as usize]))
+            .collect();
 
         PageManager {
-            filename: filename.to_string(),
-            pages,
+            backend,
+            read_error,
+            pages: RefCell::new(
+                pages
+                    .into_iter()
+                    .map(|page| Some(Self::stored_page(page)))
+                    .collect(),
+            ),
+            free_page_ids: RefCell::new(free_page_ids),
+            prefetching: RefCell::new(None),
+            transaction: RefCell::new(None),
+            synchronous_mode: SynchronousMode::default(),
         }
     }
 
-    /// Creates a new pages and returns its page_id and the page itself.
+    /// Creates a new pages and returns its page_id and the page itself. Pages that have been
+    /// freed are used again before the number of pages grows.
     pub fn create_page(&mut self) -> (PageId, SharedInternalPage) {
-        let page = Rc::new(RwLock::new(InternalPage::new()));
-        let page_id = self.pages.len();
-        self.pages.push(page.clone());
+        let page = Rc::new(LockedPage::new(InternalPage::new()));
+        let mut pages = self.pages.borrow_mut();
+
+        if let Some(page_id) = self.free_page_ids.borrow_mut().pop() {
+            pages[page_id as usize] = Some(page.clone());
+            return (page_id, page);
+        }
+        let page_id = pages.len();
+        pages.push(Some(page.clone()));
 
         return (page_id as u32, page);
     }
 
-    /// Returns the page if it exists.
-    pub fn fetch_page(&self, page_id: PageId) -> Option<SharedInternalPage> {
-        self.pages.get(page_id as usize).cloned()
+    /// Creates a new page with a copy of `page` and returns its page_id and the page itself.
+    pub fn create_page_from(&mut self, page: &SharedInternalPage) -> (PageId, SharedInternalPage) {
+        let (page_id, new_page) = self.create_page();
+        *new_page.write().unwrap() = page.read().unwrap().clone();
+
+        (page_id, new_page)
+    }
+
+    pub fn synchronous_mode(&self) -> SynchronousMode {
+        self.synchronous_mode
+    }
+
+    pub fn set_synchronous_mode(&mut self, synchronous_mode: SynchronousMode) {
+        self.synchronous_mode = synchronous_mode;
+    }
+
+    /// Adds the page to the free list, so that `create_page` can hand it out again. The page
+    /// mustn't be used anymore, and is cleared.
+    pub fn free_page(&mut self, page_id: PageId) {
+        let page = {
+            let mut pages = self.pages.borrow_mut();
+            let Some(page) = pages.get_mut(page_id as usize) else {
+                return;
+            };
+            // Evicted pages aren't read back, since they're cleared anyway.
+            page.get_or_insert_with(|| Rc::new(LockedPage::new(InternalPage::new())))
+                .clone()
+        };
+        let mut free_page_ids = self.free_page_ids.borrow_mut();
+        if free_page_ids.contains(&page_id) {
+            return;
+        }
+
+        let mut page = page.write().unwrap();
+        *page = InternalPage::new();
+        page.metadata[..FREE_PAGE_MARKER.len()].copy_from_slice(FREE_PAGE_MARKER);
+
+        // Keep the lowest page ids at the end, so that they're used first.
+        let position = free_page_ids.partition_point(|free_page_id| *free_page_id > page_id);
+        free_page_ids.insert(position, page_id);
+    }
+
+    /// Returns the number of pages on the free list, see `free_page`.
+    pub fn free_page_count(&self) -> usize {
+        self.free_page_ids.borrow().len()
+    }
+
+    /// Returns the number of pages, including the ones that aren't loaded into memory.
+    pub fn page_count(&self) -> usize {
+        self.pages.borrow().len()
+    }
+
+    /// Returns the page if it exists. Pages that have been evicted are read back from the
+    /// backend, which fails with `Error::StorageFailed` when the backend can't read them.
+    pub fn fetch_page(&self, page_id: PageId) -> Result<Option<SharedInternalPage>, Error> {
+        match self.pages.borrow().get(page_id as usize) {
+            Some(Some(page)) => return Ok(Some(page.clone())),
+            Some(None) => (),
+            None => return Ok(None),
+        }
+
+        let page = match self.receive_prefetched_page(page_id) {
+            Some(page) => page,
+            None => {
+                let Some(backend) = self.backend.as_ref() else {
+                    return Ok(None);
+                };
+                let bytes = backend
+                    .read_page(page_id)
+                    .map_err(|error| Error::StorageFailed(error.to_string()))?;
+                Self::page_from_bytes(&bytes)
+            }
+        };
+        let page = Self::stored_page(page);
+        self.pages.borrow_mut()[page_id as usize] = Some(page.clone());
+
+        Ok(Some(page))
+    }
+
+    /// Starts reading the given pages from the backend in the background so that a sequential
+    /// scan over them doesn't have to wait for every page to be read. Only pages that aren't
+    /// loaded into memory are read.
+    pub fn prefetch(&self, page_ids: &[PageId]) {
+        let Some(backend) = self.backend.as_ref() else {
+            return;
+        };
+
+        let page_ids: Vec<PageId> = {
+            let pages = self.pages.borrow();
+            page_ids
+                .iter()
+                .copied()
+                .filter(|page_id| matches!(pages.get(*page_id as usize), Some(None)))
+                .collect()
+        };
+
+        if page_ids.is_empty() {
+            return;
+        }
+
+        let pending_page_ids = page_ids.iter().copied().collect();
+        let Some(receiver) = backend.prefetch(page_ids) else {
+            return;
+        };
+
+        *self.prefetching.borrow_mut() = Some(Prefetch {
+            pending_page_ids,
+            receiver,
+        });
+    }
+
+    /// Returns the number of pages that are currently loaded into memory.
+    pub fn loaded_page_count(&self) -> usize {
+        self.pages
+            .borrow()
+            .iter()
+            .filter(|page| page.is_some())
+            .count()
+    }
+
+    /// Writes all pages to the backend and releases every page that isn't in use elsewhere.
+    /// Released pages are read back from the backend when they are needed again. During a
+    /// transaction the pages are kept, since they can't be written yet, and so are the pages
+    /// that couldn't be written.
+    pub fn shrink_to_fit(&mut self) {
+        if self.backend.is_some() && !self.in_transaction() {
+            let _result = self.commit();
+
+            for page in self.pages.borrow_mut().iter_mut() {
+                if page
+                    .as_ref()
+                    .is_some_and(|page| Rc::strong_count(page) == 1 && !page.is_dirty())
+                {
+                    *page = None;
+                }
+            }
+        }
+
+        self.pages.borrow_mut().shrink_to_fit();
+    }
+
+    /// Returns whether pages can be fetched and written: none of the loaded pages were left
+    /// poisoned by a panic while they were locked, the pages could be read from the backend and
+    /// the backend accepts writes.
+    pub fn is_healthy(&self) -> bool {
+        let Ok(pages) = self.pages.try_borrow() else {
+            return false;
+        };
+
+        pages.iter().flatten().all(|page| !page.is_poisoned())
+            && self.read_error.is_none()
+            && self
+                .backend
+                .as_ref()
+                .map_or(true, |backend| backend.is_writable())
+    }
+
+    /// Starts a transaction: until it's committed nothing is written to the backend, and rolling
+    /// it back restores the pages to how they are now. This copies every page that is loaded
+    /// into memory.
+    pub fn begin_transaction(&self) {
+        *self.transaction.borrow_mut() = Some(Transaction {
+            snapshot: self.snapshot(),
+            savepoints: vec![],
+        });
+    }
+
+    pub fn in_transaction(&self) -> bool {
+        self.transaction.borrow().is_some()
     }
 
-    /// Write the pages to a disk on file.
-    pub fn commit(&self) {
-        if self.filename == ":memory:" {
+    /// Ends the transaction and writes the pages to the backend, syncing them unless the
+    /// `SynchronousMode` is `Off`. When they can't be written the transaction still ends, and the
+    /// pages are written with the next commit.
+    pub fn commit_transaction(&self) -> Result<(), Error> {
+        *self.transaction.borrow_mut() = None;
+        self.write_dirty_pages(|_page_id| true)?;
+        self.sync()
+    }
+
+    /// Ends the transaction and restores every page to how it was when the transaction began.
+    /// Pages that were created during the transaction are removed.
+    pub fn rollback_transaction(&self) {
+        let Some(transaction) = self.transaction.borrow_mut().take() else {
             return;
+        };
+
+        self.restore(&transaction.snapshot);
+    }
+
+    /// Marks the current state of the pages within the transaction, so that it can be restored
+    /// with `rollback_to_savepoint` without rolling back the whole transaction. Like a
+    /// transaction, this copies every page that is loaded into memory. Returns false when there
+    /// is no transaction.
+    pub fn create_savepoint(&self, name: &str) -> bool {
+        let snapshot = self.snapshot();
+        let mut transaction = self.transaction.borrow_mut();
+        let Some(transaction) = transaction.as_mut() else {
+            return false;
+        };
+
+        transaction.savepoints.push((name.to_string(), snapshot));
+        true
+    }
+
+    /// Restores the pages to how they were when the savepoint was created. The savepoint is kept,
+    /// but the savepoints that were created after it are removed. When several savepoints have
+    /// the same name the last one is used. Returns false when there is no such savepoint.
+    pub fn rollback_to_savepoint(&self, name: &str) -> bool {
+        let mut transaction = self.transaction.borrow_mut();
+        let Some(transaction) = transaction.as_mut() else {
+            return false;
+        };
+        let Some(position) = transaction
+            .savepoints
+            .iter()
+            .rposition(|(savepoint_name, _snapshot)| savepoint_name == name)
+        else {
+            return false;
+        };
+
+        transaction.savepoints.truncate(position + 1);
+        self.restore(&transaction.savepoints[position].1);
+        true
+    }
+
+    /// Removes the savepoint and the savepoints that were created after it, keeping the changes
+    /// that were made since. Returns false when there is no such savepoint.
+    pub fn release_savepoint(&self, name: &str) -> bool {
+        let mut transaction = self.transaction.borrow_mut();
+        let Some(transaction) = transaction.as_mut() else {
+            return false;
+        };
+        let Some(position) = transaction
+            .savepoints
+            .iter()
+            .rposition(|(savepoint_name, _snapshot)| savepoint_name == name)
+        else {
+            return false;
+        };
+
+        transaction.savepoints.truncate(position);
+        true
+    }
+
+    /// Returns the current state of the pages, which `restore` brings them back to. Like a
+    /// savepoint, this copies every page that is loaded into memory. The pages have to be
+    /// restored before they're written to the backend with `commit`.
+    pub fn snapshot(&self) -> Snapshot {
+        let loaded_pages = self
+            .pages
+            .borrow()
+            .iter()
+            .map(|page| {
+                let page = page.as_ref()?;
+                Some((page.read().unwrap().clone(), page.is_dirty()))
+            })
+            .collect::<Vec<Option<(InternalPage, bool)>>>();
+
+        Snapshot {
+            page_count: loaded_pages.len(),
+            free_page_ids: self.free_page_ids.borrow().clone(),
+            loaded_pages,
         }
+    }
 
-        let mut file = File::create(&self.filename).unwrap();
+    /// Restores every page to its state in `snapshot` and removes the pages that were created
+    /// after it.
+    pub fn restore(&self, snapshot: &Snapshot) {
+        let mut pages = self.pages.borrow_mut();
+        pages.truncate(snapshot.page_count);
+        *self.free_page_ids.borrow_mut() = snapshot.free_page_ids.clone();
 
-        for page in &self.pages {
-            let page = page.read().unwrap();
+        for (page, loaded_page) in pages.iter_mut().zip(snapshot.loaded_pages.iter()) {
+            match (page.as_ref(), loaded_page) {
+                // Restore the page in place, so that everyone who holds on to it sees the change.
+                (Some(page), Some((loaded_page, dirty))) => {
+                    *page.write().unwrap() = loaded_page.clone();
+                    page.set_dirty(*dirty);
+                }
+                (None, Some((loaded_page, dirty))) => {
+                    let loaded_page = Rc::new(LockedPage::new(loaded_page.clone()));
+                    loaded_page.set_dirty(*dirty);
+                    *page = Some(loaded_page);
+                }
+                (_, None) => *page = None,
+            }
+        }
+    }
 
-            file.write_all(&page.metadata).unwrap();
-            file.write_all(&page.data).unwrap();
+    /// Write the pages that have been modified to the backend, and sync them when the
+    /// `SynchronousMode` is `Full`. During a transaction the pages are only written once the
+    /// transaction is committed.
+    pub fn commit(&self) -> Result<(), Error> {
+        if self.write_dirty_pages(|_page_id| true)? > 0
+            && self.synchronous_mode == SynchronousMode::Full
+        {
+            self.sync()?;
         }
+
+        Ok(())
+    }
+
+    /// Writes the pages that have been modified to the backend like `commit` does, and then waits
+    /// for the backend to store them durably, e.g. on disk, unless the `SynchronousMode` is
+    /// `Off`. Returns the number of pages that were written.
+    pub fn flush(&self) -> Result<usize, Error> {
+        let written_page_count = self.write_dirty_pages(|_page_id| true)?;
+        self.sync()?;
+
+        Ok(written_page_count)
+    }
+
+    /// Like `flush`, but only for a single page. Returns whether the page was written.
+    pub fn flush_page(&self, page_id: PageId) -> Result<bool, Error> {
+        let is_written = self.write_dirty_pages(|other_page_id| other_page_id == page_id)? > 0;
+        self.sync()?;
+
+        Ok(is_written)
+    }
+
+    /// Returns whether the page is loaded into memory and has been locked for writing since it
+    /// was last written to the backend (or was never written at all).
+    pub fn is_dirty(&self, page_id: PageId) -> bool {
+        matches!(self.pages.borrow().get(page_id as usize), Some(Some(page)) if page.is_dirty())
+    }
+
+    /// Returns the pages that `commit` would write, in order.
+    pub fn dirty_page_ids(&self) -> Vec<PageId> {
+        (0..self.page_count() as PageId)
+            .filter(|page_id| self.is_dirty(*page_id))
+            .collect()
+    }
+
+    /// Writes the dirty pages for which `filter` returns true and returns how many were written.
+    /// Nothing is written during a transaction. Pages that couldn't be written stay dirty.
+    fn write_dirty_pages(&self, filter: impl Fn(PageId) -> bool) -> Result<usize, Error> {
+        let Some(backend) = self.backend.as_ref() else {
+            return Ok(0);
+        };
+        if self.in_transaction() {
+            return Ok(0);
+        }
+        if let Some(read_error) = &self.read_error {
+            return Err(Error::StorageFailed(read_error.clone()));
+        }
+
+        // Evicted pages haven't changed since they were written to the backend.
+        let dirty_pages: Vec<(PageId, SharedInternalPage)> = self
+            .pages
+            .borrow()
+            .iter()
+            .enumerate()
+            .filter(|(page_id, _page)| filter(*page_id as PageId))
+            .filter_map(|(page_id, page)| {
+                let page = page.as_ref().filter(|page| page.is_dirty())?;
+                Some((page_id as PageId, page.clone()))
+            })
+            .collect();
+        if dirty_pages.is_empty() {
+            return Ok(0);
+        }
+
+        let pages = dirty_pages
+            .iter()
+            .map(|(page_id, page)| {
+                let page = page.read().unwrap();
+                (*page_id, [page.metadata, page.data].concat())
+            })
+            .collect();
+        backend
+            .write_pages(pages)
+            .map_err(|error| Error::StorageFailed(error.to_string()))?;
+
+        for (_page_id, page) in dirty_pages.iter() {
+            page.set_dirty(false);
+        }
+
+        Ok(dirty_pages.len())
+    }
+
+    fn sync(&self) -> Result<(), Error> {
+        let Some(backend) = self.backend.as_ref() else {
+            return Ok(());
+        };
+        if self.in_transaction() || self.synchronous_mode == SynchronousMode::Off {
+            return Ok(());
+        }
+
+        backend
+            .sync()
+            .map_err(|error| Error::StorageFailed(error.to_string()))
+    }
+
+    /// Waits for `page_id` to be read by the backend, if it's being prefetched. Pages that are
+    /// received before it are put into memory along the way.
+    fn receive_prefetched_page(&self, page_id: PageId) -> Option<InternalPage> {
+        let mut prefetching = self.prefetching.borrow_mut();
+        let prefetch = prefetching.as_mut()?;
+
+        if !prefetch.pending_page_ids.contains(&page_id) {
+            return None;
+        }
+
+        while let Ok((received_page_id, bytes)) = prefetch.receiver.recv() {
+            prefetch.pending_page_ids.remove(&received_page_id);
+            let page = Self::page_from_bytes(&bytes);
+
+            if received_page_id == page_id {
+                return Some(page);
+            }
+
+            if let Some(slot @ None) = self.pages.borrow_mut().get_mut(received_page_id as usize) {
+                *slot = Some(Self::stored_page(page));
+            }
+        }
+
+        // The prefetching stopped early, fall back to reading the page directly.
+        *prefetching = None;
+        None
+    }
+
+    fn is_free(page: &InternalPage) -> bool {
+        page.metadata.starts_with(FREE_PAGE_MARKER)
+            && page.metadata[FREE_PAGE_MARKER.len()..]
+                .iter()
+                .chain(page.data.iter())
+                .all(|byte| *byte == 0)
+    }
+
+    /// Wraps a page that was read from the backend, which is clean until it's locked for
+    /// writing.
+    fn stored_page(page: InternalPage) -> SharedInternalPage {
+        let page = Rc::new(LockedPage::new(page));
+        page.set_dirty(false);
+
+        page
+    }
+
+    fn page_from_bytes(bytes: &[u8]) -> InternalPage {
+        let (metadata, data) = bytes.split_at(PAGE_SIZE / 2);
+
+        let mut page = InternalPage::new();
+        page.metadata.copy_from_slice(metadata);
+        page.data.copy_from_slice(data);
+
+        page
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::internal::MemoryBackend;
+
+    #[test]
+    fn test_health_of_the_backend() {
+        let mut page_manager = PageManager::new(":memory:");
+        let (_page_id, page) = page_manager.create_page();
+        assert!(page_manager.is_healthy());
+
+        // A panic while the page is locked leaves it poisoned.
+        let _result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            let _page = page.write().unwrap();
+            panic!("Failed while writing the page");
+        }));
+        assert!(!page_manager.is_healthy());
+
+        let page_manager = PageManager::new("/bsql-directory-that-does-not-exist/bsql.db");
+        assert!(!page_manager.is_healthy());
+    }
+
+    #[test]
+    fn test_shrinking_releases_unused_pages() {
+        let filename = std::env::temp_dir().join(format!(
+            "bsql_page_manager_shrink_{}.db",
+            std::process::id()
+        ));
+        let filename = filename.to_str().unwrap();
+
+        let mut page_manager = PageManager::new(filename);
+        let (unused_page_id, unused_page) = page_manager.create_page();
+        let (_page_id, used_page) = page_manager.create_page();
+        unused_page.write().unwrap().data[0] = 42;
+        drop(unused_page);
+
+        page_manager.shrink_to_fit();
+        assert_eq!(1, page_manager.loaded_page_count());
+
+        let reloaded_page = page_manager
+            .fetch_page(unused_page_id)
+            .unwrap()
+            .expect("Failed to reload the released page");
+        assert_eq!(42, reloaded_page.read().unwrap().data[0]);
+        assert_eq!(2, page_manager.loaded_page_count());
+
+        drop(used_page);
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_prefetching_released_pages() {
+        let filename = std::env::temp_dir().join(format!(
+            "bsql_page_manager_prefetch_{}.db",
+            std::process::id()
+        ));
+        let filename = filename.to_str().unwrap();
+
+        let mut page_manager = PageManager::new(filename);
+        for index in 0..4 {
+            let (_page_id, page) = page_manager.create_page();
+            page.write().unwrap().data[0] = index;
+        }
+        page_manager.shrink_to_fit();
+        assert_eq!(0, page_manager.loaded_page_count());
+
+        page_manager.prefetch(&[1, 2, 3]);
+
+        let page = page_manager
+            .fetch_page(3)
+            .unwrap()
+            .expect("Failed to fetch a prefetched page");
+        assert_eq!(3, page.read().unwrap().data[0]);
+
+        // Pages read ahead of the requested one are kept in memory.
+        assert_eq!(3, page_manager.loaded_page_count());
+        assert_eq!(
+            1,
+            page_manager
+                .fetch_page(1)
+                .unwrap()
+                .unwrap()
+                .read()
+                .unwrap()
+                .data[0]
+        );
+        assert_eq!(
+            0,
+            page_manager
+                .fetch_page(0)
+                .unwrap()
+                .unwrap()
+                .read()
+                .unwrap()
+                .data[0]
+        );
+
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_freeing_pages() {
+        let filename =
+            std::env::temp_dir().join(format!("bsql_page_manager_free_{}.db", std::process::id()));
+        let filename = filename.to_str().unwrap();
+
+        let mut page_manager = PageManager::new(filename);
+        for index in 0..4 {
+            let (_page_id, page) = page_manager.create_page();
+            page.write().unwrap().data[0] = index + 1;
+        }
+        page_manager.free_page(2);
+        page_manager.free_page(1);
+        page_manager.free_page(1);
+        assert_eq!(2, page_manager.free_page_count());
+        assert_eq!(
+            0,
+            page_manager
+                .fetch_page(2)
+                .unwrap()
+                .unwrap()
+                .read()
+                .unwrap()
+                .data[0]
+        );
+        page_manager.commit().unwrap();
+
+        // The free list is rebuilt from the backend.
+        let mut page_manager = PageManager::new(filename);
+        assert_eq!(2, page_manager.free_page_count());
+        assert_eq!(
+            4,
+            page_manager
+                .fetch_page(3)
+                .unwrap()
+                .unwrap()
+                .read()
+                .unwrap()
+                .data[0]
+        );
+
+        // The lowest free page id is used first, and the page is cleared.
+        let (page_id, page) = page_manager.create_page();
+        assert_eq!(1, page_id);
+        assert!(page.read().unwrap().metadata.iter().all(|byte| *byte == 0));
+        assert_eq!(2, page_manager.create_page().0);
+        assert_eq!(4, page_manager.create_page().0);
+        assert_eq!(0, page_manager.free_page_count());
+
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_custom_storage_backends() {
+        let mut page_manager = PageManager::with_backend(Box::new(MemoryBackend::new()));
+        let (page_id, page) = page_manager.create_page();
+        page.write().unwrap().data[0] = 42;
+        drop(page);
+
+        page_manager.shrink_to_fit();
+        assert_eq!(0, page_manager.loaded_page_count());
+
+        let page = page_manager
+            .fetch_page(page_id)
+            .unwrap()
+            .expect("Failed to reload the released page");
+        assert_eq!(42, page.read().unwrap().data[0]);
+    }
+
+    #[test]
+    fn test_rolling_back_transactions() {
+        let mut page_manager = PageManager::with_backend(Box::new(MemoryBackend::new()));
+        let (_page_id, page) = page_manager.create_page();
+        let (evicted_page_id, evicted_page) = page_manager.create_page();
+        page.write().unwrap().data[0] = 1;
+        evicted_page.write().unwrap().data[0] = 1;
+        drop(evicted_page);
+        page_manager.shrink_to_fit();
+        assert_eq!(1, page_manager.loaded_page_count());
+
+        page_manager.begin_transaction();
+        page.write().unwrap().data[0] = 2;
+        page_manager
+            .fetch_page(evicted_page_id)
+            .unwrap()
+            .unwrap()
+            .write()
+            .unwrap()
+            .data[0] = 2;
+        page_manager.create_page();
+
+        // Nothing is written or released during the transaction.
+        page_manager.commit().unwrap();
+        page_manager.shrink_to_fit();
+        assert_eq!(3, page_manager.loaded_page_count());
+
+        page_manager.rollback_transaction();
+        assert!(!page_manager.in_transaction());
+        assert_eq!(1, page.read().unwrap().data[0]);
+        assert_eq!(
+            1,
+            page_manager
+                .fetch_page(evicted_page_id)
+                .unwrap()
+                .unwrap()
+                .read()
+                .unwrap()
+                .data[0]
+        );
+        assert!(page_manager.fetch_page(2).unwrap().is_none());
+
+        page_manager.begin_transaction();
+        page.write().unwrap().data[0] = 3;
+        page_manager.commit_transaction().unwrap();
+        drop(page);
+        page_manager.shrink_to_fit();
+        assert_eq!(0, page_manager.loaded_page_count());
+        assert_eq!(
+            3,
+            page_manager
+                .fetch_page(0)
+                .unwrap()
+                .unwrap()
+                .read()
+                .unwrap()
+                .data[0]
+        );
+    }
+
+    #[test]
+    fn test_rolling_back_to_savepoints() {
+        let mut page_manager = PageManager::new(":memory:");
+        let (_page_id, page) = page_manager.create_page();
+        assert!(!page_manager.create_savepoint("before"));
+
+        page_manager.begin_transaction();
+        page.write().unwrap().data[0] = 1;
+        assert!(page_manager.create_savepoint("first"));
+        page.write().unwrap().data[0] = 2;
+        page_manager.create_page();
+        assert!(page_manager.create_savepoint("second"));
+        page.write().unwrap().data[0] = 3;
+
+        assert!(page_manager.rollback_to_savepoint("first"));
+        assert_eq!(1, page.read().unwrap().data[0]);
+        assert_eq!(1, page_manager.page_count());
+
+        // Rolling back removes the later savepoints, but keeps the savepoint itself.
+        assert!(!page_manager.rollback_to_savepoint("second"));
+        page.write().unwrap().data[0] = 4;
+        assert!(page_manager.rollback_to_savepoint("first"));
+        assert_eq!(1, page.read().unwrap().data[0]);
+
+        assert!(page_manager.release_savepoint("first"));
+        assert!(!page_manager.rollback_to_savepoint("first"));
+        assert!(page_manager.in_transaction());
+
+        page_manager.rollback_transaction();
+        assert_eq!(0, page.read().unwrap().data[0]);
+    }
+
+    #[test]
+    fn test_flushing_dirty_pages() {
+        let mut page_manager = PageManager::with_backend(Box::new(MemoryBackend::new()));
+        let (_page_id, page) = page_manager.create_page();
+        page_manager.create_page();
+        assert_eq!(vec![0, 1], page_manager.dirty_page_ids());
+
+        assert_eq!(Ok(2), page_manager.flush());
+        assert!(page_manager.dirty_page_ids().is_empty());
+        assert_eq!(Ok(0), page_manager.flush());
+
+        page.write().unwrap().data[0] = 1;
+        assert!(page_manager.is_dirty(0));
+        assert_eq!(Ok(false), page_manager.flush_page(1));
+        assert_eq!(Ok(true), page_manager.flush_page(0));
+        assert!(!page_manager.is_dirty(0));
+
+        // Locking a page for writing marks it as dirty, even when it isn't changed.
+        drop(page.write().unwrap());
+        assert!(page_manager.is_dirty(0));
+        assert_eq!(Ok(true), page_manager.flush_page(0));
+        assert!(!page.is_dirty());
+
+        // Nothing is written during a transaction.
+        page_manager.begin_transaction();
+        page.write().unwrap().data[0] = 3;
+        assert_eq!(Ok(0), page_manager.flush());
+        assert!(page_manager.is_dirty(0));
+        page_manager.rollback_transaction();
+        assert!(!page_manager.is_dirty(0));
+    }
+
+    /// Fails every write, like a full disk.
+    struct FailingBackend;
+
+    impl StorageBackend for FailingBackend {
+        fn read_all(&self) -> io::Result<Vec<Vec<u8>>> {
+            Ok(vec![])
+        }
+
+        fn read_page(&self, _page_id: PageId) -> io::Result<Vec<u8>> {
+            Err(io::ErrorKind::UnexpectedEof.into())
+        }
+
+        fn write_pages(&self, _pages: Vec<(PageId, Vec<u8>)>) -> io::Result<()> {
+            Err(io::Error::new(io::ErrorKind::Other, "disk full"))
+        }
+    }
+
+    #[test]
+    fn test_failed_writes_keep_pages_dirty() {
+        let mut page_manager = PageManager::with_backend(Box::new(FailingBackend));
+        page_manager.create_page();
+
+        assert_eq!(
+            Err(Error::StorageFailed("disk full".to_string())),
+            page_manager.commit()
+        );
+        assert!(page_manager.is_dirty(0));
+
+        // Dirty pages aren't released, since they couldn't be read back.
+        page_manager.shrink_to_fit();
+        assert_eq!(1, page_manager.loaded_page_count());
+
+        page_manager.begin_transaction();
+        assert_eq!(
+            Err(Error::StorageFailed("disk full".to_string())),
+            page_manager.commit_transaction()
+        );
+        assert!(!page_manager.in_transaction());
+    }
+
+    #[test]
+    fn test_unreadable_files_are_not_written() {
+        let page_manager = PageManager::new("/bsql-directory-that-does-not-exist/bsql.db");
+        assert!(!page_manager.is_healthy());
+        assert!(matches!(page_manager.flush(), Err(Error::StorageFailed(_))));
+    }
+
+    /// Counts how often the pages are synced.
+    struct SyncCountingBackend {
+        backend: MemoryBackend,
+        sync_count: Rc<std::cell::Cell<usize>>,
+    }
+
+    impl StorageBackend for SyncCountingBackend {
+        fn read_all(&self) -> io::Result<Vec<Vec<u8>>> {
+            self.backend.read_all()
+        }
+
+        fn read_page(&self, page_id: PageId) -> io::Result<Vec<u8>> {
+            self.backend.read_page(page_id)
+        }
+
+        fn write_pages(&self, pages: Vec<(PageId, Vec<u8>)>) -> io::Result<()> {
+            self.backend.write_pages(pages)
+        }
+
+        fn sync(&self) -> io::Result<()> {
+            self.sync_count.set(self.sync_count.get() + 1);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_synchronous_modes() {
+        let sync_count = Rc::new(std::cell::Cell::new(0));
+        let mut page_manager = PageManager::with_backend(Box::new(SyncCountingBackend {
+            backend: MemoryBackend::new(),
+            sync_count: sync_count.clone(),
+        }));
+        assert_eq!(SynchronousMode::Normal, page_manager.synchronous_mode());
+        let (_page_id, page) = page_manager.create_page();
+
+        page_manager.commit().unwrap();
+        assert_eq!(0, sync_count.get());
+        page_manager.flush().unwrap();
+        assert_eq!(1, sync_count.get());
+        page_manager.begin_transaction();
+        page.write().unwrap().data[0] = 1;
+        page_manager.commit_transaction().unwrap();
+        assert_eq!(2, sync_count.get());
+
+        page_manager.set_synchronous_mode(SynchronousMode::Full);
+        page_manager.commit().unwrap();
+        assert_eq!(2, sync_count.get(), "Nothing was written");
+        page.write().unwrap().data[0] = 2;
+        page_manager.commit().unwrap();
+        assert_eq!(3, sync_count.get());
+
+        page_manager.set_synchronous_mode(SynchronousMode::Off);
+        page.write().unwrap().data[0] = 3;
+        page_manager.commit().unwrap();
+        page_manager.flush().unwrap();
+        page_manager.begin_transaction();
+        page_manager.commit_transaction().unwrap();
+        assert_eq!(3, sync_count.get());
+        assert!(!page_manager.is_dirty(0));
+    }
+
+    #[test]
+    fn test_shrinking_keeps_in_memory_pages() {
+        let mut page_manager = PageManager::new(":memory:");
+        page_manager.create_page();
+
+        page_manager.shrink_to_fit();
+
+        assert_eq!(1, page_manager.loaded_page_count());
+        assert!(page_manager.fetch_page(0).unwrap().is_some());
     }
 }
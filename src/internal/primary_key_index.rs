@@ -0,0 +1,268 @@
+use std::ops::RangeBounds;
+use std::rc::Rc;
+use std::sync::RwLock;
+
+use super::{BTree, BitmapIndex, Error, PageId, PageManager, SharedInternalPage};
+
+const LEGACY_KEY_BITMAP_RANGE: std::ops::Range<usize> = 0..32;
+const LEGACY_RECORD_ID_SIZE: usize = 8;
+
+const VERSION_OFFSET: usize = 32;
+const LEGACY_NEXT_PAGE_ID_RANGE: std::ops::Range<usize> = 33..37;
+const LEGACY_ENTRY_COUNT_RANGE: std::ops::Range<usize> = 37..41;
+const LEGACY_ENTRY_SIZE: usize = 16;
+
+const VERSION: u8 = 2;
+
+/// Maps the values of a `PRIMARY KEY` column to the ids of their records. The index is a `BTree`
+/// whose root has the version of the index at 32 of its metadata.
+///
+/// Older versions of the index are migrated when they are loaded:
+/// - Version 1 was a chain of pages, where the metadata of every page had the id of the next page
+///   (0 for the last page) at 33..37 and the number of entries in its data at 37..41. Every entry
+///   was the key followed by the record id.
+/// - When integers fit in a single byte, the index was a single page with a slot for every
+///   possible key: a bitmap of the keys in use at 0..32 of the metadata and the record id of every
+///   key in the data. Those pages have version 0.
+pub struct PrimaryKeyIndex {
+    tree: BTree,
+}
+
+impl PrimaryKeyIndex {
+    pub fn initialize(
+        page_manager: Rc<RwLock<PageManager>>,
+        shared_page: SharedInternalPage,
+    ) -> Self {
+        {
+            let mut page = shared_page.write().unwrap();
+            page.metadata[LEGACY_KEY_BITMAP_RANGE].fill(0);
+            page.metadata[VERSION_OFFSET] = VERSION;
+        }
+
+        Self {
+            tree: BTree::initialize(page_manager, shared_page),
+        }
+    }
+
+    pub fn load(
+        page_manager: Rc<RwLock<PageManager>>,
+        shared_page: SharedInternalPage,
+    ) -> Result<Self, Error> {
+        let version = shared_page.read().unwrap().metadata[VERSION_OFFSET];
+        let legacy_entries = match version {
+            VERSION => {
+                return Ok(Self {
+                    tree: BTree::load(page_manager, shared_page),
+                })
+            }
+            1 => Self::chained_entries(&page_manager, &shared_page)?,
+            _ => Self::single_byte_entries(&shared_page),
+        };
+
+        // The pages of the chain after the first one aren't used anymore.
+        let mut index = Self::initialize(page_manager, shared_page);
+        for (key, record_id) in legacy_entries {
+            index.insert(key, record_id)?;
+        }
+
+        Ok(index)
+    }
+
+    /// Returns the id of the record with the given key, if there is one.
+    pub fn get(&self, key: i64) -> Result<Option<u64>, Error> {
+        self.tree.get(key)
+    }
+
+    /// Points `key` at `record_id`, replacing the record it pointed at before.
+    pub fn insert(&mut self, key: i64, record_id: u64) -> Result<(), Error> {
+        self.tree.insert(key, record_id)
+    }
+
+    /// Removes `key` from the index, but only if it still points at `record_id`.
+    pub fn remove(&mut self, key: i64, record_id: u64) -> Result<(), Error> {
+        if self.tree.get(key)? == Some(record_id) {
+            self.tree.remove(key)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns every key in the index together with the id of its record, ordered by key.
+    pub fn entries(&self) -> Result<Vec<(i64, u64)>, Error> {
+        self.range(..)
+    }
+
+    /// Returns the keys in `range` together with the ids of their records, ordered by key.
+    pub fn range(&self, range: impl RangeBounds<i64>) -> Result<Vec<(i64, u64)>, Error> {
+        self.tree.range(range)
+    }
+
+    /// Returns the number of pages of the index.
+    pub fn page_count(&self) -> Result<u32, Error> {
+        self.tree.page_count()
+    }
+
+    fn chained_entries(
+        page_manager: &Rc<RwLock<PageManager>>,
+        shared_page: &SharedInternalPage,
+    ) -> Result<Vec<(i64, u64)>, Error> {
+        let mut entries = vec![];
+        let mut page = Some(shared_page.clone());
+
+        while let Some(shared_page) = page {
+            let page_data = shared_page.read().unwrap();
+            let entry_count = u32::from_be_bytes(
+                page_data.metadata[LEGACY_ENTRY_COUNT_RANGE]
+                    .try_into()
+                    .unwrap(),
+            ) as usize;
+            entries.extend((0..entry_count).map(|position| {
+                let offset = position * LEGACY_ENTRY_SIZE;
+                (
+                    i64::from_be_bytes(page_data.data[offset..offset + 8].try_into().unwrap()),
+                    u64::from_be_bytes(
+                        page_data.data[offset + 8..offset + LEGACY_ENTRY_SIZE]
+                            .try_into()
+                            .unwrap(),
+                    ),
+                )
+            }));
+
+            let next_page_id = PageId::from_be_bytes(
+                page_data.metadata[LEGACY_NEXT_PAGE_ID_RANGE]
+                    .try_into()
+                    .unwrap(),
+            );
+            page = match next_page_id {
+                0 => None,
+                next_page_id => page_manager.read().unwrap().fetch_page(next_page_id)?,
+            };
+        }
+
+        Ok(entries)
+    }
+
+    fn single_byte_entries(shared_page: &SharedInternalPage) -> Vec<(i64, u64)> {
+        let mut page = shared_page.write().unwrap();
+        let key_index =
+            BitmapIndex::from_raw(&mut page.metadata[LEGACY_KEY_BITMAP_RANGE], 255).unwrap();
+
+        key_index
+            .indices()
+            .into_iter()
+            .map(|key| {
+                let offset = key as usize * LEGACY_RECORD_ID_SIZE;
+                let record_id = u64::from_be_bytes(
+                    page.data[offset..offset + LEGACY_RECORD_ID_SIZE]
+                        .try_into()
+                        .unwrap(),
+                );
+                (key as i64, record_id)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::internal::{InternalPage, LockedPage};
+
+    #[test]
+    fn test_inserting_and_removing_keys() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let shared_page = Rc::new(LockedPage::new(InternalPage::new()));
+        let mut index = PrimaryKeyIndex::initialize(page_manager.clone(), shared_page.clone());
+
+        index.insert(0, 1 << 32).unwrap();
+        index.insert(-70_000, 2 << 32 | 7).unwrap();
+        assert_eq!(Some(1 << 32), index.get(0).unwrap());
+        assert_eq!(Some(2 << 32 | 7), index.get(-70_000).unwrap());
+        assert_eq!(None, index.get(1).unwrap());
+
+        index.insert(0, 3 << 32).unwrap();
+        assert_eq!(Some(3 << 32), index.get(0).unwrap());
+
+        // Keys are only removed if they still point at the removed record.
+        index.remove(0, 1 << 32).unwrap();
+        assert_eq!(Some(3 << 32), index.get(0).unwrap());
+        index.remove(0, 3 << 32).unwrap();
+        assert_eq!(None, index.get(0).unwrap());
+
+        let loaded_index = PrimaryKeyIndex::load(page_manager, shared_page).unwrap();
+        assert_eq!(
+            vec![(-70_000, 2 << 32 | 7)],
+            loaded_index.entries().unwrap()
+        );
+    }
+
+    #[test]
+    fn test_growing_beyond_a_single_page() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let (_page_id, shared_page) = page_manager.write().unwrap().create_page();
+        let mut index = PrimaryKeyIndex::initialize(page_manager.clone(), shared_page.clone());
+
+        let keys: Vec<i64> = (0..1000).map(|key| key * 1_000_003).collect();
+        for (record_id, key) in keys.iter().enumerate() {
+            index.insert(*key, record_id as u64).unwrap();
+        }
+        // The keys are inserted in order, so every leaf is split in half: the root points at 7
+        // leaves.
+        assert_eq!(8, index.page_count().unwrap());
+        assert_eq!(
+            vec![(keys[10], 10), (keys[11], 11)],
+            index.range(keys[10]..=keys[11]).unwrap()
+        );
+
+        index.remove(keys[10], 10).unwrap();
+        let loaded_index = PrimaryKeyIndex::load(page_manager, shared_page).unwrap();
+        assert_eq!(None, loaded_index.get(keys[10]).unwrap());
+        assert_eq!(Some(999), loaded_index.get(keys[999]).unwrap());
+        assert_eq!(999, loaded_index.entries().unwrap().len());
+    }
+
+    #[test]
+    fn test_loading_chained_index_pages() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let (_page_id, shared_page) = page_manager.write().unwrap().create_page();
+        let (next_page_id, next_page) = page_manager.write().unwrap().create_page();
+
+        // Write the pages like they were written by version 1 of the index.
+        for (page, next_page_id, key) in [(&shared_page, next_page_id, 7i64), (&next_page, 0, -3)] {
+            let mut page = page.write().unwrap();
+            page.metadata[VERSION_OFFSET] = 1;
+            page.metadata[LEGACY_NEXT_PAGE_ID_RANGE].copy_from_slice(&next_page_id.to_be_bytes());
+            page.metadata[LEGACY_ENTRY_COUNT_RANGE].copy_from_slice(&1u32.to_be_bytes());
+            page.data[0..8].copy_from_slice(&key.to_be_bytes());
+            page.data[8..16].copy_from_slice(&((key + 100) as u64).to_be_bytes());
+        }
+
+        let index = PrimaryKeyIndex::load(page_manager, shared_page.clone()).unwrap();
+        assert_eq!(vec![(-3, 97), (7, 107)], index.entries().unwrap());
+        assert_eq!(
+            VERSION,
+            shared_page.read().unwrap().metadata[VERSION_OFFSET]
+        );
+    }
+
+    #[test]
+    fn test_loading_legacy_index_pages() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let shared_page = Rc::new(LockedPage::new(InternalPage::new()));
+
+        {
+            // Write the page like it was written when keys were a single byte.
+            let mut page = shared_page.write().unwrap();
+            page.metadata[0] = 0b0000_0001;
+            page.metadata[31] = 0b0100_0000;
+            page.data[0..8].copy_from_slice(&(1u64 << 32).to_be_bytes());
+            page.data[254 * 8..255 * 8].copy_from_slice(&(2u64 << 32 | 7).to_be_bytes());
+        }
+
+        let index = PrimaryKeyIndex::load(page_manager, shared_page).unwrap();
+        assert_eq!(
+            vec![(0, 1 << 32), (254, 2 << 32 | 7)],
+            index.entries().unwrap()
+        );
+    }
+}
@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use super::{Error, QueryResult};
+
+/// Receives an entry for every logged query, see `Manager::set_query_log`.
+pub type QueryLog = Box<dyn FnMut(&QueryLogEntry)>;
+
+#[derive(Debug)]
+pub struct QueryLogEntry<'a> {
+    pub database_name: &'a str,
+    pub query: &'a str,
+    pub duration: Duration,
+
+    /// The number of rows that were returned or inserted.
+    pub rows: usize,
+    pub error: Option<&'a Error>,
+}
+
+impl<'a> QueryLogEntry<'a> {
+    pub fn new(
+        database_name: &'a str,
+        query: &'a str,
+        duration: Duration,
+        result: &'a Result<QueryResult, Error>,
+    ) -> Self {
+        let rows = match result {
+            Ok(QueryResult::RowResult(row_result)) => row_result.rows().len(),
+            Ok(QueryResult::InsertSuccess { count }) => *count,
+            _ => 0,
+        };
+
+        Self {
+            database_name,
+            query,
+            duration,
+            rows,
+            error: result.as_ref().err(),
+        }
+    }
+}
@@ -1,8 +1,28 @@
+use super::row_result::RowValues;
 use super::RowResult;
 
 #[derive(Debug, PartialEq)]
 pub enum QueryResult {
     CommandSuccessMessage(String),
-    InsertSuccess { count: usize },
+    InsertSuccess {
+        count: usize,
+    },
     RowResult(RowResult),
+    /// The database that the following statements run in, after `USE database_name`.
+    DatabaseChanged {
+        database_name: String,
+    },
+}
+
+/// Iterates over the rows of a `RowResult`. The other results don't have any rows.
+impl IntoIterator for QueryResult {
+    type Item = RowValues;
+    type IntoIter = alloc::vec::IntoIter<RowValues>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        match self {
+            QueryResult::RowResult(row_result) => row_result.into_iter(),
+            _ => vec![].into_iter(),
+        }
+    }
 }
@@ -0,0 +1,54 @@
+use super::Clock;
+
+/// A small, deterministic pseudo-random number generator (SplitMix64). It's not suitable for
+/// anything security related, but the same seed always produces the same sequence of numbers.
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Seeds the generator from `clock`, for when the sequence doesn't have to be reproducible.
+    pub fn from_clock(clock: &dyn Clock) -> Self {
+        Self::new(clock.since_epoch().as_nanos() as u64)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E3779B97F4A7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    pub fn next_u128(&mut self) -> u128 {
+        u128::from(self.next_u64()) << 64 | u128::from(self.next_u64())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_seed_gives_same_sequence() {
+        let mut first = Random::new(42);
+        let mut second = Random::new(42);
+        let mut other = Random::new(43);
+
+        let sequence: Vec<u64> = (0..8).map(|_| first.next_u64()).collect();
+
+        assert_eq!(
+            sequence,
+            (0..8).map(|_| second.next_u64()).collect::<Vec<u64>>()
+        );
+        assert_ne!(
+            sequence,
+            (0..8).map(|_| other.next_u64()).collect::<Vec<u64>>()
+        );
+    }
+}
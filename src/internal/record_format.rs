@@ -0,0 +1,436 @@
+use super::{ColumnDefinition, DataType, Value};
+
+/// The size of the length that precedes values without a fixed size, see `LengthPrefixed`.
+pub const LENGTH_PREFIX_SIZE: usize = 2;
+
+/// How the values of a record are laid out in the data of a `TablePage`. Every page stores the
+/// version of the format it was written with, so pages written before the layout changed can still
+/// be read.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordFormat {
+    /// Version 0: the values are packed without any padding. Pages written before the format was
+    /// versioned have a 0 where the version is stored, so they are read with this format.
+    Packed,
+
+    /// Version 1: every value starts at a multiple of the alignment of its data type and every
+    /// record is padded to a multiple of the largest alignment of its values.
+    Aligned,
+
+    /// Version 2: like `Aligned`, but integers are 8 bytes instead of a single unsigned byte.
+    WideIntegers,
+
+    /// Version 3: records have different sizes, so every slot of the page points at its record.
+    /// The values are stored in order without padding, and values without a fixed size (text and
+    /// bytea) are prefixed with their length as a `u16`. Only used for pages with such columns.
+    LengthPrefixed,
+
+    /// Version 4: like `WideIntegers`, but every record starts with a bitmap of its `NULL` values,
+    /// see `null_bitmap_size`. The bytes of `NULL` values are zeroed.
+    NullableWideIntegers,
+
+    /// Version 5: like `LengthPrefixed`, but every record starts with a bitmap of its `NULL`
+    /// values, which take no room in the record.
+    NullableLengthPrefixed,
+
+    /// Version 6: like `NullableWideIntegers`, but the bitmap of the used slots is at the start of
+    /// the data and is sized for the number of records that fit in the page, so that pages can
+    /// hold more than 255 small records.
+    WideSlots,
+}
+
+impl RecordFormat {
+    /// The format that new pages with the given columns are written with.
+    pub fn for_columns(column_definitions: &[ColumnDefinition]) -> Self {
+        if column_definitions
+            .iter()
+            .any(|column_definition| column_definition.data_type().bsql_size().is_none())
+        {
+            RecordFormat::NullableLengthPrefixed
+        } else {
+            RecordFormat::WideSlots
+        }
+    }
+
+    /// Returns the format with the given version, or `None` for versions written by a newer
+    /// version of bsql.
+    pub fn from_version(version: u8) -> Option<Self> {
+        match version {
+            0 => Some(RecordFormat::Packed),
+            1 => Some(RecordFormat::Aligned),
+            2 => Some(RecordFormat::WideIntegers),
+            3 => Some(RecordFormat::LengthPrefixed),
+            4 => Some(RecordFormat::NullableWideIntegers),
+            5 => Some(RecordFormat::NullableLengthPrefixed),
+            6 => Some(RecordFormat::WideSlots),
+
+            _ => None,
+        }
+    }
+
+    pub fn version(&self) -> u8 {
+        match self {
+            RecordFormat::Packed => 0,
+            RecordFormat::Aligned => 1,
+            RecordFormat::WideIntegers => 2,
+            RecordFormat::LengthPrefixed => 3,
+            RecordFormat::NullableWideIntegers => 4,
+            RecordFormat::NullableLengthPrefixed => 5,
+            RecordFormat::WideSlots => 6,
+        }
+    }
+
+    /// Returns whether records have different sizes, so that every slot of the page points at its
+    /// record.
+    pub fn is_length_prefixed(&self) -> bool {
+        matches!(
+            self,
+            RecordFormat::LengthPrefixed | RecordFormat::NullableLengthPrefixed
+        )
+    }
+
+    /// Returns whether the bitmap of the used slots is sized for the capacity of the page, instead
+    /// of having 255 slots.
+    pub fn has_wide_slots(&self) -> bool {
+        matches!(self, RecordFormat::WideSlots)
+    }
+
+    /// Returns the size of the bitmap at the start of records with `column_count` values, which
+    /// has a bit for every value that is set when the value is `NULL`. Formats that can't store
+    /// `NULL` don't have a bitmap.
+    pub fn null_bitmap_size(&self, column_count: usize) -> usize {
+        match self {
+            RecordFormat::NullableWideIntegers
+            | RecordFormat::NullableLengthPrefixed
+            | RecordFormat::WideSlots => (column_count + 7) / 8,
+            _ => 0,
+        }
+    }
+
+    /// Returns whether the bit of the value at `index` is set in a bitmap of `NULL` values.
+    pub fn is_null(null_bitmap: &[u8], index: usize) -> bool {
+        null_bitmap
+            .get(index / 8)
+            .is_some_and(|byte| byte & (1 << (index % 8)) != 0)
+    }
+
+    /// Sets the bit of the value at `index` in a bitmap of `NULL` values.
+    pub fn set_null(null_bitmap: &mut [u8], index: usize) {
+        null_bitmap[index / 8] |= 1 << (index % 8);
+    }
+
+    /// Returns the size and the alignment of the values of `data_type` in records of this format.
+    /// Values without a fixed size only have their length prefix in the record.
+    pub fn value_layout(&self, data_type: &DataType) -> (usize, usize) {
+        match (self, data_type) {
+            (RecordFormat::Packed | RecordFormat::Aligned, DataType::Integer) => (1, 1),
+            _ => (
+                data_type
+                    .bsql_size()
+                    .map_or(LENGTH_PREFIX_SIZE, |size| size as usize),
+                data_type.bsql_alignment() as usize,
+            ),
+        }
+    }
+
+    /// Returns the size of the record that `write_length_prefixed` writes, without serializing
+    /// it.
+    pub fn length_prefixed_size(&self, values: &[Option<Value>]) -> usize {
+        self.null_bitmap_size(values.len())
+            + values
+                .iter()
+                .flatten()
+                .map(|value| match value {
+                    Value::Text(_) | Value::Bytes(_) | Value::Json(_) => {
+                        LENGTH_PREFIX_SIZE + value.bsql_data_len()
+                    }
+                    _ => value.bsql_data_len(),
+                })
+                .sum::<usize>()
+    }
+
+    /// Serializes a record with one of the length-prefixed formats into the start of `data`, which
+    /// has to be at least `length_prefixed_size` bytes. Panics when a value is `NULL` and the
+    /// format doesn't have a bitmap of `NULL` values.
+    pub fn write_length_prefixed(&self, values: &[Option<Value>], data: &mut [u8]) {
+        let null_bitmap_size = self.null_bitmap_size(values.len());
+        data[..null_bitmap_size].fill(0);
+        let mut offset = null_bitmap_size;
+
+        for (index, value) in values.iter().enumerate() {
+            let Some(value) = value else {
+                RecordFormat::set_null(&mut data[..null_bitmap_size], index);
+                continue;
+            };
+
+            if let Value::Text(_) | Value::Bytes(_) | Value::Json(_) = value {
+                let length = value.bsql_data_len() as u16;
+                data[offset..offset + LENGTH_PREFIX_SIZE].copy_from_slice(&length.to_be_bytes());
+                offset += LENGTH_PREFIX_SIZE;
+            }
+            offset += value.write_bsql_data(&mut data[offset..]);
+        }
+    }
+
+    /// Deserializes the values of the columns at `positions`, in that order, of a record that was
+    /// serialized with `write_length_prefixed`. The other values are skipped by their sizes.
+    /// Returns `None` when the data doesn't hold a value for every column.
+    pub fn decode_length_prefixed_columns(
+        &self,
+        column_definitions: &[ColumnDefinition],
+        data: &[u8],
+        positions: &[usize],
+    ) -> Option<Vec<Option<Value>>> {
+        let null_bitmap = data.get(..self.null_bitmap_size(column_definitions.len()))?;
+        let mut offset = null_bitmap.len();
+
+        // The range of the data of every value, or `None` for `NULL`.
+        let mut value_ranges = Vec::with_capacity(column_definitions.len());
+        for (index, column_definition) in column_definitions.iter().enumerate() {
+            if RecordFormat::is_null(null_bitmap, index) {
+                value_ranges.push(None);
+                continue;
+            }
+
+            let size = match column_definition.data_type().bsql_size() {
+                Some(size) => size as usize,
+                None => {
+                    let prefix = data.get(offset..offset + LENGTH_PREFIX_SIZE)?;
+                    offset += LENGTH_PREFIX_SIZE;
+                    u16::from_be_bytes(prefix.try_into().unwrap()) as usize
+                }
+            };
+
+            value_ranges.push(Some(offset..offset + size));
+            offset += size;
+        }
+        data.get(..offset)?;
+
+        positions
+            .iter()
+            .map(|position| match &value_ranges[*position] {
+                Some(range) => DataType::to_bsql_value(
+                    column_definitions[*position].data_type(),
+                    &data[range.clone()],
+                )
+                .map(Some),
+                None => Some(None),
+            })
+            .collect()
+    }
+
+    /// Returns the offset of every value in a record with the given columns, followed by the size
+    /// of the record. Only meaningful for the formats where every record has the same size.
+    pub fn layout(&self, column_definitions: &[ColumnDefinition]) -> (Vec<usize>, usize) {
+        let null_bitmap_size = self.null_bitmap_size(column_definitions.len());
+        let fields: Vec<(usize, usize)> = (null_bitmap_size > 0)
+            .then_some((null_bitmap_size, 1))
+            .into_iter()
+            .chain(
+                column_definitions
+                    .iter()
+                    .map(|column_definition| self.value_layout(column_definition.data_type())),
+            )
+            .collect();
+
+        let (mut offsets, record_size) = self.field_layout(&fields);
+        if null_bitmap_size > 0 {
+            offsets.remove(0);
+        }
+
+        (offsets, record_size)
+    }
+
+    /// Lays out fields given as `(size, alignment)`, see `layout`.
+    fn field_layout(&self, fields: &[(usize, usize)]) -> (Vec<usize>, usize) {
+        let mut offsets = Vec::with_capacity(fields.len());
+        let mut offset: usize = 0;
+
+        for (size, alignment) in fields {
+            if !matches!(self, RecordFormat::Packed) && !self.is_length_prefixed() {
+                offset = round_up(offset, *alignment);
+            }
+
+            offsets.push(offset);
+            offset += size;
+        }
+
+        let record_size = match self {
+            RecordFormat::Packed
+            | RecordFormat::LengthPrefixed
+            | RecordFormat::NullableLengthPrefixed => offset,
+            RecordFormat::Aligned
+            | RecordFormat::WideIntegers
+            | RecordFormat::NullableWideIntegers
+            | RecordFormat::WideSlots => {
+                let record_alignment = fields
+                    .iter()
+                    .map(|(_size, alignment)| *alignment)
+                    .max()
+                    .unwrap_or(1);
+                round_up(offset, record_alignment)
+            }
+        };
+
+        (offsets, record_size)
+    }
+}
+
+/// Rounds `value` up to the next multiple of `multiple`, like `usize::next_multiple_of`, which
+/// the toolchain of bsql doesn't have yet.
+fn round_up(value: usize, multiple: usize) -> usize {
+    (value + multiple - 1) / multiple * multiple
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_versions_round_trip() {
+        for format in [
+            RecordFormat::Packed,
+            RecordFormat::Aligned,
+            RecordFormat::WideIntegers,
+            RecordFormat::LengthPrefixed,
+            RecordFormat::NullableWideIntegers,
+            RecordFormat::NullableLengthPrefixed,
+            RecordFormat::WideSlots,
+        ] {
+            assert_eq!(Some(format), RecordFormat::from_version(format.version()));
+        }
+
+        assert_eq!(None, RecordFormat::from_version(7));
+    }
+
+    #[test]
+    fn test_formats_for_columns() {
+        let integer = ColumnDefinition::new(1, DataType::Integer, "id".to_string());
+        let text = ColumnDefinition::new(2, DataType::Text, "name".to_string());
+
+        assert_eq!(
+            RecordFormat::WideSlots,
+            RecordFormat::for_columns(std::slice::from_ref(&integer))
+        );
+        assert_eq!(
+            RecordFormat::NullableLengthPrefixed,
+            RecordFormat::for_columns(&[integer, text])
+        );
+    }
+
+    fn encode(record_format: RecordFormat, values: &[Option<Value>]) -> Vec<u8> {
+        let mut data = vec![0; record_format.length_prefixed_size(values)];
+        record_format.write_length_prefixed(values, &mut data);
+
+        data
+    }
+
+    #[test]
+    fn test_length_prefixed_records_round_trip() {
+        let column_definitions = [
+            ColumnDefinition::new(1, DataType::Text, "name".to_string()),
+            ColumnDefinition::new(2, DataType::Integer, "id".to_string()),
+            ColumnDefinition::new(3, DataType::Text, "note".to_string()),
+            ColumnDefinition::new(4, DataType::Bytea, "hash".to_string()),
+        ];
+        let values = vec![
+            Some(Value::Text("Zoë".to_string())),
+            Some(Value::Integer(-7)),
+            Some(Value::Text(String::new())),
+            Some(Value::Bytes(vec![0x00, 0xFF])),
+        ];
+        let record_format = RecordFormat::LengthPrefixed;
+
+        let data = encode(record_format, &values);
+        assert_eq!(2 + 4 + 8 + 2 + 2 + 2, data.len());
+        assert_eq!(
+            Some(values),
+            record_format.decode_length_prefixed_columns(&column_definitions, &data, &[0, 1, 2, 3])
+        );
+        assert_eq!(
+            None,
+            record_format.decode_length_prefixed_columns(&column_definitions, &data[..10], &[0])
+        );
+    }
+
+    #[test]
+    fn test_nullable_length_prefixed_records_round_trip() {
+        let column_definitions: Vec<ColumnDefinition> = (0..10)
+            .map(|column_id| {
+                ColumnDefinition::new(column_id, DataType::Text, format!("c{column_id}"))
+            })
+            .collect();
+        let values: Vec<Option<Value>> = (0..10)
+            .map(|index| (index % 3 != 0).then(|| Value::Text(index.to_string())))
+            .collect();
+        let record_format = RecordFormat::NullableLengthPrefixed;
+
+        let data = encode(record_format, &values);
+        assert_eq!([0b0100_1001, 0b0000_0010], data[..2]);
+        assert_eq!(2 + 6 * (2 + 1), data.len());
+
+        // The page data that a record is written to isn't necessarily zeroed.
+        let mut buffer = vec![0xFF; data.len() + 1];
+        record_format.write_length_prefixed(&values, &mut buffer);
+        assert_eq!(data[..], buffer[..data.len()]);
+        assert_eq!(
+            Some(vec![values[9].clone(), None, values[1].clone()]),
+            record_format.decode_length_prefixed_columns(&column_definitions, &data, &[9, 0, 1])
+        );
+        assert_eq!(
+            None,
+            record_format.decode_length_prefixed_columns(&column_definitions, &data[..1], &[0])
+        );
+    }
+
+    #[test]
+    fn test_field_layout() {
+        let fields = [(1, 1), (8, 8), (1, 1), (4, 4)];
+
+        assert_eq!(
+            (vec![0, 1, 9, 10], 14),
+            RecordFormat::Packed.field_layout(&fields)
+        );
+        assert_eq!(
+            (vec![0, 8, 16, 20], 24),
+            RecordFormat::Aligned.field_layout(&fields)
+        );
+        assert_eq!(
+            (vec![0, 8, 16, 20], 24),
+            RecordFormat::WideIntegers.field_layout(&fields)
+        );
+        assert_eq!((vec![], 0), RecordFormat::Aligned.field_layout(&[]));
+    }
+
+    #[test]
+    fn test_layout_with_a_null_bitmap() {
+        let column_definitions: Vec<ColumnDefinition> = (0..9)
+            .map(|column_id| {
+                ColumnDefinition::new(column_id, DataType::Integer, format!("c{column_id}"))
+            })
+            .collect();
+
+        // The 2 bytes of the bitmap are padded to the alignment of the first integer.
+        let (offsets, record_size) = RecordFormat::NullableWideIntegers.layout(&column_definitions);
+        assert_eq!(
+            (0..9).map(|index| 8 + index * 8).collect::<Vec<_>>(),
+            offsets
+        );
+        assert_eq!(80, record_size);
+
+        let (offsets, record_size) = RecordFormat::WideIntegers.layout(&column_definitions);
+        assert_eq!(0, offsets[0]);
+        assert_eq!(72, record_size);
+    }
+
+    #[test]
+    fn test_integer_sizes() {
+        assert_eq!(
+            (1, 1),
+            RecordFormat::Aligned.value_layout(&DataType::Integer)
+        );
+        assert_eq!(
+            (8, 8),
+            RecordFormat::WideIntegers.value_layout(&DataType::Integer)
+        );
+    }
+}
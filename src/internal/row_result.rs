@@ -31,4 +31,97 @@ impl RowResult {
     pub fn count(&self) -> &usize {
         &self.count
     }
+
+    /// Estimates the bytes of memory that the rows take up.
+    pub fn memory_usage(&self) -> usize {
+        self.rows
+            .iter()
+            .map(|row| Self::row_memory_usage(row))
+            .sum()
+    }
+
+    /// Estimates the bytes of memory that a single row takes up, see `memory_usage`.
+    pub fn row_memory_usage(row: &[Option<Value>]) -> usize {
+        row.iter()
+            .map(|value| {
+                core::mem::size_of::<Option<Value>>()
+                    + match value {
+                        Some(Value::Text(text)) => text.len(),
+                        _ => 0,
+                    }
+            })
+            .sum()
+    }
+
+    /// Returns an iterator over the rows, where every row pairs the name of each column with its
+    /// value, e.g. `[("id", Some(Value::Integer(1))), ("name", None)]`.
+    pub fn into_named_rows(self) -> impl Iterator<Item = Vec<(String, Option<Value>)>> {
+        let columns = self.columns;
+
+        self.rows
+            .into_iter()
+            .map(move |row| columns.iter().cloned().zip(row).collect())
+    }
+}
+
+/// Iterates over the rows, without copying them.
+impl IntoIterator for RowResult {
+    type Item = RowValues;
+    type IntoIter = alloc::vec::IntoIter<RowValues>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::QueryResult;
+    use super::*;
+
+    #[test]
+    fn test_iterating_over_rows() {
+        let row_result = || {
+            RowResult::new(
+                vec!["id".to_string(), "name".to_string()],
+                vec![
+                    vec![
+                        Some(Value::Integer(1)),
+                        Some(Value::Text("gin".to_string())),
+                    ],
+                    vec![Some(Value::Integer(2)), None],
+                ],
+            )
+        };
+
+        assert_eq!(
+            vec![
+                vec![
+                    Some(Value::Integer(1)),
+                    Some(Value::Text("gin".to_string()))
+                ],
+                vec![Some(Value::Integer(2)), None],
+            ],
+            row_result().into_iter().collect::<Vec<RowValues>>()
+        );
+        assert_eq!(
+            vec![
+                vec![
+                    ("id".to_string(), Some(Value::Integer(1))),
+                    ("name".to_string(), Some(Value::Text("gin".to_string()))),
+                ],
+                vec![
+                    ("id".to_string(), Some(Value::Integer(2))),
+                    ("name".to_string(), None),
+                ],
+            ],
+            row_result().into_named_rows().collect::<Vec<_>>()
+        );
+
+        assert_eq!(2, QueryResult::RowResult(row_result()).into_iter().count());
+        assert_eq!(
+            0,
+            QueryResult::InsertSuccess { count: 2 }.into_iter().count()
+        );
+    }
 }
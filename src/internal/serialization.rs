@@ -0,0 +1,175 @@
+//! `Serialize` and `Deserialize` implementations for the results of queries, behind the `serde`
+//! feature. A `RowResult` is serialized like the `json` output format of the shell: a sequence
+//! with a map from column name to value for every row.
+
+use core::fmt::{self, Formatter};
+
+use serde::de::{self, Visitor};
+use serde::ser::{SerializeMap, SerializeSeq, SerializeStructVariant};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use super::{Decimal, QueryResult, RowResult, Value};
+
+/// Integers and booleans are serialized as themselves. Decimals are serialized as strings so they
+/// keep all of their digits, and the other values as the text that `Display` writes for them,
+/// e.g. `"\\xbeef"` for bytes.
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            Value::Integer(value) => serializer.serialize_i64(*value),
+            Value::Boolean(value) => serializer.serialize_bool(*value),
+            Value::Text(value) | Value::Json(value) => serializer.serialize_str(value),
+            value => serializer.collect_str(value),
+        }
+    }
+}
+
+/// Deserializes integers, booleans, strings (as text) and floating point numbers (as decimals),
+/// e.g. to bind the values of a JSON request as parameters.
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl Visitor<'_> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.write_str("an integer, boolean, number or string")
+    }
+
+    fn visit_bool<E: de::Error>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Boolean(value))
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::Integer(value))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Value, E> {
+        i64::try_from(value)
+            .map(Value::Integer)
+            .map_err(|_| E::invalid_value(de::Unexpected::Unsigned(value), &self))
+    }
+
+    fn visit_f64<E: de::Error>(self, value: f64) -> Result<Value, E> {
+        Decimal::parse(&value.to_string())
+            .map(Value::Decimal)
+            .ok_or(E::invalid_value(de::Unexpected::Float(value), &self))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::Text(value.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, value: String) -> Result<Value, E> {
+        Ok(Value::Text(value))
+    }
+}
+
+impl Serialize for RowResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut rows = serializer.serialize_seq(Some(self.rows().len()))?;
+        for row in self.rows() {
+            rows.serialize_element(&Row {
+                columns: self.columns(),
+                values: row,
+            })?;
+        }
+
+        rows.end()
+    }
+}
+
+struct Row<'a> {
+    columns: &'a [String],
+    values: &'a [Option<Value>],
+}
+
+impl Serialize for Row<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut row = serializer.serialize_map(Some(self.columns.len()))?;
+        for (column, value) in self.columns.iter().zip(self.values) {
+            row.serialize_entry(column, value)?;
+        }
+
+        row.end()
+    }
+}
+
+/// Serialized like `#[derive(Serialize)]` would, e.g. `{"InsertSuccess": {"count": 1}}`.
+impl Serialize for QueryResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            QueryResult::CommandSuccessMessage(message) => serializer.serialize_newtype_variant(
+                "QueryResult",
+                0,
+                "CommandSuccessMessage",
+                message,
+            ),
+            QueryResult::InsertSuccess { count } => {
+                let mut variant =
+                    serializer.serialize_struct_variant("QueryResult", 1, "InsertSuccess", 1)?;
+                variant.serialize_field("count", count)?;
+                variant.end()
+            }
+            QueryResult::RowResult(row_result) => {
+                serializer.serialize_newtype_variant("QueryResult", 2, "RowResult", row_result)
+            }
+            QueryResult::DatabaseChanged { database_name } => {
+                let mut variant =
+                    serializer.serialize_struct_variant("QueryResult", 3, "DatabaseChanged", 1)?;
+                variant.serialize_field("database_name", database_name)?;
+                variant.end()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serializing_results() {
+        let row_result = RowResult::new(
+            vec!["id".to_string(), "price".to_string(), "hash".to_string()],
+            vec![
+                vec![
+                    Some(Value::Integer(1)),
+                    Some(Value::Decimal(Decimal::parse("4.50").unwrap())),
+                    Some(Value::Bytes(vec![0xBE, 0xEF])),
+                ],
+                vec![Some(Value::Integer(2)), None, None],
+            ],
+        );
+
+        assert_eq!(
+            r#"{"RowResult":[{"id":1,"price":"4.50","hash":"\\xbeef"},{"id":2,"price":null,"hash":null}]}"#,
+            serde_json::to_string(&QueryResult::RowResult(row_result)).unwrap()
+        );
+        assert_eq!(
+            r#"{"InsertSuccess":{"count":3}}"#,
+            serde_json::to_string(&QueryResult::InsertSuccess { count: 3 }).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_deserializing_values() {
+        assert_eq!(
+            vec![
+                Some(Value::Integer(1)),
+                Some(Value::Boolean(true)),
+                Some(Value::Text("gin".to_string())),
+                Some(Value::Decimal(Decimal::parse("4.5").unwrap())),
+                None,
+            ],
+            serde_json::from_str::<Vec<Option<Value>>>(r#"[1, true, "gin", 4.5, null]"#).unwrap()
+        );
+        assert!(serde_json::from_str::<Value>("18446744073709551615").is_err());
+        assert!(serde_json::from_str::<Value>("[1]").is_err());
+    }
+}
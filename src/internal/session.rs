@@ -0,0 +1,166 @@
+use super::{Error, FromRow, Manager, QueryResult, Value, Warning};
+
+/// The state of a single client of a `Manager`: the database its statements run against and the
+/// settings they run with, see `Manager::session`. `USE` statements change the database of the
+/// session.
+pub struct Session {
+    manager: Manager,
+    database_name: String,
+
+    /// Applied to the statements of this session only, see `Manager::set_query_memory_limit`.
+    query_memory_limit: Option<usize>,
+}
+
+impl Session {
+    pub fn new(manager: Manager, database_name: &str) -> Self {
+        Self {
+            query_memory_limit: manager.query_memory_limit(),
+            manager,
+            database_name: database_name.to_string(),
+        }
+    }
+
+    /// Returns the name of the database that the statements run against, which is empty until
+    /// the session connects to one.
+    pub fn database_name(&self) -> &str {
+        &self.database_name
+    }
+
+    /// Runs the statements from now on against `database_name`, like `USE`.
+    pub fn connect(&mut self, database_name: &str) -> Result<(), Error> {
+        if !self.manager.database_exists(database_name) {
+            return Err(Error::DatabaseDoesNotExist(database_name.to_string()));
+        }
+
+        self.database_name = database_name.to_string();
+        Ok(())
+    }
+
+    /// Returns whether a transaction was begun and not yet committed or rolled back.
+    pub fn in_transaction(&self) -> bool {
+        self.manager.in_transaction()
+    }
+
+    pub fn set_query_memory_limit(&mut self, limit: Option<usize>) {
+        self.query_memory_limit = limit;
+    }
+
+    pub fn query_memory_limit(&self) -> Option<usize> {
+        self.query_memory_limit
+    }
+
+    /// Executes `query` against the database of the session, see `Manager::execute`.
+    pub fn execute(&mut self, query: &str) -> Result<QueryResult, Error> {
+        self.execute_all(query).pop().unwrap()
+    }
+
+    /// Executes every statement of `query` against the database of the session, see
+    /// `Manager::execute_all`.
+    pub fn execute_all(&mut self, query: &str) -> Vec<Result<QueryResult, Error>> {
+        self.manager.set_query_memory_limit(self.query_memory_limit);
+        let results = self.manager.execute_all(&self.database_name, query);
+        for result in &results {
+            self.follow_database_change(result);
+        }
+
+        results
+    }
+
+    /// Executes `query` against the database of the session, see
+    /// `Manager::execute_with_parameters`.
+    pub fn execute_with_parameters(
+        &mut self,
+        query: &str,
+        parameters: &[Value],
+    ) -> Result<QueryResult, Error> {
+        self.manager.set_query_memory_limit(self.query_memory_limit);
+        let result = self
+            .manager
+            .execute_with_parameters(&self.database_name, query, parameters);
+        self.follow_database_change(&result);
+
+        result
+    }
+
+    /// Executes `query` against the database of the session, see `Manager::query_as`.
+    pub fn query_as<T: FromRow>(&mut self, query: &str) -> Result<Vec<T>, Error> {
+        self.manager.set_query_memory_limit(self.query_memory_limit);
+        self.manager.query_as(&self.database_name, query)
+    }
+
+    /// Returns the warnings raised by the last statements of the session.
+    pub fn warnings(&self) -> &[Warning] {
+        self.manager.warnings()
+    }
+
+    pub fn manager(&self) -> &Manager {
+        &self.manager
+    }
+
+    pub fn manager_mut(&mut self) -> &mut Manager {
+        &mut self.manager
+    }
+
+    pub fn into_manager(self) -> Manager {
+        self.manager
+    }
+
+    fn follow_database_change(&mut self, result: &Result<QueryResult, Error>) {
+        if let Ok(QueryResult::DatabaseChanged { database_name }) = result {
+            self.database_name = database_name.clone();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::sync::RwLock;
+
+    use super::*;
+    use crate::internal::PageManager;
+
+    #[test]
+    fn test_sessions() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut manager = Manager::new(page_manager);
+        manager
+            .execute("", "CREATE DATABASE a; CREATE DATABASE b;")
+            .unwrap();
+        manager
+            .execute("b", "CREATE TABLE t (id integer);")
+            .unwrap();
+
+        let mut session = manager.session("a");
+        assert_eq!("a", session.database_name());
+        assert_eq!(
+            Err(Error::TableDoesNotExist("t".to_string())),
+            session.execute("SELECT id FROM t;")
+        );
+
+        // `USE` and `connect` change the database of the statements after them.
+        session.execute("USE b; INSERT INTO t VALUES (1);").unwrap();
+        assert_eq!("b", session.database_name());
+        assert_eq!(Ok(vec![(1,)]), session.query_as("SELECT id FROM t;"));
+        assert_eq!(
+            Err(Error::DatabaseDoesNotExist("c".to_string())),
+            session.connect("c")
+        );
+        session.connect("a").unwrap();
+        assert_eq!("a", session.database_name());
+
+        session.execute("BEGIN;").unwrap();
+        assert!(session.in_transaction());
+        session.execute("ROLLBACK;").unwrap();
+        assert!(!session.in_transaction());
+
+        session.connect("b").unwrap();
+        session.set_query_memory_limit(Some(1));
+        assert!(matches!(
+            session.execute("SELECT id FROM t;"),
+            Err(Error::QueryMemoryLimitExceeded { limit: 1, .. })
+        ));
+        session.set_query_memory_limit(None);
+        assert!(session.execute("SELECT id FROM t;").is_ok());
+    }
+}
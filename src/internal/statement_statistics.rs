@@ -0,0 +1,106 @@
+use std::time::Duration;
+
+use super::{RowResult, Value};
+
+/// The name of the system view with the statement statistics, e.g.
+/// `SELECT * FROM bsql_stat_statements;`.
+pub const STATEMENT_STATISTICS_VIEW: &str = "bsql_stat_statements";
+
+/// How often a statement was executed and how long that took in total, see
+/// `Manager::statement_statistics`. Statements that only differ in their literals are counted as
+/// the same statement.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatementStatistics {
+    /// The normalized statement, where every literal is replaced by `?`.
+    pub query: String,
+    pub calls: usize,
+    pub total_time: Duration,
+
+    /// The number of rows that were returned or inserted by all calls together.
+    pub rows: usize,
+}
+
+impl StatementStatistics {
+    pub fn new(query: String) -> Self {
+        Self {
+            query,
+            calls: 0,
+            total_time: Duration::ZERO,
+            rows: 0,
+        }
+    }
+
+    pub fn mean_time(&self) -> Duration {
+        match self.calls {
+            0 => Duration::ZERO,
+            calls => self.total_time.div_f64(calls as f64),
+        }
+    }
+
+    pub fn record(&mut self, duration: Duration, rows: usize) {
+        self.calls += 1;
+        self.total_time += duration;
+        self.rows += rows;
+    }
+
+    /// Returns the statistics as the rows of the `bsql_stat_statements` view. There is no type for
+    /// fractions, so the times (in milliseconds) are returned as text.
+    pub fn to_row_result(statistics: &[StatementStatistics]) -> RowResult {
+        RowResult::new(
+            vec![
+                "query".to_string(),
+                "calls".to_string(),
+                "total_time_ms".to_string(),
+                "mean_time_ms".to_string(),
+                "rows".to_string(),
+            ],
+            statistics
+                .iter()
+                .map(|statistics| {
+                    vec![
+                        Some(Value::Text(statistics.query.clone())),
+                        Some(Value::Integer(statistics.calls as i64)),
+                        Some(Value::Text(format!(
+                            "{:.3}",
+                            statistics.total_time.as_secs_f64() * 1000.0
+                        ))),
+                        Some(Value::Text(format!(
+                            "{:.3}",
+                            statistics.mean_time().as_secs_f64() * 1000.0
+                        ))),
+                        Some(Value::Integer(statistics.rows as i64)),
+                    ]
+                })
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recording_calls() {
+        let mut statistics = StatementStatistics::new("SELECT * FROM t".to_string());
+        assert_eq!(Duration::ZERO, statistics.mean_time());
+
+        statistics.record(Duration::from_millis(2), 3);
+        statistics.record(Duration::from_millis(4), 1);
+        assert_eq!(2, statistics.calls);
+        assert_eq!(4, statistics.rows);
+        assert_eq!(Duration::from_millis(3), statistics.mean_time());
+
+        let row_result = StatementStatistics::to_row_result(&[statistics]);
+        assert_eq!(
+            &[vec![
+                Some(Value::Text("SELECT * FROM t".to_string())),
+                Some(Value::Integer(2)),
+                Some(Value::Text("6.000".to_string())),
+                Some(Value::Text("3.000".to_string())),
+                Some(Value::Integer(4)),
+            ]],
+            row_result.rows()
+        );
+    }
+}
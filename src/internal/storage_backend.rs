@@ -0,0 +1,187 @@
+use std::cell::{RefCell, RefMut};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::sync::mpsc::{channel, Receiver};
+
+use super::PageId;
+
+pub const PAGE_SIZE: usize = 8192;
+
+/// Where the `PageManager` reads pages from and writes them to. Every page is stored as
+/// `PAGE_SIZE` bytes. Failures are returned to the `PageManager`, which reports them as
+/// `Error::StorageFailed`.
+pub trait StorageBackend {
+    /// Returns every page that has been stored so far, ordered by their page id.
+    fn read_all(&self) -> io::Result<Vec<Vec<u8>>>;
+
+    fn read_page(&self, page_id: PageId) -> io::Result<Vec<u8>>;
+
+    fn write_pages(&self, pages: Vec<(PageId, Vec<u8>)>) -> io::Result<()>;
+
+    /// Starts reading the given pages in the background and returns where they are delivered.
+    /// Backends that can't read ahead return `None` and the pages are read when they're needed.
+    fn prefetch(&self, _page_ids: Vec<PageId>) -> Option<Receiver<(PageId, Vec<u8>)>> {
+        None
+    }
+
+    /// Waits until the pages written so far are stored durably. Backends that write durably
+    /// right away don't need to do anything.
+    fn sync(&self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Returns whether pages can currently be written, without writing any.
+    fn is_writable(&self) -> bool {
+        true
+    }
+}
+
+/// Stores the pages in a file on disk. The file is created when it doesn't exist yet, and is kept
+/// open once it has been opened.
+pub struct FileBackend {
+    filename: String,
+    file: RefCell<Option<File>>,
+}
+
+impl FileBackend {
+    pub fn new(filename: &str) -> Self {
+        Self {
+            filename: filename.to_string(),
+            file: RefCell::new(None),
+        }
+    }
+
+    /// Returns the open file, opening it first if it isn't yet. Files that can't be written are
+    /// opened for reading only, so that writing to them fails instead of reading them.
+    fn file(&self) -> io::Result<RefMut<'_, File>> {
+        let mut file = self.file.borrow_mut();
+        if file.is_none() {
+            let opened = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&self.filename)
+                .or_else(|_error| File::open(&self.filename))?;
+            *file = Some(opened);
+        }
+
+        Ok(RefMut::map(file, |file| file.as_mut().unwrap()))
+    }
+}
+
+impl StorageBackend for FileBackend {
+    fn read_all(&self) -> io::Result<Vec<Vec<u8>>> {
+        let mut content = vec![];
+        let mut file = self.file()?;
+        file.seek(SeekFrom::Start(0))?;
+        file.read_to_end(&mut content)?;
+
+        Ok(content
+            .chunks(PAGE_SIZE)
+            .map(|chunk| chunk.to_vec())
+            .collect())
+    }
+
+    fn read_page(&self, page_id: PageId) -> io::Result<Vec<u8>> {
+        let mut file = self.file()?;
+        let mut bytes = vec![0; PAGE_SIZE];
+
+        file.seek(SeekFrom::Start(page_id as u64 * PAGE_SIZE as u64))?;
+        file.read_exact(&mut bytes)?;
+
+        Ok(bytes)
+    }
+
+    fn write_pages(&self, pages: Vec<(PageId, Vec<u8>)>) -> io::Result<()> {
+        let mut file = self.file()?;
+
+        for (page_id, bytes) in pages {
+            file.seek(SeekFrom::Start(page_id as u64 * PAGE_SIZE as u64))?;
+            file.write_all(&bytes)?;
+        }
+
+        Ok(())
+    }
+
+    fn prefetch(&self, page_ids: Vec<PageId>) -> Option<Receiver<(PageId, Vec<u8>)>> {
+        let (sender, receiver) = channel();
+        let filename = self.filename.clone();
+
+        std::thread::spawn(move || {
+            let Ok(mut file) = File::open(filename) else {
+                return;
+            };
+
+            for page_id in page_ids {
+                let mut bytes = vec![0; PAGE_SIZE];
+
+                let read = file
+                    .seek(SeekFrom::Start(page_id as u64 * PAGE_SIZE as u64))
+                    .and_then(|_| file.read_exact(&mut bytes));
+
+                if read.is_err() || sender.send((page_id, bytes)).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Some(receiver)
+    }
+
+    fn sync(&self) -> io::Result<()> {
+        self.file()?.sync_all()
+    }
+
+    fn is_writable(&self) -> bool {
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.filename)
+            .is_ok()
+    }
+}
+
+/// Stores the pages in memory, which works on targets without a file system such as wasm32.
+/// Unlike a `:memory:` page manager, pages written to it can be released from the page manager
+/// and read back later.
+#[derive(Default)]
+pub struct MemoryBackend {
+    pages: RefCell<Vec<Vec<u8>>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StorageBackend for MemoryBackend {
+    fn read_all(&self) -> io::Result<Vec<Vec<u8>>> {
+        Ok(self.pages.borrow().clone())
+    }
+
+    fn read_page(&self, page_id: PageId) -> io::Result<Vec<u8>> {
+        self.pages
+            .borrow()
+            .get(page_id as usize)
+            .cloned()
+            .ok_or(io::ErrorKind::UnexpectedEof.into())
+    }
+
+    fn write_pages(&self, pages: Vec<(PageId, Vec<u8>)>) -> io::Result<()> {
+        let mut stored_pages = self.pages.borrow_mut();
+
+        for (page_id, bytes) in pages {
+            let page_id = page_id as usize;
+            if stored_pages.len() <= page_id {
+                stored_pages.resize(page_id + 1, vec![0; PAGE_SIZE]);
+            }
+
+            stored_pages[page_id] = bytes;
+        }
+
+        Ok(())
+    }
+}
@@ -1,15 +1,39 @@
+use std::collections::{HashMap, HashSet};
 use std::{rc::Rc, sync::RwLock};
 
 use super::{
-    page_manager::SharedInternalPage, BitmapIndex, ColumnDefinition, DataType, Error, PageId,
-    PageManager, RowResult, TablePage, Value,
+    page_manager::SharedInternalPage, BitmapIndex, ColumnDefinition, DataType, Error, InternalPage,
+    LockedPage, PageId, PageManager, PrimaryKeyIndex, Random, RowResult, TablePage, Value,
 };
 
+use super::row_result::RowValues;
+
 type ColumnId = u8;
 
 const COLUMN_BITMAP_RANGE: std::ops::Range<usize> = 0..32;
 const COLUMN_TABLE_NAME_RANGE: std::ops::Range<usize> = 32..96;
 const COLUMN_DEFINITION_START_OFFSET: usize = 96;
+/// The first page of the `PrimaryKeyIndex`, where 0 means that the table has no primary key.
+const PRIMARY_KEY_INDEX_PAGE_ID_RANGE: std::ops::Range<usize> = 4092..4096;
+/// The last value that was assigned to the `serial` columns of the table.
+const SERIAL_COUNTER_RANGE: std::ops::Range<usize> = 4084..4092;
+/// The percentage of the slots of a page that inserts fill, where 0 means 100.
+const FILL_FACTOR_OFFSET: usize = 4083;
+/// The number of records in the pages of the table, so counting them doesn't read every page.
+const ROW_COUNT_RANGE: std::ops::Range<usize> = 4075..4083;
+/// The first page of the free space list of the table, where 0 means that the list is empty. The
+/// list links the pages that can have room for more records, see `find_writable_page`.
+const FREE_SPACE_LIST_RANGE: std::ops::Range<usize> = 4071..4075;
+/// Format version 3 kept a flag for each of the first 255 pages here, which was set when the page
+/// was filled to the fill factor. Cleared by `build_free_space_list`.
+const FREE_SPACE_MAP_RANGE: std::ops::Range<usize> = 4043..4075;
+/// The high byte of the number of page ids, whose low byte directly precedes the page ids. The page
+/// ids can fill the metadata page up to here. Files from before format version 3 have a zero here,
+/// as their tables couldn't have more than 255 pages.
+const PAGE_COUNT_HIGH_BYTE_OFFSET: usize = 4042;
+
+/// The smallest fill factor that a table can have.
+pub const MIN_FILL_FACTOR: u8 = 10;
 
 pub struct TableManager {
     page_manager: Rc<RwLock<PageManager>>,
@@ -18,19 +42,6 @@ pub struct TableManager {
 }
 
 impl TableManager {
-    pub fn new(page_manager: Rc<RwLock<PageManager>>, table_name: &str) -> Result<Self, Error> {
-        if table_name.len() >= COLUMN_TABLE_NAME_RANGE.len() - 1 {
-            return Err(Error::TableNameTooLong);
-        }
-
-        let (_page_id, shared_page) = {
-            let mut page_manager = page_manager.write().unwrap();
-            page_manager.create_page()
-        };
-
-        return Self::initialize(page_manager, shared_page.clone(), table_name);
-    }
-
     pub fn initialize(
         page_manager: Rc<RwLock<PageManager>>,
         shared_page: SharedInternalPage,
@@ -101,9 +112,9 @@ impl TableManager {
         if !self.column_exists(column_name) {
             let column_id = {
                 let mut page = self.page.write().unwrap();
-                let mut column_index: BitmapIndex<255> =
-                    BitmapIndex::from_raw(&mut page.metadata[COLUMN_BITMAP_RANGE]).unwrap();
-                column_index.consume().ok_or(Error::TooManyColumnsInUse)?
+                let mut column_index =
+                    BitmapIndex::from_raw(&mut page.metadata[COLUMN_BITMAP_RANGE], 255).unwrap();
+                column_index.consume().ok_or(Error::TooManyColumnsInUse)? as ColumnId
             };
 
             column_definitions.push(ColumnDefinition::new(
@@ -125,318 +136,1929 @@ impl TableManager {
         }
     }
 
-    pub fn insert_record(&mut self, values: Vec<Value>) -> Option<u64> {
-        // Check that we have the same amount of `values` as we have `column_definitions`.
-        if values.len() != self.column_definitions().len() {
-            return None;
+    /// Makes the column `NOT NULL`, which fails if any record doesn't have a value for it.
+    pub fn set_not_null(&mut self, column_name: &str) -> Result<(), Error> {
+        let mut column_definitions = self.column_definitions();
+        let position = column_definitions
+            .iter()
+            .position(|column_definition| column_definition.name() == column_name)
+            .ok_or(Error::ColumnDoesNotExist(column_name.to_string()))?;
+
+        if self
+            .get_records()?
+            .rows()
+            .iter()
+            .any(|row| row[position].is_none())
+        {
+            return Err(Error::NotNullViolation(column_name.to_string()));
         }
 
-        let (page_id, record_slot) = {
-            let (page_id, mut active_table_page) = self.get_writable_page();
+        column_definitions[position] = column_definitions[position].clone().with_not_null(true);
 
-            let record_slot = active_table_page.insert_record(values.clone())?;
+        Self::write_metadata_page(
+            self.page.clone(),
+            &self.name(),
+            &column_definitions,
+            &self.page_ids(),
+        );
 
-            (page_id, record_slot)
-        };
+        Ok(())
+    }
 
-        Some((page_id << 32) as u64 | record_slot as u64)
+    /// Returns the `PRIMARY KEY` column, if the table has one.
+    pub fn primary_key(&self) -> Option<ColumnDefinition> {
+        self.column_definitions()
+            .into_iter()
+            .find(|column_definition| column_definition.is_primary_key())
     }
 
-    pub fn get_record(&self, record_id: u64) -> Option<RowResult> {
-        let page_id = (record_id >> 32) & 0xFFFF_FFFF;
-        let record_slot = record_id & 0xFFFF_FFFF;
+    /// Makes the column the `PRIMARY KEY` of the table, which also makes it `NOT NULL`. Fails if
+    /// the table already has a primary key, if the column isn't an integer column (the index only
+    /// has integer keys) or if the existing records don't have unique values for the column.
+    pub fn set_primary_key(&mut self, column_name: &str) -> Result<(), Error> {
+        if self.primary_key().is_some() {
+            return Err(Error::MultiplePrimaryKeys(self.name()));
+        }
 
-        let table_page = {
-            let page_manager = self.page_manager.read().unwrap();
-            let shared_page = page_manager.fetch_page(page_id as u32).unwrap();
-            TablePage::load(self.page_manager.clone(), shared_page)
-        };
+        let mut column_definitions = self.column_definitions();
+        let position = column_definitions
+            .iter()
+            .position(|column_definition| column_definition.name() == column_name)
+            .ok_or(Error::ColumnDoesNotExist(column_name.to_string()))?;
+        if column_definitions[position].data_type() != &DataType::Integer {
+            return Err(Error::TypeMismatch);
+        }
 
-        let page_columns = table_page.column_definitions();
-        let row_data = table_page
-            .get_record(record_slot as u8)
-            .map(|record| self.normalize_page_record(&page_columns, record))?;
+        let mut keys: Vec<(i64, u64)> = vec![];
+        let mut seen_keys = HashSet::new();
+        for (record_id, row) in self.get_records_with_ids()? {
+            let Some(Value::Integer(key)) = row[position] else {
+                return Err(Error::NotNullViolation(column_name.to_string()));
+            };
 
-        Some(RowResult::new(self.column_names(), vec![row_data]))
-    }
+            if !seen_keys.insert(key) {
+                return Err(Error::UniqueViolation(column_name.to_string()));
+            }
+            keys.push((key, record_id));
+        }
 
-    pub fn get_records(&self) -> RowResult {
-        let mut rows: Vec<Vec<Option<Value>>> = Vec::new();
+        let (index_page_id, shared_page) = {
+            let mut page_manager = self.page_manager.write().unwrap();
+            page_manager.create_page()
+        };
+        let mut index = PrimaryKeyIndex::initialize(self.page_manager.clone(), shared_page);
+        for (key, record_id) in keys {
+            index.insert(key, record_id)?;
+        }
 
-        for page_id in &self.page_ids() {
-            let table_page = {
-                let page_manager = self.page_manager.read().unwrap();
-                let shared_page = page_manager.fetch_page(*page_id as u32).unwrap();
-                TablePage::load(self.page_manager.clone(), shared_page)
-            };
+        column_definitions[position] = column_definitions[position]
+            .clone()
+            .with_not_null(true)
+            .with_primary_key(true);
 
-            let page_columns = table_page.column_definitions();
-            let page_records = table_page.get_records();
+        Self::write_metadata_page(
+            self.page.clone(),
+            &self.name(),
+            &column_definitions,
+            &self.page_ids(),
+        );
+        self.page.write().unwrap().metadata[PRIMARY_KEY_INDEX_PAGE_ID_RANGE]
+            .copy_from_slice(&index_page_id.to_be_bytes());
 
-            // For every column that we want to return (all might not exist), figure out how to
-            // transform the order of the record we retrieved into what we expect.
-            for page_record in page_records.into_iter() {
-                rows.push(self.normalize_page_record(page_columns, page_record));
+        Ok(())
+    }
+
+    /// Makes the column `serial` (and `NOT NULL`). The counter continues after the largest value
+    /// that the existing records have for the column.
+    pub fn set_serial(&mut self, column_name: &str) -> Result<(), Error> {
+        let mut column_definitions = self.column_definitions();
+        let position = column_definitions
+            .iter()
+            .position(|column_definition| column_definition.name() == column_name)
+            .ok_or(Error::ColumnDoesNotExist(column_name.to_string()))?;
+
+        let mut last_value = self.serial_counter();
+        for row in self.get_records()?.rows() {
+            match &row[position] {
+                Some(Value::Integer(value)) => {
+                    last_value = last_value.max(u64::try_from(*value).unwrap_or(0))
+                }
+                Some(_) => return Err(Error::TypeMismatch),
+                None => return Err(Error::NotNullViolation(column_name.to_string())),
             }
         }
 
-        RowResult::new(self.column_names(), rows)
+        column_definitions[position] = column_definitions[position]
+            .clone()
+            .with_not_null(true)
+            .with_serial(true);
+
+        Self::write_metadata_page(
+            self.page.clone(),
+            &self.name(),
+            &column_definitions,
+            &self.page_ids(),
+        );
+        self.set_serial_counter(last_value);
+
+        Ok(())
     }
 
-    pub fn get_records_for_columns(&self, column_names: &Vec<&str>) -> Result<RowResult, Error> {
-        let sorted_column_indices: Vec<ColumnId> = column_names
-            .into_iter()
-            .map(|column_name| {
-                self.column_definitions()
-                    .iter()
-                    .find(|cd| cd.name() == column_name)
-                    .ok_or(Error::ColumnDoesNotExist(column_name.to_string()))
-                    .map(|cd| cd.column_id())
+    /// Adds the `serial` columns that a record doesn't have a value for to its columns, with the
+    /// next value of the table's counter. Every `serial` column of the record gets the same value.
+    pub fn assign_serial_values(
+        &mut self,
+        column_definitions: Vec<ColumnDefinition>,
+        values: Vec<Option<Value>>,
+    ) -> Result<(Vec<ColumnDefinition>, Vec<Option<Value>>), Error> {
+        let table_columns = self.column_definitions();
+        let missing_columns: Vec<ColumnDefinition> = table_columns
+            .iter()
+            .filter(|column_definition| {
+                column_definition.is_serial()
+                    && !column_definitions
+                        .iter()
+                        .any(|other| other.column_id() == column_definition.column_id())
             })
-            .collect::<Result<Vec<ColumnId>, Error>>()?;
+            .cloned()
+            .collect();
 
-        let records = self.get_records();
-        let sorted_rows = records
-            .rows()
+        if missing_columns.is_empty() {
+            return Ok((column_definitions, values));
+        }
+
+        let value = self.serial_counter() + 1;
+        let Ok(integer) = i64::try_from(value) else {
+            return Err(Error::IntegerOutOfRange);
+        };
+        self.set_serial_counter(value);
+
+        let mut columns: Vec<(ColumnDefinition, Option<Value>)> = column_definitions
             .into_iter()
-            .map(|row| {
-                return sorted_column_indices
-                    .iter()
-                    .map(|column_index| row.get(*column_index as usize).unwrap().clone())
-                    .collect();
-            })
+            .zip(values)
+            .chain(
+                missing_columns
+                    .into_iter()
+                    .map(|column_definition| (column_definition, Some(Value::Integer(integer)))),
+            )
             .collect();
+        columns.sort_by_key(|(column_definition, _value)| {
+            table_columns
+                .iter()
+                .position(|table_column| table_column.column_id() == column_definition.column_id())
+        });
 
-        Ok(RowResult::new(
-            column_names.iter().map(|name| name.to_string()).collect(),
-            sorted_rows,
-        ))
+        Ok(columns.into_iter().unzip())
     }
 
-    fn get_writable_page(&mut self) -> (usize, TablePage) {
-        let mut page_ids = self.page_ids();
+    /// Returns the id of the record whose primary key is `key`, without scanning the table.
+    /// Returns `None` if the table has no primary key.
+    pub fn find_by_primary_key(&self, key: &Value) -> Result<Option<u64>, Error> {
+        let (Value::Integer(key), Some(index)) = (key, self.primary_key_index()?) else {
+            return Ok(None);
+        };
 
-        for page_id in &page_ids {
-            // Load the `TablePage` from the `page_id`
-            let page_manager = self.page_manager.read().unwrap();
-            let page = page_manager.fetch_page(*page_id).unwrap();
-            let table_page = TablePage::load(self.page_manager.clone(), page);
+        index.get(*key)
+    }
 
-            // If this `TablePage` have different columns than us, skip to the next one.
-            if *table_page.column_definitions() != self.column_definitions() {
-                continue;
-            }
+    /// Inserts every record like `insert_record_into_columns`, but keeps filling the same page
+    /// until it's full (or filled to the fill factor), and only updates the row count of the table
+    /// once. At most `max_new_pages` pages are added to the table, if given. Nothing is inserted
+    /// when a record doesn't fit the columns of the table, or doesn't fit into the pages that may
+    /// be added (see also `can_add_page`), then the records that were already written are deleted
+    /// again. Returns the ids
+    /// of the new records.
+    pub fn insert_records(
+        &mut self,
+        records: Vec<(Vec<ColumnDefinition>, Vec<Option<Value>>)>,
+        max_new_pages: Option<u32>,
+    ) -> Result<Option<Vec<u64>>, Error> {
+        if !records
+            .iter()
+            .all(|(column_definitions, values)| Self::accepts_record(column_definitions, values))
+        {
+            return Ok(None);
+        }
 
-            // Check if the page is full, otherwise return it.
-            if table_page.is_full() {
-                // Create a new page and return that.
-                let mut page_manager = self.page_manager.write().unwrap();
-                let (page_id, shared_page) = page_manager.create_page();
+        let primary_key = self.primary_key();
+        let mut primary_key_index = self.primary_key_index()?;
+        let fill_factor = self.fill_factor();
+        let mut new_pages = 0;
 
-                let table_page = TablePage::initialize(
-                    self.page_manager.clone(),
-                    shared_page,
-                    self.column_definitions(),
-                );
-                page_ids.push(page_id);
+        let mut record_ids = Vec::with_capacity(records.len());
+        let mut writable_page: Option<(usize, TablePage)> = None;
+        for (column_definitions, values) in records {
+            let primary_key = primary_key.as_ref().and_then(|primary_key| {
+                column_definitions
+                    .iter()
+                    .position(|column_definition| {
+                        column_definition.column_id() == primary_key.column_id()
+                    })
+                    .and_then(|position| values[position].clone())
+            });
+
+            let (page_id, table_page) = match writable_page.take() {
+                Some((page_id, table_page))
+                    if table_page.column_definitions() == &column_definitions
+                        && !table_page.is_filled_to(fill_factor)
+                        && table_page.has_room_for(&values) =>
+                {
+                    (page_id, table_page)
+                }
+                _ => {
+                    let writable_page =
+                        match self.find_writable_page(&column_definitions, &values)? {
+                            Some(writable_page) => Some(writable_page),
+                            None if max_new_pages.is_some_and(|max| new_pages >= max) => None,
+                            None => {
+                                new_pages += 1;
+                                self.create_writable_page(column_definitions)
+                            }
+                        };
+                    let Some(writable_page) = writable_page else {
+                        self.delete_inserted_records(record_ids)?;
+                        return Ok(None);
+                    };
+
+                    writable_page
+                }
+            };
+            let (page_id, table_page) = writable_page.insert((page_id, table_page));
 
-                Self::write_metadata_page(
-                    self.page.clone(),
-                    &self.name(),
-                    &self.column_definitions(),
-                    &page_ids,
-                );
+            let Some(record_slot) = table_page.insert_record(values) else {
+                self.delete_inserted_records(record_ids)?;
+                return Ok(None);
+            };
+            let record_id = ((*page_id as u64) << 32) | record_slot as u64;
 
-                return (page_id as usize, table_page);
-            } else {
-                return (*page_id as usize, table_page);
+            if let (Some(Value::Integer(key)), Some(index)) = (primary_key, &mut primary_key_index)
+            {
+                index.insert(key, record_id)?;
             }
+            record_ids.push(record_id);
         }
+        self.set_row_count(self.row_count() + record_ids.len() as u64);
 
-        // Create a new page and return that.
-        let mut page_manager = self.page_manager.write().unwrap();
-        let (page_id, shared_page) = page_manager.create_page();
-
-        let table_page = TablePage::initialize(
-            self.page_manager.clone(),
-            shared_page,
-            self.column_definitions(),
-        );
-        page_ids.push(page_id);
+        Ok(Some(record_ids))
+    }
 
-        Self::write_metadata_page(
-            self.page.clone(),
-            &self.name(),
-            &self.column_definitions(),
-            &page_ids,
-        );
+    /// Deletes the records that `insert_records` wrote before it failed, which aren't counted in
+    /// the row count yet.
+    fn delete_inserted_records(&mut self, record_ids: Vec<u64>) -> Result<(), Error> {
+        self.set_row_count(self.row_count() + record_ids.len() as u64);
+        for record_id in record_ids {
+            self.delete_record(record_id)?;
+        }
 
-        return (page_id as usize, table_page);
+        Ok(())
     }
 
-    // Takes a `Vec<Value>` and transforms it into a `Vec<Option<Value>>` where any missing columns
-    // in the input gets casted to `None`.
-    fn normalize_page_record(
-        &self,
-        page_columns: &Vec<ColumnDefinition>,
-        page_record: Vec<Value>,
-    ) -> Vec<Option<Value>> {
-        // TODO: Make sure that `self.column_names.iter()` always returns the same order.
-
-        // For every column that we want to return (all might not exist), figure out how to
-        // transform the order of the record we retrieved into what we expect.
-        let value_index_order: Vec<Option<ColumnId>> = self
-            .column_definitions()
-            .iter()
-            .map(|expected_column| {
-                page_columns
-                    .iter()
-                    .find(|page_column| expected_column.column_id() == page_column.column_id())
-                    .map(|page_column| page_column.column_id())
-            })
-            .collect();
+    /// Inserts a record which only has values for some of the columns, see `resolve_columns`.
+    /// The other columns are `NULL` for the record, like the values that are `None`.
+    pub fn insert_record_into_columns(
+        &mut self,
+        column_definitions: Vec<ColumnDefinition>,
+        values: Vec<Option<Value>>,
+    ) -> Result<Option<u64>, Error> {
+        if !Self::accepts_record(&column_definitions, &values) {
+            return Ok(None);
+        }
 
-        return value_index_order
-            .clone()
-            .into_iter()
-            .map(|value_index| {
-                value_index.and_then(|index| page_record.get(index as usize).map(|i| i.clone()))
-            })
-            .collect();
+        self.write_record(column_definitions, values, None)
     }
 
-    fn column_exists(&self, column_name: &str) -> bool {
-        self.column_definitions()
-            .iter()
-            .find(|cd| cd.name() == column_name)
-            .is_some()
+    /// Replaces the record with a record for the given columns. The new record is kept in the
+    /// page of the old one when it has the same columns and the page isn't full, so the room that
+    /// the fill factor leaves in a page is used by updates. Returns the id of the new record.
+    pub fn update_record(
+        &mut self,
+        record_id: u64,
+        column_definitions: Vec<ColumnDefinition>,
+        values: Vec<Option<Value>>,
+    ) -> Result<Option<u64>, Error> {
+        if !Self::accepts_record(&column_definitions, &values) {
+            return Ok(None);
+        }
+
+        self.delete_record(record_id)?;
+        self.write_record(
+            column_definitions,
+            values,
+            Some((record_id >> 32) as PageId),
+        )
     }
 
-    fn column_names(&self) -> Vec<String> {
-        self.column_definitions()
-            .iter()
-            .map(|cd| cd.name().clone())
-            .collect()
+    /// Checks that there is a value for every column and that every value that isn't `NULL` can be
+    /// stored in its column.
+    fn accepts_record(column_definitions: &[ColumnDefinition], values: &[Option<Value>]) -> bool {
+        values.len() == column_definitions.len()
+            && column_definitions
+                .iter()
+                .zip(values.iter())
+                .all(|(column_definition, value)| {
+                    value
+                        .as_ref()
+                        .map_or(true, |value| column_definition.data_type().accepts(value))
+                })
     }
 
-    fn write_metadata_page(
-        shared_page: SharedInternalPage,
-        table_name: &str,
-        column_definitions: &Vec<ColumnDefinition>,
-        page_ids: &Vec<PageId>,
-    ) {
-        let mut page = shared_page.write().unwrap();
+    fn write_record(
+        &mut self,
+        column_definitions: Vec<ColumnDefinition>,
+        values: Vec<Option<Value>>,
+        preferred_page_id: Option<PageId>,
+    ) -> Result<Option<u64>, Error> {
+        let primary_key = self.primary_key().and_then(|primary_key| {
+            column_definitions
+                .iter()
+                .position(|column_definition| {
+                    column_definition.column_id() == primary_key.column_id()
+                })
+                .and_then(|position| values[position].clone())
+        });
+
+        let Some((page_id, mut active_table_page)) =
+            self.get_writable_page(column_definitions, &values, preferred_page_id)?
+        else {
+            return Ok(None);
+        };
+        let Some(record_slot) = active_table_page.insert_record(values) else {
+            return Ok(None);
+        };
+        let record_id = ((page_id as u64) << 32) | record_slot as u64;
+        self.set_row_count(self.row_count() + 1);
 
+        if let (Some(Value::Integer(key)), Some(mut index)) =
+            (primary_key, self.primary_key_index()?)
         {
-            // Write the table name on the range COLUMN_TABLE_NAME_RANGE
-            page.metadata[COLUMN_TABLE_NAME_RANGE.start] = table_name.len() as u8;
-            page.metadata[COLUMN_TABLE_NAME_RANGE.start + 1
-                ..COLUMN_TABLE_NAME_RANGE.start + 1 + table_name.len()]
-                .copy_from_slice(table_name.as_bytes());
+            index.insert(key, record_id)?;
         }
 
-        let column_definitions_length = {
-            // Write the column definitions
-            let mut column_definition_data: Vec<u8> = Vec::new();
-            column_definition_data.push(column_definitions.len() as u8);
-
-            for column_definition in column_definitions.iter() {
-                column_definition_data.extend_from_slice(&column_definition.to_raw_bytes());
-            }
+        Ok(Some(record_id))
+    }
 
-            let mut metadata: Vec<u8> = Vec::with_capacity(column_definition_data.len());
-            metadata.extend_from_slice(&column_definition_data);
+    pub fn get_record(&self, record_id: u64) -> Result<Option<RowResult>, Error> {
+        let page_id = (record_id >> 32) & 0xFFFF_FFFF;
+        let record_slot = record_id & 0xFFFF_FFFF;
 
-            page.metadata
-                [COLUMN_DEFINITION_START_OFFSET..COLUMN_DEFINITION_START_OFFSET + metadata.len()]
-                .copy_from_slice(&metadata);
+        let table_page = Self::load_table_page(&self.page_manager, page_id as PageId)?;
 
-            metadata.len()
+        let page_columns = table_page.column_definitions();
+        let Some(row_data) = table_page
+            .get_record(record_slot as u16)
+            .map(|record| self.normalize_page_record(&page_columns, record))
+        else {
+            return Ok(None);
         };
 
-        {
-            let mut page_ids_cursor = COLUMN_DEFINITION_START_OFFSET + column_definitions_length;
+        Ok(Some(RowResult::new(self.column_names(), vec![row_data])))
+    }
 
-            // Write page_ids after table_name
-            page.metadata[page_ids_cursor] = page_ids.len() as u8;
-            page_ids_cursor += 1;
+    /// Deletes the record, if it exists.
+    pub fn delete_record(&mut self, record_id: u64) -> Result<(), Error> {
+        let page_id = (record_id >> 32) & 0xFFFF_FFFF;
+        let record_slot = record_id & 0xFFFF_FFFF;
 
-            let page_ids_array: Vec<u8> = page_ids
+        let mut table_page = Self::load_table_page(&self.page_manager, page_id as PageId)?;
+
+        if let (Some(primary_key), Some(mut index)) =
+            (self.primary_key(), self.primary_key_index()?)
+        {
+            let page_columns = table_page.column_definitions();
+            if let Some(Value::Integer(key)) = page_columns
                 .iter()
-                .map(|pid| pid.to_be_bytes())
-                .flatten()
-                .collect();
+                .position(|column_definition| {
+                    column_definition.column_id() == primary_key.column_id()
+                })
+                .and_then(|position| {
+                    table_page
+                        .get_record(record_slot as u16)?
+                        .get(position)
+                        .cloned()
+                        .flatten()
+                })
+            {
+                index.remove(key, record_id)?;
+            }
+        }
 
-            page.metadata[page_ids_cursor..page_ids_cursor + page_ids_array.len()]
-                .copy_from_slice(&page_ids_array);
+        if table_page.delete_record(record_slot as u16) {
+            self.set_row_count(self.row_count() - 1);
+
+            // The page has room again.
+            self.add_to_free_space_list(page_id as PageId, &mut table_page);
         }
+
+        Ok(())
     }
 
-    fn page_ids(&self) -> Vec<PageId> {
-        let page = self.page.read().unwrap();
+    /// Returns every record together with its record id.
+    pub fn get_records_with_ids(&self) -> Result<Vec<(u64, RowValues)>, Error> {
+        self.scan_pages(self.page_ids()).collect()
+    }
 
-        let mut cursor = COLUMN_DEFINITION_START_OFFSET;
+    pub fn get_records(&self) -> Result<RowResult, Error> {
+        Ok(RowResult::new(
+            self.column_names(),
+            self.scan().collect::<Result<Vec<RowValues>, Error>>()?,
+        ))
+    }
 
-        // Skip all the definitions
-        let number_of_definitions = page.metadata[cursor];
-        cursor += 1;
+    /// Returns an iterator over the records of the table. The pages are only read and decoded
+    /// once the iterator reaches them, so the rows of the whole table are never in memory at once.
+    /// A page that can't be read from the storage ends up as an error among the rows.
+    pub fn scan(&self) -> impl Iterator<Item = Result<RowValues, Error>> {
+        self.scan_pages(self.page_ids())
+            .map(|record| record.map(|(_record_id, row)| row))
+    }
 
-        for _ in 0..number_of_definitions {
-            let number_of_bytes_for_definition = page.metadata[cursor];
-            cursor += number_of_bytes_for_definition as usize + 1;
-        }
+    /// Returns an iterator over the records of a random selection of pages like `scan`, where
+    /// every page is picked with a chance of `percentage` percent. The pages are picked before the
+    /// iterator is returned.
+    pub fn scan_sampled(
+        &self,
+        percentage: u8,
+        random: &mut Random,
+    ) -> impl Iterator<Item = Result<RowValues, Error>> {
+        let page_ids = self
+            .page_ids()
+            .into_iter()
+            .filter(|_page_id| random.next_u64() % 100 < percentage as u64)
+            .collect();
 
-        let number_of_pages = page.metadata[cursor];
-        cursor += 1;
+        self.scan_pages(page_ids)
+            .map(|record| record.map(|(_record_id, row)| row))
+    }
 
-        let mut page_ids = Vec::new();
+    /// Like `scan`, but only returns the records for which `predicate` returns true. The predicate
+    /// is called with the names of the columns and the record as it's stored in its page, where the
+    /// columns that the page doesn't have are `NULL`, so the records that don't match are never
+    /// put in the order of the table's columns.
+    pub fn scan_filtered<P>(
+        &self,
+        mut predicate: P,
+    ) -> impl Iterator<Item = Result<RowValues, Error>>
+    where
+        P: FnMut(&[String], &[Option<Value>]) -> Result<bool, Error>,
+    {
+        let page_ids = self.page_ids();
+        self.page_manager.read().unwrap().prefetch(&page_ids);
+
+        let page_manager = self.page_manager.clone();
+        let column_definitions = self.column_definitions();
+
+        page_ids.into_iter().flat_map(move |page_id| {
+            let table_page = match Self::load_table_page(&page_manager, page_id) {
+                Ok(table_page) => table_page,
+                Err(error) => return vec![Err(error)],
+            };
+            let page_columns = table_page.column_definitions();
 
-        for _ in 0..number_of_pages {
-            let page_id =
-                PageId::from_be_bytes(page.metadata[cursor..cursor + 4].try_into().unwrap());
-            page_ids.push(page_id);
+            // The columns of the page, followed by the columns of the table that it doesn't have.
+            let mut columns: Vec<String> = page_columns
+                .iter()
+                .map(|column_definition| column_definition.name().clone())
+                .collect();
+            let mut positions = Self::record_positions(&column_definitions, page_columns);
+            for (column_definition, position) in column_definitions.iter().zip(positions.iter_mut())
+            {
+                if position.is_none() {
+                    *position = Some(columns.len());
+                    columns.push(column_definition.name().clone());
+                }
+            }
 
-            cursor += 4;
-        }
+            let mut rows = vec![];
+            for mut page_record in table_page.get_records() {
+                page_record.resize(columns.len(), None);
 
-        return page_ids;
+                match predicate(&columns, &page_record) {
+                    Ok(true) => rows.push(Ok(Self::reorder_record(&positions, page_record))),
+                    Ok(false) => (),
+                    Err(error) => rows.push(Err(error)),
+                }
+            }
+
+            rows
+        })
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use std::rc::Rc;
-    use std::sync::RwLock;
+    /// Returns an iterator over the records of the pages together with their record ids, see
+    /// `scan`.
+    fn scan_pages(
+        &self,
+        page_ids: Vec<PageId>,
+    ) -> impl Iterator<Item = Result<(u64, RowValues), Error>> {
+        self.page_manager.read().unwrap().prefetch(&page_ids);
 
-    use super::*;
-    use crate::internal::InternalPage;
+        let page_manager = self.page_manager.clone();
+        let column_definitions = self.column_definitions();
 
-    #[test]
-    fn fetching_table_name_works() {
-        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let table_manager = TableManager::new(page_manager, "test").unwrap();
-        assert_eq!("test", table_manager.name());
+        page_ids.into_iter().flat_map(move |page_id| {
+            let table_page = match Self::load_table_page(&page_manager, page_id) {
+                Ok(table_page) => table_page,
+                Err(error) => return vec![Err(error)],
+            };
+            let positions =
+                Self::record_positions(&column_definitions, table_page.column_definitions());
+
+            table_page
+                .get_indexed_records()
+                .into_iter()
+                .map(|(record_slot, page_record)| {
+                    Ok((
+                        (page_id as u64) << 32 | record_slot as u64,
+                        Self::reorder_record(&positions, page_record),
+                    ))
+                })
+                .collect()
+        })
     }
 
-    #[test]
-    fn get_records_for_pages_with_different_columns() {
-        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut table_manager = TableManager::new(page_manager, "test").unwrap();
-        table_manager.add_column("day", DataType::Integer).unwrap();
-        assert!(table_manager
-            .insert_record(vec![Value::Integer(31)])
-            .is_some());
+    /// Returns an iterator over the values of the named columns of every record, like `scan`. Only
+    /// the values of those columns are decoded from the pages.
+    pub fn scan_columns(
+        &self,
+        column_names: &[&str],
+    ) -> Result<impl Iterator<Item = Result<RowValues, Error>>, Error> {
+        let column_definitions = self.column_definitions();
+        let column_ids: Vec<ColumnId> = column_names
+            .iter()
+            .map(|column_name| {
+                column_definitions
+                    .iter()
+                    .find(|cd| cd.name() == column_name)
+                    .ok_or(Error::ColumnDoesNotExist(column_name.to_string()))
+                    .map(|cd| cd.column_id())
+            })
+            .collect::<Result<Vec<ColumnId>, Error>>()?;
 
-        table_manager
-            .add_column("month", DataType::Integer)
-            .unwrap();
-        assert!(table_manager
-            .insert_record(vec![Value::Integer(1), Value::Integer(5)])
-            .is_some());
+        let page_ids = self.page_ids();
+        self.page_manager.read().unwrap().prefetch(&page_ids);
 
-        // Returns records for the _current_ columns. Order is not guaranteed.
-        let records = table_manager.get_records();
-        assert!(
+        let page_manager = self.page_manager.clone();
+        Ok(page_ids.into_iter().flat_map(move |page_id| {
+            let table_page = match Self::load_table_page(&page_manager, page_id) {
+                Ok(table_page) => table_page,
+                Err(error) => return vec![Err(error)],
+            };
+            let page_columns = table_page.column_definitions();
+
+            // The position of every column in the records of the page, and the position of its
+            // value among the decoded values.
+            let page_positions: Vec<Option<usize>> = column_ids
+                .iter()
+                .map(|column_id| {
+                    page_columns
+                        .iter()
+                        .position(|page_column| page_column.column_id() == *column_id)
+                })
+                .collect();
+            let decoded_positions: Vec<usize> = page_positions.iter().flatten().copied().collect();
+            let mut decoded_count = 0;
+            let value_positions: Vec<Option<usize>> = page_positions
+                .iter()
+                .map(|position| {
+                    position.map(|_position| {
+                        decoded_count += 1;
+                        decoded_count - 1
+                    })
+                })
+                .collect();
+
+            table_page
+                .get_indexed_record_columns(&decoded_positions)
+                .into_iter()
+                .map(|(_record_slot, page_record)| {
+                    Ok(Self::reorder_record(&value_positions, page_record))
+                })
+                .collect()
+        }))
+    }
+
+    /// Looks up the definitions of the named columns and puts them (and the `values` for them) in
+    /// the order of the table's columns, so that records for the same columns always end up on
+    /// the same pages.
+    pub fn resolve_columns(
+        &self,
+        column_names: &[String],
+        values: Vec<Option<Value>>,
+    ) -> Result<(Vec<ColumnDefinition>, Vec<Option<Value>>), Error> {
+        if column_names.len() != values.len() {
+            return Err(Error::InsertFailed);
+        }
+
+        let table_columns = self.column_definitions();
+        let mut columns = Vec::with_capacity(column_names.len());
+
+        for (column_name, value) in column_names.iter().zip(values) {
+            let position = table_columns
+                .iter()
+                .position(|column_definition| column_definition.name() == column_name)
+                .ok_or(Error::ColumnDoesNotExist(column_name.clone()))?;
+
+            if columns.iter().any(|(other, _)| *other == position) {
+                return Err(Error::ColumnAlreadyExist(column_name.clone()));
+            }
+
+            columns.push((position, value));
+        }
+
+        columns.sort_by_key(|(position, _)| *position);
+
+        Ok(columns
+            .into_iter()
+            .map(|(position, value)| (table_columns[position].clone(), value))
+            .unzip())
+    }
+
+    /// Asserts that every page of the table exists, is only listed once, only stores columns of
+    /// the table and is filled to the fill factor when the free space map says so. Only checked
+    /// in debug builds.
+    pub fn debug_check_invariants(&self) {
+        let column_ids: Vec<ColumnId> = self
+            .column_definitions()
+            .iter()
+            .map(|column_definition| column_definition.column_id())
+            .collect();
+        let page_ids = self.page_ids();
+        let free_space_list = self.free_space_list().unwrap();
+        for (index, page_id) in free_space_list.iter().enumerate() {
+            debug_assert!(
+                page_ids.contains(page_id) && !free_space_list[..index].contains(page_id),
+                "Page {} is in the free space list of table {} twice or isn't a page of the table",
+                page_id,
+                self.name()
+            );
+        }
+
+        for (index, page_id) in page_ids.iter().enumerate() {
+            debug_assert!(
+                !page_ids[..index].contains(page_id),
+                "Page {} is listed twice in table {}",
+                page_id,
+                self.name()
+            );
+
+            let Ok(Some(shared_page)) = self.page_manager.read().unwrap().fetch_page(*page_id)
+            else {
+                debug_assert!(
+                    false,
+                    "Page {} of table {} is missing or can't be read",
+                    page_id,
+                    self.name()
+                );
+                continue;
+            };
+            let table_page = TablePage::load(shared_page);
+
+            debug_assert!(
+                table_page
+                    .column_definitions()
+                    .iter()
+                    .all(|column_definition| column_ids.contains(&column_definition.column_id())),
+                "Page {} of table {} has columns that the table doesn't have",
+                page_id,
+                self.name()
+            );
+            debug_assert_eq!(
+                table_page.free_space_list_next().is_some(),
+                free_space_list.contains(page_id),
+                "Page {} of table {} is flagged differently than the free space list has it",
+                page_id,
+                self.name()
+            );
+            table_page.debug_check_invariants();
+        }
+
+        debug_assert_eq!(
+            self.row_count(),
+            self.get_records_with_ids().unwrap().len() as u64,
+            "The row count of table {} doesn't match its records",
+            self.name()
+        );
+
+        if let (Some(primary_key), Some(index)) =
+            (self.primary_key(), self.primary_key_index().unwrap())
+        {
+            let position = self
+                .column_definitions()
+                .iter()
+                .position(|column_definition| {
+                    column_definition.column_id() == primary_key.column_id()
+                })
+                .unwrap();
+            let mut keys: Vec<(i64, u64)> = self
+                .get_records_with_ids()
+                .unwrap()
+                .into_iter()
+                .filter_map(|(record_id, row)| match row[position] {
+                    Some(Value::Integer(key)) => Some((key, record_id)),
+                    _ => None,
+                })
+                .collect();
+            keys.sort();
+
+            debug_assert_eq!(
+                keys,
+                index.entries().unwrap(),
+                "The primary key index of table {} doesn't match its records",
+                self.name()
+            );
+        }
+    }
+
+    /// Returns the number of pages used by the table, including its own metadata page and the
+    /// pages of its primary key index.
+    pub fn page_count(&self) -> Result<u32, Error> {
+        let index_page_count = match self.primary_key_index()? {
+            Some(index) => index.page_count()?,
+            None => 0,
+        };
+
+        Ok(1 + self.page_ids().len() as u32 + index_page_count)
+    }
+
+    /// Copies the table to `page_manager`, followed by the pages with its records and its primary
+    /// key index. Pages without any records are left out. The records keep their slots, but get
+    /// the page id of their copied page, and the free space list of the copy is built from the
+    /// copied pages. Returns the page id of the copy.
+    pub fn copy_into(&self, page_manager: &Rc<RwLock<PageManager>>) -> Result<PageId, Error> {
+        let (page_id, shared_page) = page_manager.write().unwrap().create_page_from(&self.page);
+
+        let mut page_ids = vec![];
+        let mut copied_page_ids = HashMap::new();
+        for source_page_id in self.page_ids() {
+            let source_page = self
+                .page_manager
+                .read()
+                .unwrap()
+                .fetch_page(source_page_id)?
+                .expect("The pages of the table exist");
+            if TablePage::load(source_page.clone()).is_empty() {
+                continue;
+            }
+
+            let (copied_page_id, _page) =
+                page_manager.write().unwrap().create_page_from(&source_page);
+            page_ids.push(copied_page_id);
+            copied_page_ids.insert(source_page_id as u64, copied_page_id as u64);
+        }
+        Self::write_metadata_page(
+            shared_page.clone(),
+            &self.name(),
+            &self.column_definitions(),
+            &page_ids,
+        );
+        let mut copy = Self::load(page_manager.clone(), shared_page.clone())?;
+        copy.build_free_space_list()?;
+
+        if let Some(index) = self.primary_key_index()? {
+            let (index_page_id, index_page) = page_manager.write().unwrap().create_page();
+            let mut copied_index = PrimaryKeyIndex::initialize(page_manager.clone(), index_page);
+            for (key, record_id) in index.entries()? {
+                let copied_page_id = copied_page_ids[&(record_id >> 32)];
+                copied_index.insert(key, copied_page_id << 32 | record_id & 0xFFFF_FFFF)?;
+            }
+
+            shared_page.write().unwrap().metadata[PRIMARY_KEY_INDEX_PAGE_ID_RANGE]
+                .copy_from_slice(&index_page_id.to_be_bytes());
+        }
+
+        Ok(page_id)
+    }
+
+    /// Moves the records into as few pages as the fill factor allows, keeping their order, and
+    /// frees the pages that are left over. The records get new record ids, which the primary key
+    /// index is updated with. Returns the number of freed pages, or `None` without changing the
+    /// table when the records would need more than `max_data_pages` pages.
+    pub fn vacuum(&mut self, max_data_pages: Option<u32>) -> Result<Option<u32>, Error> {
+        let page_ids = self.page_ids();
+        let mut records: Vec<(Vec<ColumnDefinition>, Vec<Option<Value>>)> = vec![];
+        for page_id in page_ids.iter() {
+            let table_page = Self::load_table_page(&self.page_manager, *page_id)?;
+            let page_columns = table_page.column_definitions();
+
+            records.extend(
+                table_page
+                    .get_records()
+                    .into_iter()
+                    .map(|record| (page_columns.clone(), record)),
+            );
+        }
+
+        if max_data_pages.is_some_and(|max| self.vacuumed_page_count(&records) > max as usize) {
+            return Ok(None);
+        }
+
+        Self::write_metadata_page(
+            self.page.clone(),
+            &self.name(),
+            &self.column_definitions(),
+            &vec![],
+        );
+        self.set_free_space_list_head(None);
+        self.set_row_count(0);
+        {
+            let mut page_manager = self.page_manager.write().unwrap();
+            for page_id in page_ids.iter() {
+                page_manager.free_page(*page_id);
+            }
+        }
+
+        // The freed pages are used again for the records, starting at the lowest page id.
+        for (column_definitions, values) in records {
+            self.write_record(column_definitions, values, None)?
+                .ok_or(Error::InsertFailed)?;
+        }
+
+        Ok(Some(page_ids.len() as u32 - self.data_page_count()))
+    }
+
+    /// Returns the number of pages that `vacuum` needs for `records`, by writing them to pages
+    /// that aren't stored anywhere. A record goes to the last of these pages, like in
+    /// `insert_records`, or to a new page when it has other columns or the page has no room left.
+    fn vacuumed_page_count(
+        &self,
+        records: &[(Vec<ColumnDefinition>, Vec<Option<Value>>)],
+    ) -> usize {
+        let fill_factor = self.fill_factor();
+
+        let mut table_pages: Vec<TablePage> = vec![];
+        for (column_definitions, values) in records {
+            let has_room = table_pages.last().is_some_and(|table_page| {
+                table_page.column_definitions() == column_definitions
+                    && !table_page.is_filled_to(fill_factor)
+                    && table_page.has_room_for(values)
+            });
+            if !has_room {
+                table_pages.push(TablePage::initialize(
+                    Rc::new(LockedPage::new(InternalPage::new())),
+                    column_definitions.clone(),
+                ));
+            }
+            table_pages
+                .last_mut()
+                .unwrap()
+                .insert_record(values.clone());
+        }
+
+        table_pages.len()
+    }
+
+    /// Returns the number of pages with records, which is what a scan of the table reads.
+    pub fn data_page_count(&self) -> u32 {
+        self.page_ids().len() as u32
+    }
+
+    /// Returns whether a record with the given columns and values can be inserted without
+    /// allocating a new page.
+    pub fn has_writable_page(
+        &self,
+        column_definitions: &Vec<ColumnDefinition>,
+        values: &[Option<Value>],
+    ) -> Result<bool, Error> {
+        Ok(self
+            .find_writable_page(column_definitions, values)?
+            .is_some())
+    }
+
+    /// Returns the first page of the free space list that has room for the record. The pages at the
+    /// front of the list that are filled to the fill factor, that don't have room for the record or
+    /// that don't have the columns of the table anymore are removed from the list, so they aren't
+    /// loaded again until a record is deleted from them. Records with other columns than the
+    /// table's get a page of their own when the first page has the columns of the table.
+    fn find_writable_page(
+        &self,
+        column_definitions: &Vec<ColumnDefinition>,
+        values: &[Option<Value>],
+    ) -> Result<Option<(usize, TablePage)>, Error> {
+        let fill_factor = self.fill_factor();
+
+        while let Some(page_id) = self.free_space_list_head() {
+            let mut table_page = Self::load_table_page(&self.page_manager, page_id)?;
+
+            if table_page.column_definitions() == column_definitions {
+                if !table_page.is_filled_to(fill_factor) && table_page.has_room_for(values) {
+                    return Ok(Some((page_id as usize, table_page)));
+                }
+            } else if table_page.column_definitions() == &self.column_definitions() {
+                return Ok(None);
+            }
+
+            self.set_free_space_list_head(table_page.free_space_list_next());
+            table_page.set_free_space_list_next(None);
+        }
+
+        Ok(None)
+    }
+
+    /// Returns the first page of the free space list, see `FREE_SPACE_LIST_RANGE`.
+    fn free_space_list_head(&self) -> Option<PageId> {
+        let page = self.page.read().unwrap();
+        let page_id =
+            PageId::from_be_bytes(page.metadata[FREE_SPACE_LIST_RANGE].try_into().unwrap());
+
+        (page_id != 0).then_some(page_id)
+    }
+
+    fn set_free_space_list_head(&self, page_id: Option<PageId>) {
+        self.page.write().unwrap().metadata[FREE_SPACE_LIST_RANGE]
+            .copy_from_slice(&page_id.unwrap_or(0).to_be_bytes());
+    }
+
+    /// Adds the page to the front of the free space list, unless it's in the list already.
+    fn add_to_free_space_list(&self, page_id: PageId, table_page: &mut TablePage) {
+        if table_page.free_space_list_next().is_some() {
+            return;
+        }
+
+        table_page.set_free_space_list_next(Some(self.free_space_list_head().unwrap_or(0)));
+        self.set_free_space_list_head(Some(page_id));
+    }
+
+    /// Returns the pages in the free space list, in order.
+    fn free_space_list(&self) -> Result<Vec<PageId>, Error> {
+        let mut page_ids = vec![];
+        let mut next = self.free_space_list_head();
+        while let Some(page_id) = next {
+            page_ids.push(page_id);
+            next = Self::load_table_page(&self.page_manager, page_id)?
+                .free_space_list_next()
+                .filter(|page_id| *page_id != 0);
+        }
+
+        Ok(page_ids)
+    }
+
+    /// Links the pages of the table with the columns of the table that aren't filled to the fill
+    /// factor into a new free space list, lowest page ids first. Loads every page of the table.
+    pub fn build_free_space_list(&mut self) -> Result<(), Error> {
+        let column_definitions = self.column_definitions();
+        let fill_factor = self.fill_factor();
+        self.page.write().unwrap().metadata[FREE_SPACE_MAP_RANGE].fill(0);
+
+        let mut page_ids = self.page_ids();
+        page_ids.sort();
+        for page_id in page_ids.into_iter().rev() {
+            let mut table_page = Self::load_table_page(&self.page_manager, page_id)?;
+            table_page.set_free_space_list_next(None);
+
+            if table_page.column_definitions() == &column_definitions
+                && !table_page.is_filled_to(fill_factor)
+            {
+                self.add_to_free_space_list(page_id, &mut table_page);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns a page with room for a record with the given columns. The page `preferred_page_id`
+    /// is used as long as it isn't full, even when it's filled beyond the fill factor. Returns
+    /// `None` when a new page is needed, but the table can't have more pages.
+    fn get_writable_page(
+        &mut self,
+        column_definitions: Vec<ColumnDefinition>,
+        values: &[Option<Value>],
+        preferred_page_id: Option<PageId>,
+    ) -> Result<Option<(usize, TablePage)>, Error> {
+        if let Some(page_id) = preferred_page_id {
+            let table_page = Self::load_table_page(&self.page_manager, page_id)?;
+
+            if table_page.column_definitions() == &column_definitions
+                && table_page.has_room_for(values)
+            {
+                return Ok(Some((page_id as usize, table_page)));
+            }
+        }
+
+        if let Some(writable_page) = self.find_writable_page(&column_definitions, values)? {
+            return Ok(Some(writable_page));
+        }
+
+        Ok(self.create_writable_page(column_definitions))
+    }
+
+    /// Adds a new page for records with the given columns to the table, unless the table can't
+    /// have more pages, see `can_add_page`.
+    fn create_writable_page(
+        &mut self,
+        column_definitions: Vec<ColumnDefinition>,
+    ) -> Option<(usize, TablePage)> {
+        if !self.can_add_page() {
+            return None;
+        }
+
+        let (page_id, shared_page) = self.page_manager.write().unwrap().create_page();
+
+        let mut table_page = TablePage::initialize(shared_page, column_definitions);
+        self.append_page_id(page_id);
+        self.add_to_free_space_list(page_id, &mut table_page);
+
+        Some((page_id as usize, table_page))
+    }
+
+    // Takes a record of a page and puts its values in the order of the table's columns, where any
+    // columns that are missing from the page are `None`.
+    fn normalize_page_record(
+        &self,
+        page_columns: &Vec<ColumnDefinition>,
+        page_record: Vec<Option<Value>>,
+    ) -> Vec<Option<Value>> {
+        let positions = Self::record_positions(&self.column_definitions(), page_columns);
+
+        Self::reorder_record(&positions, page_record)
+    }
+
+    /// Returns the position in the records of a page of every column of the table, or `None` for
+    /// the columns that the page doesn't have.
+    fn record_positions(
+        column_definitions: &[ColumnDefinition],
+        page_columns: &[ColumnDefinition],
+    ) -> Vec<Option<usize>> {
+        column_definitions
+            .iter()
+            .map(|expected_column| {
+                page_columns
+                    .iter()
+                    .position(|page_column| expected_column.column_id() == page_column.column_id())
+            })
+            .collect()
+    }
+
+    /// Puts the values of a page record in the order of the table's columns, see
+    /// `record_positions`. The values are moved out of the page record rather than cloned, which
+    /// works because no position appears twice.
+    fn reorder_record(
+        positions: &[Option<usize>],
+        mut page_record: Vec<Option<Value>>,
+    ) -> RowValues {
+        positions
+            .iter()
+            .map(|position| position.and_then(|index| page_record.get_mut(index)?.take()))
+            .collect()
+    }
+
+    fn column_exists(&self, column_name: &str) -> bool {
+        self.column_definitions()
+            .iter()
+            .find(|cd| cd.name() == column_name)
+            .is_some()
+    }
+
+    pub fn column_names(&self) -> Vec<String> {
+        self.column_definitions()
+            .iter()
+            .map(|cd| cd.name().clone())
+            .collect()
+    }
+
+    fn write_metadata_page(
+        shared_page: SharedInternalPage,
+        table_name: &str,
+        column_definitions: &Vec<ColumnDefinition>,
+        page_ids: &Vec<PageId>,
+    ) {
+        let mut page = shared_page.write().unwrap();
+
+        {
+            // Write the table name on the range COLUMN_TABLE_NAME_RANGE
+            page.metadata[COLUMN_TABLE_NAME_RANGE.start] = table_name.len() as u8;
+            page.metadata[COLUMN_TABLE_NAME_RANGE.start + 1
+                ..COLUMN_TABLE_NAME_RANGE.start + 1 + table_name.len()]
+                .copy_from_slice(table_name.as_bytes());
+        }
+
+        let column_definitions_length = {
+            // Write the column definitions
+            let mut column_definition_data: Vec<u8> = Vec::new();
+            column_definition_data.push(column_definitions.len() as u8);
+
+            for column_definition in column_definitions.iter() {
+                column_definition_data.extend_from_slice(&column_definition.to_raw_bytes());
+            }
+
+            let mut metadata: Vec<u8> = Vec::with_capacity(column_definition_data.len());
+            metadata.extend_from_slice(&column_definition_data);
+
+            page.metadata
+                [COLUMN_DEFINITION_START_OFFSET..COLUMN_DEFINITION_START_OFFSET + metadata.len()]
+                .copy_from_slice(&metadata);
+
+            metadata.len()
+        };
+
+        {
+            let mut page_ids_cursor = COLUMN_DEFINITION_START_OFFSET + column_definitions_length;
+
+            // Write page_ids after table_name
+            Self::write_page_count(&mut page, page_ids.len());
+            page_ids_cursor += 1;
+
+            let page_ids_array: Vec<u8> = page_ids
+                .iter()
+                .map(|pid| pid.to_be_bytes())
+                .flatten()
+                .collect();
+
+            page.metadata[page_ids_cursor..page_ids_cursor + page_ids_array.len()]
+                .copy_from_slice(&page_ids_array);
+        }
+    }
+
+    /// Returns the percentage of the slots of a page that inserts fill before they continue in
+    /// another page.
+    pub fn fill_factor(&self) -> u8 {
+        match self.page.read().unwrap().metadata[FILL_FACTOR_OFFSET] {
+            0 => 100,
+            fill_factor => fill_factor,
+        }
+    }
+
+    /// Sets the fill factor, which is clamped to `MIN_FILL_FACTOR..=100`. Pages that are already
+    /// filled beyond it are kept as they are.
+    pub fn set_fill_factor(&mut self, fill_factor: u8) -> Result<(), Error> {
+        let previous_fill_factor = self.fill_factor();
+        self.page.write().unwrap().metadata[FILL_FACTOR_OFFSET] =
+            fill_factor.clamp(MIN_FILL_FACTOR, 100);
+
+        // Pages that were filled to the old fill factor can have room for the new one.
+        if self.fill_factor() > previous_fill_factor {
+            self.build_free_space_list()?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the number of records in the table.
+    pub fn row_count(&self) -> u64 {
+        let page = self.page.read().unwrap();
+        u64::from_be_bytes(page.metadata[ROW_COUNT_RANGE].try_into().unwrap())
+    }
+
+    /// Counts the records in the pages of the table again, for files that were written before
+    /// the table kept its row count.
+    pub fn recount_rows(&mut self) -> Result<(), Error> {
+        let mut row_count = 0;
+        for page_id in self.page_ids() {
+            row_count += Self::load_table_page(&self.page_manager, page_id)?.record_count();
+        }
+
+        self.set_row_count(row_count as u64);
+
+        Ok(())
+    }
+
+    fn set_row_count(&mut self, value: u64) {
+        let mut page = self.page.write().unwrap();
+        page.metadata[ROW_COUNT_RANGE].copy_from_slice(&value.to_be_bytes());
+    }
+
+    fn serial_counter(&self) -> u64 {
+        let page = self.page.read().unwrap();
+        u64::from_be_bytes(page.metadata[SERIAL_COUNTER_RANGE].try_into().unwrap())
+    }
+
+    fn set_serial_counter(&mut self, value: u64) {
+        let mut page = self.page.write().unwrap();
+        page.metadata[SERIAL_COUNTER_RANGE].copy_from_slice(&value.to_be_bytes());
+    }
+
+    fn primary_key_index(&self) -> Result<Option<PrimaryKeyIndex>, Error> {
+        let page_id = {
+            let page = self.page.read().unwrap();
+            PageId::from_be_bytes(
+                page.metadata[PRIMARY_KEY_INDEX_PAGE_ID_RANGE]
+                    .try_into()
+                    .unwrap(),
+            )
+        };
+
+        if page_id == 0 {
+            return Ok(None);
+        }
+
+        let shared_page = self
+            .page_manager
+            .read()
+            .unwrap()
+            .fetch_page(page_id)?
+            .expect("The pages of the primary key index exist");
+        Ok(Some(PrimaryKeyIndex::load(
+            self.page_manager.clone(),
+            shared_page,
+        )?))
+    }
+
+    /// Loads the page of the table with the given id, see `PageManager::fetch_page`.
+    fn load_table_page(
+        page_manager: &Rc<RwLock<PageManager>>,
+        page_id: PageId,
+    ) -> Result<TablePage, Error> {
+        let shared_page = page_manager
+            .read()
+            .unwrap()
+            .fetch_page(page_id)?
+            .expect("The pages of the table exist");
+
+        Ok(TablePage::load(shared_page))
+    }
+
+    fn page_ids(&self) -> Vec<PageId> {
+        let page = self.page.read().unwrap();
+
+        let mut cursor = Self::page_ids_offset(&page);
+
+        let number_of_pages = Self::page_count_of(&page);
+        cursor += 1;
+
+        let mut page_ids = Vec::new();
+
+        for _ in 0..number_of_pages {
+            let page_id =
+                PageId::from_be_bytes(page.metadata[cursor..cursor + 4].try_into().unwrap());
+            page_ids.push(page_id);
+
+            cursor += 4;
+        }
+
+        return page_ids;
+    }
+
+    /// Adds a page to the end of the table's page ids, without writing the rest of the metadata
+    /// page again.
+    fn append_page_id(&mut self, page_id: PageId) {
+        let mut page = self.page.write().unwrap();
+
+        let cursor = Self::page_ids_offset(&page);
+        let number_of_pages = Self::page_count_of(&page);
+        Self::write_page_count(&mut page, number_of_pages + 1);
+
+        let start = cursor + 1 + number_of_pages * 4;
+        page.metadata[start..start + 4].copy_from_slice(&page_id.to_be_bytes());
+    }
+
+    /// Returns whether there is room for one more page id in the metadata page, so that the
+    /// table can get another page.
+    pub fn can_add_page(&self) -> bool {
+        let page = self.page.read().unwrap();
+        let end = Self::page_ids_offset(&page) + 1 + (Self::page_count_of(&page) + 1) * 4;
+
+        end <= PAGE_COUNT_HIGH_BYTE_OFFSET
+    }
+
+    /// Returns the number of page ids, see `PAGE_COUNT_HIGH_BYTE_OFFSET`.
+    fn page_count_of(page: &InternalPage) -> usize {
+        let low_byte = page.metadata[Self::page_ids_offset(page)];
+
+        u16::from_be_bytes([page.metadata[PAGE_COUNT_HIGH_BYTE_OFFSET], low_byte]) as usize
+    }
+
+    fn write_page_count(page: &mut InternalPage, page_count: usize) {
+        let [high_byte, low_byte] = (page_count as u16).to_be_bytes();
+        let cursor = Self::page_ids_offset(page);
+        page.metadata[cursor] = low_byte;
+        page.metadata[PAGE_COUNT_HIGH_BYTE_OFFSET] = high_byte;
+    }
+
+    /// Returns the offset of the number of page ids, which directly follow the column
+    /// definitions.
+    fn page_ids_offset(page: &InternalPage) -> usize {
+        let mut cursor = COLUMN_DEFINITION_START_OFFSET;
+
+        // Skip all the definitions
+        let number_of_definitions = page.metadata[cursor];
+        cursor += 1;
+
+        for _ in 0..number_of_definitions {
+            let number_of_bytes_for_definition = page.metadata[cursor];
+            cursor += number_of_bytes_for_definition as usize + 1;
+        }
+
+        cursor
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::sync::RwLock;
+
+    use super::*;
+    use crate::internal::{arbitrary, LockedPage, MemoryBackend};
+
+    fn create_table_manager(
+        page_manager: Rc<RwLock<PageManager>>,
+        table_name: &str,
+    ) -> Result<TableManager, Error> {
+        let (_page_id, shared_page) = page_manager.write().unwrap().create_page();
+        TableManager::initialize(page_manager, shared_page, table_name)
+    }
+
+    /// Inserts a record with a value for every column of the table.
+    fn insert_values(table_manager: &mut TableManager, values: Vec<Value>) -> Option<u64> {
+        table_manager
+            .insert_record_into_columns(
+                table_manager.column_definitions(),
+                values.into_iter().map(Some).collect(),
+            )
+            .unwrap()
+    }
+
+    #[test]
+    fn test_loading_metadata_round_trips() {
+        arbitrary::check(|random| {
+            let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+            let table_name = arbitrary::name(random, 62);
+            let mut table_manager =
+                create_table_manager(page_manager.clone(), &table_name).unwrap();
+
+            for column_definition in arbitrary::column_definitions(random, 8) {
+                let column_name = column_definition.name();
+                table_manager
+                    .add_column(column_name, column_definition.data_type().clone())
+                    .unwrap();
+
+                if column_definition.is_not_null() {
+                    table_manager.set_not_null(column_name).unwrap();
+                }
+            }
+
+            for _ in 0..arbitrary::between(random, 0..=600) {
+                let column_definitions = table_manager.column_definitions();
+                let record = arbitrary::record(random, &column_definitions);
+                assert!(table_manager
+                    .insert_record_into_columns(column_definitions, record)
+                    .unwrap()
+                    .is_some());
+            }
+
+            let loaded_table_manager =
+                TableManager::load(page_manager, arbitrary::copy_page(&table_manager.page))
+                    .unwrap();
+            assert_eq!(table_name, loaded_table_manager.name());
+            assert_eq!(
+                table_manager.column_definitions(),
+                loaded_table_manager.column_definitions()
+            );
+            assert_eq!(table_manager.page_ids(), loaded_table_manager.page_ids());
+            assert_eq!(
+                table_manager.get_records().unwrap(),
+                loaded_table_manager.get_records().unwrap()
+            );
+        });
+    }
+
+    #[test]
+    fn test_assigning_serial_values() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut table_manager = create_table_manager(page_manager, "users").unwrap();
+        table_manager.add_column("id", DataType::Integer).unwrap();
+        table_manager.add_column("age", DataType::Integer).unwrap();
+        insert_values(
+            &mut table_manager,
+            vec![Value::Integer(253), Value::Integer(20)],
+        )
+        .unwrap();
+        insert_values(
+            &mut table_manager,
+            vec![Value::Integer(-4), Value::Integer(25)],
+        )
+        .unwrap();
+
+        // The counter continues after the values that the column already has.
+        table_manager.set_serial("id").unwrap();
+        let age_column = table_manager.column_definitions()[1..].to_vec();
+        assert_eq!(
+            Ok((
+                table_manager.column_definitions(),
+                vec![Some(Value::Integer(254)), Some(Value::Integer(30))]
+            )),
+            table_manager.assign_serial_values(age_column.clone(), vec![Some(Value::Integer(30))])
+        );
+
+        table_manager.set_serial_counter(i64::MAX as u64 - 1);
+        assert!(table_manager
+            .assign_serial_values(age_column.clone(), vec![Some(Value::Integer(30))])
+            .is_ok());
+        assert_eq!(
+            Err(Error::IntegerOutOfRange),
+            table_manager.assign_serial_values(age_column, vec![Some(Value::Integer(30))])
+        );
+    }
+
+    #[test]
+    fn test_fill_factor_leaves_room_for_updates() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut table_manager = create_table_manager(page_manager, "users").unwrap();
+        table_manager.add_column("age", DataType::Integer).unwrap();
+        assert_eq!(100, table_manager.fill_factor());
+
+        table_manager.set_fill_factor(0).unwrap();
+        assert_eq!(MIN_FILL_FACTOR, table_manager.fill_factor());
+
+        // 10% of the 255 slots of a page are filled by 26 records.
+        let record_ids: Vec<u64> = (0..27)
+            .map(|age| insert_values(&mut table_manager, vec![Value::Integer(age)]).unwrap())
+            .collect();
+        let page_ids = table_manager.page_ids();
+        assert_eq!(2, page_ids.len());
+        assert_eq!(page_ids[0] as u64, record_ids[25] >> 32);
+        assert_eq!(page_ids[1] as u64, record_ids[26] >> 32);
+
+        // Updates stay in the page of the record, even though it's filled to the fill factor.
+        let columns = table_manager.column_definitions();
+        let record_id = table_manager
+            .update_record(record_ids[0], columns, vec![Some(Value::Integer(100))])
+            .unwrap()
+            .unwrap();
+        assert_eq!(page_ids[0] as u64, record_id >> 32);
+        assert_eq!(
+            Some(vec![Some(Value::Integer(100))]),
+            table_manager
+                .get_record(record_id)
+                .unwrap()
+                .map(|row_result| row_result.rows()[0].clone())
+        );
+        assert_eq!(27, table_manager.get_records().unwrap().rows().len());
+    }
+
+    #[test]
+    fn test_free_space_list() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut table_manager = create_table_manager(page_manager, "users").unwrap();
+        table_manager.add_column("age", DataType::Integer).unwrap();
+        table_manager.set_fill_factor(MIN_FILL_FACTOR).unwrap();
+
+        // Every page holds 26 records, and is removed from the list once an insert passes over it.
+        let record_ids: Vec<u64> = (0..80)
+            .map(|age| insert_values(&mut table_manager, vec![Value::Integer(age)]).unwrap())
+            .collect();
+        let page_ids = table_manager.page_ids();
+        assert_eq!(4, page_ids.len());
+        assert_eq!(vec![page_ids[3]], table_manager.free_space_list().unwrap());
+        table_manager.debug_check_invariants();
+
+        // A deleted record leaves room in its page, which is used by the next insert.
+        table_manager.delete_record(record_ids[30]).unwrap();
+        table_manager.delete_record(record_ids[31]).unwrap();
+        assert_eq!(
+            vec![page_ids[1], page_ids[3]],
+            table_manager.free_space_list().unwrap()
+        );
+        let record_id = insert_values(&mut table_manager, vec![Value::Integer(100)]).unwrap();
+        assert_eq!(page_ids[1] as u64, record_id >> 32);
+        table_manager.debug_check_invariants();
+
+        // The pages have room again with a larger fill factor.
+        table_manager.set_fill_factor(100).unwrap();
+        assert_eq!(page_ids, table_manager.free_space_list().unwrap());
+        let record_id = insert_values(&mut table_manager, vec![Value::Integer(101)]).unwrap();
+        assert_eq!(page_ids[0] as u64, record_id >> 32);
+        table_manager.debug_check_invariants();
+    }
+
+    #[test]
+    fn test_pages_without_room_for_a_record_leave_the_free_space_list() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut table_manager = create_table_manager(page_manager, "users").unwrap();
+        table_manager.add_column("name", DataType::Text).unwrap();
+        let name = |length: usize| vec![Value::Text("a".repeat(length))];
+
+        let first_record_id = insert_values(&mut table_manager, name(2000)).unwrap();
+        let second_record_id = insert_values(&mut table_manager, name(2000)).unwrap();
+        assert_ne!(first_record_id >> 32, second_record_id >> 32);
+        let page_ids = table_manager.page_ids();
+        assert_eq!(vec![page_ids[1]], table_manager.free_space_list().unwrap());
+
+        // The first page still has room for smaller records, but isn't looked at again.
+        let record_id = insert_values(&mut table_manager, name(10)).unwrap();
+        assert_eq!(page_ids[1] as u64, record_id >> 32);
+
+        table_manager.delete_record(first_record_id).unwrap();
+        assert_eq!(page_ids, table_manager.free_space_list().unwrap());
+        let record_id = insert_values(&mut table_manager, name(10)).unwrap();
+        assert_eq!(page_ids[0] as u64, record_id >> 32);
+        table_manager.debug_check_invariants();
+    }
+
+    #[test]
+    fn test_inserting_into_a_table_with_many_pages() {
+        let page_manager = Rc::new(RwLock::new(PageManager::with_backend(Box::new(
+            MemoryBackend::new(),
+        ))));
+        let mut table_manager = create_table_manager(page_manager.clone(), "users").unwrap();
+        table_manager.add_column("age", DataType::Integer).unwrap();
+        table_manager.set_fill_factor(MIN_FILL_FACTOR).unwrap();
+        let column_definitions = table_manager.column_definitions();
+        let records = |ages: std::ops::Range<i64>| {
+            ages.map(|age| (column_definitions.clone(), vec![Some(Value::Integer(age))]))
+                .collect()
+        };
+
+        // Every page holds 26 records.
+        let record_ids = table_manager
+            .insert_records(records(0..26 * 300), None)
+            .unwrap()
+            .unwrap();
+        let page_ids = table_manager.page_ids();
+        assert_eq!(300, page_ids.len());
+        assert_eq!(
+            page_ids,
+            TableManager::load(
+                page_manager.clone(),
+                arbitrary::copy_page(&table_manager.page)
+            )
+            .unwrap()
+            .page_ids()
+        );
+        table_manager.debug_check_invariants();
+
+        // An insert only loads the first page of the free space list, wherever it is in the table.
+        let loaded_page_count = || {
+            let mut page_manager = page_manager.write().unwrap();
+            let loaded_page_count = page_manager.loaded_page_count();
+            page_manager.shrink_to_fit();
+            loaded_page_count
+        };
+        table_manager.delete_record(record_ids[26 * 280]).unwrap();
+        loaded_page_count();
+        let record_id = insert_values(&mut table_manager, vec![Value::Integer(-1)]).unwrap();
+        assert_eq!(page_ids[280] as u64, record_id >> 32);
+        assert_eq!(2, loaded_page_count());
+
+        table_manager.delete_record(record_ids[26 * 10]).unwrap();
+        loaded_page_count();
+        let record_id = insert_values(&mut table_manager, vec![Value::Integer(-2)]).unwrap();
+        assert_eq!(page_ids[10] as u64, record_id >> 32);
+        assert_eq!(2, loaded_page_count());
+        assert_eq!(300, table_manager.data_page_count());
+        table_manager.debug_check_invariants();
+
+        // The filled pages at the front of the list are removed once, then a new page is added.
+        insert_values(&mut table_manager, vec![Value::Integer(-3)]).unwrap();
+        assert_eq!(301, table_manager.data_page_count());
+        assert_eq!(1, table_manager.free_space_list().unwrap().len());
+        loaded_page_count();
+        insert_values(&mut table_manager, vec![Value::Integer(-4)]).unwrap();
+        assert_eq!(2, loaded_page_count());
+
+        // The page ids can fill the metadata page, but not more. The pages that were added for the
+        // records that didn't fit are kept for later inserts.
+        assert_eq!(
+            None,
+            table_manager
+                .insert_records(records(0..26 * 1000), None)
+                .unwrap()
+        );
+        assert!(!table_manager.can_add_page());
+        assert_eq!(26 * 300 + 2, table_manager.row_count());
+        let page_count = table_manager.data_page_count();
+        assert!(insert_values(&mut table_manager, vec![Value::Integer(-5)]).is_some());
+        assert_eq!(page_count, table_manager.data_page_count());
+        table_manager.debug_check_invariants();
+    }
+
+    #[test]
+    fn test_vacuum_compacts_pages() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut table_manager = create_table_manager(page_manager.clone(), "users").unwrap();
+        table_manager.add_column("id", DataType::Integer).unwrap();
+        table_manager.set_primary_key("id").unwrap();
+
+        let record_ids: Vec<u64> = (0..600)
+            .map(|id| insert_values(&mut table_manager, vec![Value::Integer(id)]).unwrap())
+            .collect();
+        assert_eq!(3, table_manager.data_page_count());
+
+        // Every record but one out of ten is deleted, which leaves every page sparse.
+        for (id, record_id) in record_ids.iter().enumerate() {
+            if id % 10 != 0 {
+                table_manager.delete_record(*record_id).unwrap();
+            }
+        }
+
+        // The records need one page, so a vacuum with no pages to spare doesn't free anything.
+        assert_eq!(Ok(None), table_manager.vacuum(Some(0)));
+        assert_eq!(3, table_manager.data_page_count());
+
+        assert_eq!(Ok(Some(2)), table_manager.vacuum(Some(1)));
+        assert_eq!(1, table_manager.data_page_count());
+        assert_eq!(2, page_manager.read().unwrap().free_page_count());
+        table_manager.debug_check_invariants();
+
+        let ids: Vec<RowValues> = (0..60)
+            .map(|id| vec![Some(Value::Integer(id * 10))])
+            .collect();
+        assert_eq!(ids, table_manager.get_records().unwrap().rows().to_vec());
+        let record_id = table_manager
+            .find_by_primary_key(&Value::Integer(590))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            Some(vec![Some(Value::Integer(590))]),
+            table_manager
+                .get_record(record_id)
+                .unwrap()
+                .map(|row_result| row_result.rows()[0].clone())
+        );
+
+        // The freed pages are used again for new records.
+        for id in 600..800 {
+            insert_values(&mut table_manager, vec![Value::Integer(id)]).unwrap();
+        }
+        assert_eq!(2, table_manager.data_page_count());
+        assert!(page_manager.read().unwrap().free_page_count() < 2);
+    }
+
+    #[test]
+    fn test_scanning_records() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut table_manager = create_table_manager(page_manager, "users").unwrap();
+        table_manager.add_column("id", DataType::Integer).unwrap();
+        for id in 0..1000 {
+            insert_values(&mut table_manager, vec![Value::Integer(id)]).unwrap();
+        }
+        table_manager.add_column("age", DataType::Integer).unwrap();
+        insert_values(
+            &mut table_manager,
+            vec![Value::Integer(1000), Value::Integer(30)],
+        )
+        .unwrap();
+
+        let mut rows = table_manager.scan();
+        assert_eq!(Some(Ok(vec![Some(Value::Integer(0)), None])), rows.next());
+        assert_eq!(1000, rows.count());
+        assert_eq!(
+            table_manager.get_records().unwrap().rows(),
+            &table_manager
+                .scan()
+                .collect::<Result<Vec<RowValues>, _>>()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_scanning_filtered_records() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut table_manager = create_table_manager(page_manager, "users").unwrap();
+        table_manager.add_column("id", DataType::Integer).unwrap();
+        insert_values(&mut table_manager, vec![Value::Integer(1)]).unwrap();
+        table_manager.add_column("age", DataType::Integer).unwrap();
+        insert_values(
+            &mut table_manager,
+            vec![Value::Integer(2), Value::Integer(30)],
+        )
+        .unwrap();
+
+        // The page of the first record doesn't have `age`, so it's `NULL` for the predicate.
+        let rows: Vec<Result<RowValues, Error>> = table_manager
+            .scan_filtered(|columns, row| {
+                assert_eq!(&["id".to_string(), "age".to_string()], columns);
+                Ok(row[1].is_none())
+            })
+            .collect();
+        assert_eq!(vec![Ok(vec![Some(Value::Integer(1)), None])], rows);
+
+        let rows: Vec<Result<RowValues, Error>> = table_manager
+            .scan_filtered(|_columns, _row| Err(Error::DivisionByZero))
+            .collect();
+        assert_eq!(
+            vec![Err(Error::DivisionByZero), Err(Error::DivisionByZero)],
+            rows
+        );
+    }
+
+    #[test]
+    fn test_fetching_records_for_columns() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut table_manager = create_table_manager(page_manager, "users").unwrap();
+        table_manager.add_column("id", DataType::Integer).unwrap();
+        table_manager.add_column("name", DataType::Text).unwrap();
+        insert_values(
+            &mut table_manager,
+            vec![Value::Integer(1), Value::Text("Ada".to_string())],
+        )
+        .unwrap();
+        table_manager.add_column("age", DataType::Integer).unwrap();
+        insert_values(
+            &mut table_manager,
+            vec![
+                Value::Integer(2),
+                Value::Text("Grace".to_string()),
+                Value::Integer(30),
+            ],
+        )
+        .unwrap();
+
+        let rows: Vec<RowValues> = table_manager
+            .scan_columns(&["age", "name", "age"])
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            vec![
+                vec![None, Some(Value::Text("Ada".to_string())), None],
+                vec![
+                    Some(Value::Integer(30)),
+                    Some(Value::Text("Grace".to_string())),
+                    Some(Value::Integer(30))
+                ],
+            ],
+            rows
+        );
+
+        assert_eq!(
+            Some(Error::ColumnDoesNotExist("missing".to_string())),
+            table_manager.scan_columns(&["missing"]).err()
+        );
+    }
+
+    #[test]
+    fn test_inserting_records_in_a_batch() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut table_manager = create_table_manager(page_manager, "users").unwrap();
+        table_manager.add_column("id", DataType::Integer).unwrap();
+        table_manager.add_column("age", DataType::Integer).unwrap();
+        table_manager.set_primary_key("id").unwrap();
+        insert_values(
+            &mut table_manager,
+            vec![Value::Integer(0), Value::Integer(20)],
+        )
+        .unwrap();
+        let column_definitions = table_manager.column_definitions();
+        let record = |id: i64| {
+            (
+                column_definitions.clone(),
+                vec![Some(Value::Integer(id)), Some(Value::Integer(30))],
+            )
+        };
+
+        let record_ids = table_manager
+            .insert_records((1..1000).map(record).collect(), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(999, record_ids.len());
+        assert_eq!(1000, table_manager.row_count());
+        assert_eq!(
+            Some(record_ids[500]),
+            table_manager
+                .find_by_primary_key(&Value::Integer(501))
+                .unwrap()
+        );
+        // The pages that the batch filled are left out of the free space list.
+        assert_eq!(
+            vec![*table_manager.page_ids().last().unwrap()],
+            table_manager.free_space_list().unwrap()
+        );
+        table_manager.debug_check_invariants();
+
+        let mut single_inserts =
+            create_table_manager(Rc::new(RwLock::new(PageManager::new(":memory:"))), "users")
+                .unwrap();
+        single_inserts.add_column("id", DataType::Integer).unwrap();
+        single_inserts.add_column("age", DataType::Integer).unwrap();
+        for id in 0..1000 {
+            insert_values(
+                &mut single_inserts,
+                vec![Value::Integer(id), Value::Integer(30)],
+            )
+            .unwrap();
+        }
+        assert_eq!(
+            single_inserts.data_page_count(),
+            table_manager.data_page_count()
+        );
+
+        // Nothing is inserted when one of the records doesn't fit the columns.
+        assert_eq!(
+            None,
+            table_manager
+                .insert_records(
+                    vec![
+                        record(1000),
+                        (column_definitions.clone(), vec![Some(Value::Integer(1001))]),
+                    ],
+                    None
+                )
+                .unwrap()
+        );
+        assert_eq!(1000, table_manager.row_count());
+
+        // Or when the records need more pages than the batch may add, then the records that were
+        // already written are deleted again.
+        assert_eq!(
+            None,
+            table_manager
+                .insert_records((1000..2000).map(record).collect(), Some(1))
+                .unwrap()
+        );
+        assert_eq!(1000, table_manager.row_count());
+        assert_eq!(1000, table_manager.get_records().unwrap().rows().len());
+        assert_eq!(
+            None,
+            table_manager
+                .find_by_primary_key(&Value::Integer(1000))
+                .unwrap()
+        );
+        table_manager.debug_check_invariants();
+    }
+
+    #[test]
+    fn test_appending_page_ids() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut table_manager = create_table_manager(page_manager.clone(), "users").unwrap();
+        table_manager.add_column("id", DataType::Integer).unwrap();
+        table_manager.add_column("age", DataType::Integer).unwrap();
+        let column_definitions = table_manager.column_definitions();
+
+        for id in 0..1000 {
+            insert_values(
+                &mut table_manager,
+                vec![Value::Integer(id), Value::Integer(30)],
+            )
+            .unwrap();
+        }
+        let page_ids = table_manager.page_ids();
+        assert!(page_ids.len() > 1);
+
+        let loaded_table_manager =
+            TableManager::load(page_manager, arbitrary::copy_page(&table_manager.page)).unwrap();
+        assert_eq!("users", loaded_table_manager.name());
+        assert_eq!(
+            column_definitions,
+            loaded_table_manager.column_definitions()
+        );
+        assert_eq!(page_ids, loaded_table_manager.page_ids());
+        assert_eq!(
+            1000,
+            loaded_table_manager.get_records().unwrap().rows().len()
+        );
+    }
+
+    #[test]
+    fn test_keeping_the_row_count() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut table_manager = create_table_manager(page_manager.clone(), "users").unwrap();
+        table_manager.add_column("id", DataType::Integer).unwrap();
+        assert_eq!(0, table_manager.row_count());
+
+        let record_ids: Vec<u64> = (0..300)
+            .map(|id| insert_values(&mut table_manager, vec![Value::Integer(id)]).unwrap())
+            .collect();
+        assert_eq!(300, table_manager.row_count());
+
+        // Deleting a record that doesn't exist anymore doesn't change the count.
+        table_manager.delete_record(record_ids[0]).unwrap();
+        table_manager.delete_record(record_ids[0]).unwrap();
+        assert_eq!(299, table_manager.row_count());
+
+        let column_definitions = table_manager.column_definitions();
+        table_manager
+            .update_record(
+                record_ids[1],
+                column_definitions,
+                vec![Some(Value::Integer(1000))],
+            )
+            .unwrap();
+        assert_eq!(299, table_manager.row_count());
+
+        table_manager.vacuum(None).unwrap();
+        assert_eq!(299, table_manager.row_count());
+
+        table_manager.set_row_count(0);
+        table_manager.recount_rows().unwrap();
+        assert_eq!(299, table_manager.row_count());
+        table_manager.debug_check_invariants();
+    }
+
+    #[test]
+    fn fetching_table_name_works() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let table_manager = create_table_manager(page_manager, "test").unwrap();
+        assert_eq!("test", table_manager.name());
+    }
+
+    #[test]
+    fn get_records_for_pages_with_different_columns() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut table_manager = create_table_manager(page_manager, "test").unwrap();
+        table_manager.add_column("day", DataType::Integer).unwrap();
+        assert!(insert_values(&mut table_manager, vec![Value::Integer(31)]).is_some());
+
+        table_manager
+            .add_column("month", DataType::Integer)
+            .unwrap();
+        assert!(insert_values(
+            &mut table_manager,
+            vec![Value::Integer(1), Value::Integer(5)]
+        )
+        .is_some());
+
+        // Returns records for the _current_ columns. Order is not guaranteed.
+        let records = table_manager.get_records().unwrap();
+        assert!(
             records
                 .rows()
                 .contains(&vec![Some(Value::Integer(31)), None]),
@@ -453,51 +2075,61 @@ mod tests {
     #[test]
     fn test_get_records_with_specific_columns() {
         let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut table_manager = TableManager::new(page_manager, "test").unwrap();
+        let mut table_manager = create_table_manager(page_manager, "test").unwrap();
         table_manager.add_column("day", DataType::Integer).unwrap();
-        assert!(table_manager
-            .insert_record(vec![Value::Integer(13)])
-            .is_some());
+        assert!(insert_values(&mut table_manager, vec![Value::Integer(13)]).is_some());
 
         table_manager
             .add_column("month", DataType::Integer)
             .unwrap();
-        assert!(table_manager
-            .insert_record(vec![Value::Integer(2), Value::Integer(4)])
-            .is_some());
+        assert!(insert_values(
+            &mut table_manager,
+            vec![Value::Integer(2), Value::Integer(4)]
+        )
+        .is_some());
 
         // Returns records for the _given_ columns. Order is not guaranteed.
-        let records = table_manager
-            .get_records_for_columns(&vec!["month", "day"])
-            .expect("Failed to get the result");
+        let records: Vec<RowValues> = table_manager
+            .scan_columns(&["month", "day"])
+            .expect("Failed to get the result")
+            .collect::<Result<_, _>>()
+            .unwrap();
 
-        assert_eq!(2, *records.count());
+        assert_eq!(2, records.len());
         assert!(
-            records
-                .rows()
-                .contains(&vec![None, Some(Value::Integer(13))]),
+            records.contains(&vec![None, Some(Value::Integer(13))]),
             "Missing record with only first column"
         );
         assert!(
-            records
-                .rows()
-                .contains(&vec![Some(Value::Integer(4)), Some(Value::Integer(2))]),
+            records.contains(&vec![Some(Value::Integer(4)), Some(Value::Integer(2))]),
             "Missing record both column"
         );
     }
 
+    #[test]
+    fn test_inserting_record_with_mismatching_types() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut table_manager = create_table_manager(page_manager, "test").unwrap();
+        table_manager.add_column("day", DataType::Integer).unwrap();
+
+        assert_eq!(
+            None,
+            insert_values(&mut table_manager, vec![Value::Boolean(true)])
+        );
+    }
+
     #[test]
     fn test_getting_a_single_record_works() {
         let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut table_manager = TableManager::new(page_manager, "test").unwrap();
+        let mut table_manager = create_table_manager(page_manager, "test").unwrap();
         table_manager.add_column("day", DataType::Integer).unwrap();
 
-        let record_id = table_manager
-            .insert_record(vec![Value::Integer(13)])
+        let record_id = insert_values(&mut table_manager, vec![Value::Integer(13)])
             .expect("Failed to insert record");
 
         let record = table_manager
             .get_record(record_id)
+            .unwrap()
             .expect("Failed to fetch inserted record.");
 
         assert_eq!(
@@ -509,19 +2141,19 @@ mod tests {
     #[test]
     fn test_get_records_with_specific_columns_with_invalid_columns() {
         let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let mut table_manager = TableManager::new(page_manager, "test").unwrap();
+        let mut table_manager = create_table_manager(page_manager, "test").unwrap();
         table_manager.add_column("day", DataType::Integer).unwrap();
 
         assert_eq!(
-            Err(Error::ColumnDoesNotExist("month".to_string())),
-            table_manager.get_records_for_columns(&vec!["month", "day"])
+            Some(Error::ColumnDoesNotExist("month".to_string())),
+            table_manager.scan_columns(&["month", "day"]).err()
         )
     }
 
     #[test]
     fn test_initialize_and_load() {
         let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
-        let page = Rc::new(RwLock::new(InternalPage::new()));
+        let page = Rc::new(LockedPage::new(InternalPage::new()));
 
         {
             let table_manager =
@@ -1,14 +1,158 @@
 use std::fmt::Display;
 
-#[derive(Debug, Clone, PartialEq)]
+use super::json::Json;
+use super::parser::{CastType, LiteralValue};
+use super::{hex, Decimal, Error, Uuid};
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
 pub enum Value {
-    Integer(u8),
+    Integer(i64),
+    Boolean(bool),
+    Text(String),
+    Decimal(Decimal),
+    Bytes(Vec<u8>),
+    Uuid(Uuid),
+    /// Text that is valid JSON, which is kept as it was written.
+    Json(String),
 }
 
 impl Value {
     pub fn to_bsql_data(&self) -> Vec<u8> {
+        self.with_bsql_data(|data| data.to_vec())
+    }
+
+    /// Returns the number of bytes of `to_bsql_data`.
+    pub fn bsql_data_len(&self) -> usize {
+        self.with_bsql_data(|data| data.len())
+    }
+
+    /// Writes the bytes of `to_bsql_data` to the start of `buffer` without allocating them first.
+    /// Returns the number of bytes that were written.
+    pub fn write_bsql_data(&self, buffer: &mut [u8]) -> usize {
+        self.with_bsql_data(|data| {
+            buffer[..data.len()].copy_from_slice(data);
+            data.len()
+        })
+    }
+
+    fn with_bsql_data<R>(&self, f: impl FnOnce(&[u8]) -> R) -> R {
         match self {
-            Value::Integer(value) => vec![*value],
+            Value::Integer(value) => f(&value.to_be_bytes()),
+            Value::Boolean(value) => f(&[*value as u8]),
+            Value::Text(value) => f(value.as_bytes()),
+            // The scale is part of the column's type, see `DataType::coerce`.
+            Value::Decimal(value) => f(&value.mantissa().to_be_bytes()),
+            Value::Bytes(value) => f(value),
+            Value::Uuid(value) => f(value.as_bytes()),
+            Value::Json(value) => f(value.as_bytes()),
+        }
+    }
+
+    /// Converts the value to another type. Integers are true when they aren't 0, and text is
+    /// converted the way it would be written as a literal (ignoring surrounding whitespace).
+    /// Numerics are rounded to the nearest integer. Binary data is written as text in the hex
+    /// format, which is also the format that text starting with `\x` is read in. Only valid JSON
+    /// text can be converted to `json`.
+    pub fn cast(self, data_type: CastType) -> Result<Value, Error> {
+        match (self, data_type) {
+            (Value::Integer(value), CastType::Integer) => Ok(Value::Integer(value)),
+            (Value::Integer(value), CastType::Boolean) => Ok(Value::Boolean(value != 0)),
+            (Value::Integer(value), CastType::Text) => Ok(Value::Text(value.to_string())),
+            (Value::Integer(value), CastType::Numeric) => Ok(Value::Decimal(value.into())),
+
+            (Value::Boolean(value), CastType::Integer) => Ok(Value::Integer(value as i64)),
+            (Value::Boolean(value), CastType::Boolean) => Ok(Value::Boolean(value)),
+            (Value::Boolean(value), CastType::Text) => Ok(Value::Text(value.to_string())),
+            (Value::Boolean(value), CastType::Numeric) => Ok(Value::Decimal((value as i64).into())),
+
+            (Value::Text(value), CastType::Integer) => {
+                let digits = value.trim();
+                let unsigned_digits = digits.strip_prefix(['-', '+']).unwrap_or(digits);
+                match digits.parse::<i64>() {
+                    Ok(integer) => Ok(Value::Integer(integer)),
+                    Err(_)
+                        if !unsigned_digits.is_empty()
+                            && unsigned_digits.chars().all(|c| c.is_ascii_digit()) =>
+                    {
+                        Err(Error::IntegerOutOfRange)
+                    }
+                    Err(_) => Err(Error::InvalidCast { value, data_type }),
+                }
+            }
+            (Value::Text(value), CastType::Boolean) => match value.trim().to_lowercase().as_str() {
+                "true" | "t" | "yes" | "y" | "on" | "1" => Ok(Value::Boolean(true)),
+                "false" | "f" | "no" | "n" | "off" | "0" => Ok(Value::Boolean(false)),
+                _ => Err(Error::InvalidCast { value, data_type }),
+            },
+            (Value::Text(value), CastType::Text) => Ok(Value::Text(value)),
+            (Value::Text(value), CastType::Numeric) => match Decimal::parse(value.trim()) {
+                Some(decimal) => Ok(Value::Decimal(decimal)),
+                None => Err(Error::InvalidCast { value, data_type }),
+            },
+            (Value::Text(value), CastType::Bytea) => match value.strip_prefix("\\x") {
+                Some(digits) => match hex::decode(digits) {
+                    Some(bytes) => Ok(Value::Bytes(bytes)),
+                    None => Err(Error::InvalidCast { value, data_type }),
+                },
+                None => Ok(Value::Bytes(value.into_bytes())),
+            },
+
+            (Value::Decimal(value), CastType::Integer) => value
+                .to_integer()
+                .map(Value::Integer)
+                .ok_or(Error::IntegerOutOfRange),
+            (Value::Decimal(value), CastType::Boolean) => Ok(Value::Boolean(!value.is_zero())),
+            (Value::Decimal(value), CastType::Text) => Ok(Value::Text(value.to_string())),
+            (Value::Decimal(value), CastType::Numeric) => Ok(Value::Decimal(value)),
+
+            (Value::Text(value), CastType::Uuid) => match Uuid::parse(value.trim()) {
+                Some(uuid) => Ok(Value::Uuid(uuid)),
+                None => Err(Error::InvalidCast { value, data_type }),
+            },
+
+            (Value::Bytes(value), CastType::Bytea) => Ok(Value::Bytes(value)),
+            (value @ Value::Bytes(_), CastType::Text) => Ok(Value::Text(value.to_string())),
+
+            (Value::Uuid(value), CastType::Uuid) => Ok(Value::Uuid(value)),
+            (Value::Uuid(value), CastType::Text) => Ok(Value::Text(value.to_string())),
+
+            (Value::Text(value), CastType::Json) => match Json::parse(&value) {
+                Some(_) => Ok(Value::Json(value)),
+                None => Err(Error::InvalidCast { value, data_type }),
+            },
+            (Value::Json(value), CastType::Json) => Ok(Value::Json(value)),
+            (Value::Json(value), CastType::Text) => Ok(Value::Text(value)),
+
+            (Value::Bytes(_) | Value::Uuid(_) | Value::Json(_), _)
+            | (_, CastType::Bytea | CastType::Uuid | CastType::Json) => Err(Error::TypeMismatch),
+        }
+    }
+}
+
+impl From<LiteralValue> for Value {
+    fn from(value: LiteralValue) -> Self {
+        match value {
+            LiteralValue::Integer(value) => Value::Integer(value),
+            LiteralValue::Boolean(value) => Value::Boolean(value),
+            LiteralValue::Text(value) => Value::Text(value),
+            LiteralValue::Decimal(value) => Value::Decimal(value),
+            LiteralValue::Bytes(value) => Value::Bytes(value),
+            LiteralValue::Uuid(value) => Value::Uuid(value),
+            LiteralValue::Json(value) => Value::Json(value),
+        }
+    }
+}
+
+impl From<Value> for LiteralValue {
+    fn from(value: Value) -> Self {
+        match value {
+            Value::Integer(value) => LiteralValue::Integer(value),
+            Value::Boolean(value) => LiteralValue::Boolean(value),
+            Value::Text(value) => LiteralValue::Text(value),
+            Value::Decimal(value) => LiteralValue::Decimal(value),
+            Value::Bytes(value) => LiteralValue::Bytes(value),
+            Value::Uuid(value) => LiteralValue::Uuid(value),
+            Value::Json(value) => LiteralValue::Json(value),
         }
     }
 }
@@ -17,6 +161,12 @@ impl Display for Value {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Value::Integer(value) => write!(f, "{}", value),
+            Value::Boolean(value) => write!(f, "{}", value),
+            Value::Text(value) => write!(f, "{}", value),
+            Value::Decimal(value) => write!(f, "{}", value),
+            Value::Bytes(value) => write!(f, "\\x{}", hex::encode(value)),
+            Value::Uuid(value) => write!(f, "{}", value),
+            Value::Json(value) => write!(f, "{}", value),
         }
     }
 }
@@ -0,0 +1,20 @@
+/// A problem with a statement that didn't stop it from being executed. Warnings are collected
+/// while executing a statement and can be read with `Manager::warnings`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Warning {
+    /// `BEGIN` was ignored because a transaction is already in progress.
+    TransactionAlreadyInProgress,
+
+    /// `COMMIT` or `ROLLBACK` was ignored because there is no transaction in progress.
+    NoTransactionInProgress,
+}
+
+impl Warning {
+    /// Returns a SQLSTATE-like code for the warning, see `Error::code`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Warning::TransactionAlreadyInProgress => "25001",
+            Warning::NoTransactionInProgress => "25P01",
+        }
+    }
+}
@@ -1,6 +1,15 @@
-#![allow(incomplete_features)]
-#![feature(adt_const_params, generic_const_exprs)]
+extern crate alloc;
 
+pub mod fuzz;
 mod internal;
+pub mod render;
+pub mod repl;
 
-pub use internal::{ColumnDefinition, Error, Manager, PageManager, QueryResult, RowResult};
+pub use internal::{
+    locate_syntax_error, parse, parse_statements, split_statements, CatalogChange,
+    CatalogChangeKind, CatalogChangeListener, Clock, ColumnDefinition, Command, Decimal, Error,
+    ErrorCategory, Expression, FileBackend, FromRow, FromValue, Manager, MemoryBackend,
+    PageManager, ParseError, QueryLog, QueryLogEntry, QueryResult, Row, RowResult, Session,
+    StatementStatistics, StoppedClock, StorageBackend, SynchronousMode, SyntaxErrorLocation,
+    SystemClock, Uuid, Value, Warning, FORMAT_VERSION,
+};
@@ -1,99 +1,88 @@
-#![allow(incomplete_features)]
-#![feature(adt_const_params, generic_const_exprs)]
-
 use std::{io::Write, rc::Rc, sync::RwLock};
 
-mod print_table;
-
-use bsql::{ColumnDefinition, Error, Manager, PageManager, QueryResult};
-use print_table::{print_row_result, print_table};
+use bsql::repl::Repl;
+use bsql::{Manager, PageManager};
 
 fn main() {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    if let [subcommand, filename] = &arguments[..] {
+        if subcommand == "compact" {
+            return compact(filename);
+        }
+    }
+
     let page_manager = Rc::new(RwLock::new(PageManager::new("bsql.db")));
 
-    let mut database_manager = Manager::new(page_manager);
-    let mut active_database = String::new();
+    let mut manager = open("bsql.db", page_manager.clone());
+    manager.set_copy_directory(std::env::current_dir().ok());
+    let mut repl = Repl::new(manager);
 
     loop {
-        let expression = prompt(&format!("{}> ", active_database));
-        let command_parts: Vec<&str> = expression.split(" ").collect();
-
-        match &command_parts[..] {
-            ["\\c", database_name] => {
-                if database_manager.database_exists(database_name) {
-                    active_database = database_name.to_string();
-                    println!("You are now connected to database \"{}\".", database_name);
-                } else {
-                    println!("FATAL: database \"{}\" does not exist", database_name);
-                }
-            }
-
-            ["\\l"] => print_databases(database_manager.database_names()),
-            ["\\list"] => print_databases(database_manager.database_names()),
-
-            ["\\dt"] => match database_manager.database_table_names(&active_database) {
-                Ok(table_names) => print_tables(table_names),
-                Err(error) => print_error(&error),
-            },
-
-            ["\\d+", table_name] => {
-                match database_manager.table_definition(&active_database, table_name) {
-                    Ok(table_definition) => print_table_definition(&table_definition),
-                    Err(error) => print_error(&error),
-                }
-            }
-
-            ["exit"] => {
-                break;
-            }
-
-            _ => match database_manager.execute(&active_database, &expression) {
-                Ok(query_result) => print_query_result(&query_result),
-                Err(error) => print_error(&error),
-            },
+        let line = prompt(&repl.prompt());
+        let should_continue = repl
+            .handle_line(&line, &mut std::io::stdout(), &mut std::io::stderr())
+            .expect("Error: Could not write the output");
+
+        if !should_continue {
+            break;
         }
     }
-}
 
-fn print_databases(database_names: Vec<String>) {
-    print_table(
-        vec!["Database name"],
-        database_names.into_iter().map(|name| vec![name]).collect(),
-    );
+    let flushed = page_manager.read().unwrap().flush();
+    if let Err(error) = flushed {
+        eprintln!("FATAL: could not write \"bsql.db\": {}", error);
+        std::process::exit(1);
+    }
 }
 
-fn print_tables(table_names: Vec<String>) {
-    print_table(
-        vec!["Table name"],
-        table_names.into_iter().map(|name| vec![name]).collect(),
-    );
-}
+/// Rewrites the database file with only the pages that are still in use, see
+/// `Manager::compact_into`.
+fn compact(filename: &str) {
+    if !std::path::Path::new(filename).exists() {
+        eprintln!("FATAL: database file \"{}\" does not exist", filename);
+        std::process::exit(1);
+    }
 
-fn print_table_definition(column_definitions: &Vec<ColumnDefinition>) {
-    print_table(
-        vec!["Column name", "Data type"],
-        column_definitions
-            .into_iter()
-            .map(|definition| {
-                vec![
-                    definition.name().clone(),
-                    definition.data_type().to_string(),
-                ]
-            })
-            .collect(),
-    );
-}
+    let page_manager = Rc::new(RwLock::new(PageManager::new(filename)));
+    let page_count = page_manager.read().unwrap().page_count();
+    let database_manager = open(filename, page_manager);
 
-fn print_query_result(query_result: &QueryResult) {
-    match query_result {
-        QueryResult::CommandSuccessMessage(message) => println!("{}", message),
-        QueryResult::InsertSuccess { count } => println!("INSERT 0 {}", count),
-        QueryResult::RowResult(row_result) => print_row_result(row_result),
+    let compacted_filename = format!("{}.compact", filename);
+    let _result = std::fs::remove_file(&compacted_filename);
+    let compacted_page_manager = Rc::new(RwLock::new(PageManager::new(&compacted_filename)));
+    if let Err(error) = database_manager.compact_into(compacted_page_manager.clone()) {
+        eprintln!("FATAL: could not compact \"{}\": {}", filename, error);
+        std::process::exit(1);
     }
+
+    let compacted_page_count = {
+        let compacted_page_manager = compacted_page_manager.read().unwrap();
+        if let Err(error) = compacted_page_manager.commit() {
+            eprintln!(
+                "FATAL: could not write \"{}\": {}",
+                compacted_filename, error
+            );
+            std::process::exit(1);
+        }
+        compacted_page_manager.page_count()
+    };
+
+    if let Err(error) = std::fs::rename(&compacted_filename, filename) {
+        eprintln!("FATAL: could not replace \"{}\": {}", filename, error);
+        std::process::exit(1);
+    }
+    println!(
+        "Compacted \"{}\" from {} to {} pages.",
+        filename, page_count, compacted_page_count
+    );
 }
 
-fn print_error(error: &Error) {
-    eprintln!("ERROR: {:?}", error);
+/// Opens the databases of the file, or exits when this version of bsql can't read it.
+fn open(filename: &str, page_manager: Rc<RwLock<PageManager>>) -> Manager {
+    Manager::open(page_manager).unwrap_or_else(|error| {
+        eprintln!("FATAL: could not open \"{}\": {}", filename, error);
+        std::process::exit(1);
+    })
 }
 
 fn prompt(name: &str) -> String {
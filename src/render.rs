@@ -0,0 +1,274 @@
+//! Renders the rows of a query result as text. The REPL renders every result through a
+//! `ResultRenderer`, so new output formats only need to implement the trait.
+
+use std::io::{self, Write};
+
+use crate::internal::Value;
+use crate::RowResult;
+
+pub trait ResultRenderer {
+    fn render(&self, output: &mut dyn Write, row_result: &RowResult) -> io::Result<()>;
+}
+
+/// Renders an aligned table with a header row, like `psql` does.
+pub struct TableRenderer {
+    /// Shown in place of `NULL` values.
+    pub null: String,
+
+    /// Cuts values (and headers) that are wider than this many characters, ending them in `…`.
+    pub max_width: Option<usize>,
+}
+
+impl Default for TableRenderer {
+    fn default() -> Self {
+        Self {
+            null: "NULL".to_string(),
+            max_width: None,
+        }
+    }
+}
+
+impl TableRenderer {
+    fn cell(&self, text: &str) -> String {
+        match self.max_width {
+            Some(max_width) if text.chars().count() > max_width => {
+                let mut cell: String = text.chars().take(max_width.saturating_sub(1)).collect();
+                cell.push('…');
+                cell
+            }
+            _ => text.to_string(),
+        }
+    }
+}
+
+impl ResultRenderer for TableRenderer {
+    fn render(&self, output: &mut dyn Write, row_result: &RowResult) -> io::Result<()> {
+        let headers: Vec<String> = row_result
+            .columns()
+            .iter()
+            .map(|column| self.cell(column))
+            .collect();
+        let rows: Vec<Vec<String>> = row_result
+            .rows()
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|value| match value {
+                        Some(value) => self.cell(&value.to_string()),
+                        None => self.cell(&self.null),
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Calculate the maximum width of each column
+        let column_widths: Vec<usize> = headers
+            .iter()
+            .enumerate()
+            .map(|(i, header)| {
+                let max_item_width = rows
+                    .iter()
+                    .map(|row| row[i].chars().count())
+                    .max()
+                    .unwrap_or(0);
+                std::cmp::max(header.chars().count(), max_item_width)
+            })
+            .collect();
+
+        // Print the header row
+        for (i, header) in headers.iter().enumerate() {
+            write!(output, " {:width$} ", header, width = column_widths[i])?;
+            write!(output, "| ")?;
+        }
+        writeln!(output)?;
+
+        // Print the separator row
+        for width in column_widths.iter() {
+            write!(output, "{:-<width$}", "", width = width + 2)?;
+            write!(output, "+")?;
+        }
+        writeln!(output)?;
+
+        // Print the data rows
+        for row in rows {
+            for (i, item) in row.iter().enumerate() {
+                write!(output, " {:width$} ", item, width = column_widths[i])?;
+                write!(output, "| ")?;
+            }
+            writeln!(output)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Renders comma-separated values with a header line, quoting values as described in RFC 4180.
+#[derive(Default)]
+pub struct CsvRenderer {
+    /// Written in place of `NULL` values.
+    pub null: String,
+}
+
+impl ResultRenderer for CsvRenderer {
+    fn render(&self, output: &mut dyn Write, row_result: &RowResult) -> io::Result<()> {
+        let fields: Vec<String> = row_result
+            .columns()
+            .iter()
+            .map(|column| csv_field(column))
+            .collect();
+        writeln!(output, "{}", fields.join(","))?;
+
+        for row in row_result.rows() {
+            let fields: Vec<String> = row
+                .iter()
+                .map(|value| match value {
+                    Some(value) => csv_field(&value.to_string()),
+                    None => csv_field(&self.null),
+                })
+                .collect();
+            writeln!(output, "{}", fields.join(","))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn csv_field(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Renders an array with an object per row, keyed by the column names. `NULL` values become
+/// `null` and only text values are quoted.
+#[derive(Default)]
+pub struct JsonRenderer;
+
+impl ResultRenderer for JsonRenderer {
+    fn render(&self, output: &mut dyn Write, row_result: &RowResult) -> io::Result<()> {
+        if row_result.rows().is_empty() {
+            return writeln!(output, "[]");
+        }
+
+        writeln!(output, "[")?;
+        for (index, row) in row_result.rows().iter().enumerate() {
+            let members: Vec<String> = row_result
+                .columns()
+                .iter()
+                .zip(row.iter())
+                .map(|(column, value)| {
+                    let value = match value {
+                        Some(Value::Text(text)) => json_string(text),
+                        Some(value @ Value::Bytes(_)) => json_string(&value.to_string()),
+                        Some(value) => value.to_string(),
+                        None => "null".to_string(),
+                    };
+                    format!("{}: {}", json_string(column), value)
+                })
+                .collect();
+
+            let separator = if index + 1 < row_result.rows().len() {
+                ","
+            } else {
+                ""
+            };
+            writeln!(output, "  {{{}}}{}", members.join(", "), separator)?;
+        }
+        writeln!(output, "]")
+    }
+}
+
+fn json_string(text: &str) -> String {
+    let mut string = String::from("\"");
+    for character in text.chars() {
+        match character {
+            '"' => string.push_str("\\\""),
+            '\\' => string.push_str("\\\\"),
+            '\n' => string.push_str("\\n"),
+            '\r' => string.push_str("\\r"),
+            '\t' => string.push_str("\\t"),
+            character if character < ' ' => {
+                string.push_str(&format!("\\u{:04x}", character as u32))
+            }
+            character => string.push(character),
+        }
+    }
+    string.push('"');
+    string
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row_result() -> RowResult {
+        RowResult::new(
+            vec!["id".to_string(), "name".to_string()],
+            vec![
+                vec![
+                    Some(Value::Integer(1)),
+                    Some(Value::Text("Jo, \"Jr\"".to_string())),
+                ],
+                vec![Some(Value::Integer(2)), None],
+            ],
+        )
+    }
+
+    fn render(renderer: &dyn ResultRenderer, row_result: &RowResult) -> String {
+        let mut output = Vec::new();
+        renderer.render(&mut output, row_result).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn test_rendering_a_table() {
+        assert_eq!(
+            " id |  name     | \n----+----------+\n 1  |  Jo, \"Jr\" | \n 2  |  NULL     | \n",
+            render(&TableRenderer::default(), &row_result())
+        );
+
+        let renderer = TableRenderer {
+            null: "∅".to_string(),
+            max_width: Some(4),
+        };
+        assert_eq!(
+            " id |  name | \n----+------+\n 1  |  Jo,… | \n 2  |  ∅    | \n",
+            render(&renderer, &row_result())
+        );
+    }
+
+    #[test]
+    fn test_rendering_csv() {
+        assert_eq!(
+            "id,name\n1,\"Jo, \"\"Jr\"\"\"\n2,\n",
+            render(&CsvRenderer::default(), &row_result())
+        );
+    }
+
+    #[test]
+    fn test_rendering_json() {
+        assert_eq!(
+            "[\n  {\"id\": 1, \"name\": \"Jo, \\\"Jr\\\"\"},\n  {\"id\": 2, \"name\": null}\n]\n",
+            render(&JsonRenderer, &row_result())
+        );
+        assert_eq!(
+            "[]\n",
+            render(
+                &JsonRenderer,
+                &RowResult::new(vec!["id".to_string()], vec![])
+            )
+        );
+
+        // Binary data is rendered as a string in the hex format.
+        let row_result = RowResult::new(
+            vec!["hash".to_string()],
+            vec![vec![Some(Value::Bytes(vec![0xBE, 0xEF]))]],
+        );
+        assert_eq!(
+            "[\n  {\"hash\": \"\\\\xbeef\"}\n]\n",
+            render(&JsonRenderer, &row_result)
+        );
+    }
+}
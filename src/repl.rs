@@ -0,0 +1,385 @@
+//! The interactive shell behind the `bsql` binary. It is kept out of `main.rs` so that scripted
+//! sessions can be replayed against it, see `tests/transcripts.rs`.
+
+use std::io::{self, Write};
+
+use crate::internal::Value;
+use crate::render::{CsvRenderer, JsonRenderer, ResultRenderer, TableRenderer};
+use crate::{
+    locate_syntax_error, split_statements, ColumnDefinition, Error, ErrorCategory, Manager,
+    QueryResult, RowResult, Session, Warning,
+};
+
+pub struct Repl {
+    session: Session,
+
+    renderer: Box<dyn ResultRenderer>,
+}
+
+impl Repl {
+    pub fn new(manager: Manager) -> Self {
+        Self {
+            session: manager.session(""),
+
+            renderer: Box::new(TableRenderer::default()),
+        }
+    }
+
+    /// Renders every row result from now on, including the ones of meta-commands. Also available
+    /// as `\pset format table|csv|json`, which uses the default settings of each format.
+    pub fn set_renderer(&mut self, renderer: Box<dyn ResultRenderer>) {
+        self.renderer = renderer;
+    }
+
+    /// The prompt shown before every line, e.g. `drinkr> ` once connected to `drinkr`.
+    pub fn prompt(&self) -> String {
+        format!("{}> ", self.session.database_name())
+    }
+
+    pub fn manager(&self) -> &Manager {
+        self.session.manager()
+    }
+
+    /// Runs a single line of input, which is either a meta-command such as `\dt` or a SQL
+    /// statement. Results go to `output` while errors and warnings go to `errors`.
+    ///
+    /// Returns `false` once the session should end.
+    pub fn handle_line(
+        &mut self,
+        line: &str,
+        output: &mut dyn Write,
+        errors: &mut dyn Write,
+    ) -> io::Result<bool> {
+        let command_parts: Vec<&str> = line.split(" ").collect();
+
+        match &command_parts[..] {
+            ["\\c", database_name] => {
+                if self.session.connect(database_name).is_ok() {
+                    writeln!(
+                        output,
+                        "You are now connected to database \"{}\".",
+                        database_name
+                    )?;
+                } else {
+                    writeln!(
+                        output,
+                        "FATAL: database \"{}\" does not exist",
+                        database_name
+                    )?;
+                }
+            }
+
+            ["\\l"] | ["\\list"] => match self.session.manager().database_names() {
+                Ok(database_names) => self
+                    .renderer
+                    .render(output, &databases_result(database_names))?,
+                Err(error) => print_error(errors, &error)?,
+            },
+
+            ["\\dt"] => match self
+                .session
+                .manager()
+                .database_table_names(self.session.database_name())
+            {
+                Ok(table_names) => self.renderer.render(output, &tables_result(table_names))?,
+                Err(error) => print_error(errors, &error)?,
+            },
+
+            ["\\dt+"] => match self
+                .session
+                .manager()
+                .table_sizes(self.session.database_name())
+            {
+                Ok(table_sizes) => self
+                    .renderer
+                    .render(output, &table_sizes_result(table_sizes))?,
+                Err(error) => print_error(errors, &error)?,
+            },
+
+            ["\\d+", table_name] => {
+                match self
+                    .session
+                    .manager()
+                    .table_definition(self.session.database_name(), table_name)
+                {
+                    Ok(table_definition) => {
+                        let row_result = table_definition_result(&table_definition);
+                        self.renderer.render(output, &row_result)?;
+                    }
+                    Err(error) => print_error(errors, &error)?,
+                }
+            }
+
+            ["\\pset", "format", format] => {
+                let renderer: Box<dyn ResultRenderer> = match *format {
+                    "table" => Box::new(TableRenderer::default()),
+                    "csv" => Box::new(CsvRenderer::default()),
+                    "json" => Box::new(JsonRenderer),
+                    _ => {
+                        writeln!(errors, "\\pset: allowed formats are table, csv, json")?;
+                        return Ok(true);
+                    }
+                };
+                self.set_renderer(renderer);
+                writeln!(output, "Output format is {}.", format)?;
+            }
+
+            ["\\flush"] => match self.session.manager().flush() {
+                Ok(written_page_count) => writeln!(output, "FLUSH {}", written_page_count)?,
+                Err(error) => print_error(errors, &error)?,
+            },
+
+            ["\\restore", path] => match self.session.manager_mut().restore(path) {
+                Ok(database_names) => writeln!(output, "RESTORE {}", database_names.len())?,
+                Err(error) => print_error(errors, &error)?,
+            },
+
+            ["exit"] => return Ok(false),
+
+            _ => {
+                let statements = split_statements(line);
+                let results = self.session.execute_all(line);
+
+                for (index, result) in results.into_iter().enumerate() {
+                    match result {
+                        Ok(query_result) => {
+                            self.print_query_result(output, &query_result)?;
+                        }
+                        Err(error) => {
+                            print_error(errors, &error)?;
+
+                            if error.category() == ErrorCategory::Syntax {
+                                if let Some(statement) = statements.get(index) {
+                                    print_syntax_error_location(errors, statement)?;
+                                }
+                            }
+                        }
+                    }
+                }
+
+                for warning in self.session.warnings() {
+                    print_warning(errors, warning)?;
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn print_query_result(
+        &self,
+        output: &mut dyn Write,
+        query_result: &QueryResult,
+    ) -> io::Result<()> {
+        match query_result {
+            QueryResult::CommandSuccessMessage(message) => writeln!(output, "{}", message),
+            QueryResult::InsertSuccess { count } => writeln!(output, "INSERT 0 {}", count),
+            QueryResult::RowResult(row_result) => self.renderer.render(output, row_result),
+            QueryResult::DatabaseChanged { database_name } => writeln!(
+                output,
+                "You are now connected to database \"{}\".",
+                database_name
+            ),
+        }
+    }
+}
+
+/// Builds the row result of a meta-command, which only has text values.
+fn text_result(headers: Vec<&str>, rows: Vec<Vec<String>>) -> RowResult {
+    RowResult::new(
+        headers.into_iter().map(String::from).collect(),
+        rows.into_iter()
+            .map(|row| {
+                row.into_iter()
+                    .map(|text| Some(Value::Text(text)))
+                    .collect()
+            })
+            .collect(),
+    )
+}
+
+fn databases_result(database_names: Vec<String>) -> RowResult {
+    text_result(
+        vec!["Database name"],
+        database_names.into_iter().map(|name| vec![name]).collect(),
+    )
+}
+
+fn tables_result(table_names: Vec<String>) -> RowResult {
+    text_result(
+        vec!["Table name"],
+        table_names.into_iter().map(|name| vec![name]).collect(),
+    )
+}
+
+fn table_sizes_result(table_sizes: Vec<(String, u64, u32)>) -> RowResult {
+    text_result(
+        vec!["Table name", "Rows", "Pages"],
+        table_sizes
+            .into_iter()
+            .map(|(name, row_count, page_count)| {
+                vec![name, row_count.to_string(), page_count.to_string()]
+            })
+            .collect(),
+    )
+}
+
+fn table_definition_result(column_definitions: &[ColumnDefinition]) -> RowResult {
+    text_result(
+        vec!["Column name", "Data type", "Nullable", "Key"],
+        column_definitions
+            .iter()
+            .map(|definition| {
+                vec![
+                    definition.name().clone(),
+                    definition.data_type().to_string(),
+                    match definition.is_not_null() {
+                        true => "not null".to_string(),
+                        false => String::new(),
+                    },
+                    match definition.is_primary_key() {
+                        true => "primary key".to_string(),
+                        false => String::new(),
+                    },
+                ]
+            })
+            .collect(),
+    )
+}
+
+fn print_error(errors: &mut dyn Write, error: &Error) -> io::Result<()> {
+    writeln!(errors, "ERROR: {}: {}", error.code(), error)
+}
+
+/// Points at the token of `statement` that a syntax error is about, like `psql` does.
+fn print_syntax_error_location(errors: &mut dyn Write, statement: &str) -> io::Result<()> {
+    let Some(location) = locate_syntax_error(statement) else {
+        return Ok(());
+    };
+
+    let prefix = format!("LINE {}: ", location.line);
+    let line = statement.lines().nth(location.line - 1).unwrap_or_default();
+    writeln!(errors, "{}{}", prefix, line)?;
+    writeln!(
+        errors,
+        "{:width$}^",
+        "",
+        width = prefix.len() + location.column - 1
+    )?;
+    writeln!(errors, "DETAIL: {}", location.message())
+}
+
+fn print_warning(errors: &mut dyn Write, warning: &Warning) -> io::Result<()> {
+    writeln!(errors, "WARNING: {}: {:?}", warning.code(), warning)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+    use std::sync::RwLock;
+
+    use super::*;
+    use crate::{MemoryBackend, PageManager};
+
+    fn run(repl: &mut Repl, line: &str) -> (String, String) {
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+        assert!(repl.handle_line(line, &mut output, &mut errors).unwrap());
+
+        (
+            String::from_utf8(output).unwrap(),
+            String::from_utf8(errors).unwrap(),
+        )
+    }
+
+    #[test]
+    fn test_connecting_changes_the_prompt() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut repl = Repl::new(Manager::new(page_manager));
+        assert_eq!(repl.prompt(), "> ");
+
+        let (output, _) = run(&mut repl, "\\c drinkr");
+        assert_eq!(output, "FATAL: database \"drinkr\" does not exist\n");
+        assert_eq!(repl.prompt(), "> ");
+
+        run(&mut repl, "CREATE DATABASE drinkr;");
+        let (output, _) = run(&mut repl, "\\c drinkr");
+        assert_eq!(output, "You are now connected to database \"drinkr\".\n");
+        assert_eq!(repl.prompt(), "drinkr> ");
+    }
+
+    #[test]
+    fn test_errors_are_written_separately() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut repl = Repl::new(Manager::new(page_manager));
+
+        let (output, errors) = run(&mut repl, "SELECT a FROM t;");
+        assert_eq!(output, "");
+        assert!(errors.starts_with("ERROR: "));
+    }
+
+    #[test]
+    fn test_flushing_pages() {
+        let page_manager = Rc::new(RwLock::new(PageManager::with_backend(Box::new(
+            MemoryBackend::new(),
+        ))));
+        let mut repl = Repl::new(Manager::new(page_manager.clone()));
+        run(&mut repl, "CREATE DATABASE drinkr;");
+
+        // Every statement writes the pages it modifies, so there's nothing left to flush.
+        let (output, _) = run(&mut repl, "\\flush");
+        assert_eq!(output, "FLUSH 0\n");
+
+        page_manager
+            .read()
+            .unwrap()
+            .fetch_page(0)
+            .unwrap()
+            .unwrap()
+            .write()
+            .unwrap()
+            .data[0] ^= 1;
+        let (output, _) = run(&mut repl, "\\flush");
+        assert_eq!(output, "FLUSH 1\n");
+    }
+
+    #[test]
+    fn test_restoring_backups() {
+        let filename =
+            std::env::temp_dir().join(format!("bsql_repl_restore_{}.db", std::process::id()));
+        let filename = filename.to_str().unwrap();
+
+        let mut manager = Manager::new(Rc::new(RwLock::new(PageManager::new(":memory:"))));
+        manager.execute("", "CREATE DATABASE drinkr;").unwrap();
+        manager
+            .execute(
+                "drinkr",
+                "CREATE TABLE brands (brand_id integer); INSERT INTO brands VALUES (1), (2);",
+            )
+            .unwrap();
+        manager.backup(filename).unwrap();
+
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut repl = Repl::new(Manager::new(page_manager));
+        let (output, _) = run(&mut repl, &format!("\\restore {}", filename));
+        assert_eq!(output, "RESTORE 1\n");
+        run(&mut repl, "\\c drinkr");
+        let (output, _) = run(&mut repl, "SELECT brand_id FROM brands;");
+        assert!(output.ends_with(" 1        | \n 2        | \n"));
+
+        let (_, errors) = run(&mut repl, &format!("\\restore {}", filename));
+        assert_eq!(errors, "ERROR: 42P04: database \"drinkr\" already exists\n");
+
+        std::fs::remove_file(filename).unwrap();
+    }
+
+    #[test]
+    fn test_exit_ends_the_session() {
+        let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+        let mut repl = Repl::new(Manager::new(page_manager));
+
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+        assert!(!repl.handle_line("exit", &mut output, &mut errors).unwrap());
+    }
+}
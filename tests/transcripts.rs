@@ -0,0 +1,69 @@
+//! Replays the scripted REPL sessions in `tests/transcripts/` and compares the rendered output
+//! against the transcript. Lines starting with the current prompt are fed into the REPL, every
+//! other line is expected output (with errors and warnings following the regular output). The
+//! invariants of the storage layer are checked after every line.
+//!
+//! Run with `BSQL_BLESS=1` to rewrite the transcripts after an intended change in the output.
+
+use std::{fs, path::Path, rc::Rc, sync::RwLock};
+
+use bsql::repl::Repl;
+use bsql::{Manager, PageManager};
+
+fn replay(transcript: &str) -> String {
+    let page_manager = Rc::new(RwLock::new(PageManager::new(":memory:")));
+    let mut repl = Repl::new(Manager::new(page_manager));
+    let mut rendered = String::new();
+
+    for line in transcript.lines() {
+        let prompt = repl.prompt();
+        let Some(input) = line.strip_prefix(&prompt) else {
+            continue;
+        };
+
+        let mut output = Vec::new();
+        let mut errors = Vec::new();
+        let should_continue = repl.handle_line(input, &mut output, &mut errors).unwrap();
+        repl.manager().debug_check_invariants();
+
+        rendered.push_str(line);
+        rendered.push('\n');
+        rendered.push_str(&String::from_utf8(output).unwrap());
+        rendered.push_str(&String::from_utf8(errors).unwrap());
+
+        if !should_continue {
+            break;
+        }
+    }
+
+    rendered
+}
+
+#[test]
+fn test_transcripts() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/transcripts");
+    let bless = std::env::var_os("BSQL_BLESS").is_some();
+
+    let mut paths: Vec<_> = fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "txt"))
+        .collect();
+    paths.sort();
+    assert!(!paths.is_empty(), "No transcripts in {:?}", directory);
+
+    for path in paths {
+        let transcript = fs::read_to_string(&path).unwrap();
+        let rendered = replay(&transcript);
+
+        if bless {
+            fs::write(&path, rendered).unwrap();
+        } else {
+            assert_eq!(
+                transcript, rendered,
+                "{:?} doesn't match, run with BSQL_BLESS=1 to update it",
+                path
+            );
+        }
+    }
+}
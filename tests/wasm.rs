@@ -0,0 +1,68 @@
+//! Runs statements against a `MemoryBackend`, like a browser demo would. Also runs on
+//! wasm32-unknown-unknown, where there's no clock and `usize` has 32 bits, with
+//! `wasm-pack test --node -- --test wasm`.
+
+use std::{rc::Rc, sync::RwLock};
+
+use bsql::{Manager, MemoryBackend, PageManager, QueryResult, Value};
+
+fn memory_manager() -> Manager {
+    let page_manager = PageManager::with_backend(Box::new(MemoryBackend::new()));
+    Manager::new(Rc::new(RwLock::new(page_manager)))
+}
+
+fn rows(manager: &mut Manager, query: &str) -> Vec<Vec<Option<Value>>> {
+    let Ok(QueryResult::RowResult(row_result)) = manager.execute("db", query) else {
+        panic!("Did not get the expected result");
+    };
+
+    row_result.rows().to_vec()
+}
+
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test::wasm_bindgen_test)]
+#[cfg_attr(not(target_arch = "wasm32"), test)]
+fn test_executing_statements_in_memory() {
+    let mut manager = memory_manager();
+    manager.execute("", "CREATE DATABASE db;").unwrap();
+    manager
+        .execute("db", "CREATE TABLE t (id integer PRIMARY KEY, name text);")
+        .unwrap();
+
+    // Enough rows for a few pages, so the record ids have page ids in their upper 32 bits.
+    let values: Vec<String> = (0..1000)
+        .map(|id| format!("({}, 'name {}')", id, id))
+        .collect();
+    manager
+        .execute(
+            "db",
+            &format!("INSERT INTO t VALUES {};", values.join(", ")),
+        )
+        .unwrap();
+    assert!(manager.table_sizes("db").unwrap()[0].2 > 1);
+
+    // The pages are read back from the backend.
+    manager.shrink_to_fit();
+
+    assert_eq!(
+        vec![vec![Some(Value::Text("name 999".to_string()))]],
+        rows(&mut manager, "SELECT name FROM t WHERE id = 999;")
+    );
+    assert_eq!(
+        vec![vec![Some(Value::Integer(1000))]],
+        rows(&mut manager, "SELECT COUNT(*) FROM t;")
+    );
+    assert_eq!(
+        1000,
+        rows(&mut manager, "SELECT id FROM t TABLESAMPLE SYSTEM (100);").len()
+    );
+
+    assert_eq!(1, rows(&mut manager, "SELECT GEN_RANDOM_UUID();").len());
+
+    manager
+        .execute("db", "BEGIN; INSERT INTO t VALUES (1000, 'name'); ROLLBACK;")
+        .unwrap();
+    assert_eq!(
+        vec![vec![Some(Value::Integer(1000))]],
+        rows(&mut manager, "SELECT COUNT(*) FROM t;")
+    );
+}